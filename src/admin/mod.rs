@@ -0,0 +1,72 @@
+pub mod routes;
+
+use std::path::PathBuf;
+
+use actix_web::dev::ServerHandle;
+use actix_web::middleware::Logger;
+use actix_web::web::Data;
+use actix_web::{App, HttpServer};
+use tracing::info;
+
+use crate::auth::Authentication;
+use crate::error::McpEctorsError;
+use crate::router::RouterServiceManager;
+use crate::transport::ListenAddress;
+
+pub use routes::{configure_admin_routes, MAX_UPLOAD_SIZE};
+
+/// A listener of its own for operators, apart from the one MCP clients use, to add, replace,
+/// disable, reload and remove routers while the server runs, see [`configure_admin_routes`]
+pub struct AdminServer {
+    address: ListenAddress,
+    manager: RouterServiceManager,
+    upload_dir: PathBuf,
+    auth: Authentication,
+}
+
+impl AdminServer {
+    /// Uploaded components are written to `upload_dir`
+    pub fn new(address: ListenAddress, manager: RouterServiceManager, upload_dir: PathBuf) -> Self {
+        Self { address, manager, upload_dir, auth: Authentication::new() }
+    }
+
+    /// Required, the admin API is not served to anyone who asks
+    pub fn with_auth(mut self, auth: Authentication) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Binds the listener and serves the admin API until the handle stops it
+    pub fn start(self) -> Result<ServerHandle, McpEctorsError> {
+        if !self.auth.is_enabled() {
+            return Err(McpEctorsError::InvalidParams("The admin API needs authentication".to_string()));
+        }
+        let auth = Data::new(self.auth);
+        let routes = configure_admin_routes(self.manager, self.upload_dir);
+        let server = HttpServer::new(move || {
+            App::new()
+                .wrap(Logger::default())
+                .app_data(auth.clone())
+                .configure(routes.clone())
+        })
+        .workers(1);
+        let bind_error = |e: std::io::Error| McpEctorsError::Internal(format!("Failed to bind the admin API on {}: {}", self.address, e));
+        let server = match &self.address {
+            ListenAddress::Tcp(address) => server.bind(address).map_err(bind_error)?,
+            #[cfg(unix)]
+            ListenAddress::Unix(path) => server.bind_uds(path).map_err(bind_error)?,
+            #[cfg(not(unix))]
+            ListenAddress::Unix(_) => return Err(McpEctorsError::Internal("Unix domain sockets are not supported on this platform".to_string())),
+        };
+        info!("Admin API listening on {}", self.address);
+
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = server.await {
+                tracing::error!("Admin server run error: {:?}", e);
+            }
+        });
+        Ok(handle)
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use actix_web::http::StatusCode;
+use actix_web::middleware::from_fn;
+use actix_web::web::{self, Bytes, Data};
+use actix_web::HttpResponse;
+use serde::Serialize;
+use serde_json::json;
+use tracing::info;
+
+use crate::auth::authenticate;
+use crate::error::McpEctorsError;
+use crate::router::wasm_router::validate_component;
+use crate::router::{RouterInfo, RouterServiceManager};
+
+/// Bytes a component uploaded to `PUT /routers/{id}` may have
+pub const MAX_UPLOAD_SIZE: usize = 64 * 1024 * 1024;
+
+// where uploaded components are written to
+#[derive(Clone)]
+struct UploadDir(PathBuf);
+
+/// Adds the admin API to an actix-web app, every endpoint authenticated against the
+/// `Data<Authentication>` of the app:
+///
+/// - `GET /routers` and `GET /routers/{id}`: what routers offer, their state and usage
+/// - `PUT /routers/{id}`: a WASM component as body, loaded as a new router or replacing one
+/// - `DELETE /routers/{id}`: unregisters a router, its file stays
+/// - `POST /routers/{id}/enable`, `/disable` and `/reload`
+/// - `GET /routers/{id}/tools`, `/prompts` and `/resources`
+pub fn configure_admin_routes(manager: RouterServiceManager, upload_dir: PathBuf) -> impl Fn(&mut web::ServiceConfig) + Clone {
+    move |cfg: &mut web::ServiceConfig| {
+        cfg.app_data(Data::new(manager.clone()))
+            .app_data(Data::new(UploadDir(upload_dir.clone())))
+            .app_data(web::PayloadConfig::new(MAX_UPLOAD_SIZE))
+            .service(
+                web::scope("/routers")
+                    .wrap(from_fn(authenticate))
+                    .route("", web::get().to(list_routers))
+                    .route("/{id}", web::get().to(get_router))
+                    .route("/{id}", web::put().to(upload_router))
+                    .route("/{id}", web::delete().to(delete_router))
+                    .route("/{id}/enable", web::post().to(enable_router))
+                    .route("/{id}/disable", web::post().to(disable_router))
+                    .route("/{id}/reload", web::post().to(reload_router))
+                    .route("/{id}/tools", web::get().to(router_tools))
+                    .route("/{id}/prompts", web::get().to(router_prompts))
+                    .route("/{id}/resources", web::get().to(router_resources)),
+            );
+    }
+}
+
+async fn list_routers(manager: Data<RouterServiceManager>) -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "routers": manager.routers() }))
+}
+
+async fn get_router(manager: Data<RouterServiceManager>, router_id: web::Path<String>) -> HttpResponse {
+    respond(StatusCode::OK, found(&router_id, manager.router(&router_id)))
+}
+
+async fn upload_router(manager: Data<RouterServiceManager>, upload_dir: Data<UploadDir>, router_id: web::Path<String>, component: Bytes) -> HttpResponse {
+    let replaced = manager.router(&router_id).is_some();
+    let uploaded = upload(manager.get_ref().clone(), &upload_dir.0, &router_id, component).await;
+    respond(if replaced { StatusCode::OK } else { StatusCode::CREATED }, uploaded)
+}
+
+async fn delete_router(manager: Data<RouterServiceManager>, router_id: web::Path<String>) -> HttpResponse {
+    let mut manager = manager.get_ref().clone();
+    let deleted = match found(&router_id, manager.router(&router_id)) {
+        Ok(router) => manager.unregister_router(&router_id).await.map(|_| router),
+        Err(e) => Err(e),
+    };
+    respond(StatusCode::OK, deleted)
+}
+
+async fn enable_router(manager: Data<RouterServiceManager>, router_id: web::Path<String>) -> HttpResponse {
+    set_enabled(&manager, &router_id, true).await
+}
+
+async fn disable_router(manager: Data<RouterServiceManager>, router_id: web::Path<String>) -> HttpResponse {
+    set_enabled(&manager, &router_id, false).await
+}
+
+async fn reload_router(manager: Data<RouterServiceManager>, router_id: web::Path<String>) -> HttpResponse {
+    let mut manager = manager.get_ref().clone();
+    let reloaded = match manager.reload_wasm_router(&router_id, None).await {
+        Ok(()) => found(&router_id, manager.router(&router_id)),
+        Err(e) => Err(e),
+    };
+    respond(StatusCode::OK, reloaded)
+}
+
+async fn router_tools(manager: Data<RouterServiceManager>, router_id: web::Path<String>) -> HttpResponse {
+    respond(StatusCode::OK, found(&router_id, manager.router_tools(&router_id)).map(|tools| json!({ "tools": tools })))
+}
+
+async fn router_prompts(manager: Data<RouterServiceManager>, router_id: web::Path<String>) -> HttpResponse {
+    respond(StatusCode::OK, found(&router_id, manager.router_prompts(&router_id)).map(|prompts| json!({ "prompts": prompts })))
+}
+
+async fn router_resources(manager: Data<RouterServiceManager>, router_id: web::Path<String>) -> HttpResponse {
+    respond(StatusCode::OK, found(&router_id, manager.router_resources(&router_id)).map(|resources| json!({ "resources": resources })))
+}
+
+async fn set_enabled(manager: &RouterServiceManager, router_id: &str, enabled: bool) -> HttpResponse {
+    let changed = match manager.set_router_enabled(router_id, enabled).await {
+        Ok(()) => found(router_id, manager.router(router_id)),
+        Err(e) => Err(e),
+    };
+    respond(StatusCode::OK, changed)
+}
+
+/// Checks the component before anything is written, then swaps the file in whole so no scan
+/// ever sees half of it
+async fn upload(mut manager: RouterServiceManager, upload_dir: &Path, router_id: &str, component: Bytes) -> Result<RouterInfo, McpEctorsError> {
+    // the id names the file too
+    if router_id.is_empty() || !router_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(McpEctorsError::InvalidRouterId(router_id.to_string()));
+    }
+    let existing = manager.router(router_id);
+    if let Some(router) = existing.as_ref().filter(|router| router.path.is_none()) {
+        return Err(McpEctorsError::RouterExists(router.router_id.clone()));
+    }
+    let bytes = component.clone();
+    web::block(move || validate_component(&bytes))
+        .await
+        .map_err(|e| McpEctorsError::Internal(e.to_string()))??;
+
+    let file = upload_dir.join(format!("{}.wasm", router_id));
+    let partial = upload_dir.join(format!(".{}.wasm.partial", router_id));
+    fs::create_dir_all(upload_dir)
+        .and_then(|_| fs::write(&partial, &component))
+        .and_then(|_| fs::rename(&partial, &file))
+        .map_err(|e| McpEctorsError::Internal(format!("Could not write {}: {}", file.display(), e)))?;
+    info!("Uploaded {} bytes to {}", component.len(), file.display());
+
+    match existing {
+        Some(_) => manager.reload_wasm_router(router_id, Some(&file)).await?,
+        None => manager.load_wasm_router(router_id, &file).await?,
+    }
+    found(router_id, manager.router(router_id))
+}
+
+fn found<T>(router_id: &str, value: Option<T>) -> Result<T, McpEctorsError> {
+    value.ok_or_else(|| McpEctorsError::RouterNotFound(router_id.to_string()))
+}
+
+fn respond(status: StatusCode, result: Result<impl Serialize, McpEctorsError>) -> HttpResponse {
+    match result {
+        Ok(body) => HttpResponse::build(status).json(body),
+        Err(e) => {
+            let status = match e {
+                McpEctorsError::RouterNotFound(_) => StatusCode::NOT_FOUND,
+                McpEctorsError::RouterExists(_) => StatusCode::CONFLICT,
+                McpEctorsError::InvalidParams(_) | McpEctorsError::InvalidRouterId(_) => StatusCode::BAD_REQUEST,
+                McpEctorsError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            HttpResponse::build(status).json(json!({ "error": e.to_string() }))
+        }
+    }
+}
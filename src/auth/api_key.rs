@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use actix_web::http::header::HeaderMap;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::error::McpEctorsError;
+use crate::router::router::ResponseFuture;
+
+use super::{bearer_token, AuthMethod, Authenticator, Principal};
+
+/// Header an API key can be sent in, besides `Authorization: Bearer <key>`
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+const HASH_PREFIX: &str = "sha256:";
+
+/// Authenticates static API keys. Only the SHA-256 hashes of the keys are kept, so a leaked
+/// configuration does not leak the keys.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeyAuthenticator {
+    /// hex encoded hash of the key -> the name of the principal
+    keys: HashMap<String, String>,
+}
+
+impl ApiKeyAuthenticator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The hash to put in the configuration for `key`, `sha256:<hex>`
+    pub fn hash_key(key: &str) -> String {
+        let digest = Sha256::digest(key.as_bytes());
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("{}{}", HASH_PREFIX, hex)
+    }
+
+    /// Adds the key with hash `hash` (as made by [`ApiKeyAuthenticator::hash_key`]) for principal `name`
+    pub fn with_hashed_key(mut self, name: &str, hash: &str) -> Result<Self, McpEctorsError> {
+        let hex = hash.strip_prefix(HASH_PREFIX).unwrap_or(hash).to_lowercase();
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(McpEctorsError::InvalidParams(format!("API key hash for {} is not a SHA-256 hash", name)));
+        }
+        self.keys.insert(format!("{}{}", HASH_PREFIX, hex), name.to_string());
+        Ok(self)
+    }
+
+    /// Reads `name: sha256:<hex>` lines, blank lines and lines starting with `#` are skipped
+    pub fn from_file(path: &Path) -> Result<Self, McpEctorsError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| McpEctorsError::Internal(format!("Could not read API keys from {}: {}", path.display(), e)))?;
+        let mut authenticator = Self::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (name, hash) = line.split_once(':')
+                .ok_or_else(|| McpEctorsError::InvalidParams(format!("Expected `name: sha256:<hex>` in {}", path.display())))?;
+            authenticator = authenticator.with_hashed_key(name.trim(), hash.trim())?;
+        }
+        Ok(authenticator)
+    }
+}
+
+impl Authenticator for ApiKeyAuthenticator {
+    fn authenticate(&self, headers: &HeaderMap) -> ResponseFuture<Result<Option<Principal>, McpEctorsError>> {
+        let key = headers.get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| bearer_token(headers));
+        // a bearer token that is no API key may still be a JWT
+        let principal = key.and_then(|key| self.keys.get(&Self::hash_key(key))).map(|name| Principal {
+            subject: name.clone(),
+            method: AuthMethod::ApiKey,
+            scopes: vec![],
+            claims: Value::Null,
+            permissions: None,
+        });
+        Box::pin(std::future::ready(Ok(principal)))
+    }
+}
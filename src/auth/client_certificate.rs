@@ -0,0 +1,35 @@
+use rustls::pki_types::CertificateDer;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use x509_parser::parse_x509_certificate;
+
+use super::{AuthMethod, Principal};
+
+/// The principal of a client that presented `certificate` in the TLS handshake. The certificate
+/// has already been verified against the client CA bundle by then, so it only needs to be read.
+///
+/// The subject is the common name of the certificate, or its whole distinguished name if it has
+/// none. The claims hold the subject and issuer names, the serial number and the SHA-256
+/// fingerprint of the certificate.
+pub fn client_certificate_principal(certificate: &CertificateDer<'_>) -> Option<Principal> {
+    let (_, parsed) = parse_x509_certificate(certificate.as_ref()).ok()?;
+    let subject_name = parsed.subject().to_string();
+    let subject = parsed.subject().iter_common_name()
+        .next()
+        .and_then(|common_name| common_name.as_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| subject_name.clone());
+    let fingerprint: String = Sha256::digest(certificate.as_ref()).iter().map(|byte| format!("{:02x}", byte)).collect();
+    Some(Principal {
+        subject,
+        method: AuthMethod::ClientCertificate,
+        scopes: vec![],
+        claims: json!({
+            "subject": subject_name,
+            "issuer": parsed.issuer().to_string(),
+            "serial": parsed.raw_serial_as_string(),
+            "fingerprint": fingerprint,
+        }),
+        permissions: None,
+    })
+}
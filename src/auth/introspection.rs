@@ -0,0 +1,101 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_web::http::header::HeaderMap;
+use serde_json::Value;
+
+use crate::error::McpEctorsError;
+use crate::router::router::ResponseFuture;
+
+use super::{bearer_token, AuthMethod, Authenticator, Principal};
+
+/// How long the introspection endpoint gets to answer
+pub const INTROSPECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Validates opaque bearer tokens by asking the authorization server, RFC 7662 token introspection.
+#[derive(Debug, Clone)]
+pub struct IntrospectionAuthenticator {
+    endpoint: String,
+    /// The credentials of this server at the authorization server, sent as basic auth
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    /// If set, the `aud` of the token must contain it
+    audience: Option<String>,
+    client: reqwest::Client,
+}
+
+impl IntrospectionAuthenticator {
+    pub fn new(endpoint: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(INTROSPECTION_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { endpoint: endpoint.to_string(), client_id: None, client_secret: None, audience: None, client }
+    }
+
+    pub fn with_client_credentials(mut self, client_id: &str, client_secret: Option<&str>) -> Self {
+        self.client_id = Some(client_id.to_string());
+        self.client_secret = client_secret.map(|secret| secret.to_string());
+        self
+    }
+
+    pub fn with_audience(mut self, audience: &str) -> Self {
+        self.audience = Some(audience.to_string());
+        self
+    }
+
+    /// Turns the introspection response into a principal, if the token is active and for us
+    fn principal(response: Value, audience: Option<&str>) -> Result<Principal, McpEctorsError> {
+        if response.get("active").and_then(|active| active.as_bool()) != Some(true) {
+            return Err(McpEctorsError::Unauthorized("token is not active".to_string()));
+        }
+        // the authorization server should not call an expired token active, but it may cache
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default();
+        if response.get("exp").and_then(|exp| exp.as_u64()).is_some_and(|exp| exp < now) {
+            return Err(McpEctorsError::Unauthorized("token is expired".to_string()));
+        }
+        if let Some(audience) = audience {
+            let for_us = match response.get("aud") {
+                Some(Value::String(aud)) => aud == audience,
+                Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !for_us {
+                return Err(McpEctorsError::Unauthorized(format!("token is not for {}", audience)));
+            }
+        }
+        let subject = response.get("sub")
+            .or_else(|| response.get("username"))
+            .and_then(|sub| sub.as_str())
+            .ok_or_else(|| McpEctorsError::Unauthorized("token has no subject".to_string()))?
+            .to_string();
+        let scopes = response.get("scope")
+            .and_then(|scope| scope.as_str())
+            .map(|scope| scope.split_whitespace().map(|s| s.to_string()).collect())
+            .unwrap_or_default();
+        Ok(Principal { subject, method: AuthMethod::Introspection, scopes, claims: response, permissions: None })
+    }
+}
+
+impl Authenticator for IntrospectionAuthenticator {
+    fn authenticate(&self, headers: &HeaderMap) -> ResponseFuture<Result<Option<Principal>, McpEctorsError>> {
+        let Some(token) = bearer_token(headers).map(|token| token.to_string()) else {
+            return Box::pin(std::future::ready(Ok(None)));
+        };
+        let mut request = self.client.post(&self.endpoint)
+            .form(&[("token", token.as_str()), ("token_type_hint", "access_token")]);
+        if let Some(client_id) = &self.client_id {
+            request = request.basic_auth(client_id, self.client_secret.as_ref());
+        }
+        let audience = self.audience.clone();
+        Box::pin(async move {
+            let unavailable = |e: reqwest::Error| McpEctorsError::Unavailable(format!("token introspection failed: {}", e));
+            let response: Value = request.send().await
+                .and_then(|response| response.error_for_status())
+                .map_err(unavailable)?
+                .json()
+                .await
+                .map_err(unavailable)?;
+            Self::principal(response, audience.as_deref()).map(Some)
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use actix_web::http::header::HeaderMap;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde_json::Value;
+
+use crate::error::McpEctorsError;
+use crate::router::router::ResponseFuture;
+
+use super::{bearer_token, AuthMethod, Authenticator, Principal};
+
+/// Seconds of clock skew tolerated when checking `exp` and `nbf`
+pub const JWT_LEEWAY: u64 = 30;
+
+/// Validates JWT bearer tokens against the keys of a local JWKS file. Tokens must be signed by
+/// one of its keys, be issued by `issuer` for `audience` and must not be expired.
+#[derive(Debug, Clone)]
+pub struct JwtAuthenticator {
+    jwks: JwkSet,
+    issuer: String,
+    audience: String,
+    leeway: u64,
+}
+
+impl JwtAuthenticator {
+    pub fn new(jwks: JwkSet, issuer: &str, audience: &str) -> Self {
+        Self { jwks, issuer: issuer.to_string(), audience: audience.to_string(), leeway: JWT_LEEWAY }
+    }
+
+    pub fn from_jwks_file(path: &Path, issuer: &str, audience: &str) -> Result<Self, McpEctorsError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| McpEctorsError::Internal(format!("Could not read JWKS from {}: {}", path.display(), e)))?;
+        let jwks: JwkSet = serde_json::from_str(&content)
+            .map_err(|e| McpEctorsError::InvalidParams(format!("Invalid JWKS in {}: {}", path.display(), e)))?;
+        Ok(Self::new(jwks, issuer, audience))
+    }
+
+    pub fn with_leeway(mut self, leeway: u64) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    fn validate(&self, token: &str) -> Result<Principal, McpEctorsError> {
+        let unauthorized = |e: jsonwebtoken::errors::Error| McpEctorsError::Unauthorized(format!("invalid token: {}", e));
+        let header = decode_header(token).map_err(unauthorized)?;
+        let jwk = match &header.kid {
+            Some(kid) => self.jwks.find(kid),
+            // without a key id only an unambiguous key set will do
+            None if self.jwks.keys.len() == 1 => self.jwks.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| McpEctorsError::Unauthorized("token is not signed by a known key".to_string()))?;
+        if let Some(key_algorithm) = jwk.common.key_algorithm {
+            if Algorithm::from_str(&key_algorithm.to_string()).ok() != Some(header.alg) {
+                return Err(McpEctorsError::Unauthorized(format!("key {:?} does not sign with {:?}", header.kid, header.alg)));
+            }
+        }
+        let key = DecodingKey::from_jwk(jwk).map_err(unauthorized)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        validation.leeway = self.leeway;
+        let claims = decode::<Value>(token, &key, &validation).map_err(unauthorized)?.claims;
+
+        let subject = claims.get("sub").and_then(|sub| sub.as_str()).unwrap_or_default().to_string();
+        let scopes = claims.get("scope")
+            .and_then(|scope| scope.as_str())
+            .map(|scope| scope.split_whitespace().map(|s| s.to_string()).collect())
+            .unwrap_or_default();
+        Ok(Principal { subject, method: AuthMethod::Jwt, scopes, claims, permissions: None })
+    }
+}
+
+impl Authenticator for JwtAuthenticator {
+    fn authenticate(&self, headers: &HeaderMap) -> ResponseFuture<Result<Option<Principal>, McpEctorsError>> {
+        let result = match bearer_token(headers) {
+            // anything else than header.payload.signature is not for us
+            Some(token) if token.split('.').count() == 3 => self.validate(token).map(Some),
+            _ => Ok(None),
+        };
+        Box::pin(std::future::ready(result))
+    }
+}
@@ -0,0 +1,199 @@
+pub mod api_key;
+pub mod client_certificate;
+pub mod introspection;
+pub mod jwt;
+pub mod oauth;
+pub mod policy;
+
+use std::sync::Arc;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderMap, AUTHORIZATION, WWW_AUTHENTICATE};
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::{HttpMessage, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::error::McpEctorsError;
+use crate::messages::outgoing_error;
+use crate::router::router::ResponseFuture;
+use crate::router::router_registry::ROUTER_SEPERATOR;
+
+pub use api_key::ApiKeyAuthenticator;
+pub use client_certificate::client_certificate_principal;
+pub use introspection::IntrospectionAuthenticator;
+pub use jwt::JwtAuthenticator;
+pub use oauth::{ProtectedResource, ScopeMap};
+pub use policy::{AccessControl, Policy};
+
+/// How a principal proved who it is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuthMethod {
+    ApiKey,
+    Jwt,
+    /// A token the authorization server vouched for through token introspection
+    Introspection,
+    /// A client certificate verified against the client CA bundle of the TLS listener (mTLS)
+    ClientCertificate,
+}
+
+/// The authenticated caller of the HTTP transport, attached to its session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Principal {
+    /// The API key name, the `sub` claim of the token or the common name of the client certificate
+    pub subject: String,
+    pub method: AuthMethod,
+    /// The scopes granted by the `scope` claim of the token, empty for API keys and client certificates
+    pub scopes: Vec<String>,
+    /// All claims of the token or the names of the client certificate, `Null` for API keys
+    pub claims: Value,
+    /// The routers (`counter`) and namespaced tools, prompts or resources (`counter_increment`) the
+    /// scopes grant, `*` for all of them. `None` if the caller is not limited by its scopes.
+    pub permissions: Option<Vec<String>>,
+}
+
+impl Principal {
+    /// Whether `other` is the same caller, e.g. the POSTs to a session must come from whoever opened it
+    pub fn same_as(&self, other: &Principal) -> bool {
+        self.subject == other.subject && self.method == other.method
+    }
+
+    /// Whether the caller may use the namespaced tool, prompt or resource `name`
+    pub fn may_use(&self, name: &str) -> bool {
+        let Some(permissions) = &self.permissions else {
+            return true;
+        };
+        let router_id = name.split(ROUTER_SEPERATOR).next().unwrap_or(name);
+        permissions.iter().any(|permission| permission == "*" || permission == name || permission == router_id)
+    }
+}
+
+/// A way to authenticate HTTP requests, e.g. API keys, JWT bearer tokens or token introspection.
+pub trait Authenticator: Send + Sync {
+    /// `Ok(None)` if the request carries no credentials this authenticator understands, an
+    /// error if it does but they are not valid
+    fn authenticate(&self, headers: &HeaderMap) -> ResponseFuture<Result<Option<Principal>, McpEctorsError>>;
+}
+
+/// The authenticators of a transport, tried in order. Without any authenticator every request is let in.
+#[derive(Clone, Default)]
+pub struct Authentication {
+    authenticators: Vec<Arc<dyn Authenticator>>,
+    /// Served at `/.well-known/oauth-protected-resource` and pointed at by the `401` challenge
+    protected_resource: Option<ProtectedResource>,
+    scope_map: Option<ScopeMap>,
+}
+
+impl Authentication {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_authenticator(mut self, authenticator: impl Authenticator + 'static) -> Self {
+        self.authenticators.push(Arc::new(authenticator));
+        self
+    }
+
+    /// Makes the transport an OAuth resource server described by `protected_resource`
+    pub fn with_protected_resource(mut self, protected_resource: ProtectedResource) -> Self {
+        self.protected_resource = Some(protected_resource);
+        self
+    }
+
+    /// Limits token based principals to what their scopes grant
+    pub fn with_scope_map(mut self, scope_map: ScopeMap) -> Self {
+        self.scope_map = Some(scope_map);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.authenticators.is_empty()
+    }
+
+    pub fn protected_resource(&self) -> Option<&ProtectedResource> {
+        self.protected_resource.as_ref()
+    }
+
+    /// The principal of the first authenticator that recognises the credentials of the request
+    pub async fn authenticate(&self, headers: &HeaderMap) -> Result<Principal, McpEctorsError> {
+        self.recognise(headers).await?
+            .ok_or_else(|| McpEctorsError::Unauthorized("missing or unknown credentials".to_string()))
+    }
+
+    /// Like [`Authentication::authenticate`], but `Ok(None)` if no authenticator recognises the credentials
+    async fn recognise(&self, headers: &HeaderMap) -> Result<Option<Principal>, McpEctorsError> {
+        for authenticator in &self.authenticators {
+            if let Some(mut principal) = authenticator.authenticate(headers).await? {
+                // API keys have no scopes, they are trusted as configured
+                if let Some(scope_map) = self.scope_map.as_ref().filter(|_| principal.method != AuthMethod::ApiKey) {
+                    principal.permissions = Some(scope_map.permissions(&principal.scopes));
+                }
+                return Ok(Some(principal));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The `WWW-Authenticate` header of a `401`, RFC 6750 and RFC 9728
+    pub fn challenge(&self, headers: &HeaderMap) -> String {
+        let mut parameters = vec![];
+        if let Some(protected_resource) = &self.protected_resource {
+            parameters.push(format!("resource_metadata=\"{}\"", protected_resource.metadata_url()));
+        }
+        if bearer_token(headers).is_some() {
+            parameters.push("error=\"invalid_token\"".to_string());
+        }
+        if parameters.is_empty() {
+            "Bearer".to_string()
+        } else {
+            format!("Bearer {}", parameters.join(", "))
+        }
+    }
+}
+
+/// The token of an `Authorization: Bearer <token>` header
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer ").or_else(|| value.strip_prefix("bearer ")))
+        .map(|token| token.trim())
+}
+
+/// actix-web middleware, use with `middleware::from_fn`. Authenticates every request against the
+/// `Data<Authentication>` of the app and makes the [`Principal`] available to handlers as `ReqData<Principal>`.
+///
+/// A client certificate of the connection, put in its connection data as a [`Principal`] by the
+/// TLS listener, identifies requests that carry no credentials of their own.
+pub async fn authenticate(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let certificate = req.conn_data::<Principal>().cloned();
+    let authentication = req.app_data::<Data<Authentication>>().filter(|auth| auth.is_enabled()).cloned();
+    if let Some(principal) = certificate.clone().filter(|_| authentication.is_none()) {
+        req.extensions_mut().insert(principal);
+    }
+    if let Some(authentication) = authentication {
+        match authentication.recognise(req.headers()).await.and_then(|principal| {
+            principal.or(certificate).ok_or_else(|| McpEctorsError::Unauthorized("missing or unknown credentials".to_string()))
+        }) {
+            Ok(principal) => {
+                req.extensions_mut().insert(principal);
+            }
+            Err(e @ McpEctorsError::Unavailable(_)) => {
+                // e.g. the introspection endpoint is down, the credentials may well be fine
+                warn!("Could not authenticate request to {}: {}", req.path(), e);
+                let response = HttpResponse::ServiceUnavailable().json(outgoing_error(None, e.to_error_data()));
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+            Err(e) => {
+                warn!("Rejected request to {} from {:?}: {}", req.path(), req.peer_addr(), e);
+                let response = HttpResponse::Unauthorized()
+                    .insert_header((WWW_AUTHENTICATE, authentication.challenge(req.headers())))
+                    .json(outgoing_error(None, e.to_error_data()));
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+        }
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
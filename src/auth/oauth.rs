@@ -0,0 +1,104 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::error::McpEctorsError;
+
+use super::Authentication;
+
+/// Where the protected resource metadata is served, RFC 9728
+pub const PROTECTED_RESOURCE_PATH: &str = "/.well-known/oauth-protected-resource";
+
+/// The OAuth 2.1 protected resource metadata of the server, tells clients which authorization
+/// servers issue tokens for it and which scopes it understands.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtectedResource {
+    /// The URL clients use to reach the server, e.g. `https://mcp.example.com`
+    pub resource: String,
+    pub authorization_servers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes_supported: Vec<String>,
+    pub bearer_methods_supported: Vec<String>,
+}
+
+impl ProtectedResource {
+    pub fn new(resource: &str, authorization_servers: Vec<String>) -> Self {
+        Self {
+            resource: resource.trim_end_matches('/').to_string(),
+            authorization_servers,
+            scopes_supported: vec![],
+            bearer_methods_supported: vec!["header".to_string()],
+        }
+    }
+
+    pub fn with_scopes_supported(mut self, scopes: Vec<String>) -> Self {
+        self.scopes_supported = scopes;
+        self
+    }
+
+    /// The absolute URL of the metadata, for the `resource_metadata` of a `WWW-Authenticate` challenge
+    pub fn metadata_url(&self) -> String {
+        format!("{}{}", self.resource, PROTECTED_RESOURCE_PATH)
+    }
+}
+
+/// Serves the [`ProtectedResource`] of the app's `Data<Authentication>`, `404` if the server is no
+/// OAuth resource server.
+pub async fn protected_resource_metadata(auth: Option<Data<Authentication>>) -> HttpResponse {
+    match auth.as_ref().and_then(|auth| auth.protected_resource()) {
+        Some(protected_resource) => HttpResponse::Ok().json(protected_resource),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+/// Maps OAuth scopes to what they grant: router ids (`counter`), namespaced tools, prompts or
+/// resources (`counter_increment`) or `*` for everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScopeMap {
+    scopes: HashMap<String, Vec<String>>,
+}
+
+impl ScopeMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_scope(mut self, scope: &str, grants: Vec<String>) -> Self {
+        self.scopes.entry(scope.to_string()).or_default().extend(grants);
+        self
+    }
+
+    /// Reads `scope = grant, grant` lines, blank lines and lines starting with `#` are skipped
+    pub fn from_file(path: &Path) -> Result<Self, McpEctorsError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| McpEctorsError::Internal(format!("Could not read scopes from {}: {}", path.display(), e)))?;
+        let mut scope_map = Self::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (scope, grants) = line.split_once('=')
+                .ok_or_else(|| McpEctorsError::InvalidParams(format!("Expected `scope = grant, grant` in {}", path.display())))?;
+            let grants = grants.split(',').map(str::trim).filter(|grant| !grant.is_empty()).map(str::to_string).collect();
+            scope_map = scope_map.with_scope(scope.trim(), grants);
+        }
+        Ok(scope_map)
+    }
+
+    /// The scopes this map knows, for `scopes_supported`
+    pub fn scopes(&self) -> Vec<String> {
+        self.scopes.keys().cloned().collect::<BTreeSet<_>>().into_iter().collect()
+    }
+
+    /// Everything granted by any of `scopes`, unknown scopes grant nothing
+    pub fn permissions(&self, scopes: &[String]) -> Vec<String> {
+        scopes.iter()
+            .filter_map(|scope| self.scopes.get(scope))
+            .flatten()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
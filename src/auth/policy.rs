@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::error::McpEctorsError;
+
+use super::Principal;
+
+/// What a role may use, as globs over namespaced names, e.g. `finance_*` or `system_all`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Wins over `allow`, also over the `allow` of another role of the same principal
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Which principals may use which tools, prompts and resources.
+///
+/// ```json
+/// {
+///   "default_roles": ["guest"],
+///   "principals": { "alice": ["finance"] },
+///   "roles": {
+///     "guest": { "allow": ["system_*"] },
+///     "finance": { "allow": ["finance_*", "system_all"], "deny": ["finance_delete*"] }
+///   }
+/// }
+/// ```
+///
+/// A principal has the roles listed for its subject plus the scopes of its token that name a
+/// role; sessions without any role, anonymous ones included, get `default_roles`. Whatever no role
+/// allows is denied.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Policy {
+    #[serde(default)]
+    pub default_roles: Vec<String>,
+    #[serde(default)]
+    pub principals: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub roles: HashMap<String, Rule>,
+}
+
+impl Policy {
+    pub fn from_file(path: &Path) -> Result<Self, McpEctorsError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| McpEctorsError::Internal(format!("Could not read policy from {}: {}", path.display(), e)))?;
+        let policy: Policy = serde_json::from_str(&content)
+            .map_err(|e| McpEctorsError::InvalidParams(format!("Invalid policy in {}: {}", path.display(), e)))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Every role a principal or the defaults refer to must exist, a typo should not lock anyone out silently
+    pub fn validate(&self) -> Result<(), McpEctorsError> {
+        let referenced = self.default_roles.iter().chain(self.principals.values().flatten());
+        match referenced.into_iter().find(|role| !self.roles.contains_key(*role)) {
+            Some(role) => Err(McpEctorsError::InvalidParams(format!("Unknown role {} in policy", role))),
+            None => Ok(()),
+        }
+    }
+
+    fn roles_of<'a>(&'a self, principal: Option<&'a Principal>) -> HashSet<&'a str> {
+        let mut roles: HashSet<&str> = HashSet::new();
+        if let Some(principal) = principal {
+            if let Some(assigned) = self.principals.get(&principal.subject) {
+                roles.extend(assigned.iter().map(String::as_str));
+            }
+            roles.extend(principal.scopes.iter().map(String::as_str).filter(|scope| self.roles.contains_key(*scope)));
+        }
+        if roles.is_empty() {
+            roles.extend(self.default_roles.iter().map(String::as_str));
+        }
+        roles
+    }
+
+    /// Whether `principal`, `None` for anonymous sessions, may use the namespaced `name`
+    pub fn allows(&self, principal: Option<&Principal>, name: &str) -> bool {
+        let rules: Vec<&Rule> = self.roles_of(principal).into_iter().filter_map(|role| self.roles.get(role)).collect();
+        let matches = |patterns: &Vec<String>| patterns.iter().any(|pattern| glob_match(pattern, name));
+        !rules.iter().any(|rule| matches(&rule.deny)) && rules.iter().any(|rule| matches(&rule.allow))
+    }
+}
+
+/// Matches `name` against `pattern`, where `*` stands for any run of characters and `?` for one
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // where the last `*` was and how much of the name it swallowed
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// The policy of a transport, shared by all its sessions. Without a policy everything is allowed.
+/// The policy can be replaced at any time, every request is checked against the current one.
+#[derive(Clone, Default)]
+pub struct AccessControl {
+    policy: Arc<RwLock<Option<Policy>>>,
+    /// Keeps the watcher of the policy file alive as long as the access control is in use
+    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+}
+
+impl fmt::Debug for AccessControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessControl").field("policy", &self.policy()).finish_non_exhaustive()
+    }
+}
+
+impl AccessControl {
+    pub fn new(policy: Policy) -> Self {
+        let access = Self::default();
+        access.set_policy(Some(policy));
+        access
+    }
+
+    /// Loads the policy from `path` and reloads it whenever the file changes. A reload that fails
+    /// keeps the previous policy.
+    pub fn from_file(path: &Path) -> Result<Self, McpEctorsError> {
+        let access = Self::new(Policy::from_file(path)?);
+        access.watch(path)?;
+        Ok(access)
+    }
+
+    pub fn set_policy(&self, policy: Option<Policy>) {
+        *self.policy.write().unwrap() = policy;
+    }
+
+    pub fn policy(&self) -> Option<Policy> {
+        self.policy.read().unwrap().clone()
+    }
+
+    pub fn allows(&self, principal: Option<&Principal>, name: &str) -> bool {
+        self.policy.read().unwrap().as_ref().is_none_or(|policy| policy.allows(principal, name))
+    }
+
+    /// Whether the scopes of `principal` and the policy both let it use `name`, a namespaced tool, prompt or resource
+    pub fn permits(&self, principal: Option<&Principal>, name: &str) -> bool {
+        principal.is_none_or(|principal| principal.may_use(name)) && self.allows(principal, name)
+    }
+
+    /// Switches to the policy of another file, watched from now on, or to none at all
+    pub fn set_policy_file(&self, path: Option<&Path>) -> Result<(), McpEctorsError> {
+        match path {
+            Some(path) => {
+                self.reload(path)?;
+                self.watch(path)
+            }
+            None => {
+                *self.watcher.lock().unwrap() = None;
+                self.set_policy(None);
+                Ok(())
+            }
+        }
+    }
+
+    /// Reloads the policy from `path`
+    pub fn reload(&self, path: &Path) -> Result<(), McpEctorsError> {
+        let policy = Policy::from_file(path)?;
+        self.set_policy(Some(policy));
+        info!("Reloaded access policy from {}", path.display());
+        Ok(())
+    }
+
+    fn watch(&self, path: &Path) -> Result<(), McpEctorsError> {
+        let path: PathBuf = path.canonicalize()
+            .map_err(|e| McpEctorsError::Internal(format!("Could not watch {}: {}", path.display(), e)))?;
+        // editors replace files instead of writing them, so the directory is watched
+        let directory = path.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+        let access = self.clone_without_watcher();
+        let file = path.clone();
+        let mut watcher = RecommendedWatcher::new(move |result: notify::Result<Event>| match result {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) && event.paths.iter().any(|p| p == &file) => {
+                if let Err(e) = access.reload(&file) {
+                    error!("Keeping the previous access policy: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => error!("Error watching the access policy: {:?}", e),
+        }, notify::Config::default())
+        .map_err(|e| McpEctorsError::Internal(format!("Could not watch {}: {}", path.display(), e)))?;
+        watcher.watch(&directory, RecursiveMode::NonRecursive)
+            .map_err(|e| McpEctorsError::Internal(format!("Could not watch {}: {}", directory.display(), e)))?;
+        *self.watcher.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+
+    /// The watcher holds on to this, it must not keep itself alive
+    fn clone_without_watcher(&self) -> Self {
+        Self { policy: self.policy.clone(), watcher: Arc::new(Mutex::new(None)) }
+    }
+}
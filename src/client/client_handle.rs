@@ -0,0 +1,91 @@
+use std::fmt;
+use std::time::Duration;
+
+use actix::Addr;
+use serde_json::Value;
+
+use mcp_spec::protocol::{JsonRpcMessage, JsonRpcNotification};
+
+use crate::error::McpEctorsError;
+use crate::messages::{CreateMessageRequest, JSONRPC_VERSION};
+
+use super::client_registry::{ClientRequestError, GetRoots, HasCapability, NotifyClient, RequestClient, Root};
+use super::ClientRegistryActor;
+
+/// How long a client gets to answer `sampling/createMessage`, it may ask its user for approval first
+pub const SAMPLING_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Lets a router send requests to the client session a request came from.
+#[derive(Clone)]
+pub struct ClientHandle {
+    client_id: u64,
+    registry: Addr<ClientRegistryActor>,
+}
+
+impl ClientHandle {
+    pub fn new(client_id: u64, registry: Addr<ClientRegistryActor>) -> Self {
+        Self { client_id, registry }
+    }
+
+    pub fn client_id(&self) -> u64 {
+        self.client_id
+    }
+
+    /// Whether the client declared `capability` (e.g. `sampling` or `roots`) in `initialize`
+    pub async fn has_capability(&self, capability: &str) -> bool {
+        self.registry
+            .send(HasCapability { client_id: self.client_id, capability: capability.to_string() })
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Sends a request to the client and waits for the result
+    pub async fn request(&self, method: &str, params: Option<Value>, capability: Option<&str>, timeout: Duration) -> Result<Value, ClientRequestError> {
+        self.registry
+            .send(RequestClient {
+                client_id: self.client_id,
+                method: method.to_string(),
+                params,
+                capability: capability.map(|c| c.to_string()),
+                timeout,
+            })
+            .await
+            .map_err(|_| ClientRequestError::Disconnected)?
+    }
+
+    /// The roots of the client as last reported via `roots/list`, empty if the client does not support roots
+    pub async fn roots(&self) -> Vec<Root> {
+        self.registry
+            .send(GetRoots { client_id: self.client_id })
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Sends a notification, e.g. `notifications/resources/updated`, fails if the client is gone
+    pub async fn notify(&self, method: &str, params: Option<Value>) -> Result<(), McpEctorsError> {
+        self.registry
+            .send(NotifyClient {
+                client_id: self.client_id,
+                message: JsonRpcMessage::Notification(JsonRpcNotification { jsonrpc: JSONRPC_VERSION.to_string(), method: method.to_string(), params }),
+            })
+            .await?
+    }
+
+    /// Asks the client's LLM for a completion via `sampling/createMessage`. `params` are the
+    /// request params as defined by MCP (messages, maxTokens, systemPrompt, ...), the result is the
+    /// client's CreateMessageResult. Fails with `NotSupported` if the client did not declare `sampling`.
+    pub async fn create_message(&self, params: Value) -> Result<Value, ClientRequestError> {
+        self.create_message_within(params, SAMPLING_TIMEOUT).await
+    }
+
+    /// Like [`ClientHandle::create_message`], but the client only gets `timeout` to answer
+    pub async fn create_message_within(&self, params: Value, timeout: Duration) -> Result<Value, ClientRequestError> {
+        self.request(CreateMessageRequest::METHOD, Some(params), Some("sampling"), timeout).await
+    }
+}
+
+impl fmt::Debug for ClientHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientHandle").field("client_id", &self.client_id).finish()
+    }
+}
@@ -20,12 +20,28 @@ pub struct DeregisterClient {
 
 /// Message to send a notification to a specific client
 #[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
+#[rtype(result = "Result<(), McpEctorsError>")]
 pub struct NotifyClient {
     pub client_id: u64,
     pub message: JsonRpcMessage,
 }
 
+/// Message to let a client also receive already serialised messages
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RegisterRawRecipient {
+    pub client_id: u64,
+    pub recipient: Recipient<RawClientMessage>,
+}
+
+/// Message to send an already serialised message to a specific client, e.g. a batch of responses
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct NotifyClientRaw {
+    pub client_id: u64,
+    pub message: Value,
+}
+
 /// Message to broadcast a message to all clients
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -33,6 +49,156 @@ pub struct BroadcastMessage {
     pub message: JsonRpcMessage,
 }
 
+/// Message to set the minimum log level a client receives via `notifications/message`
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetLogLevel {
+    pub client_id: u64,
+    pub level: LoggingLevel,
+}
+
+/// Message to remember a request that is being processed for a client, so it can be cancelled
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct TrackRequest {
+    pub client_id: u64,
+    pub request_id: RequestId,
+    pub cancellation: CancellationToken,
+}
+
+/// Message to forget a request once its response has been produced
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct UntrackRequest {
+    pub client_id: u64,
+    pub request_id: RequestId,
+}
+
+/// Message sent when a client cancels one of its in-flight requests via `notifications/cancelled`
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct CancelRequest {
+    pub client_id: u64,
+    pub request_id: RequestId,
+}
+
+/// Message to remember the capabilities a client declared in `initialize`
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetClientCapabilities {
+    pub client_id: u64,
+    pub capabilities: Value,
+}
+
+/// Message to check whether a client declared a capability, e.g. `sampling`
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct HasCapability {
+    pub client_id: u64,
+    pub capability: String,
+}
+
+/// Message to send a request to a client and wait for its response, e.g. `sampling/createMessage`.
+/// The request is only sent if the client declared `capability`.
+#[derive(Message)]
+#[rtype(result = "Result<Value, ClientRequestError>")]
+pub struct RequestClient {
+    pub client_id: u64,
+    pub method: String,
+    pub params: Option<Value>,
+    pub capability: Option<String>,
+    pub timeout: Duration,
+}
+
+/// Message carrying a response the client sent to one of our requests
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ClientResponse {
+    pub client_id: u64,
+    pub response: JsonRpcResponse,
+}
+
+/// Message to (re)fetch the roots of a client via `roots/list`, if it declared the `roots` capability.
+/// Answered once the roots are stored.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RefreshRoots {
+    pub client_id: u64,
+}
+
+/// Message to attach the authenticated principal to the session of a client
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetPrincipal {
+    pub client_id: u64,
+    pub principal: Principal,
+}
+
+/// Message to get the principal that opened the session of a client, `None` without authentication
+#[derive(Message)]
+#[rtype(result = "Option<Principal>")]
+pub struct GetPrincipal {
+    pub client_id: u64,
+}
+
+/// Message to hand a client a session token, the only way to POST into its session
+#[derive(Message)]
+#[rtype(result = "String")]
+pub struct OpenSession {
+    pub client_id: u64,
+    pub binding: SessionBinding,
+}
+
+/// Message to look up the client of a session token. Fails with `UnknownSession` for tokens that
+/// were never handed out, `SessionExpired` for expired ones and `Forbidden` if `binding` is not the
+/// one the session was opened with.
+#[derive(Message)]
+#[rtype(result = "Result<u64, McpEctorsError>")]
+pub struct ResolveSession {
+    pub token: String,
+    pub binding: SessionBinding,
+}
+
+/// Message to get the cached roots of a client
+#[derive(Message)]
+#[rtype(result = "Vec<Root>")]
+pub struct GetRoots {
+    pub client_id: u64,
+}
+
+/// A root the client exposes to the server, typically a `file://` directory of the user's workspace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// How long a client gets to answer `roots/list`
+pub const ROOTS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Why a request to a client did not produce a result
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientRequestError {
+    UnknownClient,
+    NotSupported(String),
+    Timeout,
+    Disconnected,
+    Client(ErrorData),
+}
+
+impl fmt::Display for ClientRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientRequestError::UnknownClient => write!(f, "unknown client"),
+            ClientRequestError::NotSupported(capability) => write!(f, "client did not declare the {} capability", capability),
+            ClientRequestError::Timeout => write!(f, "client did not answer in time"),
+            ClientRequestError::Disconnected => write!(f, "client disconnected"),
+            ClientRequestError::Client(error) => write!(f, "client error {}: {}", error.code, error.message),
+        }
+    }
+}
+
 /// Message wrapper for client communication
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -41,24 +207,85 @@ pub struct ClientRegistryMessage(pub JsonRpcMessage);
 /// Actor that manages registered clients
 pub struct ClientRegistryActor {
     clients: HashMap<u64, Recipient<ClientMessage>>,
+    raw_clients: HashMap<u64, Recipient<RawClientMessage>>,
+    log_sink: McpLogSink,
+    in_flight: HashMap<(u64, RequestId), CancellationToken>,
+    capabilities: HashMap<u64, Value>,
+    roots: HashMap<u64, Vec<Root>>,
+    principals: HashMap<u64, Principal>,
+    // requests we sent to clients, keyed by client and the id we gave the request
+    pending: HashMap<(u64, u64), oneshot::Sender<Result<Value, ClientRequestError>>>,
+    next_request_id: u64,
+    sessions: Sessions,
 }
 
 impl ClientRegistryActor {
     pub fn new() -> Self {
         Self {
             clients: HashMap::new(),
+            raw_clients: HashMap::new(),
+            log_sink: McpLogSink::new(),
+            in_flight: HashMap::new(),
+            capabilities: HashMap::new(),
+            roots: HashMap::new(),
+            principals: HashMap::new(),
+            pending: HashMap::new(),
+            next_request_id: 0,
+            sessions: Sessions::default(),
         }
     }
+
+    /// Shares the per session log levels with the `McpLoggingLayer`
+    pub fn with_log_sink(mut self, log_sink: McpLogSink) -> Self {
+        self.log_sink = log_sink;
+        self
+    }
+
+    /// How long the sessions of clients live, expired sessions are closed
+    pub fn with_session_limits(mut self, limits: SessionLimits) -> Self {
+        self.sessions = Sessions::new(limits);
+        self
+    }
+
+    fn deregister(&mut self, client_id: u64) {
+        self.clients.remove(&client_id);
+        self.raw_clients.remove(&client_id);
+        self.log_sink.remove(client_id);
+        // nobody is left to receive the responses of this session
+        self.in_flight.retain(|(id, _), cancellation| {
+            if *id == client_id {
+                cancellation.cancel();
+            }
+            *id != client_id
+        });
+        self.capabilities.remove(&client_id);
+        self.roots.remove(&client_id);
+        self.principals.remove(&client_id);
+        // dropping the senders fails the pending requests with Disconnected
+        self.pending.retain(|(id, _), _| *id != client_id);
+        self.sessions.close(client_id);
+    }
 }
 
 impl Actor for ClientRegistryActor {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(self.sessions.limits().sweep_interval(), |act, _ctx| {
+            // dropping the recipients of a client ends its SSE stream
+            for client_id in act.sessions.expire() {
+                info!("Session of client {} expired", client_id);
+                act.deregister(client_id);
+            }
+        });
+    }
 }
 
 impl Handler<RegisterClient> for ClientRegistryActor {
     type Result = u64;
 
     fn handle(&mut self, msg: RegisterClient, _ctx: &mut Self::Context) -> Self::Result {
+        // only identifies the client inside the server, clients are handed a session token
         let client_id: u64 = random();
         info!("New client {} registered", client_id);
         self.clients.insert(client_id, msg.recipient);
@@ -70,29 +297,273 @@ impl Handler<DeregisterClient> for ClientRegistryActor {
     type Result = ();
 
     fn handle(&mut self, msg: DeregisterClient, _ctx: &mut Self::Context) -> Self::Result {
-        self.clients.remove(&msg.client_id);
+        self.deregister(msg.client_id);
+    }
+}
+
+impl Handler<SetLogLevel> for ClientRegistryActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetLogLevel, _ctx: &mut Self::Context) -> Self::Result {
+        info!("Client {} set log level to {}", msg.client_id, msg.level);
+        self.log_sink.set_level(msg.client_id, msg.level);
+    }
+}
+
+impl Handler<TrackRequest> for ClientRegistryActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: TrackRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.in_flight.insert((msg.client_id, msg.request_id), msg.cancellation);
+    }
+}
+
+impl Handler<UntrackRequest> for ClientRegistryActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: UntrackRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.in_flight.remove(&(msg.client_id, msg.request_id));
+    }
+}
+
+impl Handler<CancelRequest> for ClientRegistryActor {
+    type Result = bool;
+
+    fn handle(&mut self, msg: CancelRequest, _ctx: &mut Self::Context) -> Self::Result {
+        match self.in_flight.remove(&(msg.client_id, msg.request_id.clone())) {
+            Some(cancellation) => {
+                info!("Client {} cancelled request {}", msg.client_id, msg.request_id);
+                cancellation.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Handler<SetClientCapabilities> for ClientRegistryActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetClientCapabilities, _ctx: &mut Self::Context) -> Self::Result {
+        self.capabilities.insert(msg.client_id, msg.capabilities);
+    }
+}
+
+impl Handler<HasCapability> for ClientRegistryActor {
+    type Result = bool;
+
+    fn handle(&mut self, msg: HasCapability, _ctx: &mut Self::Context) -> Self::Result {
+        self.has_capability(msg.client_id, &msg.capability)
+    }
+}
+
+impl ClientRegistryActor {
+    fn has_capability(&self, client_id: u64, capability: &str) -> bool {
+        self.capabilities.get(&client_id)
+            .and_then(|capabilities| capabilities.get(capability))
+            .is_some_and(|capability| !capability.is_null())
     }
 }
 
+impl Handler<RequestClient> for ClientRegistryActor {
+    type Result = ResponseActFuture<Self, Result<Value, ClientRequestError>>;
+
+    fn handle(&mut self, msg: RequestClient, _ctx: &mut Self::Context) -> Self::Result {
+        let recipient = match self.clients.get(&msg.client_id) {
+            Some(recipient) => recipient.clone(),
+            None => return Box::pin(wrap_future(async { Err(ClientRequestError::UnknownClient) })),
+        };
+        if let Some(capability) = msg.capability {
+            if !self.has_capability(msg.client_id, &capability) {
+                return Box::pin(wrap_future(async move { Err(ClientRequestError::NotSupported(capability)) }));
+            }
+        }
+
+        self.next_request_id += 1;
+        let key = (msg.client_id, self.next_request_id);
+        let (tx, rx) = oneshot::channel();
+        self.pending.insert(key, tx);
+        info!("Sending {} request {} to client {}", msg.method, key.1, msg.client_id);
+        recipient.do_send(ClientMessage(JsonRpcMessage::Request(JsonRpcRequest {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: Some(key.1),
+            method: msg.method,
+            params: msg.params,
+        })));
+
+        let timeout = msg.timeout;
+        Box::pin(wrap_future(async move {
+            match tokio::time::timeout(timeout, rx).await {
+                Ok(Ok(result)) => result,
+                Ok(Err(_)) => Err(ClientRequestError::Disconnected),
+                Err(_) => Err(ClientRequestError::Timeout),
+            }
+        }).map(move |result, act: &mut Self, _ctx| {
+            act.pending.remove(&key);
+            result
+        }))
+    }
+}
+
+impl Handler<RefreshRoots> for ClientRegistryActor {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: RefreshRoots, ctx: &mut Self::Context) -> Self::Result {
+        if !self.has_capability(msg.client_id, "roots") {
+            return Box::pin(fut::ready(()));
+        }
+        let client_id = msg.client_id;
+        let request = ctx.address().send(RequestClient {
+            client_id,
+            method: ListRootsRequest::METHOD.to_string(),
+            params: None,
+            capability: Some("roots".to_string()),
+            timeout: ROOTS_TIMEOUT,
+        });
+        Box::pin(wrap_future(request).map(move |result, act: &mut Self, _ctx| {
+            let result = match result {
+                Ok(result) => result,
+                Err(_) => return,
+            };
+            match result.and_then(|result| serde_json::from_value::<Vec<Root>>(result["roots"].clone())
+                .map_err(|e| ClientRequestError::Client(ErrorData { code: JSON_RPC_INVALID_PARAMS, message: e.to_string(), data: None }))) {
+                Ok(roots) => {
+                    info!("Client {} has {} roots", client_id, roots.len());
+                    if act.clients.contains_key(&client_id) {
+                        act.roots.insert(client_id, roots);
+                    }
+                }
+                Err(e) => error!("Could not list the roots of client {}: {}", client_id, e),
+            }
+        }))
+    }
+}
+
+impl Handler<SetPrincipal> for ClientRegistryActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetPrincipal, _ctx: &mut Self::Context) -> Self::Result {
+        info!("Client {} authenticated as {} via {:?}", msg.client_id, msg.principal.subject, msg.principal.method);
+        self.principals.insert(msg.client_id, msg.principal);
+    }
+}
+
+impl Handler<GetPrincipal> for ClientRegistryActor {
+    type Result = Option<Principal>;
+
+    fn handle(&mut self, msg: GetPrincipal, _ctx: &mut Self::Context) -> Self::Result {
+        self.principals.get(&msg.client_id).cloned()
+    }
+}
+
+impl Handler<OpenSession> for ClientRegistryActor {
+    type Result = String;
+
+    fn handle(&mut self, msg: OpenSession, _ctx: &mut Self::Context) -> Self::Result {
+        if let Some(principal) = &msg.binding.principal {
+            info!("Client {} authenticated as {} via {:?}", msg.client_id, principal.subject, principal.method);
+            self.principals.insert(msg.client_id, principal.clone());
+        }
+        self.sessions.open(msg.client_id, msg.binding)
+    }
+}
+
+impl Handler<ResolveSession> for ClientRegistryActor {
+    type Result = Result<u64, McpEctorsError>;
+
+    fn handle(&mut self, msg: ResolveSession, _ctx: &mut Self::Context) -> Self::Result {
+        self.sessions.resolve(&msg.token, &msg.binding)
+    }
+}
+
+impl Handler<GetRoots> for ClientRegistryActor {
+    type Result = Vec<Root>;
+
+    fn handle(&mut self, msg: GetRoots, _ctx: &mut Self::Context) -> Self::Result {
+        self.roots.get(&msg.client_id).cloned().unwrap_or_default()
+    }
+}
+
+impl Handler<ClientResponse> for ClientRegistryActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: ClientResponse, _ctx: &mut Self::Context) -> Self::Result {
+        let request_id = match msg.response.id {
+            Some(id) => id,
+            None => return,
+        };
+        match self.pending.remove(&(msg.client_id, request_id)) {
+            Some(tx) => {
+                let result = match (msg.response.error, msg.response.result) {
+                    (Some(error), _) => Err(ClientRequestError::Client(error)),
+                    (None, result) => Ok(result.unwrap_or(Value::Null)),
+                };
+                let _ = tx.send(result);
+            }
+            None => info!("Ignoring response {} from client {} to an unknown or expired request", request_id, msg.client_id),
+        }
+    }
+}
+
+use std::fmt;
+use std::time::Duration;
 use actix::{fut::wrap_future, Actor, Message, Recipient};
-use tracing::info;
+use mcp_spec::protocol::{ErrorData, JsonRpcRequest, JsonRpcResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
 
-use crate::messages::ClientMessage;
+use crate::auth::Principal;
+use crate::client::sessions::{SessionBinding, SessionLimits, Sessions};
+use crate::error::McpEctorsError;
+use crate::messages::{ClientMessage, ListRootsRequest, RawClientMessage, RequestId, JSONRPC_VERSION};
+use crate::utils::json_rpc::JSON_RPC_INVALID_PARAMS;
+use crate::utils::{LoggingLevel, McpLogSink};
 
 impl Handler<NotifyClient> for ClientRegistryActor {
-    type Result = ResponseActFuture<Self, Result<(), ()>>;
+    type Result = ResponseActFuture<Self, Result<(), McpEctorsError>>;
 
     fn handle(&mut self, msg: NotifyClient, _ctx: &mut Self::Context) -> Self::Result {
         if let Some(recipient) = self.clients.get(&msg.client_id) {
             let fut = recipient.send(ClientMessage(msg.message.clone()));
-            Box::pin(wrap_future(async move { fut.await.map_err(|_| ()) }))
+            Box::pin(wrap_future(async move { fut.await.map_err(McpEctorsError::from) }))
         } else {
-            Box::pin(wrap_future(async { Err(()) }))
+            let error = McpEctorsError::Unavailable(format!("Client {} is not connected", msg.client_id));
+            Box::pin(wrap_future(async { Err(error) }))
         }
     }
 }
 
 
+impl Handler<RegisterRawRecipient> for ClientRegistryActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: RegisterRawRecipient, _ctx: &mut Self::Context) -> Self::Result {
+        if self.clients.contains_key(&msg.client_id) {
+            self.raw_clients.insert(msg.client_id, msg.recipient);
+        }
+    }
+}
+
+impl Handler<NotifyClientRaw> for ClientRegistryActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: NotifyClientRaw, _ctx: &mut Self::Context) -> Self::Result {
+        if let Some(recipient) = self.raw_clients.get(&msg.client_id) {
+            recipient.do_send(RawClientMessage(msg.message));
+            return;
+        }
+        // clients that only take JsonRpcMessages get whatever fits in one
+        match (self.clients.get(&msg.client_id), serde_json::from_value::<JsonRpcMessage>(msg.message)) {
+            (Some(recipient), Ok(message)) => recipient.do_send(ClientMessage(message)),
+            (Some(_), Err(e)) => error!("Could not send message to client {}: {}", msg.client_id, e),
+            (None, _) => {}
+        }
+    }
+}
+
 impl Handler<BroadcastMessage> for ClientRegistryActor {
     type Result = ();
 
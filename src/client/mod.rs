@@ -1,5 +1,9 @@
+pub mod client_handle;
 pub mod client_registry;
 pub mod client_session;
+pub mod sessions;
 
+pub use client_handle::ClientHandle;
 pub use client_registry::ClientRegistryActor;
 pub use client_session::ClientSessionActor;
+pub use sessions::{SessionBinding, SessionLimits};
\ No newline at end of file
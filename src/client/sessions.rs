@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+
+use crate::auth::{AuthMethod, Principal};
+use crate::error::McpEctorsError;
+
+/// Bytes of randomness in a session token, 256 bits
+pub const SESSION_TOKEN_BYTES: usize = 32;
+
+/// How long a session may go without a POST before it expires
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How long a session lives at most, however active it is
+pub const DEFAULT_MAX_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often expired sessions are closed at most
+const MAX_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long sessions live, see [`crate::client::ClientRegistryActor::with_session_limits`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SessionLimits {
+    pub idle_timeout: Duration,
+    pub max_lifetime: Duration,
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        Self { idle_timeout: DEFAULT_IDLE_TIMEOUT, max_lifetime: DEFAULT_MAX_LIFETIME }
+    }
+}
+
+impl SessionLimits {
+    pub fn new(idle_timeout: Duration, max_lifetime: Duration) -> Self {
+        Self { idle_timeout, max_lifetime }
+    }
+
+    /// How often the registry looks for expired sessions, so they close about when they expire
+    pub(crate) fn sweep_interval(&self) -> Duration {
+        (self.idle_timeout.min(self.max_lifetime) / 2).clamp(Duration::from_millis(10), MAX_SWEEP_INTERVAL)
+    }
+}
+
+/// Who a session was handed to. Only requests of the same principal, over a connection with the
+/// same client certificate if the session was opened with one, may use it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionBinding {
+    pub principal: Option<Principal>,
+    /// SHA-256 fingerprint of the TLS client certificate
+    pub certificate: Option<String>,
+}
+
+impl SessionBinding {
+    /// `principal` authenticated the request, `connection` is the client certificate principal
+    /// the TLS connection carries, if any
+    pub fn new(principal: Option<Principal>, connection: Option<&Principal>) -> Self {
+        let certificate = connection
+            .filter(|connection| connection.method == AuthMethod::ClientCertificate)
+            .and_then(|connection| connection.claims.get("fingerprint"))
+            .and_then(|fingerprint| fingerprint.as_str())
+            .map(str::to_string);
+        Self { principal, certificate }
+    }
+
+    fn admits(&self, other: &SessionBinding) -> bool {
+        let principal = match (&self.principal, &other.principal) {
+            (None, None) => true,
+            (Some(owner), Some(caller)) => owner.same_as(caller),
+            _ => false,
+        };
+        principal && (self.certificate.is_none() || self.certificate == other.certificate)
+    }
+}
+
+/// A new session token: 256 bits from the thread-local CSPRNG, base64url without padding
+pub fn new_session_token() -> String {
+    let mut bytes = [0u8; SESSION_TOKEN_BYTES];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+struct Session {
+    client_id: u64,
+    binding: SessionBinding,
+    opened: Instant,
+    last_used: Instant,
+}
+
+/// The session tokens handed out to clients, and the ones that expired not long ago so clients
+/// learn why theirs stopped working
+#[derive(Default)]
+pub(crate) struct Sessions {
+    limits: SessionLimits,
+    open: HashMap<String, Session>,
+    // token -> why and when it expired
+    expired: HashMap<String, (&'static str, Instant)>,
+}
+
+impl Sessions {
+    pub(crate) fn new(limits: SessionLimits) -> Self {
+        Self { limits, ..Self::default() }
+    }
+
+    pub(crate) fn limits(&self) -> SessionLimits {
+        self.limits
+    }
+
+    pub(crate) fn open(&mut self, client_id: u64, binding: SessionBinding) -> String {
+        let token = new_session_token();
+        let now = Instant::now();
+        self.open.insert(token.clone(), Session { client_id, binding, opened: now, last_used: now });
+        token
+    }
+
+    /// The client of the session `token` stands for, if `binding` may use it
+    pub(crate) fn resolve(&mut self, token: &str, binding: &SessionBinding) -> Result<u64, McpEctorsError> {
+        let Some(session) = self.open.get_mut(token) else {
+            return Err(match self.expired.get(token) {
+                Some((reason, _)) => McpEctorsError::SessionExpired(reason.to_string()),
+                None => McpEctorsError::UnknownSession,
+            });
+        };
+        if let Some(reason) = expiry(session, &self.limits, Instant::now()) {
+            return Err(McpEctorsError::SessionExpired(reason.to_string()));
+        }
+        if !session.binding.admits(binding) {
+            return Err(McpEctorsError::Forbidden("the session belongs to another principal or connection".to_string()));
+        }
+        session.last_used = Instant::now();
+        Ok(session.client_id)
+    }
+
+    /// Expires the sessions past their limits and returns their clients
+    pub(crate) fn expire(&mut self) -> Vec<u64> {
+        let now = Instant::now();
+        let limits = self.limits;
+        let expired: Vec<(String, &'static str)> = self.open.iter()
+            .filter_map(|(token, session)| expiry(session, &limits, now).map(|reason| (token.clone(), reason)))
+            .collect();
+        let mut clients = vec![];
+        for (token, reason) in expired {
+            if let Some(session) = self.open.remove(&token) {
+                clients.push(session.client_id);
+            }
+            self.expired.insert(token, (reason, now));
+        }
+        // after a lifetime nobody holds on to an expired token any more
+        self.expired.retain(|_, (_, at)| now.duration_since(*at) < limits.max_lifetime);
+        clients
+    }
+
+    /// Forgets the sessions of a client that disconnected, its tokens are unknown from now on
+    pub(crate) fn close(&mut self, client_id: u64) {
+        self.open.retain(|_, session| session.client_id != client_id);
+    }
+}
+
+fn expiry(session: &Session, limits: &SessionLimits, now: Instant) -> Option<&'static str> {
+    if now.duration_since(session.opened) >= limits.max_lifetime {
+        Some("lifetime")
+    } else if now.duration_since(session.last_used) >= limits.idle_timeout {
+        Some("idle")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,681 @@
+pub mod reload;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::{warn, Level};
+
+use crate::admin::AdminServer;
+use crate::auth::{AccessControl, ApiKeyAuthenticator, Authentication, IntrospectionAuthenticator, JwtAuthenticator, ProtectedResource, ScopeMap};
+use crate::client::SessionLimits;
+use crate::error::McpEctorsError;
+use crate::limits::{Limit, PayloadLimit, PayloadLimits, RateLimits};
+use crate::router::router_registry::ROUTER_SEPERATOR;
+use crate::router::{RequestTimeouts, RouterServiceManager};
+use crate::telemetry::OtlpExporter;
+use crate::transport::sse_transport_actor::SseTransportConfig;
+use crate::transport::{ListenAddress, OriginPolicy};
+use crate::utils::LogConfig;
+
+pub use reload::{ConfigOverrides, ConfigReloader, ReloadConfig, ReloadReport};
+
+/// Prefix of the environment variables that override the configuration, e.g.
+/// `MCPECTORS_TRANSPORT__PORT=3000` sets `port` of `[transport]`
+pub const ENV_PREFIX: &str = "MCPECTORS_";
+
+/// Separates the keys of nested tables in environment variable names, single underscores are
+/// part of key names such as `log_level`
+pub const ENV_SEPARATOR: &str = "__";
+
+/// The environment variable naming the configuration file, if `--config` does not
+pub const CONFIG_FILE_VAR: &str = "MCPECTORS_CONFIG";
+
+/// What secrets are replaced with when the configuration is shown
+pub const MASK: &str = "********";
+
+/// The configuration of the server, read from a TOML file.
+///
+/// ```toml
+/// [transport]
+/// listen = ["127.0.0.1:3000", "unix:/run/mcp-ectors.sock"]
+///
+/// [logging]
+/// level = "info"
+///
+/// [routers]
+/// directories = ["./wasm", "/opt/mcp/routers"]
+/// request_timeout = 30
+///
+/// [router.counter]
+/// timeout = 5
+/// rate_limit = "10/20"
+/// scopes = ["counter:use"]
+/// aliases = ["count"]
+///
+/// [auth]
+/// api_keys = "keys.txt"
+///
+/// [rate_limits]
+/// principal = "10/20"
+/// ```
+///
+/// Values are layered: the defaults, then the file, then `MCPECTORS_*` environment variables,
+/// then the flags of `mcp-ectors start`. Durations are in seconds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub transport: TransportSettings,
+    pub logging: LoggingSettings,
+    pub routers: RoutersSettings,
+    /// keyed by router id
+    pub router: BTreeMap<String, RouterSettings>,
+    pub auth: AuthSettings,
+    /// JSON access policy, reloaded when it changes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    pub rate_limits: RateLimitSettings,
+    pub payload_limits: PayloadLimitSettings,
+    pub metrics: MetricsSettings,
+    pub telemetry: TelemetrySettings,
+    pub admin: AdminSettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransportSettings {
+    /// The port of `localhost` if there are no `listen` addresses
+    pub port: u16,
+    pub listen: Vec<String>,
+    pub allowed_origins: Vec<String>,
+    pub allowed_hosts: Vec<String>,
+    pub tls: TlsSettings,
+    pub session_idle_timeout: u64,
+    pub session_lifetime: u64,
+}
+
+impl Default for TransportSettings {
+    fn default() -> Self {
+        let sessions = SessionLimits::default();
+        Self {
+            port: 8080,
+            listen: vec![],
+            allowed_origins: vec![],
+            allowed_hosts: vec![],
+            tls: TlsSettings::default(),
+            session_idle_timeout: sessions.idle_timeout.as_secs(),
+            session_lifetime: sessions.max_lifetime.as_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ca: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSettings {
+    pub dir: String,
+    pub file: String,
+    /// trace, debug, info, warn or error
+    pub level: String,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self { dir: "logs".to_string(), file: "server.log".to_string(), level: "warn".to_string() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoutersSettings {
+    /// Directories WASM routers are loaded from, the first one is created if it does not exist
+    pub directories: Vec<String>,
+    /// Seconds a router may take to answer a request, unless it has a `timeout` of its own
+    pub request_timeout: u64,
+    /// Routers `/readyz` waits for
+    pub required: Vec<String>,
+    /// `tools/list` only lists the discovery tools of the system router, clients search for the others
+    pub lazy_tools: bool,
+}
+
+impl Default for RoutersSettings {
+    fn default() -> Self {
+        Self { directories: vec!["./wasm".to_string()], request_timeout: 60, required: vec![], lazy_tools: false }
+    }
+}
+
+/// Settings of one router, `[router.<router id>]`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RouterSettings {
+    /// Seconds the router may take to answer a request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// `RATE[/BURST]` of the requests to the router from all clients together
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_in_flight: Option<usize>,
+    /// OAuth scopes that grant the use of the router
+    pub scopes: Vec<String>,
+    /// Other router ids clients may call the router by, e.g. `count_increment` for `counter_increment`
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthSettings {
+    /// File with `name: sha256:<hex>` lines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_keys: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwt: Option<JwtSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub introspection: Option<IntrospectionSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oauth: Option<OAuthSettings>,
+    /// File with `scope = grant, grant` lines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JwtSettings {
+    pub jwks: String,
+    pub issuer: String,
+    pub audience: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IntrospectionSettings {
+    pub endpoint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OAuthSettings {
+    /// Public URL of this server
+    pub resource: String,
+    pub authorization_servers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSettings {
+    /// `RATE[/BURST]` for all clients together
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub global: Option<String>,
+    /// `RATE[/BURST]` for every principal and every anonymous session
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    /// Requests of one principal handled at the same time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_in_flight: Option<usize>,
+    /// `RATE[/BURST]` keyed by the subject of a principal, instead of `principal`
+    pub principals: BTreeMap<String, String>,
+}
+
+/// Overrides of the default [`PayloadLimits`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PayloadLimitSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub string: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub array: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue: Option<usize>,
+}
+
+/// What `/metrics` exposes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsSettings {
+    /// Counts requests by tool too, every tool adds its own series
+    pub tool_labels: bool,
+}
+
+/// Where the spans of requests are exported to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetrySettings {
+    /// Base URL of an OpenTelemetry collector taking OTLP over HTTP, spans are not exported without one
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self { otlp_endpoint: None, service_name: "mcp-ectors".to_string() }
+    }
+}
+
+/// The admin API, off without a `listen` address
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminSettings {
+    /// `host:port` or `unix:/path`, apart from the addresses of `[transport]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+    /// File with `name: sha256:<hex>` lines of the keys that may use the admin API
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_keys: Option<String>,
+    /// Where uploaded components are written to, the first router directory if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_dir: Option<String>,
+}
+
+impl PayloadLimitSettings {
+    pub fn set(&mut self, limit: PayloadLimit, max: usize) {
+        let field = match limit {
+            PayloadLimit::Body => &mut self.body,
+            PayloadLimit::Depth => &mut self.depth,
+            PayloadLimit::String => &mut self.string,
+            PayloadLimit::Array => &mut self.array,
+            PayloadLimit::Result => &mut self.result,
+            PayloadLimit::Queue => &mut self.queue,
+        };
+        *field = Some(max);
+    }
+}
+
+fn invalid(e: impl std::fmt::Display) -> McpEctorsError {
+    McpEctorsError::InvalidParams(e.to_string())
+}
+
+fn parse_limit(limit: &str, max_in_flight: Option<usize>) -> Result<Limit, McpEctorsError> {
+    let limit: Limit = limit.parse()?;
+    Ok(match max_in_flight {
+        Some(max_in_flight) => limit.with_max_in_flight(max_in_flight),
+        None => limit,
+    })
+}
+
+/// `limit` with `max_in_flight`, whichever of them is set
+fn optional_limit(limit: Option<&str>, max_in_flight: Option<usize>) -> Result<Option<Limit>, McpEctorsError> {
+    match (limit, max_in_flight) {
+        (Some(limit), max_in_flight) => parse_limit(limit, max_in_flight).map(Some),
+        (None, Some(max_in_flight)) => Ok(Some(Limit::in_flight(max_in_flight))),
+        (None, None) => Ok(None),
+    }
+}
+
+impl ServerConfig {
+    /// The defaults, overridden by `file` if there is one, overridden by the `MCPECTORS_*`
+    /// environment variables
+    pub fn load(file: Option<&Path>) -> Result<Self, McpEctorsError> {
+        let config = match file {
+            Some(file) => Self::from_file(file)?,
+            None => Self::default(),
+        };
+        config.with_env(std::env::vars())
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, McpEctorsError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| McpEctorsError::Internal(format!("Could not read configuration from {}: {}", path.display(), e)))?;
+        Self::from_toml(&content).map_err(|e| invalid(format!("Invalid configuration in {}: {}", path.display(), e)))
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, McpEctorsError> {
+        toml::from_str(content).map_err(invalid)
+    }
+
+    /// Overrides the values named by `MCPECTORS_<TABLE>__<KEY>` variables, e.g.
+    /// `MCPECTORS_LOGGING__LEVEL=debug` or `MCPECTORS_ROUTER__COUNTER__TIMEOUT=5`. Values are read as
+    /// TOML, `["a", "b"]` for lists, and as strings if they are not valid TOML. Variables naming
+    /// keys that do not exist are ignored with a warning.
+    pub fn with_env(self, vars: impl IntoIterator<Item = (String, String)>) -> Result<Self, McpEctorsError> {
+        let mut vars: Vec<(String, String)> = vars.into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX) && name != CONFIG_FILE_VAR)
+            .collect();
+        if vars.is_empty() {
+            return Ok(self);
+        }
+        vars.sort();
+        let base = toml::Table::try_from(&self).map_err(invalid)?;
+        let mut config = base.clone();
+        for (name, value) in vars {
+            let path: Vec<String> = name[ENV_PREFIX.len()..].split(ENV_SEPARATOR).map(str::to_lowercase).collect();
+            let value = toml::from_str::<toml::Table>(&format!("value = {}", value))
+                .ok()
+                .and_then(|mut table| table.remove("value"))
+                .unwrap_or(toml::Value::String(value));
+            // variables of keys this version does not know, e.g. set for another version, do not stop the server
+            let mut alone = base.clone();
+            set(&mut alone, &path, value.clone())?;
+            if let Err(e) = toml::Value::Table(alone).try_into::<ServerConfig>() {
+                if e.to_string().contains("unknown field") {
+                    warn!("Ignoring the environment variable {}: {}", name, e);
+                    continue;
+                }
+            }
+            set(&mut config, &path, value)?;
+        }
+        toml::Value::Table(config).try_into().map_err(|e| invalid(format!("Invalid {}* environment variable: {}", ENV_PREFIX, e)))
+    }
+
+    /// Checks every value, and that the files it names can be read, without starting anything
+    pub fn validate(&self) -> Result<(), McpEctorsError> {
+        self.listen_addresses()?;
+        self.log_level()?;
+        self.rate_limits()?;
+        self.payload_limits();
+        let tls = &self.transport.tls;
+        if tls.cert.is_some() != tls.key.is_some() || (tls.client_ca.is_some() && tls.cert.is_none()) {
+            return Err(invalid("TLS needs both a certificate and a key, a client CA needs both too"));
+        }
+        for router_id in self.router.keys().chain(self.router.values().flat_map(|router| router.aliases.iter())) {
+            if router_id.is_empty() || router_id.contains(ROUTER_SEPERATOR) {
+                return Err(McpEctorsError::InvalidRouterId(router_id.clone()));
+            }
+        }
+        if let Some(listen) = &self.admin.listen {
+            listen.parse::<ListenAddress>().map_err(invalid)?;
+            if self.admin.api_keys.is_none() {
+                return Err(invalid("The admin API needs api_keys"));
+            }
+        }
+        if let Some(api_keys) = &self.admin.api_keys {
+            ApiKeyAuthenticator::from_file(Path::new(api_keys))?;
+        }
+        if let Some(endpoint) = &self.telemetry.otlp_endpoint {
+            url::Url::parse(endpoint).map_err(|e| invalid(format!("Invalid OTLP endpoint {}: {}", endpoint, e)))?;
+        }
+        self.authentication()?;
+        if let Some(policy) = &self.policy {
+            crate::auth::Policy::from_file(Path::new(policy))?;
+        }
+        Ok(())
+    }
+
+    /// The configuration as TOML, with secrets and the files of keys masked
+    pub fn to_masked_toml(&self) -> Result<String, McpEctorsError> {
+        let mut config = toml::Value::try_from(self).map_err(invalid)?;
+        mask(&mut config);
+        toml::to_string_pretty(&config).map_err(invalid)
+    }
+
+    pub fn listen_addresses(&self) -> Result<Vec<ListenAddress>, McpEctorsError> {
+        self.transport.listen.iter()
+            .map(|address| address.parse::<ListenAddress>().map_err(invalid))
+            .collect()
+    }
+
+    pub fn log_level(&self) -> Result<Level, McpEctorsError> {
+        self.logging.level.parse().map_err(|_| invalid(format!("Unknown log level {}, expected trace, debug, info, warn or error", self.logging.level)))
+    }
+
+    pub fn log_config(&self) -> Result<LogConfig, McpEctorsError> {
+        Ok(LogConfig { log_dir: self.logging.dir.clone(), log_file: self.logging.file.clone(), level: self.log_level()? })
+    }
+
+    pub fn sse_transport_config(&self) -> Result<SseTransportConfig, McpEctorsError> {
+        Ok(SseTransportConfig {
+            port: self.transport.port,
+            listen: self.listen_addresses()?,
+            tls_cert: self.transport.tls.cert.clone(),
+            tls_key: self.transport.tls.key.clone(),
+            tls_client_ca: self.transport.tls.client_ca.clone(),
+            log_dir: self.logging.dir.clone(),
+            log_file: self.logging.file.clone(),
+        })
+    }
+
+    pub fn session_limits(&self) -> SessionLimits {
+        SessionLimits::new(Duration::from_secs(self.transport.session_idle_timeout), Duration::from_secs(self.transport.session_lifetime))
+    }
+
+    pub fn origin_policy(&self) -> OriginPolicy {
+        let origins = self.transport.allowed_origins.iter().fold(OriginPolicy::new(), |origins, origin| origins.with_allowed_origin(origin));
+        self.transport.allowed_hosts.iter().fold(origins, |origins, host| origins.with_allowed_host(host))
+    }
+
+    /// The default timeout and those of the routers that have their own
+    pub fn request_timeouts(&self) -> RequestTimeouts {
+        self.router.iter()
+            .filter_map(|(router_id, router)| router.timeout.map(|timeout| (router_id, timeout)))
+            .fold(RequestTimeouts::new(Duration::from_secs(self.routers.request_timeout)), |timeouts, (router_id, timeout)| {
+                timeouts.with_router_timeout(router_id, Duration::from_secs(timeout))
+            })
+    }
+
+    pub fn rate_limits(&self) -> Result<RateLimits, McpEctorsError> {
+        let settings = &self.rate_limits;
+        let mut limits = RateLimits::new();
+        if let Some(global) = &settings.global {
+            limits = limits.with_global_limit(global.parse()?);
+        }
+        if let Some(limit) = optional_limit(settings.principal.as_deref(), settings.max_in_flight)? {
+            limits = limits.with_principal_limit(limit);
+        }
+        for (subject, limit) in &settings.principals {
+            limits = limits.with_limit_for_principal(subject, parse_limit(limit, settings.max_in_flight)?);
+        }
+        for (router_id, router) in &self.router {
+            if let Some(limit) = optional_limit(router.rate_limit.as_deref(), router.max_in_flight)? {
+                limits = limits.with_router_limit(router_id, limit);
+            }
+        }
+        Ok(limits)
+    }
+
+    pub fn payload_limits(&self) -> PayloadLimits {
+        let settings = &self.payload_limits;
+        [
+            (PayloadLimit::Body, settings.body),
+            (PayloadLimit::Depth, settings.depth),
+            (PayloadLimit::String, settings.string),
+            (PayloadLimit::Array, settings.array),
+            (PayloadLimit::Result, settings.result),
+            (PayloadLimit::Queue, settings.queue),
+        ].into_iter()
+            .filter_map(|(limit, max)| max.map(|max| (limit, max)))
+            .fold(PayloadLimits::default(), |limits, (limit, max)| limits.with_limit(limit, max))
+    }
+
+    /// The scopes of the scopes file and those the routers grant themselves to, `None` without either
+    pub fn scope_map(&self) -> Result<Option<ScopeMap>, McpEctorsError> {
+        let mut scope_map = match &self.auth.scopes {
+            Some(scopes) => Some(ScopeMap::from_file(Path::new(scopes))?),
+            None => None,
+        };
+        for (router_id, router) in &self.router {
+            for scope in &router.scopes {
+                scope_map = Some(scope_map.unwrap_or_default().with_scope(scope, vec![router_id.clone()]));
+            }
+        }
+        Ok(scope_map)
+    }
+
+    /// The authenticators the configuration enables, reading the files it names
+    pub fn authentication(&self) -> Result<Authentication, McpEctorsError> {
+        let settings = &self.auth;
+        let mut auth = Authentication::new();
+        if let Some(api_keys) = &settings.api_keys {
+            auth = auth.with_authenticator(ApiKeyAuthenticator::from_file(Path::new(api_keys))?);
+        }
+        if let Some(jwt) = &settings.jwt {
+            auth = auth.with_authenticator(JwtAuthenticator::from_jwks_file(Path::new(&jwt.jwks), &jwt.issuer, &jwt.audience)?);
+        }
+        if let Some(settings) = &settings.introspection {
+            let mut introspection = IntrospectionAuthenticator::new(&settings.endpoint);
+            if let Some(client_id) = &settings.client_id {
+                introspection = introspection.with_client_credentials(client_id, settings.client_secret.as_deref());
+            }
+            if let Some(audience) = &settings.audience {
+                introspection = introspection.with_audience(audience);
+            }
+            auth = auth.with_authenticator(introspection);
+        }
+        let scope_map = self.scope_map()?;
+        if let Some(oauth) = &settings.oauth {
+            let scopes = scope_map.as_ref().map(ScopeMap::scopes).unwrap_or_default();
+            auth = auth.with_protected_resource(ProtectedResource::new(&oauth.resource, oauth.authorization_servers.clone()).with_scopes_supported(scopes));
+        }
+        if let Some(scope_map) = scope_map {
+            auth = auth.with_scope_map(scope_map);
+        }
+        Ok(auth)
+    }
+
+    pub fn access_control(&self) -> Result<AccessControl, McpEctorsError> {
+        match &self.policy {
+            Some(policy) => AccessControl::from_file(Path::new(policy)),
+            None => Ok(AccessControl::default()),
+        }
+    }
+
+    /// The dotted keys whose values differ in `other`, e.g. `transport.port` or `router.counter.timeout`
+    pub fn changes(&self, other: &ServerConfig) -> Vec<String> {
+        let (Ok(old), Ok(new)) = (toml::Value::try_from(self), toml::Value::try_from(other)) else {
+            return vec![];
+        };
+        let mut changes = vec![];
+        diff("", Some(&old), Some(&new), &mut changes);
+        changes
+    }
+
+    /// This configuration with the values of `other` for the dotted keys that `take` accepts
+    pub fn with_changes(&self, other: &ServerConfig, take: &dyn Fn(&str) -> bool) -> Result<ServerConfig, McpEctorsError> {
+        let old = toml::Value::try_from(self).map_err(invalid)?;
+        let new = toml::Value::try_from(other).map_err(invalid)?;
+        let merged = merge("", Some(&old), Some(&new), take).unwrap_or(old);
+        merged.try_into().map_err(invalid)
+    }
+
+    /// The exporter of the spans of requests, if there is an OTLP endpoint
+    pub fn otlp_exporter(&self) -> Result<Option<OtlpExporter>, McpEctorsError> {
+        self.telemetry.otlp_endpoint.as_deref()
+            .map(|endpoint| OtlpExporter::new(endpoint, &self.telemetry.service_name))
+            .transpose()
+    }
+
+    /// The admin API managing the routers of `manager`, if it has a listen address
+    pub fn admin_server(&self, manager: RouterServiceManager) -> Result<Option<AdminServer>, McpEctorsError> {
+        let settings = &self.admin;
+        let Some(listen) = &settings.listen else {
+            return Ok(None);
+        };
+        let api_keys = settings.api_keys.as_ref().ok_or_else(|| invalid("The admin API needs api_keys"))?;
+        let auth = Authentication::new().with_authenticator(ApiKeyAuthenticator::from_file(Path::new(api_keys))?);
+        let upload_dir = settings.upload_dir.clone()
+            .or_else(|| self.routers.directories.first().cloned())
+            .unwrap_or_else(|| "./wasm".to_string());
+        let address = listen.parse::<ListenAddress>().map_err(invalid)?;
+        Ok(Some(AdminServer::new(address, manager, PathBuf::from(upload_dir)).with_auth(auth)))
+    }
+
+    /// Router ids and the aliases they are also called by
+    pub fn router_aliases(&self) -> Vec<(String, String)> {
+        self.router.iter()
+            .flat_map(|(router_id, router)| router.aliases.iter().map(move |alias| (alias.clone(), router_id.clone())))
+            .collect()
+    }
+}
+
+/// Sets the value of the key `path` in `config`, creating the tables on the way
+fn set(config: &mut toml::Table, path: &[String], value: toml::Value) -> Result<(), McpEctorsError> {
+    let (key, tables) = path.split_last().expect("split yields at least one part");
+    let mut table = config;
+    for name in tables {
+        table = table.entry(name.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| invalid(format!("{} does not name a table", name)))?;
+    }
+    table.insert(key.clone(), value);
+    Ok(())
+}
+
+/// Whether the value of the key `name` is a secret or the file of one: keys, key files and key
+/// sets such as `api_keys` or `jwks`, secrets, passwords and tokens
+fn secret(name: &str) -> bool {
+    name == "key" || name == "jwks" || name.ends_with("_key") || name.ends_with("_keys")
+        || ["secret", "password", "token"].iter().any(|secret| name.contains(secret))
+}
+
+/// Replaces the strings of the [`secret`] keys in `value` with [`MASK`]
+fn mask(value: &mut toml::Value) {
+    let Some(table) = value.as_table_mut() else {
+        return;
+    };
+    for (name, value) in table.iter_mut() {
+        match value {
+            toml::Value::String(secret_value) if secret(name) => *secret_value = MASK.to_string(),
+            value => mask(value),
+        }
+    }
+}
+
+/// `old` with the leaves of `new` whose keys `take` accepts, the counterpart of [`diff`]
+fn merge(key: &str, old: Option<&toml::Value>, new: Option<&toml::Value>, take: &dyn Fn(&str) -> bool) -> Option<toml::Value> {
+    match (old, new) {
+        (Some(toml::Value::Table(_)), _) | (_, Some(toml::Value::Table(_))) => {
+            let table = |value: Option<&toml::Value>| value.and_then(toml::Value::as_table).cloned().unwrap_or_default();
+            let (old, new) = (table(old), table(new));
+            let mut merged = toml::map::Map::new();
+            for name in old.keys().chain(new.keys()) {
+                let key = if key.is_empty() { name.clone() } else { format!("{}.{}", key, name) };
+                if let Some(value) = merge(&key, old.get(name), new.get(name), take) {
+                    merged.insert(name.clone(), value);
+                }
+            }
+            Some(toml::Value::Table(merged))
+        }
+        (old, new) => if take(key) { new.cloned() } else { old.cloned() },
+    }
+}
+
+/// Collects the keys of the leaves that differ between `old` and `new`. Leaving a key out is the
+/// same as an empty list or table.
+fn diff(key: &str, old: Option<&toml::Value>, new: Option<&toml::Value>, changes: &mut Vec<String>) {
+    let empty = |value: Option<&toml::Value>| match value {
+        None => true,
+        Some(toml::Value::Array(items)) => items.is_empty(),
+        Some(toml::Value::Table(table)) => table.is_empty(),
+        Some(_) => false,
+    };
+    match (old, new) {
+        (Some(toml::Value::Table(_)), _) | (_, Some(toml::Value::Table(_))) => {
+            let table = |value: Option<&toml::Value>| value.and_then(toml::Value::as_table).cloned().unwrap_or_default();
+            let (old, new) = (table(old), table(new));
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for name in keys {
+                let key = if key.is_empty() { name.clone() } else { format!("{}.{}", key, name) };
+                diff(&key, old.get(name), new.get(name), changes);
+            }
+        }
+        (old, new) if empty(old) && empty(new) => {}
+        (old, new) if old != new => changes.push(key.to_string()),
+        _ => {}
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use actix::prelude::*;
+use actix::WeakAddr;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tracing::{debug, error, info, warn};
+
+use crate::auth::{AccessControl, Policy};
+use crate::error::McpEctorsError;
+use crate::limits::{PayloadLimiter, RateLimiter};
+use crate::router::RouterServiceManager;
+use crate::utils::LogLevelHandle;
+
+use super::ServerConfig;
+
+/// Keys whose changes only apply after a restart: the listeners, TLS and sessions of the
+/// transport, where the logs go, and the authenticators built at startup
+const RESTART_REQUIRED: [&str; 6] = ["transport", "logging.dir", "logging.file", "auth", "telemetry", "admin"];
+
+/// Applied to every configuration that is loaded, e.g. the flags of `mcp-ectors start`, so they
+/// keep overriding the file after a reload
+pub type ConfigOverrides = Arc<dyn Fn(ServerConfig) -> Result<ServerConfig, McpEctorsError> + Send + Sync>;
+
+/// What a reload changed, by dotted key such as `rate_limits.principal`
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ReloadReport {
+    pub applied: Vec<String>,
+    /// Changed, but the running server keeps the old values until it restarts
+    pub restart_required: Vec<String>,
+    /// Why applying the changes stopped, e.g. an alias of a router that does not exist
+    pub error: Option<String>,
+    /// Changed, but not applied because of `error`, the next reload tries them again
+    pub not_applied: Vec<String>,
+}
+
+/// Rereads the configuration and applies what changed
+#[derive(Message)]
+#[rtype(result = "Result<ReloadReport, McpEctorsError>")]
+pub struct ReloadConfig;
+
+/// Reloads the configuration when its file changes or the server gets `SIGHUP`, and applies the
+/// changes to the running server: the log level, the access policy, rate and payload limits,
+/// router timeouts and aliases, lazy tool listing, the router directories and the tool labels of the metrics. A configuration that does not
+/// validate, names a router directory that does not exist or a policy that does not load is not applied at all. When applying a
+/// setting still fails, the settings before it stay applied and the report lists the rest as not applied.
+pub struct ConfigReloader {
+    file: Option<PathBuf>,
+    config: ServerConfig,
+    overrides: Option<ConfigOverrides>,
+    router_manager: RouterServiceManager,
+    access: AccessControl,
+    limiter: RateLimiter,
+    payload_limits: PayloadLimiter,
+    log_level: Option<LogLevelHandle>,
+    watch: bool,
+    watcher: Option<RecommendedWatcher>,
+}
+
+/// What a reload changes, handed to the future that applies it
+#[derive(Clone)]
+struct Services {
+    router_manager: RouterServiceManager,
+    access: AccessControl,
+    limiter: RateLimiter,
+    payload_limits: PayloadLimiter,
+    log_level: Option<LogLevelHandle>,
+}
+
+impl ConfigReloader {
+    /// `config` is the running configuration, loaded from `file`
+    pub fn new(file: Option<PathBuf>, config: ServerConfig, router_manager: RouterServiceManager) -> Self {
+        Self {
+            file,
+            config,
+            overrides: None,
+            router_manager,
+            access: AccessControl::default(),
+            limiter: RateLimiter::default(),
+            payload_limits: PayloadLimiter::default(),
+            log_level: None,
+            watch: true,
+            watcher: None,
+        }
+    }
+
+    /// Whether to reload when the file changes or on `SIGHUP`, otherwise only [`ReloadConfig`] reloads
+    pub fn with_watch(mut self, watch: bool) -> Self {
+        self.watch = watch;
+        self
+    }
+
+    pub fn with_overrides(mut self, overrides: ConfigOverrides) -> Self {
+        self.overrides = Some(overrides);
+        self
+    }
+
+    /// The access control of the transport, its policy follows `policy`
+    pub fn with_access_control(mut self, access: AccessControl) -> Self {
+        self.access = access;
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    pub fn with_payload_limits(mut self, payload_limits: PayloadLimiter) -> Self {
+        self.payload_limits = payload_limits;
+        self
+    }
+
+    /// See [`crate::McpServer::log_level`]
+    pub fn with_log_level(mut self, log_level: Option<LogLevelHandle>) -> Self {
+        self.log_level = log_level;
+        self
+    }
+
+    /// The configuration as it is now, with the overrides applied and validated
+    fn load(&self) -> Result<ServerConfig, McpEctorsError> {
+        let config = ServerConfig::load(self.file.as_deref())?;
+        let config = match &self.overrides {
+            Some(overrides) => overrides(config)?,
+            None => config,
+        };
+        config.validate()?;
+        check(&self.config, &config)?;
+        Ok(config)
+    }
+
+    fn services(&self) -> Services {
+        Services {
+            router_manager: self.router_manager.clone(),
+            access: self.access.clone(),
+            limiter: self.limiter.clone(),
+            payload_limits: self.payload_limits.clone(),
+            log_level: self.log_level.clone(),
+        }
+    }
+
+    fn watch(&mut self, file: &Path, reloader: WeakAddr<Self>) -> Result<(), McpEctorsError> {
+        let file = file.canonicalize()
+            .map_err(|e| McpEctorsError::Internal(format!("Could not watch {}: {}", file.display(), e)))?;
+        // editors replace files instead of writing them, so the directory is watched
+        let directory = file.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+        let watched = file.clone();
+        let mut watcher = RecommendedWatcher::new(move |result: notify::Result<Event>| match result {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) && event.paths.iter().any(|p| p == &watched) => {
+                if let Some(reloader) = reloader.upgrade() {
+                    reloader.do_send(ReloadConfig);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => error!("Error watching the configuration: {:?}", e),
+        }, notify::Config::default())
+        .map_err(|e| McpEctorsError::Internal(format!("Could not watch {}: {}", file.display(), e)))?;
+        watcher.watch(&directory, RecursiveMode::NonRecursive)
+            .map_err(|e| McpEctorsError::Internal(format!("Could not watch {}: {}", directory.display(), e)))?;
+        self.watcher = Some(watcher);
+        Ok(())
+    }
+}
+
+impl Actor for ConfigReloader {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !self.watch {
+            return;
+        }
+        if let Some(file) = self.file.clone() {
+            if let Err(e) = self.watch(&file, ctx.address().downgrade()) {
+                error!("The configuration is not reloaded when it changes: {}", e);
+            }
+        }
+        #[cfg(unix)]
+        {
+            let reloader = ctx.address().downgrade();
+            ctx.spawn(async move {
+                let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                    Ok(hangup) => hangup,
+                    Err(e) => return error!("The configuration is not reloaded on SIGHUP: {}", e),
+                };
+                while hangup.recv().await.is_some() {
+                    info!("SIGHUP received, reloading the configuration");
+                    match reloader.upgrade() {
+                        Some(reloader) => reloader.do_send(ReloadConfig),
+                        None => break,
+                    }
+                }
+            }.into_actor(self));
+        }
+    }
+}
+
+impl Handler<ReloadConfig> for ConfigReloader {
+    type Result = AtomicResponse<Self, Result<ReloadReport, McpEctorsError>>;
+
+    fn handle(&mut self, _: ReloadConfig, _: &mut Self::Context) -> Self::Result {
+        let loaded = self.load();
+        let running = self.config.clone();
+        let services = self.services();
+        AtomicResponse::new(Box::pin(async move {
+            let config = loaded?;
+            let (live, applied) = apply(&running, &config, services).await;
+            Ok((config, live, applied))
+        }
+        .into_actor(self)
+        .map(|result: Result<_, McpEctorsError>, reloader, _| {
+            let (config, live, applied) = result.inspect_err(|e| error!("Keeping the running configuration: {}", e))?;
+            let (not_applied, restart_required) = live.changes(&config).into_iter().partition(|key| !restart_required(key));
+            let mut report = ReloadReport { applied: reloader.config.changes(&live), restart_required, ..Default::default() };
+            // only what is live, so the rest is reported again by the next reload
+            reloader.config = live;
+            if let Err(e) = applied {
+                error!("Stopped applying the configuration: {}", e);
+                if !report.applied.is_empty() {
+                    warn!("Applied before the error: {}", report.applied.join(", "));
+                }
+                warn!("Not applied: {}", not_applied.join(", "));
+                report.error = Some(e.to_string());
+                report.not_applied = not_applied;
+            }
+            if report.applied.is_empty() && report.restart_required.is_empty() {
+                debug!("The configuration did not change");
+            }
+            if !report.applied.is_empty() {
+                info!("Applied configuration changes: {}", report.applied.join(", "));
+            }
+            if !report.restart_required.is_empty() {
+                warn!("Restart the server to apply: {}", report.restart_required.join(", "));
+            }
+            Ok(report)
+        })))
+    }
+}
+
+/// Whether a change of `key` only applies after a restart, router scopes are part of the authentication
+fn restart_required(key: &str) -> bool {
+    RESTART_REQUIRED.iter().any(|prefix| key == *prefix || key.starts_with(&format!("{}.", prefix)))
+        || (key.starts_with("router.") && key.ends_with(".scopes"))
+}
+
+/// What would fail applying `config` but not its validation: router directories to load that are
+/// no directories and a new policy that does not load
+fn check(running: &ServerConfig, config: &ServerConfig) -> Result<(), McpEctorsError> {
+    for directory in config.routers.directories.iter().filter(|directory| !running.routers.directories.contains(directory)) {
+        if !Path::new(directory).is_dir() {
+            return Err(McpEctorsError::InvalidParams(format!("{} is not a directory", directory)));
+        }
+    }
+    if let Some(policy) = config.policy.as_ref().filter(|_| config.policy != running.policy) {
+        Policy::from_file(Path::new(policy))?;
+    }
+    Ok(())
+}
+
+/// Applies the changes from `running` to `config` that do not need a restart, one setting after
+/// the other. Returns the configuration that is live afterwards, also when a setting failed.
+async fn apply(running: &ServerConfig, config: &ServerConfig, services: Services) -> (ServerConfig, Result<(), McpEctorsError>) {
+    let mut live = running.clone();
+    let applied = apply_changes(running, config, services, &mut live).await;
+    (live, applied)
+}
+
+async fn apply_changes(running: &ServerConfig, config: &ServerConfig, services: Services, live: &mut ServerConfig) -> Result<(), McpEctorsError> {
+    let changes: Vec<String> = running.changes(config).into_iter().filter(|key| !restart_required(key)).collect();
+    let changed = |matches: &dyn Fn(&str) -> bool| changes.iter().any(|key| matches(key));
+    let router_setting = |key: &str, settings: &[&str]| key.starts_with("router.") && settings.iter().any(|setting| key.ends_with(&format!(".{}", setting)));
+
+    let log_level = |key: &str| key == "logging.level";
+    if changed(&log_level) {
+        match &services.log_level {
+            Some(handle) => {
+                handle.set_level(config.log_level()?)?;
+                *live = live.with_changes(config, &log_level)?;
+            }
+            None => warn!("Logging was not set up by the server, the log level stays"),
+        }
+    }
+    let policy = |key: &str| key == "policy";
+    if changed(&policy) {
+        services.access.set_policy_file(config.policy.as_deref().map(Path::new))?;
+        *live = live.with_changes(config, &policy)?;
+    }
+    let rate_limits = |key: &str| key.starts_with("rate_limits.") || router_setting(key, &["rate_limit", "max_in_flight"]);
+    if changed(&rate_limits) {
+        services.limiter.set_limits(config.rate_limits()?);
+        *live = live.with_changes(config, &rate_limits)?;
+    }
+    let payload_limits = |key: &str| key.starts_with("payload_limits.");
+    if changed(&payload_limits) {
+        services.payload_limits.set_limits(config.payload_limits());
+        *live = live.with_changes(config, &payload_limits)?;
+    }
+    let timeouts = |key: &str| key == "routers.request_timeout" || router_setting(key, &["timeout"]);
+    if changed(&timeouts) {
+        services.router_manager.set_timeouts(config.request_timeouts());
+        *live = live.with_changes(config, &timeouts)?;
+    }
+    let required = |key: &str| key == "routers.required";
+    if changed(&required) {
+        services.router_manager.health().set_required(config.routers.required.clone());
+        *live = live.with_changes(config, &required)?;
+    }
+    let lazy_tools = |key: &str| key == "routers.lazy_tools";
+    if changed(&lazy_tools) {
+        services.router_manager.set_lazy_tools(config.routers.lazy_tools);
+        *live = live.with_changes(config, &lazy_tools)?;
+    }
+    let tool_labels = |key: &str| key == "metrics.tool_labels";
+    if changed(&tool_labels) {
+        services.router_manager.metrics().set_tool_labels(config.metrics.tool_labels);
+        *live = live.with_changes(config, &tool_labels)?;
+    }
+
+    // directories and aliases are applied one by one, so `live` follows each of them
+    let mut router_manager = services.router_manager;
+    let directories = |key: &str| key == "routers.directories";
+    if changed(&directories) {
+        let running_directories: BTreeSet<&String> = running.routers.directories.iter().collect();
+        let wanted: BTreeSet<&String> = config.routers.directories.iter().collect();
+        for directory in running_directories.difference(&wanted) {
+            router_manager.unload_wasm_directory(directory).await?;
+            live.routers.directories.retain(|live_directory| live_directory != *directory);
+        }
+        for directory in wanted.difference(&running_directories) {
+            router_manager.load_wasm_directory(directory).await?;
+            live.routers.directories.push((*directory).clone());
+        }
+        *live = live.with_changes(config, &directories)?;
+    }
+    let aliases = |key: &str| router_setting(key, &["aliases"]);
+    if changed(&aliases) {
+        let running_aliases: BTreeSet<(String, String)> = running.router_aliases().into_iter().collect();
+        let wanted: BTreeSet<(String, String)> = config.router_aliases().into_iter().collect();
+        for (alias, router_id) in running_aliases.difference(&wanted) {
+            router_manager.remove_alias(alias).await?;
+            if let Some(settings) = live.router.get_mut(router_id) {
+                settings.aliases.retain(|live_alias| live_alias != alias);
+            }
+        }
+        for (alias, router_id) in wanted.difference(&running_aliases) {
+            router_manager.add_alias(alias, router_id).await?;
+            live.router.entry(router_id.clone()).or_default().aliases.push(alias.clone());
+        }
+        *live = live.with_changes(config, &aliases)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use std::time::Duration;
+
+use actix::MailboxError;
+use mcp_spec::handler::{PromptError, ResourceError};
+use mcp_spec::protocol::{ErrorData, JsonRpcError};
+use mcp_spec::ToolError;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::limits::PayloadLimit;
+use crate::messages::JSONRPC_VERSION;
+use crate::utils::json_rpc::{
+    JSON_RPC_INTERNAL_ERROR, JSON_RPC_INVALID_PARAMS, JSON_RPC_INVALID_REQUEST, JSON_RPC_METHOD_NOT_FOUND, JSON_RPC_PARSE_ERROR,
+    MCP_AUTH_ERROR, MCP_FORBIDDEN_ERROR, MCP_INVALID_ROUTER_ID, MCP_RATE_LIMITED, MCP_REQUEST_CANCELLED, MCP_RESOURCE_NOT_FOUND,
+    MCP_ROUTER_EXISTS, MCP_SERVICE_UNAVAILABLE, MCP_SESSION_EXPIRED, MCP_TIMEOUT_ERROR, MCP_UNKNOWN_SESSION,
+    MCP_BODY_TOO_LARGE, MCP_NESTING_TOO_DEEP, MCP_STRING_TOO_LONG, MCP_ARRAY_TOO_LONG, MCP_RESULT_TOO_LARGE, MCP_QUEUE_FULL,
+};
+
+/// Errors of the server and its actors. Every variant maps to exactly one JSON-RPC error code,
+/// see [`McpEctorsError::code`], so a failure keeps its cause all the way to the client.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum McpEctorsError {
+    #[error("Parse error: {0}")]
+    Parse(String),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Method not found: {0}")]
+    MethodNotFound(String),
+
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+
+    #[error("No router for {0}")]
+    RouterNotFound(String),
+
+    #[error("Tool not found: {0}")]
+    ToolNotFound(String),
+
+    #[error("Prompt not found: {0}")]
+    PromptNotFound(String),
+
+    #[error("Resource not found: {0}")]
+    ResourceNotFound(String),
+
+    #[error("Request timed out after {}ms", .0.as_millis())]
+    Timeout(Duration),
+
+    #[error("Request cancelled")]
+    Cancelled,
+
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Router {0} is already registered")]
+    RouterExists(String),
+
+    #[error("Invalid router id {0}")]
+    InvalidRouterId(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// `limit` names the limit, e.g. `principal:alice` or `tool:counter_increment`
+    #[error("Rate limit {limit} exceeded, retry after {}ms", .retry_after.as_millis())]
+    RateLimited { limit: String, retry_after: Duration },
+
+    /// The session id was never handed out, or the session is long gone
+    #[error("Unknown session")]
+    UnknownSession,
+
+    /// `reason` is `idle` or `lifetime`, the client has to open a new session either way
+    #[error("Session expired ({0})")]
+    SessionExpired(String),
+
+    /// A request body, or a line on stdio, is over [`PayloadLimit::Body`]
+    #[error("Request body of {size} bytes exceeds the limit of {max} bytes")]
+    BodyTooLarge { size: usize, max: usize },
+
+    #[error("JSON nested {size} levels deep exceeds the limit of {max} levels")]
+    NestingTooDeep { size: usize, max: usize },
+
+    /// Keys count too, see [`PayloadLimit::String`]
+    #[error("String of {size} bytes exceeds the limit of {max} bytes")]
+    StringTooLong { size: usize, max: usize },
+
+    /// Members of objects count too, see [`PayloadLimit::Array`]
+    #[error("Array or object of {size} items exceeds the limit of {max} items")]
+    ArrayTooLong { size: usize, max: usize },
+
+    #[error("Result of {size} bytes exceeds the limit of {max} bytes")]
+    ResultTooLarge { size: usize, max: usize },
+
+    /// The messages waiting to be sent to a session are over [`PayloadLimit::Queue`]
+    #[error("Outbound queue of {size} bytes exceeds the limit of {max} bytes")]
+    QueueFull { size: usize, max: usize },
+
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl McpEctorsError {
+    /// The error for `size` being over `limit`, which is `max`
+    pub fn payload_too_large(limit: PayloadLimit, size: usize, max: usize) -> Self {
+        match limit {
+            PayloadLimit::Body => McpEctorsError::BodyTooLarge { size, max },
+            PayloadLimit::Depth => McpEctorsError::NestingTooDeep { size, max },
+            PayloadLimit::String => McpEctorsError::StringTooLong { size, max },
+            PayloadLimit::Array => McpEctorsError::ArrayTooLong { size, max },
+            PayloadLimit::Result => McpEctorsError::ResultTooLarge { size, max },
+            PayloadLimit::Queue => McpEctorsError::QueueFull { size, max },
+        }
+    }
+
+    /// The payload limit the error is about, with the size and the limit
+    pub fn payload_limit(&self) -> Option<(PayloadLimit, usize, usize)> {
+        match *self {
+            McpEctorsError::BodyTooLarge { size, max } => Some((PayloadLimit::Body, size, max)),
+            McpEctorsError::NestingTooDeep { size, max } => Some((PayloadLimit::Depth, size, max)),
+            McpEctorsError::StringTooLong { size, max } => Some((PayloadLimit::String, size, max)),
+            McpEctorsError::ArrayTooLong { size, max } => Some((PayloadLimit::Array, size, max)),
+            McpEctorsError::ResultTooLarge { size, max } => Some((PayloadLimit::Result, size, max)),
+            McpEctorsError::QueueFull { size, max } => Some((PayloadLimit::Queue, size, max)),
+            _ => None,
+        }
+    }
+
+    /// The JSON-RPC error code, `-326xx` for the standard errors and `-320xx` for the server specific ones
+    pub fn code(&self) -> i32 {
+        match self {
+            McpEctorsError::Parse(_) => JSON_RPC_PARSE_ERROR,
+            McpEctorsError::InvalidRequest(_) => JSON_RPC_INVALID_REQUEST,
+            McpEctorsError::MethodNotFound(_) => JSON_RPC_METHOD_NOT_FOUND,
+            // MCP reports unknown tools, prompts and routers as invalid params
+            McpEctorsError::InvalidParams(_)
+            | McpEctorsError::RouterNotFound(_)
+            | McpEctorsError::ToolNotFound(_)
+            | McpEctorsError::PromptNotFound(_) => JSON_RPC_INVALID_PARAMS,
+            McpEctorsError::ResourceNotFound(_) => MCP_RESOURCE_NOT_FOUND,
+            McpEctorsError::Timeout(_) => MCP_TIMEOUT_ERROR,
+            McpEctorsError::Cancelled => MCP_REQUEST_CANCELLED,
+            McpEctorsError::Unavailable(_) => MCP_SERVICE_UNAVAILABLE,
+            McpEctorsError::RouterExists(_) => MCP_ROUTER_EXISTS,
+            McpEctorsError::InvalidRouterId(_) => MCP_INVALID_ROUTER_ID,
+            McpEctorsError::Unauthorized(_) => MCP_AUTH_ERROR,
+            McpEctorsError::Forbidden(_) => MCP_FORBIDDEN_ERROR,
+            McpEctorsError::RateLimited { .. } => MCP_RATE_LIMITED,
+            McpEctorsError::UnknownSession => MCP_UNKNOWN_SESSION,
+            McpEctorsError::SessionExpired(_) => MCP_SESSION_EXPIRED,
+            McpEctorsError::BodyTooLarge { .. } => MCP_BODY_TOO_LARGE,
+            McpEctorsError::NestingTooDeep { .. } => MCP_NESTING_TOO_DEEP,
+            McpEctorsError::StringTooLong { .. } => MCP_STRING_TOO_LONG,
+            McpEctorsError::ArrayTooLong { .. } => MCP_ARRAY_TOO_LONG,
+            McpEctorsError::ResultTooLarge { .. } => MCP_RESULT_TOO_LARGE,
+            McpEctorsError::QueueFull { .. } => MCP_QUEUE_FULL,
+            McpEctorsError::Internal(_) => JSON_RPC_INTERNAL_ERROR,
+        }
+    }
+
+    /// Machine readable details sent as the `data` of the error
+    pub fn data(&self) -> Option<Value> {
+        match self {
+            McpEctorsError::RouterNotFound(router) => Some(json!({ "router": router })),
+            McpEctorsError::ToolNotFound(tool) => Some(json!({ "tool": tool })),
+            McpEctorsError::PromptNotFound(prompt) => Some(json!({ "prompt": prompt })),
+            McpEctorsError::ResourceNotFound(uri) => Some(json!({ "uri": uri })),
+            McpEctorsError::MethodNotFound(method) => Some(json!({ "method": method })),
+            McpEctorsError::Timeout(timeout) => Some(json!({ "timeoutMs": timeout.as_millis() as u64 })),
+            McpEctorsError::RouterExists(router) | McpEctorsError::InvalidRouterId(router) => Some(json!({ "router": router })),
+            McpEctorsError::RateLimited { limit, retry_after } => Some(json!({ "limit": limit, "retryAfterMs": retry_after.as_millis() as u64 })),
+            McpEctorsError::SessionExpired(reason) => Some(json!({ "reason": reason })),
+            _ => self.payload_limit().map(|(limit, size, max)| json!({ "limit": limit, "size": size, "max": max })),
+        }
+    }
+
+    pub fn to_error_data(&self) -> ErrorData {
+        ErrorData { code: self.code(), message: self.to_string(), data: self.data() }
+    }
+
+    /// The error as the JSON-RPC error response to request `id`
+    pub fn to_json_rpc_error(&self, id: Option<u64>) -> JsonRpcError {
+        JsonRpcError { jsonrpc: JSONRPC_VERSION.to_string(), id, error: self.to_error_data() }
+    }
+}
+
+impl From<MailboxError> for McpEctorsError {
+    fn from(e: MailboxError) -> Self {
+        McpEctorsError::Unavailable(e.to_string())
+    }
+}
+
+impl From<PromptError> for McpEctorsError {
+    fn from(e: PromptError) -> Self {
+        match e {
+            PromptError::NotFound(prompt) => McpEctorsError::PromptNotFound(prompt),
+            PromptError::InvalidParameters(msg) => McpEctorsError::InvalidParams(msg),
+            PromptError::InternalError(msg) => McpEctorsError::Internal(msg),
+        }
+    }
+}
+
+impl From<ResourceError> for McpEctorsError {
+    fn from(e: ResourceError) -> Self {
+        match e {
+            ResourceError::NotFound(uri) => McpEctorsError::ResourceNotFound(uri),
+            ResourceError::ExecutionError(msg) => McpEctorsError::Internal(msg),
+        }
+    }
+}
+
+/// Only the tool errors MCP treats as protocol errors, execution failures belong in a
+/// `CallToolResult` with `is_error`
+impl From<ToolError> for McpEctorsError {
+    fn from(e: ToolError) -> Self {
+        match e {
+            ToolError::NotFound(tool) => McpEctorsError::ToolNotFound(tool),
+            ToolError::InvalidParameters(msg) => McpEctorsError::InvalidParams(msg),
+            ToolError::SchemaError(msg) | ToolError::ExecutionError(msg) => McpEctorsError::Internal(msg),
+            e => McpEctorsError::Internal(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_json_rpc_codes() {
+        let errors = vec![
+            McpEctorsError::Parse("x".into()),
+            McpEctorsError::InvalidRequest("x".into()),
+            McpEctorsError::MethodNotFound("x".into()),
+            McpEctorsError::InvalidParams("x".into()),
+            McpEctorsError::RouterNotFound("x".into()),
+            McpEctorsError::ResourceNotFound("x".into()),
+            McpEctorsError::Timeout(Duration::from_secs(1)),
+            McpEctorsError::Cancelled,
+            McpEctorsError::Unavailable("x".into()),
+            McpEctorsError::RouterExists("x".into()),
+            McpEctorsError::InvalidRouterId("x".into()),
+            McpEctorsError::Unauthorized("x".into()),
+            McpEctorsError::Forbidden("x".into()),
+            McpEctorsError::RateLimited { limit: "x".into(), retry_after: Duration::from_secs(1) },
+            McpEctorsError::UnknownSession,
+            McpEctorsError::SessionExpired("x".into()),
+            McpEctorsError::BodyTooLarge { size: 2, max: 1 },
+            McpEctorsError::NestingTooDeep { size: 2, max: 1 },
+            McpEctorsError::StringTooLong { size: 2, max: 1 },
+            McpEctorsError::ArrayTooLong { size: 2, max: 1 },
+            McpEctorsError::ResultTooLarge { size: 2, max: 1 },
+            McpEctorsError::QueueFull { size: 2, max: 1 },
+            McpEctorsError::Internal("x".into()),
+        ];
+        for error in errors {
+            let code = error.code();
+            assert!((-32700..=-32600).contains(&code) || (-32099..=-32000).contains(&code), "{:?} has code {}", error, code);
+        }
+    }
+
+    #[test]
+    fn test_json_rpc_error() {
+        let error = McpEctorsError::Timeout(Duration::from_millis(1500)).to_json_rpc_error(Some(7));
+        assert_eq!(error.id, Some(7));
+        assert_eq!(error.error.code, MCP_TIMEOUT_ERROR);
+        assert_eq!(error.error.message, "Request timed out after 1500ms");
+        assert_eq!(error.error.data, Some(json!({ "timeoutMs": 1500 })));
+
+        let error = McpEctorsError::from(ResourceError::NotFound("system_missing".into())).to_error_data();
+        assert_eq!(error.code, -32002);
+        assert_eq!(error.data, Some(json!({ "uri": "system_missing" })));
+    }
+}
@@ -0,0 +1,29 @@
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use serde_json::json;
+
+use super::Health;
+
+/// Answers as long as the server does, for liveness probes
+pub const LIVENESS_PATH: &str = "/healthz";
+/// Answers `503` until the server is ready, see [`Health::readiness`]
+pub const READINESS_PATH: &str = "/readyz";
+/// The state and last error of every router
+pub const ROUTER_HEALTH_PATH: &str = "/health/routers";
+
+pub async fn liveness_handler() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+/// Without a `Data<Health>` there is nothing to wait for
+pub async fn readiness_handler(health: Option<Data<Health>>) -> HttpResponse {
+    match health.map(|health| health.readiness()).unwrap_or(Ok(())) {
+        Ok(()) => HttpResponse::Ok().json(json!({ "status": "ready" })),
+        Err(problems) => HttpResponse::ServiceUnavailable().json(json!({ "status": "not ready", "problems": problems })),
+    }
+}
+
+pub async fn router_health_handler(health: Option<Data<Health>>) -> HttpResponse {
+    let routers = health.map(|health| health.routers()).unwrap_or_default();
+    HttpResponse::Ok().json(json!({ "routers": routers }))
+}
@@ -0,0 +1,5 @@
+pub mod endpoints;
+pub mod router_health;
+
+pub use endpoints::{liveness_handler, readiness_handler, router_health_handler, LIVENESS_PATH, READINESS_PATH, ROUTER_HEALTH_PATH};
+pub use router_health::{Health, RouterHealth, RouterState, RouterStatus};
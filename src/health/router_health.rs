@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use serde::Serialize;
+
+/// What a router can do right now
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouterState {
+    /// Its WASM instance is being created
+    Loading,
+    Ready,
+    /// It serves requests, but its guest trapped on the last one or its `health` export failed
+    Degraded,
+    /// It cannot serve requests, e.g. its instance could not be created or its thread stopped
+    Failed,
+}
+
+/// The state of one router and the last error it had, shared by the router and [`Health`]
+#[derive(Debug)]
+pub struct RouterHealth {
+    status: Mutex<(RouterState, Option<String>)>,
+}
+
+impl RouterHealth {
+    pub fn new(state: RouterState) -> Self {
+        Self { status: Mutex::new((state, None)) }
+    }
+
+    /// Ready, keeping the last error
+    pub fn ready(&self) {
+        self.status.lock().unwrap().0 = RouterState::Ready;
+    }
+
+    pub fn degraded(&self, error: impl Into<String>) {
+        *self.status.lock().unwrap() = (RouterState::Degraded, Some(error.into()));
+    }
+
+    pub fn failed(&self, error: impl Into<String>) {
+        *self.status.lock().unwrap() = (RouterState::Failed, Some(error.into()));
+    }
+
+    pub fn state(&self) -> RouterState {
+        self.status.lock().unwrap().0
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.status.lock().unwrap().1.clone()
+    }
+}
+
+/// Native routers are ready once they are registered
+impl Default for RouterHealth {
+    fn default() -> Self {
+        Self::new(RouterState::Ready)
+    }
+}
+
+/// A router as `/health/routers` reports it
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RouterStatus {
+    pub router_id: String,
+    pub state: RouterState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// The health of a server's routers, shared by the router service manager and the transports that
+/// serve `/readyz` and `/health/routers`. The server is ready once the initial scan of the WASM
+/// directory is done and every required router is registered and not failed.
+#[derive(Clone, Default)]
+pub struct Health {
+    routers: Arc<RwLock<BTreeMap<String, Arc<RouterHealth>>>>,
+    required: Arc<RwLock<Vec<String>>>,
+    scanned: Arc<AtomicBool>,
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks the health of `router_id`, replacing what was tracked for it before
+    pub fn add_router(&self, router_id: &str, health: Arc<RouterHealth>) {
+        self.routers.write().unwrap().insert(router_id.to_string(), health);
+    }
+
+    pub fn remove_router(&self, router_id: &str) {
+        self.routers.write().unwrap().remove(router_id);
+    }
+
+    /// The routers the server is not ready without
+    pub fn set_required(&self, router_ids: Vec<String>) {
+        *self.required.write().unwrap() = router_ids;
+    }
+
+    pub fn required(&self) -> Vec<String> {
+        self.required.read().unwrap().clone()
+    }
+
+    /// Called once the routers of every router directory are registered
+    pub fn set_scanned(&self) {
+        self.scanned.store(true, Ordering::Relaxed);
+    }
+
+    pub fn router(&self, router_id: &str) -> Option<RouterStatus> {
+        self.routers.read().unwrap().get(router_id)
+            .map(|health| RouterStatus { router_id: router_id.to_string(), state: health.state(), last_error: health.last_error() })
+    }
+
+    /// Every router, sorted by id
+    pub fn routers(&self) -> Vec<RouterStatus> {
+        self.routers.read().unwrap().iter()
+            .map(|(router_id, health)| RouterStatus { router_id: router_id.clone(), state: health.state(), last_error: health.last_error() })
+            .collect()
+    }
+
+    /// Whether the server is ready, and if not why
+    pub fn readiness(&self) -> Result<(), Vec<String>> {
+        let mut problems = vec![];
+        if !self.scanned.load(Ordering::Relaxed) {
+            problems.push("the WASM routers are still being loaded".to_string());
+        }
+        let routers = self.routers.read().unwrap();
+        for router_id in self.required.read().unwrap().iter() {
+            match routers.get(router_id).map(|health| health.state()) {
+                None => problems.push(format!("router {} is not loaded", router_id)),
+                Some(RouterState::Loading) => problems.push(format!("router {} is loading", router_id)),
+                Some(RouterState::Failed) => problems.push(format!("router {} failed", router_id)),
+                Some(RouterState::Ready | RouterState::Degraded) => {}
+            }
+        }
+        if problems.is_empty() { Ok(()) } else { Err(problems) }
+    }
+}
@@ -6,5 +6,14 @@ pub mod utils;
 pub mod mcp;
 pub mod examples;
 pub mod server_builder;
+pub mod error;
+pub mod auth;
+pub mod limits;
+pub mod config;
+pub mod metrics;
+pub mod telemetry;
+pub mod health;
+pub mod admin;
 
-pub use server_builder::McpServer;
\ No newline at end of file
+pub use server_builder::McpServer;
+pub use error::McpEctorsError;
\ No newline at end of file
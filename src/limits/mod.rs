@@ -0,0 +1,5 @@
+pub mod payload_limits;
+pub mod rate_limits;
+
+pub use payload_limits::{PayloadLimit, PayloadLimitState, PayloadLimiter, PayloadLimits};
+pub use rate_limits::{Caller, Limit, LimiterState, Permit, RateLimiter, RateLimits};
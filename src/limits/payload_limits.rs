@@ -0,0 +1,244 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::McpEctorsError;
+
+/// One of the size limits of [`PayloadLimits`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadLimit {
+    /// Bytes of a request body, or of a line on stdio
+    Body,
+    /// Nesting depth of the arrays and objects of a message
+    Depth,
+    /// Bytes of a string of a message, keys included
+    String,
+    /// Items of an array or members of an object of a message
+    Array,
+    /// Bytes of a response to a request, e.g. a tool result or a resource with a base64 blob
+    Result,
+    /// Bytes of the messages waiting to be sent to one session
+    Queue,
+}
+
+impl PayloadLimit {
+    pub const ALL: [PayloadLimit; 6] = [PayloadLimit::Body, PayloadLimit::Depth, PayloadLimit::String, PayloadLimit::Array, PayloadLimit::Result, PayloadLimit::Queue];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PayloadLimit::Body => "body",
+            PayloadLimit::Depth => "depth",
+            PayloadLimit::String => "string",
+            PayloadLimit::Array => "array",
+            PayloadLimit::Result => "result",
+            PayloadLimit::Queue => "queue",
+        }
+    }
+
+    fn index(&self) -> usize {
+        Self::ALL.iter().position(|limit| limit == self).expect("every limit is in ALL")
+    }
+}
+
+impl fmt::Display for PayloadLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for PayloadLimit {
+    type Err = McpEctorsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter()
+            .find(|limit| limit.name() == s.trim())
+            .ok_or_else(|| McpEctorsError::InvalidParams(format!("Unknown payload limit {}, expected one of body, depth, string, array, result or queue", s)))
+    }
+}
+
+/// How large the messages clients send, and the ones they get, may be
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PayloadLimits {
+    pub max_body_bytes: usize,
+    pub max_depth: usize,
+    pub max_string_bytes: usize,
+    pub max_array_length: usize,
+    pub max_result_bytes: usize,
+    pub max_queue_bytes: usize,
+}
+
+impl Default for PayloadLimits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 4 * 1024 * 1024,
+            max_depth: 64,
+            max_string_bytes: 1024 * 1024,
+            max_array_length: 10_000,
+            max_result_bytes: 16 * 1024 * 1024,
+            max_queue_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl PayloadLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limit(mut self, limit: PayloadLimit, max: usize) -> Self {
+        *self.max_mut(limit) = max;
+        self
+    }
+
+    pub fn max(&self, limit: PayloadLimit) -> usize {
+        match limit {
+            PayloadLimit::Body => self.max_body_bytes,
+            PayloadLimit::Depth => self.max_depth,
+            PayloadLimit::String => self.max_string_bytes,
+            PayloadLimit::Array => self.max_array_length,
+            PayloadLimit::Result => self.max_result_bytes,
+            PayloadLimit::Queue => self.max_queue_bytes,
+        }
+    }
+
+    fn max_mut(&mut self, limit: PayloadLimit) -> &mut usize {
+        match limit {
+            PayloadLimit::Body => &mut self.max_body_bytes,
+            PayloadLimit::Depth => &mut self.max_depth,
+            PayloadLimit::String => &mut self.max_string_bytes,
+            PayloadLimit::Array => &mut self.max_array_length,
+            PayloadLimit::Result => &mut self.max_result_bytes,
+            PayloadLimit::Queue => &mut self.max_queue_bytes,
+        }
+    }
+}
+
+/// Parses `NAME=MAX`, e.g. `body=1048576`
+pub fn parse_payload_limit(s: &str) -> Result<(PayloadLimit, usize), McpEctorsError> {
+    let invalid = || McpEctorsError::InvalidParams(format!("Expected NAME=MAX as payload limit, got {}", s));
+    let (name, max) = s.split_once('=').ok_or_else(invalid)?;
+    Ok((name.parse()?, max.trim().parse().map_err(|_| invalid())?))
+}
+
+/// A limit and how often it was hit, for the metrics
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PayloadLimitState {
+    pub limit: PayloadLimit,
+    pub max: usize,
+    pub rejected: u64,
+}
+
+/// Enforces [`PayloadLimits`] for all sessions of a transport and counts what each limit rejected.
+/// Without one the default limits apply.
+#[derive(Clone, Default)]
+pub struct PayloadLimiter {
+    limits: Arc<RwLock<PayloadLimits>>,
+    rejected: Arc<[AtomicU64; 6]>,
+}
+
+impl PayloadLimiter {
+    pub fn new(limits: PayloadLimits) -> Self {
+        Self { limits: Arc::new(RwLock::new(limits)), rejected: Arc::default() }
+    }
+
+    /// Replaces the limits, they apply to the next message
+    pub fn set_limits(&self, limits: PayloadLimits) {
+        *self.limits.write().unwrap() = limits;
+    }
+
+    pub fn limits(&self) -> PayloadLimits {
+        *self.limits.read().unwrap()
+    }
+
+    pub fn max(&self, limit: PayloadLimit) -> usize {
+        self.limits.read().unwrap().max(limit)
+    }
+
+    /// Fails, and counts the rejection, if `size` is over `limit`
+    pub fn check(&self, limit: PayloadLimit, size: usize) -> Result<(), McpEctorsError> {
+        let max = self.max(limit);
+        if size <= max {
+            return Ok(());
+        }
+        self.rejected[limit.index()].fetch_add(1, Ordering::Relaxed);
+        Err(McpEctorsError::payload_too_large(limit, size, max))
+    }
+
+    /// Checks the size and nesting depth of a raw message before it is parsed, so deeply nested
+    /// input never reaches the parser
+    pub fn check_raw(&self, bytes: &[u8]) -> Result<(), McpEctorsError> {
+        self.check(PayloadLimit::Body, bytes.len())?;
+        let max_depth = self.max(PayloadLimit::Depth);
+        let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+        for byte in bytes {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' if in_string => escaped = true,
+                b'"' => in_string = !in_string,
+                b'{' | b'[' if !in_string => {
+                    depth += 1;
+                    if depth > max_depth {
+                        return self.check(PayloadLimit::Depth, depth);
+                    }
+                }
+                b'}' | b']' if !in_string => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the strings and arrays of a parsed message
+    pub fn check_value(&self, value: &Value) -> Result<(), McpEctorsError> {
+        match value {
+            Value::String(string) => self.check(PayloadLimit::String, string.len()),
+            Value::Array(items) => {
+                self.check(PayloadLimit::Array, items.len())?;
+                items.iter().try_for_each(|item| self.check_value(item))
+            }
+            Value::Object(members) => {
+                self.check(PayloadLimit::Array, members.len())?;
+                members.iter().try_for_each(|(key, member)| {
+                    self.check(PayloadLimit::String, key.len())?;
+                    self.check_value(member)
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Every limit with how many messages it rejected
+    pub fn snapshot(&self) -> Vec<PayloadLimitState> {
+        let limits = self.limits();
+        PayloadLimit::ALL.into_iter()
+            .map(|limit| PayloadLimitState { limit, max: limits.max(limit), rejected: self.rejected[limit.index()].load(Ordering::Relaxed) })
+            .collect()
+    }
+}
+
+/// The length of `value` serialised as JSON, without holding the serialised bytes
+pub fn json_len(value: &Value) -> usize {
+    struct Counter(usize);
+
+    impl io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    // writing a Value to an infallible writer cannot fail
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
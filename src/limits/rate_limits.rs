@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::error::McpEctorsError;
+
+/// What a request over a max-in-flight cap is told to wait, there is no telling when a slot frees up
+pub const IN_FLIGHT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Below this many buckets the idle ones of anonymous sessions are kept
+const PRUNE_AT: usize = 1024;
+
+/// Who a request counts against
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Caller<'a> {
+    /// An authenticated principal, by its subject
+    Principal(&'a str),
+    /// An anonymous session, by its client id, so anonymous clients do not share one bucket
+    Session(u64),
+}
+
+/// A token bucket and/or a cap on the requests being handled at the same time
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Limit {
+    /// The requests per second the bucket refills with, `None` for no rate limit
+    pub per_second: Option<f64>,
+    /// How many requests the bucket holds, i.e. how many may come at once after a quiet period
+    pub burst: u32,
+    pub max_in_flight: Option<usize>,
+}
+
+impl Limit {
+    pub fn rate(per_second: f64, burst: u32) -> Self {
+        Self { per_second: Some(per_second), burst: burst.max(1), max_in_flight: None }
+    }
+
+    pub fn in_flight(max_in_flight: usize) -> Self {
+        Self { per_second: None, burst: 0, max_in_flight: Some(max_in_flight) }
+    }
+
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+}
+
+/// Parses `RATE` or `RATE/BURST`, e.g. `10/50` for 10 requests per second with bursts of 50.
+/// Without a burst the bucket holds one second worth of requests.
+impl FromStr for Limit {
+    type Err = McpEctorsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || McpEctorsError::InvalidParams(format!("Expected RATE or RATE/BURST as rate limit, got {}", s));
+        let (rate, burst) = match s.split_once('/') {
+            Some((rate, burst)) => (rate, Some(burst)),
+            None => (s, None),
+        };
+        let rate: f64 = rate.trim().parse().map_err(|_| invalid())?;
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err(invalid());
+        }
+        let burst = match burst {
+            Some(burst) => burst.trim().parse().map_err(|_| invalid())?,
+            None => rate.ceil() as u32,
+        };
+        Ok(Limit::rate(rate, burst))
+    }
+}
+
+/// Which limits apply to a request: the global one, the one of its principal, and those of the
+/// router and tool it goes to. A request must fit all of them.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimits {
+    pub global: Option<Limit>,
+    /// For every principal without a limit of its own and every anonymous session
+    pub principal: Option<Limit>,
+    /// keyed by the subject of the principal
+    pub principals: HashMap<String, Limit>,
+    /// keyed by router id, shared by all clients of the router
+    pub routers: HashMap<String, Limit>,
+    /// keyed by the namespaced tool name as clients see it, e.g. routerid_toolname
+    pub tools: HashMap<String, Limit>,
+}
+
+impl RateLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_global_limit(mut self, limit: Limit) -> Self {
+        self.global = Some(limit);
+        self
+    }
+
+    pub fn with_principal_limit(mut self, limit: Limit) -> Self {
+        self.principal = Some(limit);
+        self
+    }
+
+    pub fn with_limit_for_principal(mut self, subject: &str, limit: Limit) -> Self {
+        self.principals.insert(subject.to_string(), limit);
+        self
+    }
+
+    pub fn with_router_limit(mut self, router_id: &str, limit: Limit) -> Self {
+        self.routers.insert(router_id.to_string(), limit);
+        self
+    }
+
+    pub fn with_tool_limit(mut self, tool_name: &str, limit: Limit) -> Self {
+        self.tools.insert(tool_name.to_string(), limit);
+        self
+    }
+
+    /// The limits of a request, with the key of the bucket each one counts in
+    fn limits_for(&self, caller: Caller, router_id: Option<&str>, tool_name: Option<&str>) -> Vec<(String, Limit)> {
+        let mut limits = vec![];
+        if let Some(limit) = self.global {
+            limits.push(("global".to_string(), limit));
+        }
+        match caller {
+            Caller::Principal(subject) => if let Some(limit) = self.principals.get(subject).copied().or(self.principal) {
+                limits.push((format!("principal:{}", subject), limit));
+            },
+            Caller::Session(client_id) => if let Some(limit) = self.principal {
+                limits.push((format!("{}{}", SESSION_PREFIX, client_id), limit));
+            },
+        }
+        if let Some((router_id, limit)) = router_id.and_then(|router_id| self.routers.get(router_id).map(|limit| (router_id, limit))) {
+            limits.push((format!("router:{}", router_id), *limit));
+        }
+        if let Some((tool_name, limit)) = tool_name.and_then(|tool_name| self.tools.get(tool_name).map(|limit| (tool_name, limit))) {
+            limits.push((format!("tool:{}", tool_name), *limit));
+        }
+        limits
+    }
+}
+
+struct Bucket {
+    limit: Limit,
+    tokens: f64,
+    refilled: Instant,
+    in_flight: usize,
+    allowed: u64,
+    rejected: u64,
+}
+
+impl Bucket {
+    fn new(limit: Limit, now: Instant) -> Self {
+        Self { limit, tokens: limit.burst as f64, refilled: now, in_flight: 0, allowed: 0, rejected: 0 }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if let Some(per_second) = self.limit.per_second {
+            let elapsed = now.duration_since(self.refilled).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * per_second).min(self.limit.burst as f64);
+        }
+        self.refilled = now;
+    }
+
+    /// How long the request has to wait, `None` if it may go ahead
+    fn retry_after(&self) -> Option<Duration> {
+        if let Some(per_second) = self.limit.per_second.filter(|_| self.tokens < 1.0) {
+            return Some(Duration::from_secs_f64((1.0 - self.tokens) / per_second));
+        }
+        match self.limit.max_in_flight {
+            Some(max_in_flight) if self.in_flight >= max_in_flight => Some(IN_FLIGHT_RETRY_AFTER),
+            _ => None,
+        }
+    }
+}
+
+/// The state of one bucket, for the metrics
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LimiterState {
+    /// `global`, `principal:<subject>`, `router:<router id>`, `tool:<tool name>` or `session` for all
+    /// anonymous sessions together
+    pub key: String,
+    /// The requests the bucket still holds, `None` without a rate limit
+    pub tokens: Option<f64>,
+    pub in_flight: usize,
+    pub allowed: u64,
+    pub rejected: u64,
+}
+
+type Buckets = Arc<Mutex<HashMap<String, Bucket>>>;
+
+/// Prefix of the keys of the buckets of anonymous sessions
+const SESSION_PREFIX: &str = "session:";
+
+/// The key the buckets of anonymous sessions are summed up as in a [`RateLimiter::snapshot`], so
+/// the metrics get one series for them
+pub const SESSIONS: &str = "session";
+
+/// What the pruned buckets of anonymous sessions let through and rejected, so the counts of
+/// [`SESSIONS`] never go down
+#[derive(Default)]
+struct Retired {
+    allowed: AtomicU64,
+    rejected: AtomicU64,
+}
+
+/// Anonymous sessions come and go, their buckets are dropped once they are full and nothing is in
+/// flight, as a new bucket would be the same
+fn prune(buckets: &mut HashMap<String, Bucket>, now: Instant, retired: &Retired) {
+    buckets.retain(|key, bucket| {
+        if !key.starts_with(SESSION_PREFIX) {
+            return true;
+        }
+        bucket.refill(now);
+        let keep = bucket.in_flight > 0 || bucket.tokens < bucket.limit.burst as f64;
+        if !keep {
+            retired.allowed.fetch_add(bucket.allowed, Ordering::Relaxed);
+            retired.rejected.fetch_add(bucket.rejected, Ordering::Relaxed);
+        }
+        keep
+    });
+}
+
+/// Enforces [`RateLimits`] for all sessions of a transport. Without limits every request is let through.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    limits: Arc<RwLock<RateLimits>>,
+    buckets: Buckets,
+    /// How many buckets there may be before they are pruned again
+    prune_at: Arc<AtomicUsize>,
+    retired: Arc<Retired>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self { limits: Arc::new(RwLock::new(limits)), buckets: Buckets::default(), prune_at: Arc::default(), retired: Arc::default() }
+    }
+
+    /// Replaces the limits, buckets keep their state but follow their new limit right away
+    pub fn set_limits(&self, limits: RateLimits) {
+        *self.limits.write().unwrap() = limits;
+    }
+
+    pub fn limits(&self) -> RateLimits {
+        self.limits.read().unwrap().clone()
+    }
+
+    /// Takes a token from, and a slot in, every bucket the request counts in, or none of them if
+    /// one is exhausted. The slots are given back when the [`Permit`] is dropped.
+    pub fn acquire(&self, caller: Caller, router_id: Option<&str>, tool_name: Option<&str>) -> Result<Permit, McpEctorsError> {
+        let limits = self.limits.read().unwrap().limits_for(caller, router_id, tool_name);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= self.prune_at.load(Ordering::Relaxed).max(PRUNE_AT) {
+            prune(&mut buckets, now, &self.retired);
+            self.prune_at.store(buckets.len() * 2, Ordering::Relaxed);
+        }
+        for (key, limit) in &limits {
+            let bucket = buckets.entry(key.clone()).or_insert_with(|| Bucket::new(*limit, now));
+            if bucket.limit != *limit {
+                // a bucket that had no rate has not given out any of its tokens
+                bucket.tokens = match bucket.limit.per_second {
+                    Some(_) => bucket.tokens.min(limit.burst as f64),
+                    None => limit.burst as f64,
+                };
+                bucket.limit = *limit;
+            }
+            bucket.refill(now);
+            if let Some(retry_after) = bucket.retry_after() {
+                bucket.rejected += 1;
+                return Err(McpEctorsError::RateLimited { limit: key.clone(), retry_after });
+            }
+        }
+        for (key, _) in &limits {
+            let bucket = buckets.get_mut(key).expect("bucket was created above");
+            // a limit of in-flight requests only has no tokens to take
+            if bucket.limit.per_second.is_some() {
+                bucket.tokens -= 1.0;
+            }
+            bucket.in_flight += 1;
+            bucket.allowed += 1;
+        }
+        Ok(Permit { buckets: self.buckets.clone(), keys: limits.into_iter().map(|(key, _)| key).collect() })
+    }
+
+    /// The state of every bucket, sorted by key. The buckets of anonymous sessions are summed up
+    /// as [`SESSIONS`], without tokens.
+    pub fn snapshot(&self) -> Vec<LimiterState> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let mut sessions = LimiterState {
+            key: SESSIONS.to_string(),
+            tokens: None,
+            in_flight: 0,
+            allowed: self.retired.allowed.load(Ordering::Relaxed),
+            rejected: self.retired.rejected.load(Ordering::Relaxed),
+        };
+        let mut any_session = sessions.allowed + sessions.rejected > 0;
+        let mut states = vec![];
+        for (key, bucket) in buckets.iter_mut() {
+            bucket.refill(now);
+            if key.starts_with(SESSION_PREFIX) {
+                sessions.in_flight += bucket.in_flight;
+                sessions.allowed += bucket.allowed;
+                sessions.rejected += bucket.rejected;
+                any_session = true;
+                continue;
+            }
+            states.push(LimiterState {
+                key: key.clone(),
+                tokens: bucket.limit.per_second.map(|_| bucket.tokens),
+                in_flight: bucket.in_flight,
+                allowed: bucket.allowed,
+                rejected: bucket.rejected,
+            });
+        }
+        if any_session {
+            states.push(sessions);
+        }
+        states.sort_by(|a, b| a.key.cmp(&b.key));
+        states
+    }
+}
+
+/// The slots a request holds in its buckets while it is being handled
+pub struct Permit {
+    buckets: Buckets,
+    keys: Vec<String>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut buckets = self.buckets.lock().unwrap();
+        for key in &self.keys {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.in_flight = bucket.in_flight.saturating_sub(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_buckets(limiter: &RateLimiter) -> usize {
+        limiter.buckets.lock().unwrap().keys().filter(|key| key.starts_with(SESSION_PREFIX)).count()
+    }
+
+    #[test]
+    fn test_idle_session_buckets_are_dropped() {
+        let limiter = RateLimiter::new(RateLimits::new().with_principal_limit(Limit::rate(1e12, 1)));
+        let busy = limiter.acquire(Caller::Session(0), None, None).unwrap();
+        for client_id in 1..2000 {
+            drop(limiter.acquire(Caller::Session(client_id), None, None).unwrap());
+        }
+        assert!(session_buckets(&limiter) < 2000);
+        // a request in flight keeps its bucket
+        assert!(limiter.buckets.lock().unwrap().contains_key("session:0"));
+        // the pruned buckets still count
+        let snapshot = limiter.snapshot();
+        assert_eq!((snapshot[0].key.as_str(), snapshot[0].allowed, snapshot[0].in_flight), (SESSIONS, 2000, 1));
+        drop(busy);
+    }
+
+    #[test]
+    fn test_idle_session_buckets_of_in_flight_limits_are_dropped() {
+        let limiter = RateLimiter::new(RateLimits::new().with_principal_limit(Limit::in_flight(1)));
+        for client_id in 0..2000 {
+            drop(limiter.acquire(Caller::Session(client_id), None, None).unwrap());
+        }
+        assert!(session_buckets(&limiter) < 2000);
+
+        // without a rate no tokens are taken, a rate set later starts from a full bucket
+        limiter.set_limits(RateLimits::new().with_principal_limit(Limit::rate(0.001, 2)));
+        drop(limiter.acquire(Caller::Session(1999), None, None).unwrap());
+        assert!(limiter.acquire(Caller::Session(1999), None, None).is_ok());
+    }
+}
@@ -1,22 +1,19 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use clap::{Arg, Command};
+use actix::Actor;
+use clap::{Arg, ArgMatches, Command};
+use mcp_ectors::auth::ApiKeyAuthenticator;
+use mcp_ectors::config::{ConfigOverrides, ConfigReloader, IntrospectionSettings, JwtSettings, OAuthSettings, ServerConfig, CONFIG_FILE_VAR};
+use mcp_ectors::limits::payload_limits::parse_payload_limit;
+use mcp_ectors::limits::{PayloadLimiter, RateLimiter};
 use mcp_ectors::router::RouterServiceManager;
 use mcp_ectors::server_builder::VERSION;
 use mcp_ectors::transport::transport_config::Config;
-use mcp_ectors::utils::LogConfig;
-use mcp_ectors::McpServer;
+use mcp_ectors::{McpEctorsError, McpServer};
 use tracing::info;
 use tokio::signal;
-use tracing::Level;
-use mcp_ectors::transport::sse_transport_actor::SseTransportConfig;
-
-const LOGS_DIR: &str = "logs";
-const LOGS_FILE: &str = "server.log";
-const LOGS_LEVEL: &str = "warn";
-const PORT: &str = "8080";
-const WASM_DIR: &str = "./wasm";
 
 #[actix::main]
 async fn main() {
@@ -27,36 +24,184 @@ async fn main() {
         .subcommand_required(false)
         .subcommand(
             Command::new("start")
+                .arg(config_arg())
                 .arg(Arg::new("log_dir")
                     .long("log_dir")
-                    .default_value(LOGS_DIR)
-                    .help("Directory for log files"))
+                    .help("Directory for log files [default: logs]"))
                 .arg(Arg::new("log_file")
                     .long("log_file")
-                    .default_value(LOGS_FILE)
-                    .help("Log file name"))
+                    .help("Log file name [default: server.log]"))
                 .arg(Arg::new("log_level")
                     .long("log_level")
-                    .default_value(LOGS_LEVEL)
-                    .help("Log level can be trace, debug, info, warn, error with warn being default"))
+                    .help("Log level can be trace, debug, info, warn, error [default: warn]"))
                 .arg(Arg::new("port")
                     .long("port")
-                    .default_value(PORT)
-                    .help("Port for the server"))
+                    .value_parser(clap::value_parser!(u16))
+                    .help("Port for the server, on localhost only unless there are --listen addresses [default: 8080]"))
+                .arg(Arg::new("listen")
+                    .long("listen")
+                    .value_name("ADDRESS")
+                    .action(clap::ArgAction::Append)
+                    .help("Address to listen on instead of localhost, e.g. 0.0.0.0:3000, [::]:3000 or unix:/run/mcp-ectors.sock, can be repeated"))
+                .arg(Arg::new("allowed_origin")
+                    .long("allowed_origin")
+                    .value_name("ORIGIN")
+                    .action(clap::ArgAction::Append)
+                    .help("Origin of a web page that may call the server, e.g. https://agent.example.com or * for any, can be repeated"))
+                .arg(Arg::new("allowed_host")
+                    .long("allowed_host")
+                    .value_name("HOST")
+                    .action(clap::ArgAction::Append)
+                    .help("Host name clients may address the server as besides localhost and the listen addresses, or * for any, can be repeated"))
                 .arg(Arg::new("wasm_path")
                     .long("wasm_path")
-                    .default_value(WASM_DIR)
-                    .help("Path to WASM files"))
+                    .action(clap::ArgAction::Append)
+                    .help("Path to WASM files, replaces the directories of the configuration, can be repeated [default: ./wasm]"))
+                .arg(Arg::new("require_router")
+                    .long("require_router")
+                    .value_name("ROUTER_ID")
+                    .action(clap::ArgAction::Append)
+                    .help("Router the server is not ready without, see /readyz, can be repeated"))
                 .arg(Arg::new("tls_cert")
                     .long("tls_cert")
                     .value_name("CERT")
                     .value_parser(clap::value_parser!(String)) // Set value parser
-                    .help("TLS certificate file"))
+                    .requires("tls_key")
+                    .help("TLS certificate file, PEM, serves HTTPS and is reloaded when it changes"))
                 .arg(Arg::new("tls_key")
                     .long("tls_key")
                     .value_name("KEY")
                     .value_parser(clap::value_parser!(String)) // Set value parser
-                    .help("TLS key file"))
+                    .requires("tls_cert")
+                    .help("TLS key file, PEM"))
+                .arg(Arg::new("tls_client_ca")
+                    .long("tls_client_ca")
+                    .value_name("CA")
+                    .requires("tls_cert")
+                    .help("PEM bundle of CAs, clients must present a certificate issued by one of them (mTLS)"))
+                .arg(Arg::new("request_timeout")
+                    .long("request_timeout")
+                    .value_name("SECONDS")
+                    .value_parser(clap::value_parser!(u64))
+                    .help("Seconds a router may take to answer a request before it is aborted [default: 60]"))
+                .arg(Arg::new("session_idle_timeout")
+                    .long("session_idle_timeout")
+                    .value_name("SECONDS")
+                    .value_parser(clap::value_parser!(u64))
+                    .help("Seconds a session may go without a request before it expires [default: 1800]"))
+                .arg(Arg::new("session_lifetime")
+                    .long("session_lifetime")
+                    .value_name("SECONDS")
+                    .value_parser(clap::value_parser!(u64))
+                    .help("Seconds a session lives at most [default: 86400]"))
+                .arg(Arg::new("api_keys")
+                    .long("api_keys")
+                    .value_name("FILE")
+                    .help("File with `name: sha256:<hex>` lines, enables API key authentication"))
+                .arg(Arg::new("jwks")
+                    .long("jwks")
+                    .value_name("FILE")
+                    .requires_all(["jwt_issuer", "jwt_audience"])
+                    .help("JWKS file with the keys JWT bearer tokens must be signed with, enables JWT authentication"))
+                .arg(Arg::new("jwt_issuer")
+                    .long("jwt_issuer")
+                    .help("The issuer (iss) JWT bearer tokens must have"))
+                .arg(Arg::new("jwt_audience")
+                    .long("jwt_audience")
+                    .help("The audience (aud) JWT bearer tokens must be for"))
+                .arg(Arg::new("introspection_endpoint")
+                    .long("introspection_endpoint")
+                    .value_name("URL")
+                    .conflicts_with("jwks")
+                    .help("Token introspection endpoint (RFC 7662) of the authorization server, enables opaque bearer tokens"))
+                .arg(Arg::new("introspection_client_id")
+                    .long("introspection_client_id")
+                    .requires("introspection_endpoint")
+                    .help("Client id of this server at the introspection endpoint"))
+                .arg(Arg::new("introspection_client_secret")
+                    .long("introspection_client_secret")
+                    .requires("introspection_client_id")
+                    .help("Client secret of this server at the introspection endpoint"))
+                .arg(Arg::new("introspection_audience")
+                    .long("introspection_audience")
+                    .requires("introspection_endpoint")
+                    .help("The audience (aud) introspected tokens must be for"))
+                .arg(Arg::new("oauth_resource")
+                    .long("oauth_resource")
+                    .value_name("URL")
+                    .requires("authorization_server")
+                    .help("Public URL of this server, enables /.well-known/oauth-protected-resource"))
+                .arg(Arg::new("authorization_server")
+                    .long("authorization_server")
+                    .value_name("URL")
+                    .action(clap::ArgAction::Append)
+                    .requires("oauth_resource")
+                    .help("Authorization server that issues tokens for this server, can be repeated"))
+                .arg(Arg::new("scopes")
+                    .long("scopes")
+                    .value_name("FILE")
+                    .help("File with `scope = router, router_tool` lines, limits token holders to what their scopes grant"))
+                .arg(Arg::new("policy")
+                    .long("policy")
+                    .value_name("FILE")
+                    .help("JSON access policy with allow/deny rules per role, reloaded when the file changes"))
+                .arg(Arg::new("rate_limit")
+                    .long("rate_limit")
+                    .value_name("RATE[/BURST]")
+                    .help("Requests per second the server accepts from all clients together"))
+                .arg(Arg::new("principal_rate_limit")
+                    .long("principal_rate_limit")
+                    .value_name("RATE[/BURST]")
+                    .help("Requests per second the server accepts from one principal, or from one anonymous session"))
+                .arg(Arg::new("max_in_flight")
+                    .long("max_in_flight")
+                    .value_parser(clap::value_parser!(usize))
+                    .help("Requests of one principal the server handles at the same time"))
+                .arg(Arg::new("payload_limit")
+                    .long("payload_limit")
+                    .value_name("NAME=MAX")
+                    .action(clap::ArgAction::Append)
+                    .help("Sets a payload limit, can be repeated: body, string, result or queue bytes, depth levels or array items"))
+                .arg(Arg::new("lazy_tools")
+                    .long("lazy_tools")
+                    .action(clap::ArgAction::SetTrue)
+                    .help("Lists only the tool discovery tools of the system router in tools/list"))
+                .arg(Arg::new("metrics_tool_labels")
+                    .long("metrics_tool_labels")
+                    .action(clap::ArgAction::SetTrue)
+                    .help("Counts requests by tool too in /metrics"))
+                .arg(Arg::new("otlp_endpoint")
+                    .long("otlp_endpoint")
+                    .value_name("URL")
+                    .help("OpenTelemetry collector the spans of requests are exported to with OTLP over HTTP, e.g. http://localhost:4318"))
+                .arg(Arg::new("service_name")
+                    .long("service_name")
+                    .help("service.name of the exported spans"))
+                .arg(Arg::new("admin_listen")
+                    .long("admin_listen")
+                    .value_name("ADDRESS")
+                    .requires("admin_api_keys")
+                    .help("Address of the admin API to add, replace and remove routers on, e.g. 127.0.0.1:9090 or unix:/run/mcp-ectors-admin.sock"))
+                .arg(Arg::new("admin_api_keys")
+                    .long("admin_api_keys")
+                    .value_name("FILE")
+                    .help("File with `name: sha256:<hex>` lines of the keys that may use the admin API"))
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Work with the configuration file")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("check")
+                        .about("Validate the configuration and print the effective configuration with secrets masked")
+                        .arg(config_arg()),
+                ),
+        )
+        .subcommand(
+            Command::new("hash_key")
+                .about("Print the line to add to the API keys file for an API key")
+                .arg(Arg::new("name").required(true))
+                .arg(Arg::new("key").required(true)),
         )
         .subcommand(
             Command::new("login")
@@ -80,19 +225,32 @@ async fn main() {
 
     match matches.subcommand() {
         None => {
-            start_server(LOGS_DIR.to_string(), LOGS_FILE.to_string(), LOGS_LEVEL.to_string(), WASM_DIR.to_string(), PORT.parse().unwrap(), None, None).await;
+            let file = config_file(None);
+            let config = ServerConfig::load(file.as_deref());
+            start_server(exit_if_invalid(config), file, None).await;
         },
         Some(("start", sub_m)) => {
-            
-            let log_dir = sub_m.get_one::<String>("log_dir").unwrap().to_string();
-            let log_file = sub_m.get_one::<String>("log_file").unwrap().to_string();
-            let log_level = sub_m.get_one::<String>("log_level").unwrap().to_string();
-            let port = sub_m.get_one::<String>("port").unwrap().parse::<u16>().unwrap();
-            let wasm_path = sub_m.get_one::<String>("wasm_path").unwrap().to_string();
-            let tls_cert = sub_m.get_one::<String>("tls_cert").map(|s| s.to_string());
-            let tls_key = sub_m.get_one::<String>("tls_key").map(|s| s.to_string());
-
-            start_server(log_dir, log_file, log_level, wasm_path, port, tls_cert, tls_key).await;
+            let config = load_config(sub_m).and_then(|config| with_flags(config, sub_m));
+            let flags = sub_m.clone();
+            let overrides: ConfigOverrides = Arc::new(move |config| with_flags(config, &flags));
+            start_server(exit_if_invalid(config), config_file(sub_m.get_one::<String>("config")), Some(overrides)).await;
+        }
+        Some(("config", sub_m)) => {
+            if let Some(("check", sub_m)) = sub_m.subcommand() {
+                let config = exit_if_invalid(load_config(sub_m));
+                match config.to_masked_toml() {
+                    Ok(toml) => print!("{}", toml),
+                    Err(e) => {
+                        eprintln!("Invalid configuration: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        Some(("hash_key", sub_m)) => {
+            let name = sub_m.get_one::<String>("name").unwrap();
+            let key = sub_m.get_one::<String>("key").unwrap();
+            println!("{}: {}", name, ApiKeyAuthenticator::hash_key(key));
         }
         Some(("login", _)) => {
             // Implement OAuth login flow here
@@ -117,48 +275,190 @@ async fn main() {
     }
 }
 
-async fn start_server(log_dir: String, log_file: String, log_level: String, wasm_path: String, port: u16, tls_cert: Option<String>, tls_key: Option<String>) {
-    let level = match log_level.to_lowercase().as_str() {
-        "info" => Level::INFO,
-        "debug" => Level::DEBUG,
-        "warn" => Level::WARN,
-        "error" => Level::ERROR,
-        "trace" => Level::TRACE,
-        _ => Level::WARN,
-    };
-    let log_config = LogConfig {
-        log_dir,
-        log_file,
-        level,
-    };
+fn config_arg() -> Arg {
+    Arg::new("config")
+        .long("config")
+        .value_name("FILE")
+        .help("TOML configuration file [default: $MCPECTORS_CONFIG], MCPECTORS_* environment variables and the flags override its values")
+}
+
+/// The configuration that is valid, or why it is not before exiting
+fn exit_if_invalid(config: Result<ServerConfig, McpEctorsError>) -> ServerConfig {
+    match config.and_then(|config| config.validate().map(|_| config)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The `--config` file, or the one `MCPECTORS_CONFIG` names
+fn config_file(flag: Option<&String>) -> Option<PathBuf> {
+    flag.map(PathBuf::from).or_else(|| std::env::var_os(CONFIG_FILE_VAR).map(PathBuf::from))
+}
+
+/// The defaults, overridden by the configuration file and the environment
+fn load_config(sub_m: &ArgMatches) -> Result<ServerConfig, McpEctorsError> {
+    ServerConfig::load(config_file(sub_m.get_one::<String>("config")).as_deref())
+}
+
+/// `config` overridden by the flags given to `start`
+fn with_flags(mut config: ServerConfig, sub_m: &ArgMatches) -> Result<ServerConfig, McpEctorsError> {
+    let string = |name: &str| sub_m.get_one::<String>(name).cloned();
+    let strings = |name: &str| sub_m.get_many::<String>(name).map(|values| values.cloned().collect::<Vec<String>>());
+
+    let logging = &mut config.logging;
+    logging.dir = string("log_dir").unwrap_or(logging.dir.clone());
+    logging.file = string("log_file").unwrap_or(logging.file.clone());
+    logging.level = string("log_level").unwrap_or(logging.level.clone());
+
+    let transport = &mut config.transport;
+    transport.port = sub_m.get_one::<u16>("port").copied().unwrap_or(transport.port);
+    transport.listen = strings("listen").unwrap_or(transport.listen.clone());
+    transport.allowed_origins = strings("allowed_origin").unwrap_or(transport.allowed_origins.clone());
+    transport.allowed_hosts = strings("allowed_host").unwrap_or(transport.allowed_hosts.clone());
+    if let Some(cert) = string("tls_cert") {
+        transport.tls.cert = Some(cert);
+        transport.tls.key = string("tls_key");
+    }
+    if let Some(client_ca) = string("tls_client_ca") {
+        transport.tls.client_ca = Some(client_ca);
+    }
+    transport.session_idle_timeout = sub_m.get_one::<u64>("session_idle_timeout").copied().unwrap_or(transport.session_idle_timeout);
+    transport.session_lifetime = sub_m.get_one::<u64>("session_lifetime").copied().unwrap_or(transport.session_lifetime);
+
+    config.routers.directories = strings("wasm_path").unwrap_or(config.routers.directories.clone());
+    config.routers.request_timeout = sub_m.get_one::<u64>("request_timeout").copied().unwrap_or(config.routers.request_timeout);
+    config.routers.required = strings("require_router").unwrap_or(config.routers.required.clone());
+    if sub_m.get_flag("lazy_tools") {
+        config.routers.lazy_tools = true;
+    }
+
+    let auth = &mut config.auth;
+    if let Some(api_keys) = string("api_keys") {
+        auth.api_keys = Some(api_keys);
+    }
+    if let Some(jwks) = string("jwks") {
+        auth.jwt = Some(JwtSettings { jwks, issuer: string("jwt_issuer").unwrap(), audience: string("jwt_audience").unwrap() });
+    }
+    if let Some(endpoint) = string("introspection_endpoint") {
+        auth.introspection = Some(IntrospectionSettings {
+            endpoint,
+            client_id: string("introspection_client_id"),
+            client_secret: string("introspection_client_secret"),
+            audience: string("introspection_audience"),
+        });
+    }
+    if let Some(resource) = string("oauth_resource") {
+        auth.oauth = Some(OAuthSettings { resource, authorization_servers: strings("authorization_server").unwrap_or_default() });
+    }
+    if let Some(scopes) = string("scopes") {
+        auth.scopes = Some(scopes);
+    }
+    if let Some(policy) = string("policy") {
+        config.policy = Some(policy);
+    }
+
+    let rate_limits = &mut config.rate_limits;
+    if let Some(rate_limit) = string("rate_limit") {
+        rate_limits.global = Some(rate_limit);
+    }
+    if let Some(rate_limit) = string("principal_rate_limit") {
+        rate_limits.principal = Some(rate_limit);
+    }
+    if let Some(max_in_flight) = sub_m.get_one::<usize>("max_in_flight") {
+        rate_limits.max_in_flight = Some(*max_in_flight);
+    }
+    for limit in sub_m.get_many::<String>("payload_limit").unwrap_or_default() {
+        let (limit, max) = parse_payload_limit(limit)?;
+        config.payload_limits.set(limit, max);
+    }
+    if sub_m.get_flag("metrics_tool_labels") {
+        config.metrics.tool_labels = true;
+    }
+    if let Some(endpoint) = string("otlp_endpoint") {
+        config.telemetry.otlp_endpoint = Some(endpoint);
+    }
+    if let Some(service_name) = string("service_name") {
+        config.telemetry.service_name = service_name;
+    }
+    if let Some(listen) = string("admin_listen") {
+        config.admin.listen = Some(listen);
+    }
+    if let Some(api_keys) = string("admin_api_keys") {
+        config.admin.api_keys = Some(api_keys);
+    }
+    Ok(config)
+}
 
-    let wasm_path_dir = Path::new(&wasm_path);
+async fn start_server(config: ServerConfig, file: Option<PathBuf>, overrides: Option<ConfigOverrides>) {
+    if let Err(e) = run_server(config, file, overrides).await {
+        eprintln!("Could not start the server: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Runs the server until Ctrl-C, reloading the configuration when `file` changes or on SIGHUP
+async fn run_server(config: ServerConfig, file: Option<PathBuf>, overrides: Option<ConfigOverrides>) -> Result<(), McpEctorsError> {
+    let log_config = config.log_config()?;
+    let mut directories = config.routers.directories.iter();
+    let wasm_path = directories.next().cloned();
 
     // Check if the path exists, if not, create it
-    if !wasm_path_dir.exists() {
+    if let Some(wasm_path_dir) = wasm_path.as_deref().map(Path::new).filter(|path| !path.exists()) {
         match fs::create_dir_all(wasm_path_dir) {
             Ok(_) => println!("Created wasm directory at {:?}", wasm_path_dir),
             Err(e) => eprintln!("Failed to create wasm directory at {:?}: {}", wasm_path_dir, e),
         }
     }
 
-    let config = SseTransportConfig {
-        port,
-        tls_cert,
-        tls_key,
-        log_dir: log_config.log_dir.clone(),
-        log_file: log_config.log_file.clone(),
-    };
-
-    let router_manager = RouterServiceManager::default(Some(wasm_path)).await;
+    let mut router_manager = RouterServiceManager::default(wasm_path).await;
+    for directory in directories {
+        router_manager.load_wasm_directory(directory).await?;
+    }
+    router_manager.health().set_scanned();
+    router_manager.set_timeouts(config.request_timeouts());
+    router_manager.metrics().set_tool_labels(config.metrics.tool_labels);
+    router_manager.health().set_required(config.routers.required.clone());
+    router_manager.set_lazy_tools(config.routers.lazy_tools);
+    for (alias, router_id) in config.router_aliases() {
+        router_manager.add_alias(&alias, &router_id).await?;
+    }
 
-   
-    let server = McpServer::new()
-        .router_manager(router_manager)
-        .transport(Config::Sse(config))
+    let access = config.access_control()?;
+    let limiter = RateLimiter::new(config.rate_limits()?);
+    let payload_limits = PayloadLimiter::new(config.payload_limits());
+    let mut server = McpServer::new();
+    if let Some(exporter) = config.otlp_exporter()? {
+        server = server.with_otlp(exporter);
+    }
+    let server = server
+        .router_manager(router_manager.clone())
+        .transport(Config::Sse(config.sse_transport_config()?))
+        .with_session_limits(config.session_limits())
+        .with_auth(config.authentication()?)
+        .with_access_control(access.clone())
+        .with_rate_limiter(limiter.clone())
+        .with_payload_limits(payload_limits.clone())
+        .with_origin_policy(config.origin_policy())
         .with_logging(log_config)
         .start()
-        .unwrap();
+        .map_err(McpEctorsError::Internal)?;
+    let admin = match config.admin_server(router_manager.clone())? {
+        Some(admin) => Some(admin.start()?),
+        None => None,
+    };
+
+    let mut reloader = ConfigReloader::new(file, config, router_manager)
+        .with_access_control(access)
+        .with_rate_limiter(limiter)
+        .with_payload_limits(payload_limits)
+        .with_log_level(server.log_level());
+    if let Some(overrides) = overrides {
+        reloader = reloader.with_overrides(overrides);
+    }
+    let _reloader = reloader.start();
 
 
     // Graceful shutdown handling.
@@ -173,6 +473,10 @@ async fn start_server(log_dir: String, log_file: String, log_level: String, wasm
     tokio::select! {
         _ = ctrl_c_signal => {
             let _ = server.stop();
+            if let Some(admin) = admin {
+                admin.stop(true).await;
+            }
         },
     }
-}
\ No newline at end of file
+    Ok(())
+}
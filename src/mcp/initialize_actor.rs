@@ -2,6 +2,7 @@ use actix::prelude::*;
 use mcp_spec::protocol::{ JsonRpcError, JsonRpcRequest, JsonRpcResponse};
 use serde_json::{json, Value};
 
+use crate::error::McpEctorsError;
 use crate::messages::{ InitializedNotification, JSONRPC_VERSION};
 use crate::router::router_registry::ROUTER_SEPERATOR;
 use crate::router::topic_registry_actor::TopicMessage;
@@ -81,7 +82,7 @@ impl Actor for InitializationActor {
 
 /// Handle `InitializedNotification` messages received via the TopicRegistryActor.
 impl Handler<TopicMessage> for InitializationActor {
-    type Result = Result<Value, ()>;
+    type Result = Result<Value, McpEctorsError>;
 
     fn handle(&mut self, msg: TopicMessage, _ctx: &mut Self::Context) -> Self::Result {
         if msg.topic == InitializedNotification::METHOD {
@@ -89,7 +90,7 @@ impl Handler<TopicMessage> for InitializationActor {
 
             Ok(json!({ "status": "initialized notification received" }))
         } else {
-            Err(())
+            Err(McpEctorsError::MethodNotFound(msg.topic))
         }
     }
 }
@@ -1,4 +1,5 @@
 use actix::prelude::*;
+use crate::error::McpEctorsError;
 use crate::{messages::{AddPromptsRequest, ListPromptsRequest, RemovePromptsRequest}, router::router_registry::ROUTER_SEPERATOR};
 use mcp_spec::{prompt::Prompt, protocol::{JsonRpcResponse, ListPromptsResult}};
 
@@ -39,7 +40,7 @@ impl Actor for ListPromptsActor {
 
 /// **Actix Handler for `RouterRequest`**
 impl Handler<ListPromptsRequest> for ListPromptsActor {
-    type Result = ResponseFuture<Result<JsonRpcResponse, ()>>;
+    type Result = ResponseFuture<Result<JsonRpcResponse, McpEctorsError>>;
 
     fn handle(&mut self, msg: ListPromptsRequest, _ctx: &mut Self::Context) -> Self::Result {
         let request = msg.request.clone();
@@ -61,7 +62,7 @@ impl<T> Handler<AddPromptsRequest<T>> for ListPromptsActor
 where
     T: Actor<Context = Context<T>> + Unpin + Send + 'static,
 {
-    type Result = ResponseFuture<Result<(), ()>>;
+    type Result = ResponseFuture<Result<(), McpEctorsError>>;
 
     fn handle(&mut self, msg: AddPromptsRequest<T>, _ctx: &mut Self::Context) -> Self::Result {
         // Create a new vector of prompts with the router_id:name substitution
@@ -93,7 +94,7 @@ impl<T> Handler<RemovePromptsRequest<T>> for ListPromptsActor
 where
     T: Actor<Context = Context<T>> + Unpin + Send + 'static,
 {
-    type Result = ResponseFuture<Result<(), ()>>;
+    type Result = ResponseFuture<Result<(), McpEctorsError>>;
 
     fn handle(&mut self, msg: RemovePromptsRequest<T>, _ctx: &mut Self::Context) -> Self::Result {
         // Create a new vector of prompts with the router_id:name substitution
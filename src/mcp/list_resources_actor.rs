@@ -1,4 +1,5 @@
 use actix::prelude::*;
+use crate::error::McpEctorsError;
 use crate::{messages::{AddResourcesRequest, ListResourcesRequest, RemoveResourcesRequest}, router::router_registry::ROUTER_SEPERATOR};
 use mcp_spec::{protocol::{JsonRpcResponse, ListResourcesResult}, resource::Resource};
 
@@ -39,7 +40,7 @@ impl Actor for ListResourcesActor {
 
 /// **Actix Handler for `RouterRequest`**
 impl Handler<ListResourcesRequest> for ListResourcesActor {
-    type Result = ResponseFuture<Result<JsonRpcResponse, ()>>;
+    type Result = ResponseFuture<Result<JsonRpcResponse, McpEctorsError>>;
 
     fn handle(&mut self, msg: ListResourcesRequest, _ctx: &mut Self::Context) -> Self::Result {
         let request = msg.request.clone();
@@ -61,7 +62,7 @@ impl<T> Handler<AddResourcesRequest<T>> for ListResourcesActor
 where
     T: Actor<Context = Context<T>> + Unpin + Send + 'static,
 {
-    type Result = ResponseFuture<Result<(), ()>>;
+    type Result = ResponseFuture<Result<(), McpEctorsError>>;
 
     fn handle(&mut self, msg: AddResourcesRequest<T>, _ctx: &mut Self::Context) -> Self::Result {
         // Create a new vector of resources with the router_id:name substitution
@@ -95,23 +96,23 @@ impl<T> Handler<RemoveResourcesRequest<T>> for ListResourcesActor
 where
     T: Actor<Context = Context<T>> + Unpin + Send + 'static,
 {
-    type Result = ResponseFuture<Result<(), ()>>;
+    type Result = ResponseFuture<Result<(), McpEctorsError>>;
 
     fn handle(&mut self, msg: RemoveResourcesRequest<T>, _ctx: &mut Self::Context) -> Self::Result {
-        // Create a new vector of resources with the router_id:name substitution
+        // The resources as AddResourcesRequest listed them, with the router_id:name substitution
         let old_resources: Vec<Resource> = msg
             .resources
             .into_iter()
             .enumerate()
             .map(|(_i, resource)| {
                 // Substitute router_id:name into the name of each resource
-                let new_uri = format!("{}{}{}", msg.router_id, ROUTER_SEPERATOR, resource.uri);
+                let new_name = format!("{}{}{}", msg.router_id, ROUTER_SEPERATOR, resource.name);
                 
                 // Create new Resource with updated name and keep description and arguments intact
                 Resource {
-                    name: resource.name.clone(),
+                    name: new_name,
                     description: resource.description.clone(),
-                    uri: new_uri,
+                    uri: resource.uri.clone(),
                     mime_type: resource.mime_type.clone(),
                     annotations: resource.annotations.clone(),
                 }
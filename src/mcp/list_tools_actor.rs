@@ -1,5 +1,7 @@
 use actix::prelude::*;
-use crate::{messages::{AddToolsRequest, ListToolsRequest, RemoveToolsRequest}, router::router_registry::ROUTER_SEPERATOR};
+use crate::error::McpEctorsError;
+use crate::{messages::{AddToolsRequest, AllTools, ListToolsRequest, RemoveToolsRequest, SetLazyTools}, router::router_registry::ROUTER_SEPERATOR};
+use crate::router::system_router::SYSTEM_ROUTER_ID;
 use mcp_spec::{protocol::{JsonRpcResponse, ListToolsResult}, tool::Tool};
 
 
@@ -7,17 +9,24 @@ use mcp_spec::{protocol::{JsonRpcResponse, ListToolsResult}, tool::Tool};
 #[derive(Clone)]
 pub struct ListToolsActor {
     tools: Vec<Tool>,
+    // only the discovery tools of the system router are listed
+    lazy: bool,
 }
 
 impl ListToolsActor {
     pub fn new() -> Self {
         Self {
             tools:Vec::new(),
+            lazy: false,
         }
     }
 
     fn list_tools(&self) -> Vec<Tool> {
-        self.tools.clone()
+        if !self.lazy {
+            return self.tools.clone();
+        }
+        let prefix = format!("{}{}", SYSTEM_ROUTER_ID, ROUTER_SEPERATOR);
+        self.tools.iter().filter(|tool| tool.name.starts_with(&prefix)).cloned().collect()
     }
 
     pub fn add_tools(&mut self, new_tools: Vec<Tool>) {
@@ -39,7 +48,7 @@ impl Actor for ListToolsActor {
 
 /// **Actix Handler for `RouterRequest`**
 impl Handler<ListToolsRequest> for ListToolsActor {
-    type Result = ResponseFuture<Result<JsonRpcResponse, ()>>;
+    type Result = ResponseFuture<Result<JsonRpcResponse, McpEctorsError>>;
 
     fn handle(&mut self, msg: ListToolsRequest, _ctx: &mut Self::Context) -> Self::Result {
         let request = msg.request.clone();
@@ -57,11 +66,27 @@ impl Handler<ListToolsRequest> for ListToolsActor {
         Box::pin(fut)
     }
 }
+impl Handler<AllTools> for ListToolsActor {
+    type Result = Vec<Tool>;
+
+    fn handle(&mut self, _msg: AllTools, _ctx: &mut Self::Context) -> Self::Result {
+        self.tools.clone()
+    }
+}
+
+impl Handler<SetLazyTools> for ListToolsActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetLazyTools, _ctx: &mut Self::Context) -> Self::Result {
+        self.lazy = msg.lazy;
+    }
+}
+
 impl<T> Handler<AddToolsRequest<T>> for ListToolsActor
 where
     T: Actor<Context = Context<T>> + Unpin + Send + 'static,
 {
-    type Result = ResponseFuture<Result<(), ()>>;
+    type Result = ResponseFuture<Result<(), McpEctorsError>>;
 
     fn handle(&mut self, msg: AddToolsRequest<T>, _ctx: &mut Self::Context) -> Self::Result {
         // Create a new vector of tools with the router_id:name substitution
@@ -93,7 +118,7 @@ impl<T> Handler<RemoveToolsRequest<T>> for ListToolsActor
 where
     T: Actor<Context = Context<T>> + Unpin + Send + 'static,
 {
-    type Result = ResponseFuture<Result<(), ()>>;
+    type Result = ResponseFuture<Result<(), McpEctorsError>>;
 
     fn handle(&mut self, msg: RemoveToolsRequest<T>, _ctx: &mut Self::Context) -> Self::Result {
         // Create a new vector of tools with the router_id:name substitution
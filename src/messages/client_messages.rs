@@ -1,5 +1,7 @@
+use crate::error::McpEctorsError;
 use actix::prelude::*;
 use mcp_spec::protocol::JsonRpcMessage;
+use serde_json::Value;
 
 /// Message to register a client in ClientRegistry
 #[derive(Message)]
@@ -17,7 +19,7 @@ pub struct DeregisterClient {
 
 /// Message to send a JSON-RPC message to a specific client
 #[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
+#[rtype(result = "Result<(), McpEctorsError>")]
 pub struct NotifyClient {
     pub client_id: u64,
     pub message: JsonRpcMessage,
@@ -34,3 +36,8 @@ pub struct BroadcastMessage {
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct ClientMessage(pub JsonRpcMessage);
+
+/// An already serialised message for a client, e.g. a batch of responses or a response with a string id
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RawClientMessage(pub Value);
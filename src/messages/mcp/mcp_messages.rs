@@ -1,3 +1,4 @@
+use crate::error::McpEctorsError;
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -7,7 +8,7 @@ pub const JSONRPC_VERSION: &str = "2.0";
 
 /// A unified type for handling all JSON-RPC requests and notifications.
 #[derive(Message)]
-#[rtype(result = "Result<JsonRpcResponse, ()>")]
+#[rtype(result = "Result<JsonRpcResponse, McpEctorsError>")]
 pub enum JsonRpcRequestMessage {
     Initialize(InitializeRequest),
     Ping(PingRequest),
@@ -47,7 +48,7 @@ pub enum JsonRpcNotificationMessage {
 macro_rules! create_request {
     ($struct_name:ident, $method:expr) => {
         #[derive(Message, Debug, Clone, Serialize, Deserialize,)]
-        #[rtype(result = "Result<JsonRpcResponse, ()>")]
+        #[rtype(result = "Result<JsonRpcResponse, McpEctorsError>")]
         pub struct $struct_name {
             pub request: JsonRpcRequest,
         }
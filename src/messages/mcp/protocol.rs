@@ -1,18 +1,51 @@
 
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use mcp_spec::protocol::{ErrorData, JsonRpcError, JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::JSONRPC_VERSION;
 
+/// A JSON-RPC request id. Clients may use numbers or strings and get the id back exactly as they sent it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(serde_json::Number),
+    String(String),
+}
 
+static NEXT_INTERNAL_ID: AtomicU64 = AtomicU64::new(1);
 
+impl RequestId {
+    /// A fresh numeric id for a request inside the server, where ids are `u64`.
+    /// Responses get the original id back and requests are tracked by it, so this one only has to be unique.
+    pub fn next_internal() -> u64 {
+        NEXT_INTERNAL_ID.fetch_add(1, Ordering::Relaxed)
+    }
+}
 
+impl From<u64> for RequestId {
+    fn from(id: u64) -> Self {
+        RequestId::Number(id.into())
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::Number(id) => write!(f, "{}", id),
+            RequestId::String(id) => write!(f, "\"{}\"", id),
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 struct JsonRpcRaw {
     jsonrpc: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    id: Option<u64>,
+    id: Option<RequestId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     method: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -28,11 +61,19 @@ impl TryFrom<JsonRpcRaw> for JsonRpcMessage {
     type Error = String;
 
     fn try_from(raw: JsonRpcRaw) -> Result<Self, <Self as TryFrom<JsonRpcRaw>>::Error> {
+        let id = match raw.id {
+            Some(RequestId::Number(id)) => match id.as_u64() {
+                Some(id) => Some(id),
+                None => return Err(format!("Id {} cannot be represented, use IncomingMessage", id)),
+            },
+            Some(RequestId::String(id)) => return Err(format!("String id {} cannot be represented, use IncomingMessage", id)),
+            None => None,
+        };
         // If it has an error field, it's an error response
         if raw.error.is_some() {
             return Ok(JsonRpcMessage::Error(JsonRpcError {
                 jsonrpc: raw.jsonrpc,
-                id: raw.id,
+                id,
                 error: raw.error.unwrap(),
             }));
         }
@@ -41,7 +82,7 @@ impl TryFrom<JsonRpcRaw> for JsonRpcMessage {
         if raw.result.is_some() {
             return Ok(JsonRpcMessage::Response(JsonRpcResponse {
                 jsonrpc: raw.jsonrpc,
-                id: raw.id,
+                id,
                 result: raw.result,
                 error: None,
             }));
@@ -49,7 +90,7 @@ impl TryFrom<JsonRpcRaw> for JsonRpcMessage {
 
         // If we have a method, it's either a notification or request
         if let Some(method) = raw.method {
-            if raw.id.is_none() {
+            if id.is_none() {
                 return Ok(JsonRpcMessage::Notification(JsonRpcNotification {
                     jsonrpc: raw.jsonrpc,
                     method,
@@ -59,21 +100,21 @@ impl TryFrom<JsonRpcRaw> for JsonRpcMessage {
 
             return Ok(JsonRpcMessage::Request(JsonRpcRequest {
                 jsonrpc: raw.jsonrpc,
-                id: raw.id,
+                id,
                 method,
                 params: raw.params,
             }));
         }
 
         // If we have no method and no result/error, it's a nil response
-        if raw.id.is_none() && raw.result.is_none() && raw.error.is_none() {
+        if id.is_none() && raw.result.is_none() && raw.error.is_none() {
             return Ok(JsonRpcMessage::Nil);
         }
 
         // If we get here, something is wrong with the message
         Err(format!(
             "Invalid JSON-RPC message format: id={:?}, method={:?}, result={:?}, error={:?}",
-            raw.id, raw.method, raw.result, raw.error
+            id, raw.method, raw.result, raw.error
         ))
     }
 }
@@ -84,6 +125,57 @@ pub const INVALID_REQUEST: i32 = -32600;
 pub const METHOD_NOT_FOUND: i32 = -32601;
 pub const INVALID_PARAMS: i32 = -32602;
 pub const INTERNAL_ERROR: i32 = -32603;
+
+/// One message a client sent, possibly as an item of a batch
+#[derive(Debug, Clone, PartialEq)]
+pub enum IncomingMessage {
+    Request { id: RequestId, method: String, params: Option<Value> },
+    Notification { method: String, params: Option<Value> },
+    /// The client's answer to a request the server sent, e.g. `sampling/createMessage`
+    Response { id: RequestId, result: Option<Value>, error: Option<ErrorData> },
+    /// Not a valid JSON-RPC 2.0 message, `error` is what the client should get back
+    Invalid { id: Option<RequestId>, error: ErrorData },
+}
+
+impl IncomingMessage {
+    pub fn parse(value: Value) -> Self {
+        // keep the id if there is a usable one so the error can be correlated
+        let id = value.get("id").and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok());
+        let raw = match serde_json::from_value::<JsonRpcRaw>(value) {
+            Ok(raw) => raw,
+            Err(e) => return IncomingMessage::invalid(id, format!("Invalid JSON-RPC message: {}", e)),
+        };
+        if raw.jsonrpc != JSONRPC_VERSION {
+            return IncomingMessage::invalid(raw.id, format!("Unsupported JSON-RPC version {}", raw.jsonrpc));
+        }
+        match (raw.method, raw.id) {
+            (Some(method), Some(id)) => IncomingMessage::Request { id, method, params: raw.params },
+            (Some(method), None) => IncomingMessage::Notification { method, params: raw.params },
+            (None, Some(id)) if raw.result.is_some() || raw.error.is_some() => IncomingMessage::Response { id, result: raw.result, error: raw.error },
+            (None, id) => IncomingMessage::invalid(id, "Message has neither a method nor a result".to_string()),
+        }
+    }
+
+    fn invalid(id: Option<RequestId>, message: String) -> Self {
+        IncomingMessage::Invalid { id, error: ErrorData { code: INVALID_REQUEST, message, data: None } }
+    }
+}
+
+/// Turns the response to a request back into JSON with the id exactly as the client sent it.
+/// `id` is `None` for errors about messages whose id could not be determined, they get `"id": null`.
+pub fn outgoing_response(id: Option<&RequestId>, response: Result<JsonRpcResponse, JsonRpcError>) -> Value {
+    let mut value = match response {
+        Ok(response) => serde_json::to_value(response),
+        Err(error) => serde_json::to_value(error),
+    }.unwrap_or_else(|_| Value::Object(Default::default()));
+    value["id"] = serde_json::to_value(id).unwrap_or(Value::Null);
+    value
+}
+
+/// The error response for an invalid message
+pub fn outgoing_error(id: Option<&RequestId>, error: ErrorData) -> Value {
+    outgoing_response(id, Err(JsonRpcError { jsonrpc: JSONRPC_VERSION.to_string(), id: None, error }))
+}
 /*
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -161,7 +253,7 @@ mod tests {
     fn test_request_conversion() {
         let raw = JsonRpcRaw {
             jsonrpc: "2.0".to_string(),
-            id: Some(1),
+            id: Some(1.into()),
             method: Some("request".to_string()),
             params: Some(json!({"key": "value"})),
             result: None,
@@ -179,4 +271,54 @@ mod tests {
             _ => panic!("Expected Request"),
         }
     }
+
+    #[test]
+    fn test_incoming_string_and_number_ids() {
+        let message = IncomingMessage::parse(json!({"jsonrpc": "2.0", "id": "abc-1", "method": "ping"}));
+        assert_eq!(message, IncomingMessage::Request { id: RequestId::String("abc-1".to_string()), method: "ping".to_string(), params: None });
+
+        let message = IncomingMessage::parse(json!({"jsonrpc": "2.0", "id": 7, "method": "tools/list", "params": {}}));
+        assert_eq!(message, IncomingMessage::Request { id: RequestId::from(7), method: "tools/list".to_string(), params: Some(json!({})) });
+
+        let message = IncomingMessage::parse(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}));
+        assert_eq!(message, IncomingMessage::Notification { method: "notifications/initialized".to_string(), params: None });
+
+        let message = IncomingMessage::parse(json!({"jsonrpc": "2.0", "id": 3, "result": {"roots": []}}));
+        assert!(matches!(message, IncomingMessage::Response { id, .. } if id == RequestId::from(3)));
+    }
+
+    #[test]
+    fn test_incoming_invalid_messages() {
+        for value in [json!({"id": 1, "method": "ping"}), json!({"jsonrpc": "1.0", "id": 1, "method": "ping"}), json!({"jsonrpc": "2.0", "id": 1}), json!({"jsonrpc": "2.0", "id": {"a": 1}, "method": "ping"}), json!(42)] {
+            match IncomingMessage::parse(value.clone()) {
+                IncomingMessage::Invalid { error, .. } => assert_eq!(error.code, INVALID_REQUEST, "{}", value),
+                other => panic!("Expected Invalid for {}, got {:?}", value, other),
+            }
+        }
+        match IncomingMessage::parse(json!({"jsonrpc": "1.0", "id": "x", "method": "ping"})) {
+            IncomingMessage::Invalid { id, .. } => assert_eq!(id, Some(RequestId::String("x".to_string()))),
+            other => panic!("Expected Invalid, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_outgoing_response_restores_id() {
+        let response = JsonRpcResponse { jsonrpc: "2.0".to_string(), id: Some(RequestId::next_internal()), result: Some(json!({})), error: None };
+        let value = outgoing_response(Some(&RequestId::String("abc".to_string())), Ok(response));
+        assert_eq!(value, json!({"jsonrpc": "2.0", "id": "abc", "result": {}}));
+
+        // negative and fractional ids come back as they were sent
+        for id in [json!(-1), json!(1.5), json!(u64::MAX)] {
+            let request_id = match IncomingMessage::parse(json!({"jsonrpc": "2.0", "id": id, "method": "ping"})) {
+                IncomingMessage::Request { id, .. } => id,
+                other => panic!("Expected Request, got {:?}", other),
+            };
+            let response = JsonRpcResponse { jsonrpc: "2.0".to_string(), id: Some(RequestId::next_internal()), result: Some(json!({})), error: None };
+            assert_eq!(outgoing_response(Some(&request_id), Ok(response))["id"], id);
+        }
+        assert_ne!(RequestId::from(1), RequestId::String("1".to_string()));
+
+        let value = outgoing_error(None, ErrorData { code: PARSE_ERROR, message: "Parse error".to_string(), data: None });
+        assert_eq!(value, json!({"jsonrpc": "2.0", "id": null, "error": {"code": PARSE_ERROR, "message": "Parse error"}}));
+    }
 }
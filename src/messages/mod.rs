@@ -1,11 +1,9 @@
 pub mod transport_messages;
 pub mod router_messages;
 pub mod client_messages;
-pub mod sse;
 pub mod mcp;
 
 pub use transport_messages::*;
 pub use router_messages::*;
 pub use client_messages::*;
-pub use sse::*;
 pub use mcp::*;
\ No newline at end of file
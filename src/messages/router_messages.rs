@@ -1,3 +1,4 @@
+use crate::error::McpEctorsError;
 use std::marker::PhantomData;
 use actix::prelude::*;
 use mcp_spec::{prompt::Prompt, protocol::{JsonRpcMessage, JsonRpcRequest,JsonRpcResponse, ServerCapabilities}, Resource, Tool};
@@ -6,14 +7,14 @@ use serde_json::Value;
 use crate::router::RouterActor;
 /// **Message to handle JSON-RPC requests**
 #[derive(Message)]
-#[rtype(result = "Result<JsonRpcResponse, ()>")]
+#[rtype(result = "Result<JsonRpcResponse, McpEctorsError>")]
 pub struct HandleRequestMsg {
     pub request: JsonRpcRequest,
 }
 
 /// **Message to handle notifications**
 #[derive(Message)]
-#[rtype(result = "Result<Value, ()>")]
+#[rtype(result = "Result<Value, McpEctorsError>")]
 pub struct HandleNotificationMsg {
     pub topic: String,
     pub payload: Value,
@@ -26,11 +27,18 @@ pub struct HandleNotificationMsg {
 pub struct GetRouter {
     pub router_id: String,
     pub(crate) _marker: PhantomData<RouterActor>, // ✅ This makes Rust happy.
+    /// The span of the request the router is looked up for
+    pub(crate) span: tracing::Span,
 }
 
+/// Message to find the routers clients can use, sorted by id
+#[derive(Message)]
+#[rtype(result = "Vec<(String, Addr<RouterActor>)>")]
+pub struct ListRouters;
+
 /// Message to register a new router (Native or WASM) along with its capabilities.
 #[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
+#[rtype(result = "Result<(), McpEctorsError>")]
 pub struct RegisterRouter {
     pub router_id: String,
     pub router_addr: Addr<RouterActor>,
@@ -45,9 +53,51 @@ pub struct UnregisterRouter {
     pub router_id: String,
 }
 
+/// Message to stop clients using a registered router, or to let them again. A disabled router
+/// keeps its id and aliases.
+#[derive(Message)]
+#[rtype(result = "Result<(), McpEctorsError>")]
+pub struct SetRouterEnabled {
+    pub router_id: String,
+    pub enabled: bool,
+}
+
+/// Message to swap the actor of a registered router, e.g. for a reloaded WASM component. The
+/// router keeps its aliases and whether it is enabled.
+#[derive(Message)]
+#[rtype(result = "Result<(), McpEctorsError>")]
+pub struct ReplaceRouter {
+    pub router_id: String,
+    pub router_addr: Addr<RouterActor>,
+}
+
+/// Message to let clients call a registered router by another id too, e.g. `count_increment`
+/// for `counter_increment`. The alias goes when the router is unregistered.
+#[derive(Message)]
+#[rtype(result = "Result<(), McpEctorsError>")]
+pub struct RegisterAlias {
+    pub alias: String,
+    pub router_id: String,
+}
+
+/// Message to stop clients calling a router by an alias
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct UnregisterAlias {
+    pub alias: String,
+}
+
+/// Message that rewrites a namespaced name using an alias to the name of the router it stands
+/// for, so policies and limits see the same name whichever id the client used.
+#[derive(Message)]
+#[rtype(result = "String")]
+pub struct ResolveAlias {
+    pub name: String,
+}
+
 /// Message sent to a router to process an MCP request.
 /*#[derive(Message)]
-#[rtype(result = "Result<JsonRpcResponse, ()>")]
+#[rtype(result = "Result<JsonRpcResponse, McpEctorsError>")]
 pub struct RouterRequest {
     pub router_id: String,
     pub request: JsonRpcRequest,
@@ -69,7 +119,7 @@ pub struct GetServerCapabilities;
 
 /// Request to the list prompts actor to add prompts
 #[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
+#[rtype(result = "Result<(), McpEctorsError>")]
 pub struct AddPromptsRequest<T: Actor<Context = Context<T>> + Unpin + Send + 'static> {
     pub router_id: String,
     pub prompts: Vec<Prompt>,
@@ -78,7 +128,7 @@ pub struct AddPromptsRequest<T: Actor<Context = Context<T>> + Unpin + Send + 'st
 
 /// Request to the list prompts actor to remove prompts
 #[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
+#[rtype(result = "Result<(), McpEctorsError>")]
 pub struct RemovePromptsRequest<T: Actor<Context = Context<T>> + Unpin + Send + 'static> {
     pub router_id: String,
     pub prompts: Vec<Prompt>,
@@ -87,16 +137,28 @@ pub struct RemovePromptsRequest<T: Actor<Context = Context<T>> + Unpin + Send +
 
 /// Request to the list tools actor to add tools
 #[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
+#[rtype(result = "Result<(), McpEctorsError>")]
 pub struct AddToolsRequest<T: Actor<Context = Context<T>> + Unpin + Send + 'static> {
     pub router_id: String,
     pub tools: Vec<Tool>,
     pub router: Addr<T>,
 }
 
+/// Request to the list tools actor for every tool, also the ones lazy listing leaves out
+#[derive(Message)]
+#[rtype(result = "Vec<Tool>")]
+pub struct AllTools;
+
+/// Request to the list tools actor to list only the tools of the system router, or every tool again
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetLazyTools {
+    pub lazy: bool,
+}
+
 /// Request to the list tools actor to remove prompts
 #[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
+#[rtype(result = "Result<(), McpEctorsError>")]
 pub struct RemoveToolsRequest<T: Actor<Context = Context<T>> + Unpin + Send + 'static> {
     pub router_id: String,
     pub tools: Vec<Tool>,
@@ -105,7 +167,7 @@ pub struct RemoveToolsRequest<T: Actor<Context = Context<T>> + Unpin + Send + 's
 
 /// Request to the list resources actor to add resources
 #[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
+#[rtype(result = "Result<(), McpEctorsError>")]
 pub struct AddResourcesRequest<T: Actor<Context = Context<T>> + Unpin + Send + 'static> {
     pub router_id: String,
     pub resources: Vec<Resource>,
@@ -114,7 +176,7 @@ pub struct AddResourcesRequest<T: Actor<Context = Context<T>> + Unpin + Send + '
 
 /// Request to the list resources actor to remove resources
 #[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
+#[rtype(result = "Result<(), McpEctorsError>")]
 pub struct RemoveResourcesRequest<T: Actor<Context = Context<T>> + Unpin + Send + 'static> {
     pub router_id: String,
     pub resources: Vec<Resource>,
@@ -1,9 +0,0 @@
-mod register_sse_client;
-mod deregister_sse_client;
-mod notify_sse_client;
-mod broadcast_sse_message;
-
-pub use register_sse_client::RegisterSseClient;
-pub use deregister_sse_client::DeregisterSseClient;
-pub use notify_sse_client::NotifySseClient;
-pub use broadcast_sse_message::BroadcastSseMessage;
@@ -3,6 +3,7 @@ use actix::prelude::*;
 use mcp_spec::protocol::JsonRpcRequest;
 use mcp_spec::protocol::JsonRpcResponse;
 use mcp_spec::protocol::JsonRpcError;
+use crate::router::RequestContext;
 use crate::transport::TransportError;
 
 
@@ -16,6 +17,7 @@ pub struct StartTransport;
 #[rtype(result = "Result<JsonRpcResponse, JsonRpcError>")]
 pub struct TransportRequest {
     pub request: JsonRpcRequest,
+    pub context: RequestContext,
 }
 
 /// Message to stop a transport (graceful shutdown)
@@ -0,0 +1,5 @@
+pub mod prometheus;
+pub mod server_metrics;
+
+pub use prometheus::{metrics_handler, render, METRICS_PATH};
+pub use server_metrics::{Histogram, Metrics, RequestLabels, RequestStats, RequestTimer, SessionGuard, WasmRouterStats};
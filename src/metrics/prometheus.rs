@@ -0,0 +1,140 @@
+use std::fmt::{Display, Write};
+
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+
+use crate::limits::{PayloadLimiter, RateLimiter};
+
+use super::{Histogram, Metrics};
+
+/// The path Prometheus scrapes
+pub const METRICS_PATH: &str = "/metrics";
+
+/// The content type of the Prometheus text format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Writes metrics in the Prometheus text format
+#[derive(Default)]
+struct Exposition(String);
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.0, "# HELP {} {}", name, help);
+        let _ = writeln!(self.0, "# TYPE {} {}", name, kind);
+    }
+
+    /// A sample of `name`, labels with an empty value are left out
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        self.0.push_str(name);
+        let labels: Vec<String> = labels.iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+            .collect();
+        if !labels.is_empty() {
+            let _ = write!(self.0, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.0, " {}", value);
+    }
+
+    fn histogram(&mut self, name: &str, labels: &[(&str, &str)], histogram: &Histogram) {
+        for (bound, count) in histogram.cumulative() {
+            let bound = bound.to_string();
+            self.sample(&format!("{}_bucket", name), &[labels, &[("le", bound.as_str())]].concat(), count);
+        }
+        self.sample(&format!("{}_bucket", name), &[labels, &[("le", "+Inf")]].concat(), histogram.count);
+        self.sample(&format!("{}_sum", name), labels, histogram.sum);
+        self.sample(&format!("{}_count", name), labels, histogram.count);
+    }
+}
+
+/// Escapes a label value, Prometheus only knows `\\`, `\"` and `\n`
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// `metrics`, and the state of the rate and payload limits, in the Prometheus text format
+pub fn render(metrics: &Metrics, limiter: &RateLimiter, payload_limits: &PayloadLimiter) -> String {
+    let mut out = Exposition::default();
+    let requests = metrics.requests();
+
+    out.family("mcp_requests_total", "counter", "Requests handled, by method, router and tool");
+    for (labels, stats) in &requests {
+        out.sample("mcp_requests_total", &[("method", &labels.method), ("router", &labels.router), ("tool", &labels.tool)], stats.requests);
+    }
+    out.family("mcp_request_errors_total", "counter", "Requests answered with an error, by JSON-RPC error code");
+    for (labels, stats) in &requests {
+        for (code, count) in &stats.errors {
+            let code = code.to_string();
+            out.sample("mcp_request_errors_total", &[("method", &labels.method), ("router", &labels.router), ("tool", &labels.tool), ("code", &code)], count);
+        }
+    }
+    out.family("mcp_request_duration_seconds", "histogram", "How long requests took to answer");
+    for (labels, stats) in &requests {
+        out.histogram("mcp_request_duration_seconds", &[("method", &labels.method), ("router", &labels.router), ("tool", &labels.tool)], &stats.latency);
+    }
+
+    out.family("mcp_sse_sessions_active", "gauge", "Open SSE streams");
+    out.sample("mcp_sse_sessions_active", &[], metrics.active_sessions());
+    out.family("mcp_sse_events_dropped_total", "counter", "Events that could not be queued for an SSE client");
+    out.sample("mcp_sse_events_dropped_total", &[], metrics.dropped_events());
+
+    let wasm = metrics.wasm_routers();
+    out.family("mcp_wasm_instantiation_seconds", "histogram", "How long instantiating a WASM router took");
+    for (router_id, stats) in &wasm {
+        out.histogram("mcp_wasm_instantiation_seconds", &[("router", router_id)], &stats.instantiation());
+    }
+    out.family("mcp_wasm_fuel_consumed_total", "counter", "Fuel WASM routers consumed");
+    for (router_id, stats) in &wasm {
+        out.sample("mcp_wasm_fuel_consumed_total", &[("router", router_id)], stats.fuel_consumed());
+    }
+    out.family("mcp_wasm_memory_high_water_bytes", "gauge", "The largest linear memory of an instance of a WASM router");
+    for (router_id, stats) in &wasm {
+        out.sample("mcp_wasm_memory_high_water_bytes", &[("router", router_id)], stats.memory_high_water());
+    }
+    out.family("mcp_wasm_queue_depth", "gauge", "Requests waiting for the instance of a WASM router");
+    for (router_id, stats) in &wasm {
+        out.sample("mcp_wasm_queue_depth", &[("router", router_id)], stats.queue_depth());
+    }
+
+    let buckets = limiter.snapshot();
+    out.family("mcp_rate_limit_requests_total", "counter", "Requests a rate limit let through or rejected");
+    for state in &buckets {
+        out.sample("mcp_rate_limit_requests_total", &[("limit", &state.key), ("outcome", "allowed")], state.allowed);
+        out.sample("mcp_rate_limit_requests_total", &[("limit", &state.key), ("outcome", "rejected")], state.rejected);
+    }
+    out.family("mcp_rate_limit_in_flight", "gauge", "Requests being handled that count against a limit");
+    for state in &buckets {
+        out.sample("mcp_rate_limit_in_flight", &[("limit", &state.key)], state.in_flight);
+    }
+    out.family("mcp_rate_limit_tokens", "gauge", "Requests a rate limit still lets through right away");
+    for state in &buckets {
+        if let Some(tokens) = state.tokens {
+            out.sample("mcp_rate_limit_tokens", &[("limit", &state.key)], tokens);
+        }
+    }
+
+    let payload = payload_limits.snapshot();
+    out.family("mcp_payload_limit_max", "gauge", "The payload limits, in bytes, levels or items");
+    for state in &payload {
+        out.sample("mcp_payload_limit_max", &[("limit", state.limit.name())], state.max);
+    }
+    out.family("mcp_payload_limit_rejected_total", "counter", "Messages a payload limit rejected");
+    for state in &payload {
+        out.sample("mcp_payload_limit_rejected_total", &[("limit", state.limit.name())], state.rejected);
+    }
+    out.0
+}
+
+/// Serves the metrics of the `Data<Metrics>`, `Data<RateLimiter>` and `Data<PayloadLimiter>` of the app
+pub async fn metrics_handler(
+    metrics: Option<Data<Metrics>>,
+    limiter: Option<Data<RateLimiter>>,
+    payload_limits: Option<Data<PayloadLimiter>>,
+) -> HttpResponse {
+    let metrics = metrics.map(|metrics| metrics.get_ref().clone()).unwrap_or_default();
+    let limiter = limiter.map(|limiter| limiter.get_ref().clone()).unwrap_or_default();
+    let payload_limits = payload_limits.map(|limits| limits.get_ref().clone()).unwrap_or_default();
+    HttpResponse::Ok()
+        .content_type(CONTENT_TYPE)
+        .body(render(&metrics, &limiter, &payload_limits))
+}
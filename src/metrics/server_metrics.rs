@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::messages::{CallToolRequest, GetPromptRequest, InitializeRequest, ListPromptsRequest, ListResourceTemplatesRequest, ListResourcesRequest, ListToolsRequest, PingRequest, ReadResourceRequest, SetLevelRequest, SubscribeRequest, UnsubscribeRequest};
+use crate::router::router_registry::ROUTER_SEPERATOR;
+
+/// Upper bounds, in seconds, of the buckets of the latency histograms
+pub const LATENCY_BUCKETS: [f64; 14] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// What the `method` label of a request the transport does not handle is, so clients cannot
+/// make up labels
+pub const OTHER_METHOD: &str = "other";
+
+/// The router label of requests to a router that is not registered
+pub const UNKNOWN_ROUTER: &str = "unknown";
+
+/// The methods requests are counted by, any other one counts as [`OTHER_METHOD`]
+const METHODS: [&str; 12] = [
+    InitializeRequest::METHOD,
+    PingRequest::METHOD,
+    SetLevelRequest::METHOD,
+    ListToolsRequest::METHOD,
+    CallToolRequest::METHOD,
+    ListPromptsRequest::METHOD,
+    GetPromptRequest::METHOD,
+    ListResourcesRequest::METHOD,
+    ListResourceTemplatesRequest::METHOD,
+    ReadResourceRequest::METHOD,
+    SubscribeRequest::METHOD,
+    UnsubscribeRequest::METHOD,
+];
+
+/// Counts of observations in [`LATENCY_BUCKETS`], each bucket only holding those above the previous bound
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    pub count: u64,
+    pub sum: f64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    /// Every bucket bound with the observations up to it, as Prometheus expects them
+    pub fn cumulative(&self) -> Vec<(f64, u64)> {
+        LATENCY_BUCKETS.iter()
+            .zip(self.buckets.iter())
+            .scan(0, |total, (bound, count)| {
+                *total += count;
+                Some((*bound, *total))
+            })
+            .collect()
+    }
+}
+
+/// The labels requests are counted by. Empty ones are left out of the metrics.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RequestLabels {
+    pub method: String,
+    pub router: String,
+    /// Only set when tool labels are on, see [`Metrics::set_tool_labels`]
+    pub tool: String,
+}
+
+/// Requests, errors by JSON-RPC code and latency of one set of labels
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RequestStats {
+    pub requests: u64,
+    pub errors: BTreeMap<i32, u64>,
+    pub latency: Histogram,
+}
+
+/// What a WASM router does on its thread. Each router is a pool of one instance, which requests
+/// queue for.
+#[derive(Debug, Default)]
+pub struct WasmRouterStats {
+    instantiation: Mutex<Histogram>,
+    fuel_consumed: AtomicU64,
+    memory_high_water: AtomicUsize,
+    queue_depth: AtomicUsize,
+}
+
+impl WasmRouterStats {
+    pub fn record_instantiation(&self, duration: Duration) {
+        self.instantiation.lock().unwrap().observe(duration.as_secs_f64());
+    }
+
+    pub fn record_fuel(&self, fuel: u64) {
+        self.fuel_consumed.fetch_add(fuel, Ordering::Relaxed);
+    }
+
+    /// Called with the size a linear memory of the instance grows to
+    pub fn record_memory(&self, bytes: usize) {
+        self.memory_high_water.fetch_max(bytes, Ordering::Relaxed);
+    }
+
+    pub fn enqueued(&self) {
+        self.queue_depth.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dequeued(&self) {
+        self.queue_depth.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn instantiation(&self) -> Histogram {
+        self.instantiation.lock().unwrap().clone()
+    }
+
+    pub fn fuel_consumed(&self) -> u64 {
+        self.fuel_consumed.load(Ordering::Relaxed)
+    }
+
+    /// The largest a linear memory of any instance of the router has been, in bytes
+    pub fn memory_high_water(&self) -> usize {
+        self.memory_high_water.load(Ordering::Relaxed)
+    }
+
+    /// The requests waiting for the instance
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::Relaxed)
+    }
+}
+
+/// A registered router, its id and tools are the only router and tool labels there are
+#[derive(Default)]
+struct KnownRouter {
+    tools: HashSet<String>,
+    wasm: Option<Arc<WasmRouterStats>>,
+}
+
+/// The metrics of a server, shared by its transports and routers and exposed at `/metrics`.
+/// Labels stay bounded: methods are the ones the server handles, routers the registered ones, and
+/// tools are only labelled when [`Metrics::set_tool_labels`] turned that on.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    tool_labels: Arc<AtomicBool>,
+    routers: Arc<RwLock<HashMap<String, KnownRouter>>>,
+    requests: Arc<Mutex<BTreeMap<RequestLabels, RequestStats>>>,
+    sessions: Arc<AtomicI64>,
+    dropped_events: Arc<AtomicU64>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether requests are counted by tool too, off by default as every tool adds its own series
+    pub fn set_tool_labels(&self, tool_labels: bool) {
+        self.tool_labels.store(tool_labels, Ordering::Relaxed);
+    }
+
+    pub fn tool_labels(&self) -> bool {
+        self.tool_labels.load(Ordering::Relaxed)
+    }
+
+    /// Makes `router_id` and its tools, without the router prefix, labels requests can have
+    pub fn add_router(&self, router_id: &str, tools: impl IntoIterator<Item = String>) {
+        let mut routers = self.routers.write().unwrap();
+        routers.entry(router_id.to_string()).or_default().tools = tools.into_iter().collect();
+    }
+
+    /// Exposes the stats of the WASM instance of `router_id`
+    pub fn add_wasm_router(&self, router_id: &str, stats: Arc<WasmRouterStats>) {
+        self.routers.write().unwrap().entry(router_id.to_string()).or_default().wasm = Some(stats);
+    }
+
+    /// Requests to a router that is gone count as [`UNKNOWN_ROUTER`], the series it had stay
+    pub fn remove_router(&self, router_id: &str) {
+        self.routers.write().unwrap().remove(router_id);
+    }
+
+    /// The labels of a request with `method` to the router and namespaced tool it goes to
+    pub fn request_labels(&self, method: &str, router_id: Option<&str>, tool_name: Option<&str>) -> RequestLabels {
+        let routers = self.routers.read().unwrap();
+        let router = router_id.map(|router_id| routers.get_key_value(router_id));
+        let tool = match (router, tool_name) {
+            (Some(Some((router_id, known))), Some(tool_name)) if self.tool_labels() => tool_name
+                .strip_prefix(&format!("{}{}", router_id, ROUTER_SEPERATOR))
+                .filter(|tool| known.tools.contains(*tool))
+                .unwrap_or("")
+                .to_string(),
+            _ => String::new(),
+        };
+        RequestLabels {
+            method: METHODS.iter().find(|known| **known == method).copied().unwrap_or(OTHER_METHOD).to_string(),
+            router: match router {
+                Some(Some((router_id, _))) => router_id.clone(),
+                Some(None) => UNKNOWN_ROUTER.to_string(),
+                None => String::new(),
+            },
+            tool,
+        }
+    }
+
+    /// Starts timing a request, it is counted when the [`RequestTimer`] is finished
+    pub fn start_request(&self, labels: RequestLabels) -> RequestTimer {
+        RequestTimer { metrics: self.clone(), labels, started: Instant::now() }
+    }
+
+    /// Counts a request that took `duration` and failed with the JSON-RPC error `code`, if it failed
+    pub fn record_request(&self, labels: RequestLabels, duration: Duration, code: Option<i32>) {
+        let mut requests = self.requests.lock().unwrap();
+        let stats = requests.entry(labels).or_default();
+        stats.requests += 1;
+        stats.latency.observe(duration.as_secs_f64());
+        if let Some(code) = code {
+            *stats.errors.entry(code).or_default() += 1;
+        }
+    }
+
+    /// Counts an SSE session until the returned guard is dropped
+    pub fn open_session(&self) -> SessionGuard {
+        self.sessions.fetch_add(1, Ordering::Relaxed);
+        SessionGuard { sessions: self.sessions.clone() }
+    }
+
+    /// Counts an event that could not be queued for an SSE client
+    pub fn event_dropped(&self) {
+        self.dropped_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The requests by labels, sorted
+    pub fn requests(&self) -> Vec<(RequestLabels, RequestStats)> {
+        self.requests.lock().unwrap().iter().map(|(labels, stats)| (labels.clone(), stats.clone())).collect()
+    }
+
+    pub fn active_sessions(&self) -> i64 {
+        self.sessions.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    pub fn wasm_router(&self, router_id: &str) -> Option<Arc<WasmRouterStats>> {
+        self.routers.read().unwrap().get(router_id).and_then(|known| known.wasm.clone())
+    }
+
+    /// The stats of every registered WASM router, sorted by router id
+    pub fn wasm_routers(&self) -> Vec<(String, Arc<WasmRouterStats>)> {
+        let mut wasm: Vec<(String, Arc<WasmRouterStats>)> = self.routers.read().unwrap().iter()
+            .filter_map(|(router_id, known)| known.wasm.clone().map(|stats| (router_id.clone(), stats)))
+            .collect();
+        wasm.sort_by(|a, b| a.0.cmp(&b.0));
+        wasm
+    }
+}
+
+/// Times a request from [`Metrics::start_request`]
+pub struct RequestTimer {
+    metrics: Metrics,
+    labels: RequestLabels,
+    started: Instant,
+}
+
+impl RequestTimer {
+    /// Counts the request with the error of `reply`, if it is one, and returns its code. Requests
+    /// without a reply, e.g. cancelled ones, count as successful.
+    pub fn finish(self, reply: Option<&Value>) -> Option<i32> {
+        let code = reply
+            .and_then(|reply| reply.get("error"))
+            .and_then(|error| error.get("code"))
+            .and_then(Value::as_i64)
+            .map(|code| code as i32);
+        self.metrics.record_request(self.labels, self.started.elapsed(), code);
+        code
+    }
+}
+
+/// An open SSE session, see [`Metrics::open_session`]
+pub struct SessionGuard {
+    sessions: Arc<AtomicI64>,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.sessions.fetch_sub(1, Ordering::Relaxed);
+    }
+}
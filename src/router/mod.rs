@@ -8,7 +8,11 @@ pub mod router_service_manager;
 //pub mod actor_router_registry;
 pub mod router;
 pub mod router_actor;
+pub mod progress;
+pub mod request_context;
+pub mod request_timeouts;
 pub mod system_router;
+pub mod tool_search;
 pub mod wasm_router;
 pub mod wasix_mcp;
 
@@ -17,10 +21,13 @@ pub mod wasix_mcp;
 //pub use wasi_router_registry::WasiRouterRegistry;
 pub use topic_registry_actor::TopicRegistryActor;
 
-pub use router_service_manager::RouterServiceManager;
+pub use router_service_manager::{RouterInfo, RouterServiceManager, WasmUsage};
 //pub use actor_router_registry::ActorRouterRegistry;
 pub use router::Router;
 pub use router_actor::RouterActor;
-pub use system_router::SystemRouter;
+pub use progress::ProgressReporter;
+pub use request_context::RequestContext;
+pub use request_timeouts::RequestTimeouts;
+pub use system_router::{unwrap_proxy_calls, CatalogueEntry, RouterDescription, SystemRouter};
 pub use wasm_router::WasmRouter;
 
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix::Addr;
+use mcp_spec::protocol::{JsonRpcMessage, JsonRpcNotification};
+use serde_json::{json, Value};
+
+use crate::client::client_registry::NotifyClient;
+use crate::client::ClientRegistryActor;
+use crate::messages::{ProgressNotification, JSONRPC_VERSION};
+
+/// Progress notifications for one token are sent at most this often
+pub const PROGRESS_MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lets a router report progress on a request for which the client sent `_meta.progressToken`.
+/// Reports become `notifications/progress` to the session that sent the request. Reporting on a
+/// request without a progress token, or after its result was produced, does nothing.
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    inner: Option<Arc<ProgressInner>>,
+}
+
+struct ProgressInner {
+    token: Value,
+    client_id: u64,
+    registry: Addr<ClientRegistryActor>,
+    state: Mutex<ProgressState>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    last_sent: Option<Instant>,
+    last_progress: Option<f64>,
+    finished: bool,
+}
+
+impl ProgressReporter {
+    pub fn new(token: Value, client_id: u64, registry: Addr<ClientRegistryActor>) -> Self {
+        Self {
+            inner: Some(Arc::new(ProgressInner {
+                token,
+                client_id,
+                registry,
+                state: Mutex::new(ProgressState::default()),
+            })),
+        }
+    }
+
+    /// The progress token the client sent, if any
+    pub fn token(&self) -> Option<&Value> {
+        self.inner.as_ref().map(|inner| &inner.token)
+    }
+
+    /// Reports `progress` (out of `total` if known). Progress has to increase with every report,
+    /// reports that come too quickly after the previous one are dropped unless they complete the total.
+    pub fn report(&self, progress: f64, total: Option<f64>, message: Option<String>) {
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => return,
+        };
+        {
+            let mut state = inner.state.lock().unwrap();
+            if state.finished || state.last_progress.is_some_and(|last| progress <= last) {
+                return;
+            }
+            let completes = total.is_some_and(|total| progress >= total);
+            let now = Instant::now();
+            if !completes && state.last_sent.is_some_and(|last| now.duration_since(last) < PROGRESS_MIN_INTERVAL) {
+                return;
+            }
+            state.last_sent = Some(now);
+            state.last_progress = Some(progress);
+        }
+
+        let mut params = json!({
+            "progressToken": inner.token,
+            "progress": progress,
+        });
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        if let Some(message) = message {
+            params["message"] = Value::String(message);
+        }
+        inner.registry.do_send(NotifyClient {
+            client_id: inner.client_id,
+            message: JsonRpcMessage::Notification(JsonRpcNotification {
+                jsonrpc: JSONRPC_VERSION.to_string(),
+                method: ProgressNotification::METHOD.to_string(),
+                params: Some(params),
+            }),
+        });
+    }
+
+    /// Called once the result of the request is known, later reports are ignored
+    pub fn finish(&self) {
+        if let Some(inner) = &self.inner {
+            inner.state.lock().unwrap().finished = true;
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match &self.inner {
+            Some(inner) => inner.state.lock().unwrap().finished,
+            None => true,
+        }
+    }
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            Some(inner) => f.debug_struct("ProgressReporter")
+                .field("token", &inner.token)
+                .field("client_id", &inner.client_id)
+                .finish(),
+            None => f.write_str("ProgressReporter(disabled)"),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+use tokio_util::sync::CancellationToken;
+use tracing::Span;
+
+use crate::auth::{AccessControl, Principal};
+use crate::client::ClientHandle;
+
+use super::ProgressReporter;
+
+/// Information about where a request routed to a router comes from.
+#[derive(Clone, Debug)]
+pub struct RequestContext {
+    /// The client session that sent the request, if it came in over a session based transport.
+    pub session_id: Option<u64>,
+    /// Cancelled when the client sends `notifications/cancelled` or its session goes away.
+    pub cancellation: CancellationToken,
+    /// Turns progress reports into `notifications/progress` if the client sent a progress token.
+    pub progress: ProgressReporter,
+    /// Sends requests such as `sampling/createMessage` back to the client that sent the request.
+    pub client: Option<ClientHandle>,
+    /// The span of the request in the transport, spans of the router and WASM guest are its children.
+    pub span: Span,
+    /// Who sent the request, if the transport authenticated it.
+    pub principal: Option<Principal>,
+    /// The access policy of the transport the request came in over.
+    pub access: AccessControl,
+    /// When the router has to have answered, set by the router actor from its timeout.
+    pub deadline: Option<Instant>,
+}
+
+impl Default for RequestContext {
+    fn default() -> Self {
+        Self {
+            session_id: None,
+            cancellation: CancellationToken::new(),
+            progress: ProgressReporter::default(),
+            client: None,
+            span: Span::none(),
+            principal: None,
+            access: AccessControl::default(),
+            deadline: None,
+        }
+    }
+}
+
+impl RequestContext {
+    pub fn for_session(session_id: u64) -> Self {
+        Self { session_id: Some(session_id), ..Self::default() }
+    }
+
+    pub fn with_client(mut self, client: ClientHandle) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn with_access(mut self, principal: Option<Principal>, access: AccessControl) -> Self {
+        self.principal = principal;
+        self.access = access;
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// How long the router may still take, `None` without a deadline
+    pub fn time_left(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether the caller may use `name`, a namespaced tool, prompt or resource
+    pub fn may_use(&self, name: &str) -> bool {
+        self.access.permits(self.principal.as_ref(), name)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
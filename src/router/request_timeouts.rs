@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::router_registry::ROUTER_SEPERATOR;
+
+/// How long a request may run before it is aborted with `MCP_TIMEOUT_ERROR`.
+/// A tool timeout wins over a router timeout which wins over the default.
+#[derive(Clone, Debug)]
+pub struct RequestTimeouts {
+    pub default: Duration,
+    /// keyed by router id
+    pub routers: HashMap<String, Duration>,
+    /// keyed by the namespaced tool name as clients see it, e.g. routerid_toolname
+    pub tools: HashMap<String, Duration>,
+}
+
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        Self::new(DEFAULT_REQUEST_TIMEOUT)
+    }
+}
+
+impl RequestTimeouts {
+    pub fn new(default: Duration) -> Self {
+        Self {
+            default,
+            routers: HashMap::new(),
+            tools: HashMap::new(),
+        }
+    }
+
+    pub fn with_router_timeout(mut self, router_id: &str, timeout: Duration) -> Self {
+        self.routers.insert(router_id.to_string(), timeout);
+        self
+    }
+
+    pub fn with_tool_timeout(mut self, tool_name: &str, timeout: Duration) -> Self {
+        self.tools.insert(tool_name.to_string(), timeout);
+        self
+    }
+
+    /// Returns the timeout for a request to a router, optionally for a specific tool of that router.
+    pub fn timeout_for(&self, router_id: &str, tool_name: Option<&str>) -> Duration {
+        tool_name
+            .and_then(|tool| self.tools.get(&format!("{}{}{}", router_id, ROUTER_SEPERATOR, tool)))
+            .or_else(|| self.routers.get(router_id))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
@@ -5,6 +5,8 @@ use std::{future::Future, pin::Pin};
 
 use mcp_spec::{handler::{PromptError, ResourceError}, prompt::Prompt, protocol::{CallToolResult, GetPromptResult, PromptsCapability, ReadResourceResult, ResourcesCapability, ServerCapabilities, ToolsCapability}, Resource, Tool, ToolError};
 use serde_json::Value;
+
+use super::RequestContext;
 pub type ResponseFuture<I> = Pin<Box<dyn Future<Output = I>>>;
 pub trait Router
 where
@@ -12,6 +14,10 @@ Self: Send + Sync + 'static,
 {
     fn name(&self) -> String;
     fn instructions(&self) -> String;
+    /// Shown in the catalogue of the system router, if the router knows it
+    fn version(&self) -> Option<String> {
+        None
+    }
     fn capabilities(&self) -> ServerCapabilities;
     fn list_tools(&self) -> Vec<Tool>;
     fn call_tool(
@@ -19,11 +25,38 @@ Self: Send + Sync + 'static,
         tool_name: &str,
         arguments: Value,
     ) -> ResponseFuture<Result<CallToolResult, ToolError>>;
+    /// Like `call_tool` but with access to the request, e.g. to report progress via `context.progress`.
+    /// Routers that do not need it only implement `call_tool`.
+    fn call_tool_with_context(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        _context: RequestContext,
+    ) -> ResponseFuture<Result<CallToolResult, ToolError>> {
+        self.call_tool(tool_name, arguments)
+    }
     fn list_resources(&self) -> Vec<Resource>;
     fn read_resource(
         &self,
         uri: &str,
     ) -> ResponseFuture<Result<ReadResourceResult, ResourceError>>;
+    /// Like `read_resource` but with access to the request, e.g. to only show what the caller may use.
+    /// Routers that do not need it only implement `read_resource`.
+    fn read_resource_with_context(
+        &self,
+        uri: &str,
+        _context: RequestContext,
+    ) -> ResponseFuture<Result<ReadResourceResult, ResourceError>> {
+        self.read_resource(uri)
+    }
+    /// `resources/subscribe`, only for routers whose capabilities allow it. The router sends
+    /// `notifications/resources/updated` to `context.client` when the resource changes.
+    fn subscribe(&self, _uri: &str, _context: RequestContext) -> Result<(), ResourceError> {
+        Ok(())
+    }
+    fn unsubscribe(&self, _uri: &str, _context: RequestContext) -> Result<(), ResourceError> {
+        Ok(())
+    }
     fn list_prompts(&self) -> Vec<Prompt>;
     fn get_prompt(&self, prompt_name: &str) -> ResponseFuture<Result<GetPromptResult, PromptError>>;
 }
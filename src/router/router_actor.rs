@@ -1,28 +1,41 @@
 
 
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use actix::{Actor,Context, Handler, ResponseFuture};
 
-use mcp_spec::{handler::{PromptError, ResourceError}, prompt::Prompt, protocol::{CallToolResult, ErrorData, GetPromptResult, JsonRpcError, JsonRpcResponse, ReadResourceResult}, Resource, Tool, ToolError};
+use mcp_spec::{handler::{PromptError, ResourceError}, prompt::Prompt, protocol::{CallToolResult, GetPromptResult, JsonRpcError, JsonRpcResponse, ReadResourceResult}, Content, Resource, Tool, ToolError};
 use serde_json::{json, Value};
-use tracing::error;
+use tracing::{error, info, Instrument};
 
 
+use crate::error::McpEctorsError;
 use crate::messages::{TransportRequest, JSONRPC_VERSION};
+use crate::utils::mcp_logging::ROUTER_SPAN;
 
-use super::Router;
+use super::{RequestContext, RequestTimeouts, Router};
 
 // The `RouterActor` will wrap each `Router` and act as an actor
 pub struct RouterActor
 {
+    router_id: String, // The id the router is registered under
     router: Arc<Box<dyn Router>>, // The actual router instance
+    timeouts: Arc<RwLock<RequestTimeouts>>, // Shared with the RouterServiceManager so changes apply to running routers
 }
 
 impl RouterActor
 {
-    pub fn new(router: Arc<Box<dyn Router>>) -> Self {
-        Self { router }
+    pub fn new(router_id: String, router: Arc<Box<dyn Router>>) -> Self {
+        Self { router_id, router, timeouts: Arc::new(RwLock::new(RequestTimeouts::default())) }
+    }
+
+    pub fn with_timeouts(mut self, timeouts: Arc<RwLock<RequestTimeouts>>) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn router_id(&self) -> &str {
+        &self.router_id
     }
 }
 
@@ -59,43 +72,25 @@ impl RouterActor
     }
 }
 
-async fn handle_request(method: String, params: Value, router_clone: Arc<Box<dyn Router>>, id: Option<u64>) -> Result<JsonRpcResponse, JsonRpcError>
+async fn handle_request(method: String, params: Value, router_clone: Arc<Box<dyn Router>>, context: RequestContext) -> Result<Value, McpEctorsError>
 {
-    let result = match method.as_str() {
+    let string_param = |name: &str| params.get(name)
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string())
+        .ok_or_else(|| McpEctorsError::InvalidParams(format!("{} must be a string", name)));
+
+    match method.as_str() {
         "tools/call" => {
-            if let Some(tool_name) = params.get("name").and_then(|v| v.as_str()) {
-                let arguments = params.get("arguments").cloned().unwrap_or_default();
-                let call_result = router_clone.call_tool(tool_name, arguments).await;
-                match call_result {
-                    Ok(content) => Ok(json!(content)),
-                    Err(e) => {
-                        error!("Failed to call tool: {:?}", e);
-                        let error_response = JsonRpcResponse {
-                            jsonrpc: JSONRPC_VERSION.to_string(),
-                            id,
-                            result: None,
-                            error: Some(ErrorData{
-                                code: -32603, 
-                                message: "Tool execution failed".to_string(),
-                                data: Some(json!({
-                                    "error": format!("{:?}", e)  // Provide the error details
-                                })),
-                            }),
-                        };
-                        Err(error_response)
-                    }
+            let tool_name = string_param("name")?;
+            let arguments = params.get("arguments").cloned().unwrap_or_default();
+            match router_clone.call_tool_with_context(&tool_name, arguments, context).await {
+                Ok(content) => Ok(json!(content)),
+                // unknown tools and bad arguments are protocol errors, everything else is reported to the LLM in the result
+                Err(e @ (ToolError::NotFound(_) | ToolError::InvalidParameters(_))) => Err(McpEctorsError::from(e)),
+                Err(e) => {
+                    error!("Failed to call tool {}: {:?}", tool_name, e);
+                    Ok(json!(CallToolResult { content: vec![Content::text(e.to_string())], is_error: Some(true) }))
                 }
-            } else {
-                Err(JsonRpcResponse {
-                    jsonrpc: JSONRPC_VERSION.to_string(),
-                    id,
-                    result: None,
-                    error: Some(ErrorData {
-                        code: -32602, // Invalid params error code
-                        message: "Invalid parameters for call_tool".to_string(),
-                        data: None,
-                    }),
-                })
             }
         },
         "tools/list" => {
@@ -108,109 +103,41 @@ async fn handle_request(method: String, params: Value, router_clone: Arc<Box<dyn
             Ok(json!(resources))
         },
         "resources/read" => {
-            if let Some(uri) = params.get("uri").and_then(|v| v.as_str()) {
-                let read_result = router_clone.read_resource(uri).await;
-                match read_result {
-                    Ok(content) => Ok(json!(content)),
-                    Err(e) => {
-                        error!("Failed to read resource: {:?}", e);
-                        let error_response = JsonRpcResponse {
-                            jsonrpc: JSONRPC_VERSION.to_string(),
-                            id,
-                            result: None,
-                            error: Some(ErrorData{
-                                code: -32603, 
-                                message: "Resource execution failed".to_string(),
-                                data: Some(json!({
-                                    "error": format!("{:?}", e)  // Provide the error details
-                                })),
-                            }),
-                        };
-                        Err(error_response)
-                    }
-                }
-            } else {
-                Err(JsonRpcResponse {
-                    jsonrpc: JSONRPC_VERSION.to_string(),
-                    id,
-                    result: None,
-                    error: Some(ErrorData {
-                        code: -32602, // Invalid params error code
-                        message: "Invalid parameters for read resource".to_string(),
-                        data: None,
-                    }),
+            let uri = string_param("uri")?;
+            router_clone.read_resource_with_context(&uri, context).await
+                .map(|content| json!(content))
+                .map_err(|e| {
+                    error!("Failed to read resource {}: {:?}", uri, e);
+                    McpEctorsError::from(e)
                 })
+        },
+        "resources/subscribe" | "resources/unsubscribe" => {
+            let subscribe = router_clone.capabilities().resources.and_then(|resources| resources.subscribe).unwrap_or(false);
+            if !subscribe {
+                return Err(McpEctorsError::MethodNotFound(method));
             }
+            let uri = string_param("uri")?;
+            let result = if method == "resources/subscribe" {
+                router_clone.subscribe(&uri, context)
+            } else {
+                router_clone.unsubscribe(&uri, context)
+            };
+            result.map(|_| json!({})).map_err(McpEctorsError::from)
         },
         "prompts/list" => {
             let prompts = router_clone.list_prompts();
             Ok(json!(prompts))
         },
         "prompts/get" => {
-            if let Some(prompt_name) = params.get("name").and_then(|v| v.as_str()) {
-                let prompt_result = router_clone.get_prompt(prompt_name).await;
-                match prompt_result {
-                    Ok(prompt) => Ok(json!(prompt)),
-                    Err(e) => {
-                        error!("Failed to call prompt: {:?}", e);
-                        let error_response = JsonRpcResponse {
-                            jsonrpc: JSONRPC_VERSION.to_string(),
-                            id,
-                            result: None,
-                            error: Some(ErrorData{
-                                code: -32603, 
-                                message: "Prompt execution failed".to_string(),
-                                data: Some(json!({
-                                    "error": format!("{:?}", e)  // Provide the error details
-                                })),
-                            }),
-                        };
-                        Err(error_response)
-                    }
-                }
-            } else {
-                Err(JsonRpcResponse {
-                    jsonrpc: JSONRPC_VERSION.to_string(),
-                    id,
-                    result: None,
-                    error: Some(ErrorData {
-                        code: -32602, // Invalid params error code
-                        message: "Invalid parameters for call_prompt".to_string(),
-                        data: None,
-                    }),
+            let prompt_name = string_param("name")?;
+            router_clone.get_prompt(&prompt_name).await
+                .map(|prompt| json!(prompt))
+                .map_err(|e| {
+                    error!("Failed to get prompt {}: {:?}", prompt_name, e);
+                    McpEctorsError::from(e)
                 })
-            }
         },
-        _ => Err(JsonRpcResponse {
-            jsonrpc: JSONRPC_VERSION.to_string(),
-            id,
-            result: None,
-            error: Some(ErrorData {
-                code: -32602, // Invalid params error code
-                message: "unsupported method".to_string(),
-                data: None,
-            }),
-        })
-    };
-
-    // Create a JsonRpcResponse based on the result of the method
-    match result {
-        Ok(data) => Ok(JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            id,
-            result: Some(data),
-            error: None,
-        }),
-        Err(_) => Ok(JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            id,
-            result: None,
-            error: Some(ErrorData{
-                code: -32602,
-                message: "Invalid params".to_string(),
-                data: None,
-            }),
-        }),
+        _ => Err(McpEctorsError::MethodNotFound(method)),
     }
 }
 
@@ -223,15 +150,43 @@ impl Handler<TransportRequest> for RouterActor
     fn handle(&mut self, msg: TransportRequest, _ctx: &mut Self::Context) -> Self::Result {
         // Assuming TransportRequest contains the method name and parameters for the router
         let method = msg.request.method.clone();
-        let params = msg.request.params.unwrap();
+        let params = msg.request.params.unwrap_or_default();
         let router_clone = Arc::clone(&self.router);
         let id = msg.request.id;
+        let tool_name = match method.as_str() {
+            "tools/call" => params.get("name").and_then(|v| v.as_str()).map(|name| name.to_string()),
+            _ => None,
+        };
+        let timeout = self.timeouts.read().unwrap().timeout_for(&self.router_id, tool_name.as_deref());
+        let cancellation = msg.context.cancellation.clone();
+        let progress = msg.context.progress.clone();
+        // everything the router logs while handling this request is tagged with the router and session
+        let span = tracing::info_span!(parent: &msg.context.span, ROUTER_SPAN, router_id = %self.router_id, session_id = tracing::field::Empty);
+        if let Some(session_id) = msg.context.session_id {
+            span.record("session_id", session_id);
+        }
+        let context = msg.context.with_deadline(std::time::Instant::now() + timeout);
         Box::pin(async move {
-            handle_request(method, params, router_clone, id).await
-            
-        })
+            // dropping the router future aborts it, wasm routers interrupt the guest when that happens
+            let response = tokio::select! {
+                _ = cancellation.cancelled() => {
+                    info!("Request {:?} ({}) was cancelled", id, method);
+                    Err(McpEctorsError::Cancelled)
+                },
+                result = tokio::time::timeout(timeout, handle_request(method.clone(), params, router_clone, context)) => match result {
+                    Ok(response) => response,
+                    Err(_) => {
+                        error!("Request {:?} ({}) timed out after {:?}", id, method, timeout);
+                        Err(McpEctorsError::Timeout(timeout))
+                    }
+                },
+            };
+            let response = response
+                .map(|result| JsonRpcResponse { jsonrpc: JSONRPC_VERSION.to_string(), id, result: Some(result), error: None })
+                .map_err(|e| e.to_json_rpc_error(id));
+            // no progress notifications may follow the result
+            progress.finish();
+            response
+        }.instrument(span))
     }
-
-        
-    
 }
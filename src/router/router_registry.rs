@@ -1,8 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use actix::{Actor, Addr, Context, Handler};
 
-use crate::messages::{GetRouter, RegisterRouter, UnregisterRouter};
+use crate::error::McpEctorsError;
+use crate::messages::{GetRouter, ListRouters, RegisterAlias, RegisterRouter, ReplaceRouter, ResolveAlias, SetRouterEnabled, UnregisterAlias, UnregisterRouter};
+use crate::telemetry::GET_ROUTER_SPAN;
 
 use super::RouterActor;
 
@@ -17,6 +19,10 @@ pub trait RouterRegistry {
 #[derive(Clone)]
 pub struct ActorRouterRegistry{
     routers: HashMap<String, Addr<RouterActor>>,
+    // alias -> router id
+    aliases: HashMap<String, String>,
+    // registered routers GetRouter does not return
+    disabled: HashSet<String>,
 }
 
 impl Actor for ActorRouterRegistry
@@ -28,6 +34,18 @@ impl ActorRouterRegistry{
     pub fn new() -> Self{
         Self{
             routers: HashMap::new(),
+            aliases: HashMap::new(),
+            disabled: HashSet::new(),
+        }
+    }
+
+    /// `name` with an alias in front replaced by the id of its router
+    fn resolve_alias(&self, name: String) -> String {
+        let (router_id, action) = split_at_seperator(name.clone());
+        match (self.aliases.get(&router_id), action) {
+            (Some(target), Some(action)) => format!("{}{}{}", target, ROUTER_SEPERATOR, action),
+            (Some(target), None) => target.clone(),
+            (None, _) => name,
         }
     }
 }
@@ -70,27 +88,43 @@ impl RouterRegistry for ActorRouterRegistry
     }
 }
 
+impl Handler<ListRouters> for ActorRouterRegistry {
+    type Result = Vec<(String, Addr<RouterActor>)>;
+
+    fn handle(&mut self, _msg: ListRouters, _: &mut Self::Context) -> Self::Result {
+        let mut routers: Vec<(String, Addr<RouterActor>)> = self.routers.iter()
+            .filter(|(router_id, _)| !self.disabled.contains(*router_id))
+            .map(|(router_id, router)| (router_id.clone(), router.clone()))
+            .collect();
+        routers.sort_by(|a, b| a.0.cmp(&b.0));
+        routers
+    }
+}
+
 impl Handler<GetRouter> for ActorRouterRegistry {
     type Result = Option<(Addr<RouterActor>,String)>;
 
     fn handle(&mut self, msg: GetRouter, _: &mut Self::Context) -> Self::Result {
-        let (router_id, action_opt) = split_at_seperator(msg.router_id.clone());
+        let span = tracing::info_span!(parent: &msg.span, GET_ROUTER_SPAN, name = %msg.router_id);
+        let _entered = span.enter();
+        let (router_id, action_opt) = split_at_seperator(self.resolve_alias(msg.router_id));
         let action = action_opt.unwrap_or(router_id.clone());
         self.routers.get(&router_id)
+            .filter(|_| !self.disabled.contains(&router_id))
             .cloned() // If router exists, clone and return it
             .map(|router| (router, action))
     }
 }
 
 impl Handler<RegisterRouter> for ActorRouterRegistry {
-    type Result = Result<(), ()>;
+    type Result = Result<(), McpEctorsError>;
 
     fn handle(&mut self, msg: RegisterRouter, _: &mut Self::Context) -> Self::Result {
-        if msg.router_id.contains("_") {
-            return Err(()); // Or handle the error appropriately
+        if msg.router_id.contains(ROUTER_SEPERATOR) {
+            return Err(McpEctorsError::InvalidRouterId(msg.router_id));
         }
-        if self.routers.contains_key(&msg.router_id) {
-            return Err(()); // Handle the error (router already exists)
+        if self.routers.contains_key(&msg.router_id) || self.aliases.contains_key(&msg.router_id) {
+            return Err(McpEctorsError::RouterExists(msg.router_id));
         }
 
         // Register the router with the given ID
@@ -105,6 +139,72 @@ impl Handler<UnregisterRouter> for ActorRouterRegistry {
 
     fn handle(&mut self, msg: UnregisterRouter, _: &mut Self::Context) {
         self.routers.remove(&msg.router_id);
+        self.disabled.remove(&msg.router_id);
+        self.aliases.retain(|_, router_id| *router_id != msg.router_id);
+    }
+}
+
+impl Handler<SetRouterEnabled> for ActorRouterRegistry {
+    type Result = Result<(), McpEctorsError>;
+
+    fn handle(&mut self, msg: SetRouterEnabled, _: &mut Self::Context) -> Self::Result {
+        if !self.routers.contains_key(&msg.router_id) {
+            return Err(McpEctorsError::RouterNotFound(msg.router_id));
+        }
+        if msg.enabled {
+            self.disabled.remove(&msg.router_id);
+        } else {
+            self.disabled.insert(msg.router_id);
+        }
+        Ok(())
+    }
+}
+
+impl Handler<ReplaceRouter> for ActorRouterRegistry {
+    type Result = Result<(), McpEctorsError>;
+
+    fn handle(&mut self, msg: ReplaceRouter, _: &mut Self::Context) -> Self::Result {
+        match self.routers.get_mut(&msg.router_id) {
+            Some(router) => {
+                *router = msg.router_addr;
+                Ok(())
+            }
+            None => Err(McpEctorsError::RouterNotFound(msg.router_id)),
+        }
+    }
+}
+
+impl Handler<RegisterAlias> for ActorRouterRegistry {
+    type Result = Result<(), McpEctorsError>;
+
+    fn handle(&mut self, msg: RegisterAlias, _: &mut Self::Context) -> Self::Result {
+        if msg.alias.is_empty() || msg.alias.contains(ROUTER_SEPERATOR) {
+            return Err(McpEctorsError::InvalidRouterId(msg.alias));
+        }
+        if self.routers.contains_key(&msg.alias) || self.aliases.contains_key(&msg.alias) {
+            return Err(McpEctorsError::RouterExists(msg.alias));
+        }
+        if !self.routers.contains_key(&msg.router_id) {
+            return Err(McpEctorsError::RouterNotFound(msg.router_id));
+        }
+        self.aliases.insert(msg.alias, msg.router_id);
+        Ok(())
+    }
+}
+
+impl Handler<UnregisterAlias> for ActorRouterRegistry {
+    type Result = ();
+
+    fn handle(&mut self, msg: UnregisterAlias, _: &mut Self::Context) {
+        self.aliases.remove(&msg.alias);
+    }
+}
+
+impl Handler<ResolveAlias> for ActorRouterRegistry {
+    type Result = String;
+
+    fn handle(&mut self, msg: ResolveAlias, _: &mut Self::Context) -> Self::Result {
+        self.resolve_alias(msg.name)
     }
 }
 
@@ -1,13 +1,20 @@
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use actix::{Actor, Addr};
-use tracing::info;
+use tracing::{error, info};
 use notify::{Error, Event, EventKind, RecommendedWatcher, Watcher};
-use crate::messages::{GetRouter, RegisterRouter, UnregisterRouter};
-use crate::{mcp::{ListPromptsActor, ListToolsActor, ListResourcesActor}, messages::{AddPromptsRequest, AddResourcesRequest, AddToolsRequest}};
-use super::wasm_router::spawn_wasm_router;
+use crate::error::McpEctorsError;
+use crate::health::{Health, RouterHealth, RouterState};
+use crate::metrics::Metrics;
+use crate::messages::{GetRouter, ListRouters, RegisterAlias, RegisterRouter, ReplaceRouter, SetLazyTools, SetRouterEnabled, UnregisterAlias, UnregisterRouter};
+use crate::{mcp::{ListPromptsActor, ListToolsActor, ListResourcesActor}, messages::{AddPromptsRequest, AddResourcesRequest, AddToolsRequest, RemovePromptsRequest, RemoveResourcesRequest, RemoveToolsRequest}};
+use mcp_spec::{prompt::Prompt, protocol::ServerCapabilities, Resource, Tool};
+use serde::Serialize;
+use super::wasm_router::{spawn_wasm_router, validate_component, HEALTH_INTERVAL};
 use super::WasmRouter;
-use super::{router_registry::ActorRouterRegistry, Router, RouterActor, SystemRouter};
+use super::{router_registry::ActorRouterRegistry, system_router::SYSTEM_ROUTER_ID, RouterDescription, RequestTimeouts, Router, RouterActor, SystemRouter};
 
 pub enum RegistryType {
     Native,
@@ -19,6 +26,60 @@ pub struct RouterServiceManager {
     list_tools: Addr<ListToolsActor>,
     list_resources: Addr<ListResourcesActor>,
     active_registry: Addr<ActorRouterRegistry>,
+    timeouts: Arc<RwLock<RequestTimeouts>>,
+    health_interval: Arc<RwLock<Duration>>,
+    // router id -> what it added to the lists, so unregistering removes it again
+    listed: Arc<Mutex<HashMap<String, Listed>>>,
+    // wasm directory -> the ids of the routers loaded from it
+    directories: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    metrics: Metrics,
+    health: Health,
+    // shares its catalogue state with the actor it is registered in
+    system: SystemRouter,
+}
+
+#[derive(Clone)]
+struct Listed {
+    router: Addr<RouterActor>,
+    name: String,
+    instructions: String,
+    version: Option<String>,
+    capabilities: ServerCapabilities,
+    // the WASM component the router was loaded from
+    path: Option<PathBuf>,
+    enabled: bool,
+    tools: Vec<Tool>,
+    prompts: Vec<Prompt>,
+    resources: Vec<Resource>,
+}
+
+/// A registered router as the admin API shows it
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RouterInfo {
+    pub router_id: String,
+    pub name: String,
+    pub instructions: String,
+    /// The WASM component the router was loaded from, none for native routers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub enabled: bool,
+    pub state: RouterState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub tools: usize,
+    pub prompts: usize,
+    pub resources: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wasm: Option<WasmUsage>,
+}
+
+/// What the WASM instance of a router has used so far
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WasmUsage {
+    pub instantiations: u64,
+    pub fuel_consumed: u64,
+    pub memory_high_water: usize,
+    pub queue_depth: usize,
 }
 
 impl RouterServiceManager {
@@ -27,21 +88,58 @@ impl RouterServiceManager {
         let list_prompts = ListPromptsActor::new().start();
         let list_tools = ListToolsActor::new().start();
         let list_resources = ListResourcesActor::new().start();
+        let system = SystemRouter::new(active_registry.clone(), list_tools.clone(), list_prompts.clone(), list_resources.clone());
 
         Self {
             list_prompts,
             list_tools,
             list_resources,
             active_registry,
+            timeouts: Arc::new(RwLock::new(RequestTimeouts::default())),
+            health_interval: Arc::new(RwLock::new(HEALTH_INTERVAL)),
+            listed: Arc::new(Mutex::new(HashMap::new())),
+            directories: Arc::new(Mutex::new(HashMap::new())),
+            metrics: Metrics::default(),
+            health: Health::default(),
+            system,
         }
     }
 
+    /// Replaces the request timeouts, this also applies to routers that are already registered
+    pub fn set_timeouts(&self, timeouts: RequestTimeouts) {
+        *self.timeouts.write().unwrap() = timeouts;
+    }
+
+    /// Whether `tools/list` only lists the tool discovery tools of the system router, see [`SystemRouter`]
+    pub fn set_lazy_tools(&self, lazy: bool) {
+        self.list_tools.do_send(SetLazyTools { lazy });
+    }
+
+    /// How often the `health` export of WASM routers is called, for those loaded from now on
+    pub fn set_health_interval(&self, interval: Duration) {
+        *self.health_interval.write().unwrap() = interval;
+    }
+
+    pub fn get_timeouts(&self) -> RequestTimeouts {
+        self.timeouts.read().unwrap().clone()
+    }
+
+    /// The metrics of the routers, the transports count their requests in them too
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.clone()
+    }
+
+    /// The state of the routers and whether the server is ready, the transports serve it
+    pub fn health(&self) -> Health {
+        self.health.clone()
+    }
+
     pub async fn default(wasm_path: Option<String>) -> Self {
 
         let mut manager = RouterServiceManager::new();
-        let system = SystemRouter::new();
+        let system = manager.system.clone();
         let _ = manager
-            .register_router::<SystemRouter>("system".to_string(), Box::new(system))
+            .register_router::<SystemRouter>(SYSTEM_ROUTER_ID.to_string(), Box::new(system))
             .await;
         
         // Optionally handle the wasm directory at startup by registering all existing wasm routers
@@ -58,7 +156,6 @@ impl RouterServiceManager {
             });
             */
         }
-
         manager
     }
 
@@ -66,6 +163,7 @@ impl RouterServiceManager {
     async fn _watch_wasm_directory(&self, wasm_path: Arc<String>) -> Result<(), Box<dyn std::error::Error>> {
         let (_tx, rx) = std::sync::mpsc::channel::<Event>();
         let rsm = Arc::new(Mutex::new(self.clone()));
+        let health_interval = *self.health_interval.read().unwrap();
         let mut watcher = RecommendedWatcher::new(move |result: Result<Event, Error>| {
             match result {
                 Ok(event) => {
@@ -82,14 +180,14 @@ impl RouterServiceManager {
                                     match event.kind {
                                         EventKind::Create(_) => {
                                             println!("Wasm file created: {:?}", path);
-                                            let router = create_wasm_router(path);
+                                            let router = create_wasm_router(path, health_interval);
                                             let _ = rsm.lock().unwrap().register_router::<WasmRouter>(router_id.clone(),router);
                                         }
                                         EventKind::Modify(_) => {
                                             // this gets called twice. One time with the old and one time with the new
                                             if path.exists() {
                                                 println!("Wasm file modified - new name: {:?}", path);
-                                                let router = create_wasm_router(path);
+                                                let router = create_wasm_router(path, health_interval);
                                                 let _ = rsm.lock().unwrap().register_router::<WasmRouter>(router_id.clone(),router);
                                             } else {
                                                 println!("Wasm file modified - oldname: {:?}", path);
@@ -123,82 +221,321 @@ impl RouterServiceManager {
     }
 
 
+    /// Registers the WASM routers of another directory, e.g. one of `[routers] directories`
+    pub async fn load_wasm_directory(&mut self, wasm_path: &str) -> Result<(), McpEctorsError> {
+        if !Path::new(wasm_path).is_dir() {
+            return Err(McpEctorsError::Internal(format!("{} is not a directory", wasm_path)));
+        }
+        self.scan_and_register_wasm_files(Arc::new(wasm_path.to_string())).await;
+        Ok(())
+    }
+
+    /// Unregisters the WASM routers loaded from a directory
+    pub async fn unload_wasm_directory(&mut self, wasm_path: &str) -> Result<(), McpEctorsError> {
+        let router_ids = self.directories.lock().unwrap().remove(wasm_path).unwrap_or_default();
+        for router_id in router_ids {
+            self.unregister_router(&router_id).await?;
+        }
+        Ok(())
+    }
+
+    /// The directories WASM routers were loaded from
+    pub fn wasm_directories(&self) -> Vec<String> {
+        let mut directories: Vec<String> = self.directories.lock().unwrap().keys().cloned().collect();
+        directories.sort();
+        directories
+    }
+
+    /// Lets clients call the router `router_id` as `alias` too
+    pub async fn add_alias(&self, alias: &str, router_id: &str) -> Result<(), McpEctorsError> {
+        self.active_registry
+            .send(RegisterAlias { alias: alias.to_string(), router_id: router_id.to_string() })
+            .await??;
+        info!("Router {} is also called {}", router_id, alias);
+        Ok(())
+    }
+
+    pub async fn remove_alias(&self, alias: &str) -> Result<(), McpEctorsError> {
+        self.active_registry.send(UnregisterAlias { alias: alias.to_string() }).await?;
+        info!("Removed router alias {}", alias);
+        Ok(())
+    }
+
     // Recursively find all Wasm files in the directory and register them
     async fn scan_and_register_wasm_files(&mut self, wasm_path: Arc<String>) {
         let paths = std::fs::read_dir(Path::new(wasm_path.as_ref())).unwrap();
+        let mut router_ids = vec![];
 
         for entry in paths {
             if let Ok(entry) = entry {
                 let path = entry.path();
                 if path.extension().and_then(|e| e.to_str()) == Some("wasm") {
-                    let router = create_wasm_router(&path);
                     let router_id = path.file_stem()
                         .and_then(|name| name.to_str())  // Get the file name without the extension
                         .unwrap_or("defaultname")       // Provide a default name in case of failure
                         .replace('_', "")               // Replace all underscores
                         .to_string();
                     
-                    let _ = self.register_router::<WasmRouter>(router_id.clone(), router).await;
+                    if self.load_wasm_router(&router_id, &path).await.is_ok() {
+                        router_ids.push(router_id);
+                    }
                 }
             }
         }
+        self.directories.lock().unwrap().entry(wasm_path.to_string()).or_default().extend(router_ids);
+    }
+
+    /// Registers the WASM component at `path` as `router_id`
+    pub async fn load_wasm_router(&mut self, router_id: &str, path: &Path) -> Result<(), McpEctorsError> {
+        let router = create_wasm_router(path, *self.health_interval.read().unwrap());
+        let stats = router.stats();
+        let health = router.health();
+        self.register_router::<WasmRouter>(router_id.to_string(), router).await?;
+        self.metrics.add_wasm_router(router_id, stats);
+        self.health.add_router(router_id, health);
+        if let Some(listed) = self.listed.lock().unwrap().get_mut(router_id) {
+            listed.path = Some(path.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Creates the WASM router `router_id` anew from `path`, or from the file it was loaded from
+    /// without one. Clients go on calling it by its id and aliases, and it stays disabled if it was.
+    /// The component is checked first, the old instance stays if it is no router.
+    pub async fn reload_wasm_router(&mut self, router_id: &str, path: Option<&Path>) -> Result<(), McpEctorsError> {
+        let (loaded_from, enabled) = match self.listed.lock().unwrap().get(router_id) {
+            Some(listed) => (listed.path.clone(), listed.enabled),
+            None => return Err(McpEctorsError::RouterNotFound(router_id.to_string())),
+        };
+        let path = path.map(Path::to_path_buf)
+            .or(loaded_from)
+            .ok_or_else(|| McpEctorsError::InvalidParams(format!("Router {} is not a WASM router", router_id)))?;
+        let bytes = std::fs::read(&path)
+            .map_err(|e| McpEctorsError::Internal(format!("Could not read {}: {}", path.display(), e)))?;
+        tokio::task::spawn_blocking(move || validate_component(&bytes))
+            .await
+            .map_err(|e| McpEctorsError::Internal(e.to_string()))??;
+        let router = create_wasm_router(&path, *self.health_interval.read().unwrap());
+        let stats = router.stats();
+        let health = router.health();
+        let mut listed = self.start_router(router_id, router);
+        listed.path = Some(path);
+        listed.enabled = enabled;
+        self.active_registry
+            .send(ReplaceRouter { router_id: router_id.to_string(), router_addr: listed.router.clone() })
+            .await??;
+
+        self.metrics.add_router(router_id, listed.tools.iter().map(|tool| tool.name.clone()));
+        self.metrics.add_wasm_router(router_id, stats);
+        self.health.add_router(router_id, health);
+        // the lists match tools by value, so the old ones go before the new ones come
+        let previous = self.listed.lock().unwrap().insert(router_id.to_string(), listed.clone());
+        if enabled {
+            if let Some(previous) = previous {
+                self.remove_from_lists(router_id, previous);
+            }
+            self.add_to_lists(router_id, &listed);
+        }
+        info!("Reloaded router {}", router_id);
+        self.catalogue_changed(router_id).await;
+        Ok(())
+    }
+
+    /// Disabled routers stay registered, with their aliases, but clients can neither list nor call them
+    pub async fn set_router_enabled(&self, router_id: &str, enabled: bool) -> Result<(), McpEctorsError> {
+        self.active_registry
+            .send(SetRouterEnabled { router_id: router_id.to_string(), enabled })
+            .await??;
+        {
+            let mut routers = self.listed.lock().unwrap();
+            let listed = routers.get_mut(router_id).ok_or_else(|| McpEctorsError::RouterNotFound(router_id.to_string()))?;
+            if listed.enabled == enabled {
+                return Ok(());
+            }
+            listed.enabled = enabled;
+            if enabled {
+                self.add_to_lists(router_id, listed);
+            } else {
+                self.remove_from_lists(router_id, listed.clone());
+            }
+        }
+        info!("Router {} is {}", router_id, if enabled { "enabled" } else { "disabled" });
+        self.catalogue_changed(router_id).await;
+        Ok(())
     }
 
     // Register the router
-    pub async fn register_router<T: Router>(&mut self, router_id: String, router: Box<dyn Router>) -> Result<(), String> {
+    pub async fn register_router<T: Router>(&mut self, router_id: String, router: Box<dyn Router>) -> Result<(), McpEctorsError> {
+        let listed = self.start_router(&router_id, router);
+
+        info!("Registering router {} at {:?}", router_id.clone(), listed.router.clone());
+        //self.active_registry.register_router(router_id.clone(), router_addr.clone())?;
+        if let Err(e) = self.active_registry
+        .send(RegisterRouter { router_id: router_id.to_string(), router_addr: listed.router.clone(), capabilities: Some(listed.capabilities.clone())})
+        .await? {
+            error!("Failed to register router {}: {}", router_id, e);
+            return Err(e);
+        }
+
+        self.metrics.add_router(&router_id, listed.tools.iter().map(|tool| tool.name.clone()));
+        self.health.add_router(&router_id, Arc::new(RouterHealth::default()));
+        self.add_to_lists(&router_id, &listed);
+        self.listed.lock().unwrap().insert(router_id.clone(), listed);
+        self.catalogue_changed(&router_id).await;
+
+        Ok(())
+    }
+
+    // Starts the actor of a router and asks the router what it offers
+    fn start_router(&self, router_id: &str, router: Box<dyn Router>) -> Listed {
         let tools = router.list_tools();
         let resources = router.list_resources();
         let prompts = router.list_prompts();
         let capabilities = router.capabilities().clone();
-        let router_addr = RouterActor::new(Arc::new(router)).start();
-
-        info!("Registering router {} at {:?}", router_id.clone(), router_addr.clone());
-        //self.active_registry.register_router(router_id.clone(), router_addr.clone())?;
-        let _ = self.active_registry
-        .send(RegisterRouter { router_id: router_id.to_string(), router_addr: router_addr.clone(), capabilities: Some(capabilities)})
-        .await
-        .unwrap();
+        let name = router.name();
+        let instructions = router.instructions();
+        let version = router.version();
+        let router_addr = RouterActor::new(router_id.to_string(), Arc::new(router))
+            .with_timeouts(self.timeouts.clone())
+            .start();
+        Listed { router: router_addr, name, instructions, version, capabilities, path: None, enabled: true, tools, prompts, resources }
+    }
 
-        if prompts.len() > 0 {
+    // Lets clients list what the router offers
+    fn add_to_lists(&self, router_id: &str, listed: &Listed) {
+        if !listed.prompts.is_empty() {
             self.list_prompts.do_send(AddPromptsRequest {
-                router_id: router_id.clone(),
-                prompts,
-                router: router_addr.clone(),
+                router_id: router_id.to_string(),
+                prompts: listed.prompts.clone(),
+                router: listed.router.clone(),
             });
         }
-        if tools.len() > 0 { 
+        if !listed.tools.is_empty() {
             self.list_tools.do_send(AddToolsRequest {
-                router_id: router_id.clone(),
-                tools,
-                router: router_addr.clone(),
+                router_id: router_id.to_string(),
+                tools: listed.tools.clone(),
+                router: listed.router.clone(),
             });
         }
-        if resources.len() > 0 {
+        if !listed.resources.is_empty() {
             self.list_resources.do_send(AddResourcesRequest {
-                router_id: router_id.clone(),
-                resources,
-                router: router_addr.clone(),
+                router_id: router_id.to_string(),
+                resources: listed.resources.clone(),
+                router: listed.router.clone(),
             });
         }
+    }
 
-        Ok(())
+    // Lists the resources of the system router for the routers clients can use now, and tells
+    // the subscribers of the catalogue
+    async fn catalogue_changed(&self, router_id: &str) {
+        let Ok(routers) = self.active_registry.send(ListRouters).await else {
+            return;
+        };
+        // described by what the routers said when they were registered, asking them again could block on a WASM call
+        let described = {
+            let listed = self.listed.lock().unwrap();
+            routers.into_iter()
+                .filter_map(|(router_id, _)| {
+                    let listed = listed.get(&router_id)?;
+                    let description = RouterDescription {
+                        name: listed.name.clone(),
+                        instructions: listed.instructions.clone(),
+                        version: listed.version.clone(),
+                        capabilities: listed.capabilities.clone(),
+                    };
+                    Some((router_id, description))
+                })
+                .collect()
+        };
+        self.system.set_routers(described);
+        let resources = self.system.list_resources();
+        let listed = self.listed.lock().unwrap().get_mut(SYSTEM_ROUTER_ID)
+            .map(|system| (std::mem::replace(&mut system.resources, resources.clone()), system.router.clone(), system.enabled));
+        if let Some((previous, router, true)) = listed {
+            // the lists match resources by value, so the old ones go before the new ones come
+            self.list_resources.do_send(RemoveResourcesRequest { router_id: SYSTEM_ROUTER_ID.to_string(), resources: previous, router: router.clone() });
+            self.list_resources.do_send(AddResourcesRequest { router_id: SYSTEM_ROUTER_ID.to_string(), resources, router });
+        }
+        self.system.catalogue_changed(router_id).await;
+    }
+
+    fn remove_from_lists(&self, router_id: &str, listed: Listed) {
+        let Listed { router, tools, prompts, resources, .. } = listed;
+        self.list_tools.do_send(RemoveToolsRequest { router_id: router_id.to_string(), tools, router: router.clone() });
+        self.list_prompts.do_send(RemovePromptsRequest { router_id: router_id.to_string(), prompts, router: router.clone() });
+        self.list_resources.do_send(RemoveResourcesRequest { router_id: router_id.to_string(), resources, router });
     }
 
     // Unregister the router
-    pub async fn unregister_router(&mut self, router_id: &str) -> Result<(), String> {
+    pub async fn unregister_router(&mut self, router_id: &str) -> Result<(), McpEctorsError> {
         // Unregister the router
-        let _ = self.active_registry
+        self.active_registry
         .send(UnregisterRouter { router_id: router_id.to_string() })
-        .await
-        .unwrap();
+        .await?;
+
+        let listed = self.listed.lock().unwrap().remove(router_id);
+        if let Some(listed) = listed.filter(|listed| listed.enabled) {
+            self.remove_from_lists(router_id, listed);
+        }
+        self.metrics.remove_router(router_id);
+        self.health.remove_router(router_id);
         
         info!("Unregistered router: {}", router_id);
+        self.catalogue_changed(router_id).await;
         Ok(())
     }
 
+    /// Every registered router with what it offers, its state and its usage, sorted by id
+    pub fn routers(&self) -> Vec<RouterInfo> {
+        let listed = self.listed.lock().unwrap();
+        let mut routers: Vec<RouterInfo> = listed.iter().map(|(router_id, listed)| self.router_info(router_id, listed)).collect();
+        routers.sort_by(|a, b| a.router_id.cmp(&b.router_id));
+        routers
+    }
+
+    pub fn router(&self, router_id: &str) -> Option<RouterInfo> {
+        self.listed.lock().unwrap().get(router_id).map(|listed| self.router_info(router_id, listed))
+    }
+
+    /// The tools of a router, without the router prefix clients use
+    pub fn router_tools(&self, router_id: &str) -> Option<Vec<Tool>> {
+        self.listed.lock().unwrap().get(router_id).map(|listed| listed.tools.clone())
+    }
+
+    pub fn router_prompts(&self, router_id: &str) -> Option<Vec<Prompt>> {
+        self.listed.lock().unwrap().get(router_id).map(|listed| listed.prompts.clone())
+    }
+
+    pub fn router_resources(&self, router_id: &str) -> Option<Vec<Resource>> {
+        self.listed.lock().unwrap().get(router_id).map(|listed| listed.resources.clone())
+    }
+
+    fn router_info(&self, router_id: &str, listed: &Listed) -> RouterInfo {
+        let status = self.health.router(router_id);
+        RouterInfo {
+            router_id: router_id.to_string(),
+            name: listed.name.clone(),
+            instructions: listed.instructions.clone(),
+            path: listed.path.as_ref().map(|path| path.display().to_string()),
+            enabled: listed.enabled,
+            state: status.as_ref().map(|status| status.state).unwrap_or(RouterState::Ready),
+            last_error: status.and_then(|status| status.last_error),
+            tools: listed.tools.len(),
+            prompts: listed.prompts.len(),
+            resources: listed.resources.len(),
+            wasm: self.metrics.wasm_router(router_id).map(|stats| WasmUsage {
+                instantiations: stats.instantiation().count,
+                fuel_consumed: stats.fuel_consumed(),
+                memory_high_water: stats.memory_high_water(),
+                queue_depth: stats.queue_depth(),
+            }),
+        }
+    }
+
     pub async fn get_router(&self, action: String) -> Option<(Addr<RouterActor>, String)> {
         self.active_registry
-        .send(GetRouter { router_id: action.clone(), _marker: std::marker::PhantomData })
+        .send(GetRouter { router_id: action.clone(), _marker: std::marker::PhantomData, span: tracing::Span::current() })
         .await
         .unwrap()
     }
@@ -221,10 +558,10 @@ impl RouterServiceManager {
 }
 
 // Helper function to create a Wasm router
-fn create_wasm_router(path: &std::path::Path) -> Box<WasmRouter> {
+fn create_wasm_router(path: &std::path::Path, health_interval: Duration) -> Box<WasmRouter> {
     // Here, you should implement the logic to create the router
     // This is a simplified version
-    let handle = spawn_wasm_router(path.to_str().unwrap());
+    let handle = spawn_wasm_router(path.to_str().unwrap(), health_interval);
     let router = WasmRouter::new(handle);
     //Box::new(WasmRouter::new(path.to_str().unwrap()).expect(format!("could not create wasm router for {:?}",path.clone()).as_str()))
     Box::new(router)
@@ -1,91 +1,519 @@
-use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
 
-use crate::router::{router::CapabilitiesBuilder, router_registry::ROUTER_SEPERATOR, Router};
-use mcp_spec::{handler::{PromptError, ResourceError}, prompt::Prompt, protocol::{CallToolResult, GetPromptResult, ReadResourceResult, ServerCapabilities}, Resource, ResourceContents::{self, TextResourceContents}, Tool, ToolError};
+use actix::Addr;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::client::ClientHandle;
+use crate::mcp::{ListPromptsActor, ListResourcesActor, ListToolsActor};
+use crate::messages::{AllTools, CallToolRequest, GetRouter, ListPromptsRequest, ListResourcesRequest, ResolveAlias, ResourceUpdatedNotification, TransportRequest, JSONRPC_VERSION};
+use crate::router::{router::CapabilitiesBuilder, router_registry::{ActorRouterRegistry, ROUTER_SEPERATOR}, Router};
+use crate::server_builder::VERSION;
+use crate::utils::json_rpc::JSON_RPC_INVALID_PARAMS;
+use mcp_spec::{handler::{PromptError, ResourceError}, prompt::Prompt, protocol::{CallToolResult, GetPromptResult, JsonRpcRequest, ReadResourceResult, ServerCapabilities}, Content, Resource, ResourceContents::TextResourceContents, Tool, ToolError};
 
 use super::router::ResponseFuture;
+use super::{tool_search, RequestContext};
+
+/// The id the system router is registered under
+pub const SYSTEM_ROUTER_ID: &str = "system";
+/// The catalogue of every router, as Markdown
+pub const CATALOGUE_URI: &str = "all";
+/// The catalogue of every router, as JSON
+pub const CATALOGUE_JSON_URI: &str = "all.json";
+/// Followed by a router id, and `.json` for the JSON form
+pub const ROUTER_URI_PREFIX: &str = "router/";
+/// Finds tools by keywords
+pub const SEARCH_TOOLS: &str = "search_tools";
+/// Gives the input schema of a tool
+pub const DESCRIBE_TOOL: &str = "describe_tool";
+/// Calls another tool
+pub const CALL_TOOL: &str = "call_tool";
+
+// the tools search_tools finds if the client does not say how many
+const SEARCH_LIMIT: usize = 10;
+
+const MARKDOWN: &str = "text/markdown";
+const JSON: &str = "application/json";
+
+/// A router as the catalogue describes it, with its tools, prompts and resources named the way
+/// clients use them
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CatalogueEntry {
+    pub router_id: String,
+    pub name: String,
+    pub instructions: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub tools: Vec<Tool>,
+    pub prompts: Vec<Prompt>,
+    pub resources: Vec<Resource>,
+}
 
-/// **A simple Hello World router**
+/// What a router says about itself when it is registered, kept so the catalogue never has to ask
+/// the router again
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouterDescription {
+    pub name: String,
+    pub instructions: String,
+    pub version: Option<String>,
+    pub capabilities: ServerCapabilities,
+}
+
+/// Where the catalogue comes from, the routers and what they listed
+#[derive(Clone)]
+struct Sources {
+    registry: Addr<ActorRouterRegistry>,
+    tools: Addr<ListToolsActor>,
+    prompts: Addr<ListPromptsActor>,
+    resources: Addr<ListResourcesActor>,
+}
+
+/// Offers the catalogue of what is installed in this server as resources: `all` for every
+/// router and `router/<id>` for one, both also as `.json`. The routers clients can use are set
+/// when they change, what they offer is read from the list actors whenever the catalogue is read. Subscribers of a catalogue resource get
+/// `notifications/resources/updated` when it changes.
+///
+/// Its tools let clients work with more tools than they want to list: `search_tools` ranks the
+/// tools of the other routers for keywords, `describe_tool` gives the schema of one and
+/// `call_tool` calls it.
 #[derive(Clone)]
-pub struct SystemRouter{
-    resources: Vec<ResourceContents>,
+pub struct SystemRouter {
+    sources: Sources,
+    // the routers clients can use, they have a resource of their own
+    routers: Arc<RwLock<Vec<(String, RouterDescription)>>>,
+    // uri -> client id -> client
+    subscriptions: Arc<Mutex<HashMap<String, HashMap<u64, ClientHandle>>>>,
 }
 
 impl SystemRouter {
-    pub fn new() -> Self {
-            let resources = vec![];
+    pub fn new(
+        registry: Addr<ActorRouterRegistry>,
+        tools: Addr<ListToolsActor>,
+        prompts: Addr<ListPromptsActor>,
+        resources: Addr<ListResourcesActor>,
+    ) -> Self {
         Self {
-            resources,
+            sources: Sources { registry, tools, prompts, resources },
+            routers: Arc::new(RwLock::new(vec![])),
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The routers clients can use, sorted by id, they are listed as `router/<id>` resources.
+    /// Call [`SystemRouter::list_resources`] for the new list.
+    pub fn set_routers(&self, routers: Vec<(String, RouterDescription)>) {
+        *self.routers.write().unwrap() = routers;
+    }
+
+    /// Tells the subscribers of the catalogue and of `router_id` that they changed
+    pub async fn catalogue_changed(&self, router_id: &str) {
+        let uris = [
+            CATALOGUE_URI.to_string(),
+            CATALOGUE_JSON_URI.to_string(),
+            format!("{}{}", ROUTER_URI_PREFIX, router_id),
+            format!("{}{}.json", ROUTER_URI_PREFIX, router_id),
+        ];
+        for uri in uris {
+            let clients: Vec<ClientHandle> = match self.subscriptions.lock().unwrap().get(&uri) {
+                Some(clients) => clients.values().cloned().collect(),
+                None => continue,
+            };
+            let params = json!({ "uri": format!("{}{}{}", SYSTEM_ROUTER_ID, ROUTER_SEPERATOR, uri) });
+            for client in clients {
+                if client.notify(ResourceUpdatedNotification::METHOD, Some(params.clone())).await.is_err() {
+                    // the session is gone
+                    if let Some(clients) = self.subscriptions.lock().unwrap().get_mut(&uri) {
+                        clients.remove(&client.client_id());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every router clients can use, or only `router_id`, with the tools, prompts and resources the caller may use
+    pub async fn catalogue(&self, router_id: Option<&str>, context: &RequestContext) -> Result<Vec<CatalogueEntry>, ResourceError> {
+        let sources = &self.sources;
+        let error = |e: actix::MailboxError| ResourceError::ExecutionError(e.to_string());
+        let routers: Vec<(String, RouterDescription)> = self.routers.read().unwrap().iter()
+            .filter(|(id, _)| router_id.is_none_or(|router_id| router_id == id))
+            .cloned()
+            .collect();
+        let tools = sources.tools.send(AllTools).await.map_err(error)?;
+        let prompts: Vec<Prompt> = listed(sources.prompts.send(ListPromptsRequest { request: list_request("prompts/list") }).await.map_err(error)?, "prompts");
+        let resources: Vec<Resource> = listed(sources.resources.send(ListResourcesRequest { request: list_request("resources/list") }).await.map_err(error)?, "resources");
+
+        let mut catalogue = vec![];
+        for (id, description) in routers {
+            let prefix = format!("{}{}", id, ROUTER_SEPERATOR);
+            catalogue.push(CatalogueEntry {
+                name: description.name,
+                instructions: description.instructions,
+                version: description.version,
+                tools: tools.iter().filter(|tool| tool.name.starts_with(&prefix) && context.may_use(&tool.name)).cloned().collect(),
+                prompts: prompts.iter().filter(|prompt| prompt.name.starts_with(&prefix) && context.may_use(&prompt.name)).cloned().collect(),
+                // listed under the uri the router knows them by
+                resources: resources.iter()
+                    .filter(|resource| resource.name.starts_with(&prefix))
+                    .map(|resource| Resource { uri: format!("{}{}", prefix, resource.uri), ..resource.clone() })
+                    .filter(|resource| context.may_use(&resource.uri))
+                    .collect(),
+                router_id: id,
+            });
+        }
+        Ok(catalogue)
+    }
+
+    /// The tools of the other routers the caller may use, for the `query` keywords
+    async fn search_tools(&self, arguments: &Value, context: &RequestContext) -> Result<CallToolResult, ToolError> {
+        let query = string_argument(arguments, "query")?;
+        let limit = match arguments.get("limit") {
+            None | Some(Value::Null) => SEARCH_LIMIT,
+            Some(limit) => limit.as_u64()
+                .map(|limit| limit as usize)
+                .ok_or_else(|| ToolError::InvalidParameters("limit must be a positive integer".to_string()))?,
+        };
+        let system = format!("{}{}", SYSTEM_ROUTER_ID, ROUTER_SEPERATOR);
+        let tools: Vec<Tool> = self.all_tools().await?.into_iter()
+            .filter(|tool| !tool.name.starts_with(&system) && context.may_use(&tool.name))
+            .collect();
+        let found: Vec<Value> = tool_search::search(&tools, &query, limit).into_iter()
+            .map(|(tool, score)| json!({ "name": tool.name, "description": tool.description, "score": score }))
+            .collect();
+        Ok(text_result(json!({ "tools": found }).to_string()))
+    }
+
+    /// The tool `name` is, also by an alias, with its input schema
+    async fn describe_tool(&self, arguments: &Value, context: &RequestContext) -> Result<CallToolResult, ToolError> {
+        let name = self.resolve_alias(string_argument(arguments, "name")?).await?;
+        let tool = self.all_tools().await?.into_iter()
+            .find(|tool| tool.name == name)
+            // what the caller may not use is not there for it
+            .filter(|tool| context.may_use(&tool.name))
+            .ok_or_else(|| ToolError::ExecutionError(format!("Tool {} not found, use {}{}{} to find tools", name, SYSTEM_ROUTER_ID, ROUTER_SEPERATOR, SEARCH_TOOLS)))?;
+        Ok(text_result(json!(tool).to_string()))
+    }
+
+    /// Calls the tool `name` with `arguments` for transports that do not unwrap the call before
+    /// it gets here, see [`unwrap_proxy_calls`]
+    async fn call_tool(&self, arguments: Value, context: RequestContext) -> Result<CallToolResult, ToolError> {
+        let (name, arguments) = proxied_call(arguments)?;
+        let name = self.resolve_alias(name).await?;
+        if !context.may_use(&name) {
+            return Err(ToolError::ExecutionError(format!("Not allowed to use {}", name)));
+        }
+        let error = |e: actix::MailboxError| ToolError::ExecutionError(e.to_string());
+        let (router, tool_name) = self.sources.registry
+            .send(GetRouter { router_id: name.clone(), _marker: std::marker::PhantomData, span: context.span.clone() })
+            .await
+            .map_err(error)?
+            .ok_or_else(|| ToolError::NotFound(format!("Tool {} not found", name)))?;
+        let request = JsonRpcRequest {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: None,
+            method: CallToolRequest::METHOD.to_string(),
+            params: Some(json!({ "name": tool_name, "arguments": arguments })),
+        };
+        match router.send(TransportRequest { request, context }).await.map_err(error)? {
+            Ok(response) => serde_json::from_value(response.result.unwrap_or_default())
+                .map_err(|e| ToolError::ExecutionError(format!("{} did not return a tool result: {}", name, e))),
+            // unknown tools and bad arguments stay errors of the call
+            Err(e) if e.error.code == JSON_RPC_INVALID_PARAMS => Err(ToolError::InvalidParameters(e.error.message)),
+            Err(e) => Err(ToolError::ExecutionError(e.error.message)),
+        }
+    }
+
+    async fn all_tools(&self) -> Result<Vec<Tool>, ToolError> {
+        self.sources.tools.send(AllTools).await.map_err(|e| ToolError::ExecutionError(e.to_string()))
+    }
+
+    async fn resolve_alias(&self, name: String) -> Result<String, ToolError> {
+        self.sources.registry.send(ResolveAlias { name }).await.map_err(|e| ToolError::ExecutionError(e.to_string()))
+    }
+}
+
+/// Turns a `tools/call` of `system_call_tool` into a call of the tool it calls, so the access
+/// policy, rate limits, metrics and timeouts of that tool apply
+pub fn unwrap_proxy_calls(request: &mut JsonRpcRequest) {
+    let call_tool = format!("{}{}{}", SYSTEM_ROUTER_ID, ROUTER_SEPERATOR, CALL_TOOL);
+    if request.method != CallToolRequest::METHOD {
+        return;
+    }
+    let Some(Value::Object(params)) = request.params.as_mut() else {
+        return;
+    };
+    while params.get("name").and_then(Value::as_str) == Some(call_tool.as_str()) {
+        let arguments = params.remove("arguments").unwrap_or_default();
+        let Some(name) = arguments.get("name").and_then(Value::as_str) else {
+            // left to the system router to report
+            params.insert("arguments".to_string(), arguments);
+            return;
+        };
+        params.insert("name".to_string(), Value::String(name.to_string()));
+        params.insert("arguments".to_string(), arguments.get("arguments").cloned().unwrap_or_else(|| json!({})));
+    }
+}
+
+// the tool call_tool calls and its arguments, past any call_tool it calls
+fn proxied_call(mut arguments: Value) -> Result<(String, Value), ToolError> {
+    loop {
+        let name = string_argument(&arguments, "name")?;
+        let inner = match arguments["arguments"].take() {
+            Value::Null => json!({}),
+            inner => inner,
+        };
+        if name != CALL_TOOL && name != format!("{}{}{}", SYSTEM_ROUTER_ID, ROUTER_SEPERATOR, CALL_TOOL) {
+            return Ok((name, inner));
+        }
+        arguments = inner;
+    }
+}
+
+fn string_argument(arguments: &Value, name: &str) -> Result<String, ToolError> {
+    arguments.get(name)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| ToolError::InvalidParameters(format!("{} must be a string", name)))
+}
+
+fn text_result(text: String) -> CallToolResult {
+    CallToolResult { content: vec![Content::text(text)], is_error: None }
+}
+
+fn list_request(method: &str) -> JsonRpcRequest {
+    JsonRpcRequest { jsonrpc: JSONRPC_VERSION.to_string(), id: None, method: method.to_string(), params: None }
+}
+
+// the items of a list response, none if it failed
+fn listed<T: serde::de::DeserializeOwned>(response: Result<mcp_spec::protocol::JsonRpcResponse, crate::error::McpEctorsError>, field: &str) -> Vec<T> {
+    response.ok()
+        .and_then(|response| response.result)
+        .and_then(|mut result| serde_json::from_value(result[field].take()).ok())
+        .unwrap_or_default()
+}
+
+fn markdown(catalogue: &[CatalogueEntry], heading: &str) -> String {
+    let mut text = String::new();
+    for router in catalogue {
+        text.push_str(&format!("{} {}\n\n", heading, router.router_id));
+        text.push_str(&format!("Name: {}\n", router.name));
+        if let Some(version) = &router.version {
+            text.push_str(&format!("Version: {}\n", version));
+        }
+        if !router.instructions.is_empty() {
+            text.push_str(&format!("\n{}\n", router.instructions));
         }
+        if !router.tools.is_empty() {
+            text.push_str(&format!("\n{}# Tools\n\n", heading));
+            for tool in &router.tools {
+                text.push_str(&format!("- `{}`: {}\n", tool.name, tool.description));
+                text.push_str(&format!("\n  ```json\n  {}\n  ```\n\n", tool.input_schema));
+            }
+        }
+        if !router.prompts.is_empty() {
+            text.push_str(&format!("\n{}# Prompts\n\n", heading));
+            for prompt in &router.prompts {
+                text.push_str(&format!("- `{}`", prompt.name));
+                if let Some(description) = &prompt.description {
+                    text.push_str(&format!(": {}", description));
+                }
+                let arguments: Vec<String> = prompt.arguments.iter().flatten()
+                    .map(|argument| if argument.required == Some(true) { argument.name.clone() } else { format!("{}?", argument.name) })
+                    .collect();
+                if !arguments.is_empty() {
+                    text.push_str(&format!(" (arguments: {})", arguments.join(", ")));
+                }
+                text.push('\n');
+            }
+        }
+        if !router.resources.is_empty() {
+            text.push_str(&format!("\n{}# Resources\n\n", heading));
+            for resource in &router.resources {
+                text.push_str(&format!("- `{}` ({})", resource.uri, resource.mime_type));
+                if let Some(description) = &resource.description {
+                    text.push_str(&format!(": {}", description));
+                }
+                text.push('\n');
+            }
+        }
+        text.push('\n');
     }
+    text
+}
+
+fn contents(uri: &str, mime_type: &str, text: String) -> ReadResourceResult {
+    ReadResourceResult { contents: vec![TextResourceContents { uri: uri.to_string(), mime_type: Some(mime_type.to_string()), text }] }
+}
+
+fn catalogue_resource(uri: String, name: String, description: String, mime_type: &str) -> Resource {
+    Resource { uri, name, description: Some(description), mime_type: mime_type.to_string(), annotations: None }
 }
 
 impl Router for SystemRouter {
     fn name(&self) -> String {
-        "system".to_string()
+        SYSTEM_ROUTER_ID.to_string()
     }
 
     fn instructions(&self) -> String {
-        format!("This is the system router who offers information about what is installed in this server. To get a list do resources/read uri: system{}all",ROUTER_SEPERATOR)
+        format!(
+            "This is the system router who offers information about what is installed in this server. To get a list do resources/read uri: {}{}{}, for one router {}{}{}<router id>, add .json for JSON",
+            SYSTEM_ROUTER_ID, ROUTER_SEPERATOR, CATALOGUE_URI, SYSTEM_ROUTER_ID, ROUTER_SEPERATOR, ROUTER_URI_PREFIX,
+        )
+    }
+
+    fn version(&self) -> Option<String> {
+        Some(VERSION.to_string())
     }
 
     fn capabilities(&self) -> ServerCapabilities {
         CapabilitiesBuilder::new()
+            .with_tools(false)
             .with_resources(true, true)
             .build()
     }
 
     fn list_tools(&self) -> Vec<Tool> {
-        vec![]
+        vec![
+            Tool::new(
+                SEARCH_TOOLS.to_string(),
+                "Search the tools installed in this server by keywords, best matches first. Use it to find a tool that is not listed.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "keywords of what the tool should do" },
+                        "limit": { "type": "integer", "description": "how many tools to return at most, 10 by default" }
+                    },
+                    "required": ["query"]
+                }),
+            ),
+            Tool::new(
+                DESCRIBE_TOOL.to_string(),
+                "Describe a tool installed in this server, with the schema of its arguments.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "description": "the name of the tool, as search_tools returns it" }
+                    },
+                    "required": ["name"]
+                }),
+            ),
+            Tool::new(
+                CALL_TOOL.to_string(),
+                "Call a tool installed in this server, also one that is not listed.".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "description": "the name of the tool, as search_tools returns it" },
+                        "arguments": { "type": "object", "description": "the arguments of the tool, as describe_tool describes them" }
+                    },
+                    "required": ["name"]
+                }),
+            ),
+        ]
     }
 
     fn call_tool(
         &self,
         tool_name: &str,
-        _arguments: Value,
+        arguments: Value,
     ) -> ResponseFuture<Result<CallToolResult, ToolError>> {
-        let tool_name = tool_name.to_string();
+        self.call_tool_with_context(tool_name, arguments, RequestContext::default())
+    }
 
+    fn call_tool_with_context(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        context: RequestContext,
+    ) -> ResponseFuture<Result<CallToolResult, ToolError>> {
+        let router = self.clone();
+        let tool_name = tool_name.to_string();
         Box::pin(async move {
-            Err(ToolError::NotFound(format!("Tool {} not found", tool_name)))
+            match tool_name.as_str() {
+                SEARCH_TOOLS => router.search_tools(&arguments, &context).await,
+                DESCRIBE_TOOL => router.describe_tool(&arguments, &context).await,
+                CALL_TOOL => SystemRouter::call_tool(&router, arguments, context).await,
+                _ => Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
+            }
         })
     }
 
     fn list_resources(&self) -> Vec<Resource> {
-        vec![Resource{ 
-            uri: "all".to_string(), 
-            name: "all resources, prompts, tools,... registered in this mcp multi router server".to_string(), 
-            description: Some("this gives a description of all the resources, prompts, tools,... which different routers offer that have been installed in this multi-router mcp server.".to_string()), 
-            mime_type: "text/plain".to_string(), 
-            annotations: None }]
+        let all = "all resources, prompts, tools,... registered in this mcp multi router server".to_string();
+        let description = "this gives a description of all the resources, prompts, tools,... which different routers offer that have been installed in this multi-router mcp server.".to_string();
+        let mut resources = vec![
+            catalogue_resource(CATALOGUE_URI.to_string(), all.clone(), description.clone(), MARKDOWN),
+            catalogue_resource(CATALOGUE_JSON_URI.to_string(), format!("{} as JSON", all), description, JSON),
+        ];
+        for (router_id, _) in self.routers.read().unwrap().iter() {
+            let description = format!("the name, instructions, version, tools, prompts and resources of router {}", router_id);
+            resources.push(catalogue_resource(format!("{}{}", ROUTER_URI_PREFIX, router_id), format!("router {}", router_id), description.clone(), MARKDOWN));
+            resources.push(catalogue_resource(format!("{}{}.json", ROUTER_URI_PREFIX, router_id), format!("router {} as JSON", router_id), description, JSON));
+        }
+        resources
     }
 
     fn read_resource(
         &self,
         uri: &str,
     ) -> ResponseFuture<Result<ReadResourceResult, ResourceError>> {
-        let mut resources = self.resources.clone();
-        resources.push(TextResourceContents{
-            uri: "all".to_string(),
-            mime_type: Some("plain/text".to_string()),
-            text: "This multi-router mcp server currently has two routers installed:\n
-            counter_router: This server provides a counter tool that can increment and decrement values. The counter starts at 0 and can be modified using the 'increment' and 'decrement' tools. Use 'get_value' to check the current count.\n
-            hellow_world: This server responds with a greeting, 'Hello {name}', where 'name' is the parameter passed.
-            ".to_string()});
-            let uri_clone = uri.to_string();
-        Box::pin(async move { 
-            match uri_clone.as_str() {
-                "all" => {Ok(ReadResourceResult{ contents: resources })},
-                name => Err(ResourceError::NotFound(format!("Resource {} not found", name))) 
+        self.read_resource_with_context(uri, RequestContext::default())
+    }
+
+    fn read_resource_with_context(
+        &self,
+        uri: &str,
+        context: RequestContext,
+    ) -> ResponseFuture<Result<ReadResourceResult, ResourceError>> {
+        let router = self.clone();
+        let uri = uri.to_string();
+        Box::pin(async move {
+            let (router_id, json) = match uri.as_str() {
+                CATALOGUE_URI => (None, false),
+                CATALOGUE_JSON_URI => (None, true),
+                uri => match uri.strip_prefix(ROUTER_URI_PREFIX) {
+                    Some(router_id) => match router_id.strip_suffix(".json") {
+                        Some(router_id) => (Some(router_id), true),
+                        None => (Some(router_id), false),
+                    },
+                    None => return Err(ResourceError::NotFound(format!("Resource {} not found", uri))),
+                },
+            };
+            let catalogue = router.catalogue(router_id, &context).await?;
+            match (router_id, json) {
+                (Some(_), _) if catalogue.is_empty() => Err(ResourceError::NotFound(format!("Resource {} not found", uri))),
+                (Some(_), true) => Ok(contents(&uri, JSON, json!(catalogue[0]).to_string())),
+                (Some(_), false) => Ok(contents(&uri, MARKDOWN, markdown(&catalogue, "#"))),
+                (None, true) => Ok(contents(&uri, JSON, json!({ "routers": catalogue }).to_string())),
+                (None, false) => {
+                    let text = format!("# Routers installed in this server\n\n{}", markdown(&catalogue, "##"));
+                    Ok(contents(&uri, MARKDOWN, text))
+                },
             }
-            
         })
     }
-    
+
+    fn subscribe(&self, uri: &str, context: RequestContext) -> Result<(), ResourceError> {
+        if ![CATALOGUE_URI, CATALOGUE_JSON_URI].contains(&uri) && !uri.starts_with(ROUTER_URI_PREFIX) {
+            return Err(ResourceError::NotFound(format!("Resource {} not found", uri)));
+        }
+        let client = context.client.ok_or_else(|| ResourceError::ExecutionError("Subscribing needs a session".to_string()))?;
+        self.subscriptions.lock().unwrap().entry(uri.to_string()).or_default().insert(client.client_id(), client);
+        Ok(())
+    }
+
+    fn unsubscribe(&self, uri: &str, context: RequestContext) -> Result<(), ResourceError> {
+        if let (Some(client), Some(clients)) = (context.client, self.subscriptions.lock().unwrap().get_mut(uri)) {
+            clients.remove(&client.client_id());
+        }
+        Ok(())
+    }
+
     fn list_prompts(&self) -> Vec<Prompt> {
         vec![]
     }
-    
+
     fn get_prompt(&self, _prompt_name: &str) -> ResponseFuture<Result<GetPromptResult, PromptError>> {
 
         let result = GetPromptResult{ description: None, messages: vec![] };
@@ -94,4 +522,3 @@ impl Router for SystemRouter {
         })
     }
 }
-
@@ -0,0 +1,80 @@
+use std::collections::{HashMap, HashSet};
+
+use mcp_spec::Tool;
+
+// the usual BM25 parameters, term frequency saturation and length normalisation
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+// a query word in the name of a tool says more than one in its description
+const NAME_WEIGHT: usize = 2;
+
+/// The `limit` tools that match `query` best by BM25 over their names and descriptions, best
+/// first with their scores. Tools that match no word of the query are left out.
+pub fn search<'a>(tools: &'a [Tool], query: &str, limit: usize) -> Vec<(&'a Tool, f64)> {
+    let query: HashSet<String> = tokens(query).into_iter().collect();
+    if query.is_empty() || tools.is_empty() {
+        return vec![];
+    }
+    let documents: Vec<Vec<String>> = tools.iter().map(document).collect();
+    let average_length = documents.iter().map(Vec::len).sum::<usize>() as f64 / documents.len() as f64;
+    let mut frequencies = HashMap::new();
+    for document in &documents {
+        for term in document.iter().collect::<HashSet<_>>() {
+            *frequencies.entry(term.as_str()).or_insert(0usize) += 1;
+        }
+    }
+
+    let count = documents.len() as f64;
+    let mut scored: Vec<(&Tool, f64)> = tools.iter().zip(&documents)
+        .map(|(tool, document)| {
+            let length = document.len() as f64;
+            let score = query.iter()
+                .filter_map(|term| {
+                    let matches = document.iter().filter(|token| *token == term).count() as f64;
+                    let frequency = *frequencies.get(term.as_str())? as f64;
+                    let idf = ((count - frequency + 0.5) / (frequency + 0.5) + 1.0).ln();
+                    Some(idf * matches * (K1 + 1.0) / (matches + K1 * (1.0 - B + B * length / average_length.max(1.0))))
+                })
+                .sum::<f64>();
+            (tool, score)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    scored.sort_by(|(a, a_score), (b, b_score)| b_score.total_cmp(a_score).then_with(|| a.name.cmp(&b.name)));
+    scored.truncate(limit);
+    scored
+}
+
+// the words of a tool, those of its name repeated
+fn document(tool: &Tool) -> Vec<String> {
+    let name = tokens(&tool.name);
+    let mut document: Vec<String> = std::iter::repeat_n(name, NAME_WEIGHT).flatten().collect();
+    document.extend(tokens(&tool.description));
+    document
+}
+
+/// Lowercased words, split on everything that is not a letter or digit and between the words of
+/// camelCase, so `getWeather_forecast` is `get`, `weather` and `forecast`
+fn tokens(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut previous_lowercase = false;
+    for c in text.chars() {
+        if !c.is_alphanumeric() {
+            if !token.is_empty() {
+                tokens.push(std::mem::take(&mut token));
+            }
+            previous_lowercase = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lowercase {
+            tokens.push(std::mem::take(&mut token));
+        }
+        previous_lowercase = c.is_lowercase() || c.is_ascii_digit();
+        token.extend(c.to_lowercase());
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
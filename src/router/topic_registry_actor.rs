@@ -1,3 +1,4 @@
+use crate::error::McpEctorsError;
 use actix::prelude::*;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -20,7 +21,7 @@ pub struct Publish {
 
 // A message that subscribers receive.
 #[derive(Message)]
-#[rtype(result = "Result<serde_json::Value, ()>")]
+#[rtype(result = "Result<serde_json::Value, McpEctorsError>")]
 pub struct TopicMessage {
     pub topic: String,
     pub payload: Value,
@@ -1,17 +1,35 @@
-use std::{future::Future, pin::Pin, sync::Arc, thread};
+use std::{future::Future, pin::Pin, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex}, thread, time::{Duration, Instant}};
 
-use exports::wasix;
 use mcp_spec::{ handler::{PromptError, ResourceError}, prompt::Prompt, protocol::{CallToolResult, GetPromptResult, ReadResourceResult, ServerCapabilities}, Resource, Tool, ToolError};
+use futures::channel::oneshot;
 use serde_json::Value as JsonValue;
-use tracing::error;
-use std::sync::mpsc::{self, Sender, Receiver};
+use tracing::{error, info, Span};
+use std::sync::mpsc::{self, Sender, Receiver, RecvTimeoutError};
 use wasmtime_wasi::{IoView, ResourceTable, WasiCtx, WasiCtxBuilder, WasiView};
-use wasmtime::{component::{bindgen, Component, Linker}, Config, Engine, Store};
+use wasmtime::{component::{bindgen, Component, Linker, TypedFunc}, Config, Engine, ResourceLimiter, Store};
 use std::convert::Into;
 
-use super::{wasix_mcp::json_to_value, Router};
+use crate::client::client_handle::SAMPLING_TIMEOUT;
+use crate::error::McpEctorsError;
+use crate::health::{RouterHealth, RouterState};
+use crate::metrics::WasmRouterStats;
+use crate::telemetry::WASM_SPAN;
+
+use super::{wasix_mcp::json_to_value, RequestContext, Router};
 pub type ResponseFuture<I> = Pin<Box<dyn Future<Output = I>>>;
 
+/// The fuel a request starts with, it is only metered for the metrics and never runs out
+const FUEL: u64 = u64::MAX;
+
+/// The optional interface of `wit/world.wit` a router exports to report its health
+const HEALTH_EXPORT: &str = "wasix:mcp/health@0.0.1";
+
+/// How often the `health` export of a router is called unless the manager says otherwise
+pub const HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The `check` function of the `health` export
+type HealthCheck = TypedFunc<(), (Result<(), String>,)>;
+
 bindgen!({
     world: "mcp",
 });
@@ -21,6 +39,72 @@ bindgen!({
 pub struct MyState{
     ctx: WasiCtx,
     table: ResourceTable,
+    // context of the tool call being executed
+    context: RequestContext,
+    stats: Arc<WasmRouterStats>,
+    /// Runs what the host functions wait for on the thread of the router
+    runtime: Arc<tokio::runtime::Runtime>,
+}
+
+impl MyState {
+    /// Waits for `future` on the thread of the router, the guest with it, until the request is
+    /// cancelled or its time is up
+    fn wait<T>(&self, future: impl Future<Output = T>) -> Result<T, String> {
+        let cancellation = self.context.cancellation.clone();
+        let time_left = self.context.time_left();
+        self.runtime.block_on(async move {
+            let answered = async move {
+                match time_left {
+                    Some(time_left) => tokio::time::timeout(time_left, future).await.map_err(|_| "the request timed out".to_string()),
+                    None => Ok(future.await),
+                }
+            };
+            tokio::select! {
+                _ = cancellation.cancelled() => Err("the request was cancelled".to_string()),
+                result = answered => result,
+            }
+        })
+    }
+}
+
+/// Lets memories and tables grow as they like, only noting how large the memories get
+impl ResourceLimiter for MyState {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> anyhow::Result<bool> {
+        self.stats.record_memory(desired);
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: usize, _desired: usize, _maximum: Option<usize>) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+}
+
+impl wasix::mcp::host::Host for MyState {
+    fn report_progress(&mut self, progress: f64, total: Option<f64>, message: Option<String>) {
+        self.context.progress.report(progress, total, message);
+    }
+
+    fn create_message(&mut self, params: String) -> Result<String, String> {
+        let client = self.context.client.clone().ok_or("no client to ask")?;
+        let params: JsonValue = serde_json::from_str(&params).map_err(|e| format!("invalid params: {}", e))?;
+        // the client gets no longer than the request has left
+        let timeout = self.context.time_left().map_or(SAMPLING_TIMEOUT, |time_left| time_left.min(SAMPLING_TIMEOUT));
+        let result = self.wait(client.create_message_within(params, timeout))?.map_err(|e| e.to_string())?;
+        Ok(result.to_string())
+    }
+
+    fn roots(&mut self) -> Vec<wasix::mcp::host::Root> {
+        let Some(client) = self.context.client.clone() else {
+            return vec![];
+        };
+        match self.wait(client.roots()) {
+            Ok(roots) => roots.into_iter().map(|root| wasix::mcp::host::Root { uri: root.uri, name: root.name }).collect(),
+            Err(e) => {
+                error!("Could not get the roots of the client: {}", e);
+                vec![]
+            }
+        }
+    }
 }
 
 impl WasiView for MyState
@@ -44,11 +128,28 @@ enum WasmRequest {
     ListPrompts,
     ReadResource(String),
     GetPrompt(String),
-    CallTool(String, JsonValue),
+    CallTool(String, JsonValue, Box<RequestContext>),
     Capabilities,
     // Add other request types as needed.
 }
 
+impl WasmRequest {
+    /// The export of the guest the request calls
+    fn export(&self) -> &'static str {
+        match self {
+            WasmRequest::GetName => "name",
+            WasmRequest::GetInstructions => "instructions",
+            WasmRequest::ListTools => "list-tools",
+            WasmRequest::ListResources => "list-resources",
+            WasmRequest::ListPrompts => "list-prompts",
+            WasmRequest::ReadResource(_) => "read-resource",
+            WasmRequest::GetPrompt(_) => "get-prompt",
+            WasmRequest::CallTool(..) => "call-tool",
+            WasmRequest::Capabilities => "capabilities",
+        }
+    }
+}
+
 /// Define the possible responses from the WASM thread.
 #[allow(dead_code)]
 enum WasmResponse {
@@ -67,18 +168,77 @@ enum WasmResponse {
     Error(String),
 }
 
-/// A handle that lets callers send synchronous requests to the dedicated WASM thread.
+/// A request queued for the WASM thread, with its sequence number, where to send the response
+/// and the span of the caller.
+type QueuedRequest = (u64, WasmRequest, oneshot::Sender<WasmResponse>, Span);
+
+/// A handle that lets callers send requests to the dedicated WASM thread.
 pub struct WasmRouterHandle {
-    request_tx: Sender<(WasmRequest, Sender<WasmResponse>)>,
+    request_tx: Sender<QueuedRequest>,
+    engine: Engine,
+    // sequence number of the request the guest is executing right now
+    running: Arc<Mutex<Option<u64>>>,
+    next_seq: AtomicU64,
+    stats: Arc<WasmRouterStats>,
+    health: Arc<RouterHealth>,
+}
+
+/// Marks the router failed if its thread panics
+struct FailOnPanic(Arc<RouterHealth>);
+
+impl Drop for FailOnPanic {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.failed("the router thread panicked");
+        }
+    }
+}
+
+/// Interrupts the guest when a request is dropped (cancelled or timed out) while the guest is still executing it.
+struct InterruptGuard {
+    seq: u64,
+    response: Option<oneshot::Receiver<WasmResponse>>,
+    engine: Engine,
+    running: Arc<Mutex<Option<u64>>>,
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        // dropping the receiver first makes the WASM thread skip the request if it has not started it yet
+        self.response.take();
+        let running = self.running.lock().unwrap();
+        if *running == Some(self.seq) {
+            info!("Interrupting wasm guest for abandoned request {}", self.seq);
+            self.engine.increment_epoch();
+        }
+    }
 }
 
 impl WasmRouterHandle {
+    fn enqueue(&self, request: WasmRequest) -> Result<(u64, oneshot::Receiver<WasmResponse>), String> {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.stats.enqueued();
+        if let Err(e) = self.request_tx.send((seq, request, resp_tx, Span::current())) {
+            self.stats.dequeued();
+            return Err(format!("Send error: {}", e));
+        }
+        Ok((seq, resp_rx))
+    }
+
+    /// Instantiation time, fuel, memory and queue depth of the router, for the metrics
+    pub fn stats(&self) -> Arc<WasmRouterStats> {
+        self.stats.clone()
+    }
+
+    /// Whether the instance is loading, ready, degraded or failed, for the health endpoints
+    pub fn health(&self) -> Arc<RouterHealth> {
+        self.health.clone()
+    }
+
     fn send_request(&self, request: WasmRequest) -> Result<WasmResponse, String> {
-        let (resp_tx, resp_rx) = mpsc::channel();
-        self.request_tx
-            .send((request, resp_tx))
-            .map_err(|e| format!("Send error: {}", e))?;
-        match resp_rx.recv() {
+        let (_, resp_rx) = self.enqueue(request)?;
+        match futures::executor::block_on(resp_rx) {
             Ok(response) => match response {
                 WasmResponse::Error(err) => Err(err),
                 _ => Ok(response),
@@ -87,6 +247,23 @@ impl WasmRouterHandle {
         }
     }
 
+    /// Like `send_request` but does not block. Dropping the returned future before it completes
+    /// interrupts the guest via an epoch deadline if it is executing this request.
+    fn send_request_async(&self, request: WasmRequest) -> ResponseFuture<Result<WasmResponse, String>> {
+        let queued = self.enqueue(request);
+        let engine = self.engine.clone();
+        let running = self.running.clone();
+        Box::pin(async move {
+            let (seq, resp_rx) = queued?;
+            let mut guard = InterruptGuard { seq, response: Some(resp_rx), engine, running };
+            match guard.response.as_mut().unwrap().await {
+                Ok(WasmResponse::Error(err)) => Err(err),
+                Ok(response) => Ok(response),
+                Err(_) => Err("Unexpected response".into()),
+            }
+        })
+    }
+
     pub fn get_name(&self) -> Result<String, String> {
         match self.send_request(WasmRequest::GetName)? {
             WasmResponse::Name(name) => Ok(name),
@@ -127,30 +304,49 @@ impl WasmRouterHandle {
         }
     }
 
-    pub fn get_prompt(&self, prompt_name: &str) -> Result<GetPromptResult, String> {
-        match self.send_request(WasmRequest::GetPrompt(prompt_name.to_string()))? {
-            WasmResponse::GetPromptResult(prompt) => Ok(prompt),
-            WasmResponse::Error(err) => Err(err),
-            _ => Err("Unexpected response type".into()),
-        }
+    /// Does not block, dropping the future interrupts the guest like [`WasmRouterHandle::call_tool_async`]
+    pub fn get_prompt(&self, prompt_name: &str) -> ResponseFuture<Result<GetPromptResult, PromptError>> {
+        let response = self.send_request_async(WasmRequest::GetPrompt(prompt_name.to_string()));
+        Box::pin(async move {
+            match response.await.map_err(PromptError::InternalError)? {
+                WasmResponse::GetPromptResult(prompt) => Ok(prompt),
+                WasmResponse::RetPromptError(err) => Err(err),
+                _ => Err(PromptError::InternalError("Unexpected response type".into())),
+            }
+        })
     }
 
-    pub fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, String> {
-        match self.send_request(WasmRequest::ReadResource(uri.to_string()))? {
-            WasmResponse::ReadResource(resource) => Ok(resource),
-            WasmResponse::Error(err) => Err(err),
-            _ => Err("Unexpected response type".into()),
-        }
+    /// Does not block, dropping the future interrupts the guest like [`WasmRouterHandle::call_tool_async`]
+    pub fn read_resource(&self, uri: &str) -> ResponseFuture<Result<ReadResourceResult, ResourceError>> {
+        let response = self.send_request_async(WasmRequest::ReadResource(uri.to_string()));
+        Box::pin(async move {
+            match response.await.map_err(ResourceError::ExecutionError)? {
+                WasmResponse::ReadResource(resource) => Ok(resource),
+                WasmResponse::RetResourceError(err) => Err(err),
+                _ => Err(ResourceError::ExecutionError("Unexpected response type".into())),
+            }
+        })
     }
 
     pub fn call_tool(&self, tool_name: &str, arguments: JsonValue) -> Result<CallToolResult, String> {
-        match self.send_request(WasmRequest::CallTool(tool_name.to_string(), arguments))? {
+        match self.send_request(WasmRequest::CallTool(tool_name.to_string(), arguments, Box::default()))? {
             WasmResponse::CallToolResult(result) => Ok(result),
             WasmResponse::Error(err) => Err(err),
             _ => Err("Unexpected response type".into()),
         }
     }
 
+    pub fn call_tool_async(&self, tool_name: &str, arguments: JsonValue, context: RequestContext) -> ResponseFuture<Result<CallToolResult, String>> {
+        let response = self.send_request_async(WasmRequest::CallTool(tool_name.to_string(), arguments, Box::new(context)));
+        Box::pin(async move {
+            match response.await? {
+                WasmResponse::CallToolResult(result) => Ok(result),
+                WasmResponse::RetToolError(err) => Err(err.to_string()),
+                _ => Err("Unexpected response type".into()),
+            }
+        })
+    }
+
     pub fn capabilities(&self) -> Result<ServerCapabilities, String> {
         match self.send_request(WasmRequest::Capabilities)? {
             WasmResponse::Capabilities(capabilities) => Ok(capabilities),
@@ -160,41 +356,187 @@ impl WasmRouterHandle {
     }
 }
 
+fn engine_config() -> Config {
+    let mut config = Config::default();
+    config.async_support(false);
+    // lets an abandoned request interrupt the guest, see InterruptGuard
+    config.epoch_interruption(true);
+    // meters the guest for the metrics, see FUEL
+    config.consume_fuel(true);
+    config
+}
+
+/// A linker with WASI and the functions of the `host` interface
+fn router_linker(engine: &Engine) -> Linker<MyState> {
+    let mut linker = Linker::new(engine);
+    wasmtime_wasi::add_to_linker_sync::<MyState>(&mut linker).expect("Could not add wasi to wasm router");
+    Mcp::add_to_linker(&mut linker, |state: &mut MyState| state).expect("Could not add host functions to wasm router");
+    linker
+}
+
+/// Checks that `bytes` are a component a WASM router can be created from: it compiles, needs no
+/// imports the server does not offer and exports the router interface. It is not run.
+pub fn validate_component(bytes: &[u8]) -> Result<(), McpEctorsError> {
+    let engine = Engine::new(&engine_config()).map_err(|e| McpEctorsError::Internal(e.to_string()))?;
+    let component = Component::from_binary(&engine, bytes)
+        .map_err(|e| McpEctorsError::InvalidParams(format!("Not a WASM component: {}", e)))?;
+    router_linker(&engine).instantiate_pre(&component)
+        .and_then(McpPre::new)
+        .map_err(|e| McpEctorsError::InvalidParams(format!("The component is no MCP router: {:#}", e)))?;
+    Ok(())
+}
+
 /// Spawns a dedicated thread that owns the WASM instance and processes requests.
 /// In your real code you’d initialize the WASM engine, store, component, etc. here.
-pub fn spawn_wasm_router(wasm_path: &str) -> WasmRouterHandle {
+/// The `health` export, if the router has one, is called every `health_interval`.
+pub fn spawn_wasm_router(wasm_path: &str, health_interval: Duration) -> WasmRouterHandle {
     let (req_tx, req_rx): (
-        Sender<(WasmRequest, Sender<WasmResponse>)>,
-        Receiver<(WasmRequest, Sender<WasmResponse>)>,
+        Sender<QueuedRequest>,
+        Receiver<QueuedRequest>,
     ) = mpsc::channel();
-    
-    let file = wasm_path.to_owned();    
-    thread::spawn(move || {
 
-        // --- Initialization ---
-        let mut config = Config::default();
-        config.async_support(false);
+    // --- Initialization ---
+    // Create a Wasmtime engine, it is shared with the handle to interrupt the guest
+    let engine = Engine::new(&engine_config()).expect("engine could not be created");
+    let running = Arc::new(Mutex::new(None));
+    let stats = Arc::new(WasmRouterStats::default());
 
-        // Create a Wasmtime engine and store
-        let engine = Engine::new(&config).expect("engine could not be created");
-        let wasi = WasiCtxBuilder::new().build();
-        let state = MyState {
-            ctx: wasi,
-            table: ResourceTable::new(),
+    let file = wasm_path.to_owned();
+    let thread_engine = engine.clone();
+    let thread_running = running.clone();
+    let thread_stats = stats.clone();
+    let health = Arc::new(RouterHealth::new(RouterState::Loading));
+    let thread_health = health.clone();
+    thread::spawn(move || {
+        let engine = thread_engine;
+        let health = thread_health;
+        let _fail_on_panic = FailOnPanic(health.clone());
+        let component = match Component::from_file(&engine, &file) {
+            Ok(component) => component,
+            Err(e) => {
+                error!("wasm file {} could not be read: {}", file, e);
+                health.failed(format!("wasm file {} could not be read: {}", file, e));
+                return;
+            }
+        };
+        let linker = router_linker(&engine);
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_time().build() {
+            Ok(runtime) => Arc::new(runtime),
+            Err(e) => {
+                error!("Could not start the runtime of wasm router {}: {}", file, e);
+                health.failed(format!("Could not start its runtime: {}", e));
+                return;
+            }
         };
-        let mut store = Store::new(&engine, state);
-        let component = Component::from_file(&engine, file.clone()).expect(format!("wasm file {} could not be read",file).as_str());
-        let mut linker = Linker::new(&engine);
-        wasmtime_wasi::add_to_linker_sync::<MyState>(&mut linker).expect("Could not add wasi to wasm router");
 
-        // Instantiate the MCP router from the wasm component
-        let router = Mcp::instantiate(&mut store, &component, &linker)
-            .map_err(|err| Box::new(err) as Box<anyhow::Error>).expect(format!("Could not instantiate wasm router: {}",file).as_str());
+        // Instantiate the MCP router from the wasm component in a fresh store, with the check of
+        // its health export if it has one
+        let instantiate = || -> anyhow::Result<(Store<MyState>, Mcp, Option<HealthCheck>)> {
+            let started = Instant::now();
+            let wasi = WasiCtxBuilder::new().build();
+            let state = MyState {
+                ctx: wasi,
+                table: ResourceTable::new(),
+                context: RequestContext::default(),
+                stats: thread_stats.clone(),
+                runtime: runtime.clone(),
+            };
+            let mut store = Store::new(&engine, state);
+            store.limiter(|state| state as &mut dyn ResourceLimiter);
+            store.epoch_deadline_trap();
+            store.set_epoch_deadline(1);
+            store.set_fuel(FUEL).expect("fuel is enabled");
+            let instance = linker.instantiate(&mut store, &component)?;
+            let router = Mcp::new(&mut store, &instance)?;
+            let check = component.export_index(None, HEALTH_EXPORT)
+                .and_then(|(_, health)| component.export_index(Some(&health), "check"))
+                .map(|(_, check)| instance.get_typed_func(&mut store, check))
+                .transpose()?;
+            thread_stats.record_instantiation(started.elapsed());
+            thread_stats.record_fuel(FUEL - store.get_fuel().unwrap_or(0));
+            Ok((store, router, check))
+        };
+        // a trapped (e.g. interrupted) instance cannot be entered again
+        let reinstantiate = |error: &str| match instantiate() {
+            Ok(instance) => {
+                info!("Re-instantiated wasm router {} after: {}", file, error);
+                health.degraded(error);
+                Some(instance)
+            }
+            Err(e) => {
+                error!("Could not re-instantiate wasm router {}: {}", file, e);
+                health.failed(format!("Could not re-instantiate after {}: {}", error, e));
+                None
+            }
+        };
+        let (mut store, mut router, mut check) = match instantiate() {
+            Ok(instance) => instance,
+            Err(e) => {
+                error!("Could not instantiate wasm router {}: {}", file, e);
+                health.failed(format!("Could not instantiate: {}", e));
+                return;
+            }
+        };
+        health.ready();
+        let mut next_check = Instant::now();
 
-        
         // --- Event Loop ---
-        // Process incoming requests one at a time on this dedicated thread.
-        for (request, resp_tx) in req_rx {
+        // Process incoming requests one at a time on this dedicated thread, and call the health
+        // export when it is due.
+        loop {
+            let queued = match check {
+                Some(_) => match req_rx.recv_timeout(next_check.saturating_duration_since(Instant::now())) {
+                    Ok(queued) => Some(queued),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                },
+                None => match req_rx.recv() {
+                    Ok(queued) => Some(queued),
+                    Err(_) => break,
+                },
+            };
+            if let Some(health_check) = check.filter(|_| next_check <= Instant::now()) {
+                next_check = Instant::now() + health_interval;
+                store.set_epoch_deadline(1);
+                store.set_fuel(FUEL).expect("fuel is enabled");
+                let result = health_check.call(&mut store, ())
+                    .and_then(|(result,)| health_check.post_return(&mut store).map(|_| result));
+                thread_stats.record_fuel(FUEL - store.get_fuel().unwrap_or(0));
+                match result {
+                    Ok(Ok(())) => health.ready(),
+                    Ok(Err(e)) => health.degraded(e),
+                    Err(e) => match reinstantiate(&format!("the health check trapped: {}", e)) {
+                        Some(instance) => (store, router, check) = instance,
+                        None => break,
+                    },
+                }
+            }
+            let Some((seq, request, resp_tx, caller)) = queued else {
+                continue;
+            };
+            thread_stats.dequeued();
+            {
+                let mut running = thread_running.lock().unwrap();
+                if resp_tx.is_canceled() {
+                    // cancelled or timed out while it was queued
+                    continue;
+                }
+                store.set_epoch_deadline(1);
+                *running = Some(seq);
+            }
+            store.set_fuel(FUEL).expect("fuel is enabled");
+            // the spans of this thread go where those of the caller go
+            let _dispatch = caller.with_subscriber(|(_, dispatch)| tracing::dispatcher::set_default(dispatch));
+            // the router span of the request is entered here too, so what is logged on this thread
+            // goes to the session that made the request with the router as logger
+            let caller_entered = caller.enter();
+            // requests from outside of any span, e.g. those loading the router, are not traced
+            let span = if caller.is_none() {
+                Span::none()
+            } else {
+                tracing::info_span!(parent: &caller, WASM_SPAN, export = request.export(), fuel = tracing::field::Empty)
+            };
+            let entered = span.enter();
             let response = match request {
                 WasmRequest::GetName => {
                     match router.wasix_mcp_router().call_name(&mut store) {
@@ -251,7 +593,7 @@ pub fn spawn_wasm_router(wasm_path: &str) -> WasmRouterHandle {
                         Ok(resource_result) => {
                             match resource_result {
                                 // Correctly match ReadResourceResult
-                                Ok(wasix::mcp::router::ReadResourceResult { contents }) => {
+                                Ok(exports::wasix::mcp::router::ReadResourceResult { contents }) => {
                                     let resource_contents: Vec<mcp_spec::resource::ResourceContents> = contents
                                     .into_iter()
                                     .map(|resource| mcp_spec::resource::ResourceContents::from(resource))
@@ -262,10 +604,10 @@ pub fn spawn_wasm_router(wasm_path: &str) -> WasmRouterHandle {
                                     WasmResponse::ReadResource(resource_result)
                                 },
                                 // Handle the ResourceError
-                                Err(wasix::mcp::router::ResourceError::ExecutionError(error)) => {
+                                Err(exports::wasix::mcp::router::ResourceError::ExecutionError(error)) => {
                                     WasmResponse::RetResourceError(mcp_spec::handler::ResourceError::ExecutionError(error))
                                 },
-                                Err(wasix::mcp::router::ResourceError::NotFound(error)) => {
+                                Err(exports::wasix::mcp::router::ResourceError::NotFound(error)) => {
                                     WasmResponse::RetResourceError(mcp_spec::handler::ResourceError::NotFound(error))
                                 },
                             }
@@ -281,7 +623,7 @@ pub fn spawn_wasm_router(wasm_path: &str) -> WasmRouterHandle {
                         Ok(prompt_result) => {
                             match prompt_result {
                                 // Correctly match ReadResourceResult
-                                Ok(wasix::mcp::router::GetPromptResult{description, messages}) => {
+                                Ok(exports::wasix::mcp::router::GetPromptResult{description, messages}) => {
                                     let prompt_messages: Vec<mcp_spec::prompt::PromptMessage> = messages
                                         .into_iter()
                                         .map(|resource| mcp_spec::prompt::PromptMessage::from(resource)) // Fix to correctly map each `resource`
@@ -295,13 +637,13 @@ pub fn spawn_wasm_router(wasm_path: &str) -> WasmRouterHandle {
                                     WasmResponse::GetPromptResult(prompt)
                                 },
                                 // Handle the ResourceError
-                                Err(wasix::mcp::router::PromptError::InvalidParameters(error)) => {
+                                Err(exports::wasix::mcp::router::PromptError::InvalidParameters(error)) => {
                                     WasmResponse::RetPromptError(mcp_spec::handler::PromptError::InvalidParameters(error))
                                 },
-                                Err(wasix::mcp::router::PromptError::NotFound(error)) => {
+                                Err(exports::wasix::mcp::router::PromptError::NotFound(error)) => {
                                     WasmResponse::RetPromptError(mcp_spec::handler::PromptError::NotFound(error))
                                 },
-                                Err(wasix::mcp::router::PromptError::InternalError(error)) => {
+                                Err(exports::wasix::mcp::router::PromptError::InternalError(error)) => {
                                     WasmResponse::RetPromptError(mcp_spec::handler::PromptError::InternalError(error))
                                 },
                             }
@@ -312,30 +654,34 @@ pub fn spawn_wasm_router(wasm_path: &str) -> WasmRouterHandle {
                         },
                     }
                 },
-                WasmRequest::CallTool(name, value) => {
-                    let mcp_value = json_to_value(value);
-                    match router.wasix_mcp_router()
+                WasmRequest::CallTool(name, value, context) => {
+                    store.data_mut().context = *context;
+                    match json_to_value(value) {
+                        // wasm routers only take flat objects of string arguments
+                        None => WasmResponse::RetToolError(ToolError::InvalidParameters("arguments must be an object of string values".to_string())),
+                        Some(mcp_value) => match router.wasix_mcp_router()
                         .call_call_tool(&mut store,
                         name.as_str(), 
-                        &mcp_value.unwrap()) 
+                        &mcp_value) 
                         {
                             Ok(tool) => match tool {
-                                Ok(wasix::mcp::router::CallToolResult{content, is_error}) => {
+                                Ok(exports::wasix::mcp::router::CallToolResult{content, is_error}) => {
                                     let contents: Vec<mcp_spec::Content> = content
                                         .into_iter()
                                         .map(|item| mcp_spec::Content::from(item)) // Fix to correctly map each `resource`
                                         .collect();
                                     WasmResponse::CallToolResult(CallToolResult { content:contents, is_error})
                                 },
-                                Err(wasix::mcp::router::ToolError::ExecutionError(error)) => WasmResponse::RetToolError(ToolError::ExecutionError(error)), 
-                                Err(wasix::mcp::router::ToolError::InvalidParameters(error)) => WasmResponse::RetToolError(ToolError::InvalidParameters(error)),
-                                Err(wasix::mcp::router::ToolError::NotFound(error)) => WasmResponse::RetToolError(ToolError::NotFound(error)),
-                                Err(wasix::mcp::router::ToolError::SchemaError(error)) => WasmResponse::RetToolError(ToolError::SchemaError(error)),
+                                Err(exports::wasix::mcp::router::ToolError::ExecutionError(error)) => WasmResponse::RetToolError(ToolError::ExecutionError(error)), 
+                                Err(exports::wasix::mcp::router::ToolError::InvalidParameters(error)) => WasmResponse::RetToolError(ToolError::InvalidParameters(error)),
+                                Err(exports::wasix::mcp::router::ToolError::NotFound(error)) => WasmResponse::RetToolError(ToolError::NotFound(error)),
+                                Err(exports::wasix::mcp::router::ToolError::SchemaError(error)) => WasmResponse::RetToolError(ToolError::SchemaError(error)),
                                 
                             },
                             Err(e) => WasmResponse::Error(e.to_string()),
                 
-                        }
+                        },
+                    }
                 },
                 WasmRequest::Capabilities => {
                     match router.wasix_mcp_router()
@@ -350,12 +696,39 @@ pub fn spawn_wasm_router(wasm_path: &str) -> WasmRouterHandle {
                             }
                 },
             };
+            store.data_mut().context = RequestContext::default();
+            *thread_running.lock().unwrap() = None;
+            let fuel = FUEL - store.get_fuel().unwrap_or(0);
+            thread_stats.record_fuel(fuel);
+            span.record("fuel", fuel);
+            let mut stopped = false;
+            if let WasmResponse::Error(err) = &response {
+                match reinstantiate(err) {
+                    Some(instance) => (store, router, check) = instance,
+                    None => stopped = true,
+                }
+            } else if check.is_none() {
+                // without a health export, the router is as healthy as its last request
+                health.ready();
+            }
+            // the span ends before the caller goes on
+            drop(entered);
+            drop(span);
+            drop(caller_entered);
             let _ = resp_tx.send(response);
+            if stopped {
+                break;
+            }
         }
     });
-    
+
     WasmRouterHandle {
         request_tx: req_tx,
+        engine,
+        running,
+        next_seq: AtomicU64::new(0),
+        stats,
+        health,
     }
 }
 
@@ -371,6 +744,14 @@ impl WasmRouter {
             handle: Arc::new(handle),
         }
     }
+
+    pub fn stats(&self) -> Arc<WasmRouterStats> {
+        self.handle.stats()
+    }
+
+    pub fn health(&self) -> Arc<RouterHealth> {
+        self.handle.health()
+    }
 }
 
 impl Router for WasmRouter {
@@ -419,19 +800,23 @@ impl Router for WasmRouter {
         tool_name: &str,
         arguments: JsonValue,
     ) -> super::router::ResponseFuture<Result<CallToolResult, ToolError>> {
-        match self.handle.call_tool(tool_name, arguments.clone()) {
-            Ok(tool_result) => {
-                Box::pin(async move {
-                    Ok(tool_result)
-                })
-            },
-            Err(err) => {
+        self.call_tool_with_context(tool_name, arguments, RequestContext::default())
+    }
+
+    fn call_tool_with_context(
+        &self,
+        tool_name: &str,
+        arguments: JsonValue,
+        context: RequestContext,
+    ) -> super::router::ResponseFuture<Result<CallToolResult, ToolError>> {
+        let result = self.handle.call_tool_async(tool_name, arguments.clone(), context);
+        let tool_name = tool_name.to_string();
+        Box::pin(async move {
+            result.await.map_err(|err| {
                 error!("Error in call tool to {} with {}: {}", tool_name, arguments, err);
-                Box::pin(async move {
-                    Err(ToolError::ExecutionError(err))
-                })
-            }
-        }
+                ToolError::ExecutionError(err)
+            })
+        })
     }
     
     fn list_resources(&self) -> Vec<Resource> {
@@ -448,19 +833,11 @@ impl Router for WasmRouter {
         &self,
         uri: &str,
     ) -> super::router::ResponseFuture<Result<ReadResourceResult, ResourceError>> {
-        match self.handle.read_resource(uri) {
-            Ok(resource) => {
-                Box::pin(async move {
-                    Ok(resource)
-                })
-            },
-            Err(err) => {
-                error!("Error in reading resource for {}: {}", uri, err);
-                Box::pin(async move {
-                    Err(ResourceError::ExecutionError(err))
-                })
-            }
-        }
+        let uri = uri.to_string();
+        let result = self.handle.read_resource(&uri);
+        Box::pin(async move {
+            result.await.inspect_err(|err| error!("Error in reading resource for {}: {}", uri, err))
+        })
     }
     
     fn list_prompts(&self) -> Vec<Prompt> {
@@ -474,18 +851,10 @@ impl Router for WasmRouter {
     }
     
     fn get_prompt(&self, prompt_name: &str) -> super::router::ResponseFuture<Result<GetPromptResult, PromptError>> {
-        match self.handle.get_prompt(prompt_name) {
-            Ok(prompt) => {
-                Box::pin(async move {
-                    Ok(prompt)
-                })
-            },
-            Err(err) => {
-                error!("Error in getting prompt for {}: {}", prompt_name, err);
-                Box::pin(async move {
-                    Err(PromptError::InternalError(err))
-                })
-            }
-        }
+        let prompt_name = prompt_name.to_string();
+        let result = self.handle.get_prompt(&prompt_name);
+        Box::pin(async move {
+            result.await.inspect_err(|err| error!("Error in getting prompt for {}: {}", prompt_name, err))
+        })
     }
 }
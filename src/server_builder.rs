@@ -1,13 +1,20 @@
 use actix::{Actor, Addr};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
-use tracing_subscriber::EnvFilter;
-use crate::client::ClientRegistryActor;
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Layer, Registry};
+use crate::auth::{AccessControl, Authentication};
+use crate::client::{ClientRegistryActor, SessionLimits};
+use crate::limits::{PayloadLimiter, RateLimiter};
 use crate::mcp::InitializationActor;
 use crate::messages::{StartTransport, StopTransport};
 use crate::router::RouterServiceManager;
+use crate::telemetry::{OtlpExporter, OtlpLayer};
 use crate::transport::transport_config::Config;
-use crate::transport::{SseTransportActor, StdioTransportActor, WasiTransportActor};
-use crate::utils::LogConfig;
+use crate::transport::{OriginPolicy, SseTransportActor, StdioTransportActor, WasiTransportActor};
+use crate::utils::log_config::log_filter;
+use crate::utils::{LogConfig, LogLevelHandle, McpLogSink, McpLoggingLayer};
 
 pub const SERVER: &str = "Multi MCP Router Server";
 pub const VERSION: &str = "0.1.0";
@@ -21,6 +28,15 @@ pub struct McpServer{
     router_service_manager: Option<RouterServiceManager>,
     transport_config: Option<Config>,
     log_config: Option<LogConfig>,
+    log_level: Option<LogLevelHandle>,
+    log_sink: McpLogSink,
+    sessions: SessionLimits,
+    auth: Authentication,
+    access: AccessControl,
+    limiter: RateLimiter,
+    payload_limits: PayloadLimiter,
+    origins: OriginPolicy,
+    otlp: Option<OtlpExporter>,
     transport: Option<TransportActorEnum>,
 }
 
@@ -31,6 +47,15 @@ impl McpServer
             router_service_manager: None,
             transport_config: None,
             log_config: None,
+            log_level: None,
+            log_sink: McpLogSink::new(),
+            sessions: SessionLimits::default(),
+            auth: Authentication::default(),
+            access: AccessControl::default(),
+            limiter: RateLimiter::default(),
+            payload_limits: PayloadLimiter::default(),
+            origins: OriginPolicy::default(),
+            otlp: None,
             transport: None,
         }
     }
@@ -65,21 +90,82 @@ impl McpServer
         self
     }
 
+    /// Idle timeout and lifetime of client sessions
+    pub fn with_session_limits(mut self, sessions: SessionLimits) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    /// Authentication for the HTTP transports, without it they serve anyone
+    pub fn with_auth(mut self, auth: Authentication) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// The access policy for the HTTP transports, without it every session can use everything
+    pub fn with_access_control(mut self, access: AccessControl) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Rate limits and max-in-flight caps for the HTTP transports
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Size limits for the messages of the SSE and stdio transports, see [`PayloadLimits`](crate::limits::PayloadLimits)
+    pub fn with_payload_limits(mut self, payload_limits: PayloadLimiter) -> Self {
+        self.payload_limits = payload_limits;
+        self
+    }
+
+    /// Web pages and host names the HTTP transports let in, see [`OriginPolicy`]
+    pub fn with_origin_policy(mut self, origins: OriginPolicy) -> Self {
+        self.origins = origins;
+        self
+    }
+
+    /// Exports the spans of requests to an OpenTelemetry collector, must come before
+    /// [`McpServer::with_logging`] which sets up the layer doing it
+    pub fn with_otlp(mut self, exporter: OtlpExporter) -> Self {
+        self.otlp = Some(exporter);
+        self
+    }
+
     pub fn with_logging(mut self, log_config: LogConfig) -> Self {
         let file_appender = RollingFileAppender::new(Rotation::DAILY, log_config.clone().log_dir, log_config.clone().log_file);
-        
-        tracing_subscriber::fmt()
-            .with_env_filter(EnvFilter::from_default_env().add_directive(log_config.level.into()))
+        let (filter, handle) = reload::Layer::new(log_filter(log_config.level));
+
+        let file_layer = fmt::layer()
             .with_writer(file_appender)
             .with_target(false)
             .with_thread_ids(true)
             .with_file(true)
             .with_line_number(true)
-            .init();
+            .with_filter(filter);
+
+        // forwards logs to the clients that asked for them via logging/setLevel
+        let sink = self.log_sink.clone();
+        let mcp_layer = McpLoggingLayer::new(self.log_sink.clone())
+            .with_filter(filter_fn(move |metadata| McpLoggingLayer::wants(&sink, metadata)));
+
+        let otlp_layer = self.otlp.clone()
+            .map(|exporter| OtlpLayer::new(exporter).with_filter(filter_fn(|metadata| metadata.is_span())));
+
+        match Registry::default().with(file_layer).with(mcp_layer).with(otlp_layer).try_init() {
+            Ok(()) => self.log_level = Some(LogLevelHandle::new(handle)),
+            Err(e) => eprintln!("Logging was already initialised: {}", e),
+        }
         self.log_config = Some(log_config.clone());
         self
     }
 
+    /// Changes the level of the log file, if [`McpServer::with_logging`] set up logging
+    pub fn log_level(&self) -> Option<LogLevelHandle> {
+        self.log_level.clone()
+    }
+
     pub fn start(mut self) -> std::result::Result<Self, std::string::String> {
         
         if self.router_service_manager.is_none() || self.transport_config.is_none() {
@@ -89,7 +175,13 @@ impl McpServer
         let list_prompts_actor = self.router_service_manager.as_ref().unwrap().get_list_prompts();
         let list_tools_actor = self.router_service_manager.as_ref().unwrap().get_list_tools();
         let list_resources_actor = self.router_service_manager.as_ref().unwrap().get_list_resources();
-        let client_registry = ClientRegistryActor::new().start();
+        let metrics = self.router_service_manager.as_ref().unwrap().metrics();
+        let health = self.router_service_manager.as_ref().unwrap().health();
+        let client_registry = ClientRegistryActor::new()
+            .with_log_sink(self.log_sink.clone())
+            .with_session_limits(self.sessions)
+            .start();
+        self.log_sink.attach(client_registry.clone());
         let transport_config = self.transport_config.as_ref().unwrap().clone();
 
         let transport = match transport_config {
@@ -102,7 +194,15 @@ impl McpServer
                     list_prompts_actor,
                     list_tools_actor,
                     list_resources_actor,
-                ).start();
+                )
+                .with_auth(self.auth.clone())
+                .with_access_control(self.access.clone())
+                .with_rate_limiter(self.limiter.clone())
+                .with_payload_limits(self.payload_limits.clone())
+                .with_origin_policy(self.origins.clone())
+                .with_metrics(metrics)
+                .with_health(health)
+                .start();
                 TransportActorEnum::Sse(addr)
             },
             Config::Wasi(wasi_transport_config) => {
@@ -116,7 +216,9 @@ impl McpServer
                 TransportActorEnum::Wasi(addr)
             },
             Config::Stdio(_stdio_transport_config) => {
-                let addr = StdioTransportActor::new(router_registry).start();
+                let addr = StdioTransportActor::new(router_registry)
+                    .with_payload_limits(self.payload_limits.clone())
+                    .start();
                 TransportActorEnum::Stdio(addr)
             },
         };
@@ -0,0 +1,18 @@
+pub mod otlp;
+pub mod trace_context;
+
+pub use otlp::{OtlpExporter, OtlpLayer};
+pub use trace_context::{TraceContext, TRACEPARENT};
+
+/// Name of the span a transport opens around every request of a client, with the fields
+/// `session_id`, `request_id`, `method`, `router_id` and `tool_name`
+pub const REQUEST_SPAN: &str = "mcp.request";
+/// Name of the span the router registry opens to look up the router of a request
+pub const GET_ROUTER_SPAN: &str = "mcp.get_router";
+/// Name of the span a WASM router opens while its guest handles a request
+pub const WASM_SPAN: &str = "mcp.wasm";
+
+/// Span field with the OpenTelemetry span kind, `server` for the spans of client requests
+pub const KIND_FIELD: &str = "otel.kind";
+/// Span field with the JSON-RPC error code a request failed with, it marks the span as failed
+pub const ERROR_CODE_FIELD: &str = "error_code";
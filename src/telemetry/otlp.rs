@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Record};
+use tracing::{Id, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::error::McpEctorsError;
+
+use super::trace_context::{TraceContext, TRACEPARENT};
+use super::{ERROR_CODE_FIELD, KIND_FIELD};
+
+/// How long spans wait to be sent with others
+const EXPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Spans sent in one request at most
+const MAX_BATCH: usize = 512;
+
+/// The instrumentation scope spans are reported under
+const SCOPE: &str = "mcp-ectors";
+
+enum Command {
+    Span(Value),
+    Flush(Sender<()>),
+}
+
+/// Sends finished spans to an OpenTelemetry collector with OTLP over HTTP, as JSON, in batches
+/// from a thread of its own. A collector that cannot be reached loses the spans of that batch.
+#[derive(Clone)]
+pub struct OtlpExporter {
+    sender: Sender<Command>,
+}
+
+impl OtlpExporter {
+    /// `endpoint` is the base URL of the collector, e.g. `http://localhost:4318`, spans go to its
+    /// `/v1/traces`
+    pub fn new(endpoint: &str, service_name: &str) -> Result<Self, McpEctorsError> {
+        let endpoint = endpoint.trim_end_matches('/');
+        let url = if endpoint.ends_with("/v1/traces") { endpoint.to_string() } else { format!("{}/v1/traces", endpoint) };
+        url::Url::parse(&url).map_err(|e| McpEctorsError::InvalidParams(format!("Invalid OTLP endpoint {}: {}", endpoint, e)))?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| McpEctorsError::Internal(format!("Could not start the OTLP exporter: {}", e)))?;
+        let (sender, receiver) = mpsc::channel();
+        let resource = json!({ "attributes": [attribute("service.name", &json!(service_name))] });
+        thread::Builder::new()
+            .name("otlp-exporter".to_string())
+            // the spans of the HTTP client would be exported again and again
+            .spawn(move || tracing::dispatcher::with_default(&tracing::Dispatch::none(), || {
+                export_loop(runtime, reqwest::Client::new(), url, resource, receiver)
+            }))
+            .map_err(|e| McpEctorsError::Internal(format!("Could not start the OTLP exporter: {}", e)))?;
+        Ok(Self { sender })
+    }
+
+    fn export(&self, span: Value) {
+        let _ = self.sender.send(Command::Span(span));
+    }
+
+    /// Sends the spans that are waiting, and waits until they are sent
+    pub fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.sender.send(Command::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+}
+
+fn export_loop(runtime: tokio::runtime::Runtime, client: reqwest::Client, url: String, resource: Value, receiver: Receiver<Command>) {
+    let mut batch = Vec::new();
+    loop {
+        let (flush, done) = match receiver.recv_timeout(EXPORT_INTERVAL) {
+            Ok(Command::Span(span)) => {
+                batch.push(span);
+                (batch.len() >= MAX_BATCH, None)
+            }
+            Ok(Command::Flush(done)) => (true, Some(done)),
+            Err(RecvTimeoutError::Timeout) => (true, None),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if flush && !batch.is_empty() {
+            let body = json!({
+                "resourceSpans": [{
+                    "resource": resource,
+                    "scopeSpans": [{ "scope": { "name": SCOPE }, "spans": std::mem::take(&mut batch) }],
+                }],
+            });
+            // no tracing here, the events would end up in the spans being exported
+            match runtime.block_on(client.post(&url).json(&body).send()) {
+                Ok(response) if !response.status().is_success() => eprintln!("The OTLP collector rejected spans: {}", response.status()),
+                Ok(_) => {}
+                Err(e) => eprintln!("Could not send spans to the OTLP collector: {}", e),
+            }
+        }
+        if let Some(done) = done {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn attribute(key: &str, value: &Value) -> Value {
+    let value = match value {
+        Value::Bool(value) => json!({ "boolValue": value }),
+        // 64 bit integers are strings in OTLP JSON
+        Value::Number(number) if number.is_i64() || number.is_u64() => json!({ "intValue": number.to_string() }),
+        Value::Number(number) => json!({ "doubleValue": number }),
+        Value::String(value) => json!({ "stringValue": value }),
+        other => json!({ "stringValue": other.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+/// A span being recorded, kept in the span extensions until it closes
+struct SpanRecord {
+    context: TraceContext,
+    parent_span_id: Option<[u8; 8]>,
+    start: SystemTime,
+    attributes: BTreeMap<String, Value>,
+}
+
+impl SpanRecord {
+    fn to_otlp(&self, name: &str) -> Value {
+        let kind = match self.attributes.get(KIND_FIELD).and_then(Value::as_str) {
+            Some("server") => 2,
+            Some("client") => 3,
+            _ => 1,
+        };
+        let status = if self.attributes.contains_key(ERROR_CODE_FIELD) { json!({ "code": 2 }) } else { json!({}) };
+        let mut span = json!({
+            "traceId": self.context.trace_id_hex(),
+            "spanId": self.context.span_id_hex(),
+            "name": name,
+            "kind": kind,
+            "startTimeUnixNano": unix_nanos(self.start),
+            "endTimeUnixNano": unix_nanos(SystemTime::now()),
+            "attributes": self.attributes.iter()
+                .filter(|(key, _)| key.as_str() != KIND_FIELD)
+                .map(|(key, value)| attribute(key, value))
+                .collect::<Vec<Value>>(),
+            "status": status,
+        });
+        if let Some(parent_span_id) = self.parent_span_id {
+            span["parentSpanId"] = json!(super::trace_context::hex(&parent_span_id));
+        }
+        span
+    }
+}
+
+/// Collects the fields of a span, the `traceparent` one is the remote parent and no attribute
+#[derive(Default)]
+struct FieldVisitor {
+    attributes: BTreeMap<String, Value>,
+    traceparent: Option<TraceContext>,
+}
+
+impl FieldVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        self.attributes.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == TRACEPARENT {
+            self.traceparent = value.parse().ok();
+        } else {
+            self.insert(field, json!(value));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let value = format!("{:?}", value);
+        if field.name() == TRACEPARENT {
+            self.traceparent = value.trim_matches('"').parse().ok();
+        } else {
+            self.insert(field, json!(value));
+        }
+    }
+}
+
+/// A tracing `Layer` that exports spans with an [`OtlpExporter`]. A span continues the trace of
+/// its `traceparent` field if it has a valid one, otherwise that of its parent span, otherwise it
+/// starts a new trace. Its fields become span attributes.
+pub struct OtlpLayer {
+    exporter: OtlpExporter,
+}
+
+impl OtlpLayer {
+    pub fn new(exporter: OtlpExporter) -> Self {
+        Self { exporter }
+    }
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+        let parent = fields.traceparent.or_else(|| {
+            span.parent().and_then(|parent| parent.extensions().get::<SpanRecord>().map(|record| record.context))
+        });
+        let record = SpanRecord {
+            context: parent.map(|parent| parent.child()).unwrap_or_else(TraceContext::root),
+            parent_span_id: parent.map(|parent| parent.span_id),
+            start: SystemTime::now(),
+            attributes: fields.attributes,
+        };
+        span.extensions_mut().insert(record);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut fields = FieldVisitor::default();
+            values.record(&mut fields);
+            if let Some(record) = span.extensions_mut().get_mut::<SpanRecord>() {
+                record.attributes.extend(fields.attributes);
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            if let Some(record) = span.extensions_mut().remove::<SpanRecord>() {
+                self.exporter.export(record.to_otlp(span.name()));
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::McpEctorsError;
+
+/// Name of the HTTP header, and of the `_meta` field, carrying a [`TraceContext`]
+pub const TRACEPARENT: &str = "traceparent";
+
+/// A W3C trace context, as in `traceparent: 00-<trace id>-<parent span id>-<flags>`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    /// Only the sampled flag, `01`, is defined
+    pub flags: u8,
+}
+
+impl TraceContext {
+    /// A new trace, sampled
+    pub fn root() -> Self {
+        Self { trace_id: random_id(), span_id: random_id(), flags: 1 }
+    }
+
+    /// A span in the same trace
+    pub fn child(&self) -> Self {
+        Self { trace_id: self.trace_id, span_id: random_id(), flags: self.flags }
+    }
+
+    pub fn trace_id_hex(&self) -> String {
+        hex(&self.trace_id)
+    }
+
+    pub fn span_id_hex(&self) -> String {
+        hex(&self.span_id)
+    }
+}
+
+/// A random id that is not all zeroes, those are invalid
+fn random_id<const N: usize>() -> [u8; N] {
+    loop {
+        let id: [u8; N] = rand::random();
+        if id.iter().any(|byte| *byte != 0) {
+            return id;
+        }
+    }
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn parse_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != N * 2 || !s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return None;
+    }
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Ids are hex and must not be all zeroes
+fn parse_id<const N: usize>(s: &str) -> Option<[u8; N]> {
+    parse_hex(s).filter(|id: &[u8; N]| id.iter().any(|byte| *byte != 0))
+}
+
+/// Parses a `traceparent`. Versions after `00` may append fields, which are ignored.
+impl FromStr for TraceContext {
+    type Err = McpEctorsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || McpEctorsError::InvalidParams(format!("Invalid traceparent {}", s));
+        let mut parts = s.trim().split('-');
+        let (version, trace_id, span_id, flags) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(version), Some(trace_id), Some(span_id), Some(flags)) => (version, trace_id, span_id, flags),
+            _ => return Err(invalid()),
+        };
+        let version = parse_hex::<1>(version).map(|version| version[0]);
+        if version.is_none_or(|version| version == 0xff) || (version == Some(0) && parts.next().is_some()) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            trace_id: parse_id(trace_id).ok_or_else(invalid)?,
+            span_id: parse_id(span_id).ok_or_else(invalid)?,
+            flags: parse_hex::<1>(flags).ok_or_else(invalid)?[0],
+        })
+    }
+}
+
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "00-{}-{}-{:02x}", self.trace_id_hex(), self.span_id_hex(), self.flags)
+    }
+}
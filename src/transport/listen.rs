@@ -0,0 +1,151 @@
+use std::fmt;
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use super::TransportError;
+
+/// Prefix of listen addresses that are Unix domain sockets, e.g. `unix:/run/mcp-ectors.sock`
+pub const UNIX_PREFIX: &str = "unix:";
+
+/// The first file descriptor systemd passes with socket activation, `SD_LISTEN_FDS_START`
+pub const LISTEN_FDS_START: i32 = 3;
+
+/// Where an HTTP transport accepts connections
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListenAddress {
+    /// `host:port`, e.g. `127.0.0.1:3000`, `[::1]:3000`, `0.0.0.0:3000` or `localhost:3000`.
+    /// A host name binds every address it resolves to.
+    Tcp(String),
+    /// The path of a Unix domain socket
+    Unix(PathBuf),
+}
+
+impl ListenAddress {
+    /// Only reachable from this host, on IPv4 and IPv6
+    pub fn localhost(port: u16) -> Self {
+        ListenAddress::Tcp(format!("localhost:{}", port))
+    }
+}
+
+impl FromStr for ListenAddress {
+    type Err = TransportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix(UNIX_PREFIX) {
+            if path.is_empty() {
+                return Err(TransportError::configuration_error(format!("Missing socket path in listen address {}", s)));
+            }
+            return Ok(ListenAddress::Unix(PathBuf::from(path)));
+        }
+        if s.parse::<SocketAddr>().is_ok() {
+            return Ok(ListenAddress::Tcp(s.to_string()));
+        }
+        // a host name, IPv6 addresses need brackets to tell the port apart
+        match s.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && !host.contains(':') && port.parse::<u16>().is_ok() => Ok(ListenAddress::Tcp(s.to_string())),
+            _ => Err(TransportError::configuration_error(format!(
+                "Expected host:port, [ipv6]:port or unix:/path as listen address, got {}", s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ListenAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddress::Tcp(address) => write!(f, "{}", address),
+            ListenAddress::Unix(path) => write!(f, "{}{}", UNIX_PREFIX, path.display()),
+        }
+    }
+}
+
+/// A socket the transport accepts connections on: one it binds itself, or one it was handed
+pub enum Listener {
+    Address(ListenAddress),
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+impl Listener {
+    pub fn is_unix(&self) -> bool {
+        match self {
+            Listener::Address(address) => matches!(address, ListenAddress::Unix(_)),
+            Listener::Tcp(_) => false,
+            #[cfg(unix)]
+            Listener::Unix(_) => true,
+        }
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Listener::Address(address) => write!(f, "{}", address),
+            Listener::Tcp(listener) => match listener.local_addr() {
+                Ok(address) => write!(f, "{} (inherited)", address),
+                Err(_) => write!(f, "inherited TCP socket"),
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener) => match listener.local_addr().ok().and_then(|address| address.as_pathname().map(|path| path.to_path_buf())) {
+                Some(path) => write!(f, "{}{} (inherited)", UNIX_PREFIX, path.display()),
+                None => write!(f, "inherited Unix socket"),
+            },
+        }
+    }
+}
+
+/// The listeners of a transport: the sockets passed by systemd socket activation if there are any,
+/// they replace the configured addresses, otherwise `addresses`.
+pub fn listeners(addresses: &[ListenAddress]) -> Result<Vec<Listener>, TransportError> {
+    let inherited = inherited_listeners()?;
+    if !inherited.is_empty() {
+        return Ok(inherited);
+    }
+    Ok(addresses.iter().cloned().map(Listener::Address).collect())
+}
+
+/// The sockets systemd passed to this process, see `sd_listen_fds(3)`: `LISTEN_FDS` sockets from
+/// file descriptor 3 on, if `LISTEN_PID` is this process. Empty without socket activation.
+#[cfg(unix)]
+pub fn inherited_listeners() -> Result<Vec<Listener>, TransportError> {
+    use std::os::fd::{FromRawFd, IntoRawFd};
+    use std::os::unix::net::UnixListener;
+
+    let for_this_process = std::env::var("LISTEN_PID").ok()
+        .and_then(|pid| pid.trim().parse::<u32>().ok())
+        .is_some_and(|pid| pid == std::process::id());
+    let count = match std::env::var("LISTEN_FDS") {
+        Ok(count) if for_this_process => count.trim().parse::<i32>()
+            .map_err(|_| TransportError::configuration_error(format!("Invalid LISTEN_FDS {}", count)))?,
+        _ => return Ok(vec![]),
+    };
+    // like sd_listen_fds(1), the sockets are only taken once, a restarted transport must not take them again
+    for variable in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(variable);
+    }
+    let mut listeners = vec![];
+    for fd in LISTEN_FDS_START..LISTEN_FDS_START + count {
+        // SAFETY: systemd hands these descriptors to this process and nothing else owns them
+        let tcp = unsafe { TcpListener::from_raw_fd(fd) };
+        // only the address of a TCP socket reads as an internet address
+        match tcp.local_addr() {
+            Ok(_) => listeners.push(Listener::Tcp(tcp)),
+            Err(_) => {
+                // SAFETY: the descriptor was just released by the TCP listener
+                let unix = unsafe { UnixListener::from_raw_fd(tcp.into_raw_fd()) };
+                if unix.local_addr().is_err() {
+                    return Err(TransportError::configuration_error(format!("File descriptor {} from LISTEN_FDS is not a listening socket", fd)));
+                }
+                listeners.push(Listener::Unix(unix));
+            }
+        }
+    }
+    Ok(listeners)
+}
+
+#[cfg(not(unix))]
+pub fn inherited_listeners() -> Result<Vec<Listener>, TransportError> {
+    Ok(vec![])
+}
@@ -4,6 +4,9 @@ pub mod stdio_transport_actor;
 pub mod wasi_transport_actor;
 pub mod transport_error;
 pub mod transport_config;
+pub mod tls;
+pub mod listen;
+pub mod origin;
 
 
 pub use transport_actor::TransportActorTrait;
@@ -12,4 +15,6 @@ pub use stdio_transport_actor::StdioTransportActor;
 pub use wasi_transport_actor::WasiTransportActor;
 pub use transport_error::TransportError;
 pub use transport_config::TransportConfig;
+pub use listen::ListenAddress;
+pub use origin::OriginPolicy;
 
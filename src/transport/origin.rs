@@ -0,0 +1,147 @@
+use std::net::IpAddr;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use tracing::warn;
+
+use crate::error::McpEctorsError;
+use crate::messages::outgoing_error;
+
+use super::ListenAddress;
+
+/// Allows any origin or host
+pub const ANY: &str = "*";
+
+/// How long browsers may cache the answer to a preflight request, in seconds
+pub const PREFLIGHT_MAX_AGE: u32 = 600;
+
+/// The request headers browsers may send cross-origin, besides the ones they always may
+const ALLOWED_HEADERS: &str = "authorization, content-type, last-event-id, x-api-key";
+
+/// Which browser origins may call the transport, and by which host names it may be addressed.
+///
+/// Requests with an `Origin` header, i.e. from a web page, are only let in from the allowed
+/// origins, `scheme://host[:port]` as the browser sends it. Without allowed origins no web page
+/// gets in. Requests without `Origin` come from other clients and are not affected.
+///
+/// The `Host` of every request must be a loopback name or address, or one of the allowed hosts.
+/// This stops DNS rebinding, where a web page makes its own host name resolve to `127.0.0.1`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OriginPolicy {
+    allowed_origins: Vec<String>,
+    allowed_hosts: Vec<String>,
+}
+
+impl OriginPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets web pages of `origin` in, e.g. `https://agent.example.com`, or any page with `*`
+    pub fn with_allowed_origin(mut self, origin: &str) -> Self {
+        self.allowed_origins.push(origin.trim_end_matches('/').to_lowercase());
+        self
+    }
+
+    /// Lets requests addressed to `host` in, a name or address without port, or any host with `*`
+    pub fn with_allowed_host(mut self, host: &str) -> Self {
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+        if !self.allowed_hosts.contains(&host) {
+            self.allowed_hosts.push(host);
+        }
+        self
+    }
+
+    /// Allows the hosts the transport listens on by name or address. Wildcard addresses such as
+    /// `0.0.0.0` say nothing about the names clients use, those have to be allowed explicitly.
+    pub fn with_listen_hosts(self, addresses: &[ListenAddress]) -> Self {
+        addresses.iter()
+            .filter_map(|address| match address {
+                ListenAddress::Tcp(address) => address.rsplit_once(':').map(|(host, _)| host),
+                ListenAddress::Unix(_) => None,
+            })
+            .filter(|host| !host_ip(host).is_some_and(|ip| ip.is_unspecified()))
+            .fold(self, |policy, host| policy.with_allowed_host(host))
+    }
+
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        let origin = origin.trim_end_matches('/').to_lowercase();
+        self.allowed_origins.iter().any(|allowed| allowed == ANY || *allowed == origin)
+    }
+
+    /// Whether the host part of a `Host` header, port or not, may be served
+    pub fn allows_host(&self, host: &str) -> bool {
+        let host = strip_port(host).to_lowercase();
+        host == "localhost"
+            || host_ip(&host).is_some_and(|ip| ip.is_loopback())
+            || self.allowed_hosts.iter().any(|allowed| allowed == ANY || *allowed == host)
+    }
+}
+
+fn host_ip(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+/// `localhost:3000` -> `localhost`, `[::1]:3000` -> `::1`, `::1` -> `::1`
+fn strip_port(host: &str) -> &str {
+    if let Some(bracketed) = host.strip_prefix('[') {
+        return bracketed.split(']').next().unwrap_or(bracketed);
+    }
+    match host.rsplit_once(':') {
+        // more than one colon is an IPv6 address without brackets and port
+        Some((name, _)) if !name.contains(':') => name,
+        _ => host,
+    }
+}
+
+fn forbidden(req: ServiceRequest, reason: String) -> ServiceResponse<actix_web::body::BoxBody> {
+    warn!("Rejected {} {} from {:?}: {}", req.method(), req.path(), req.peer_addr(), reason);
+    let response = HttpResponse::Forbidden().json(outgoing_error(None, McpEctorsError::Forbidden(reason).to_error_data()));
+    req.into_response(response)
+}
+
+/// actix-web middleware, use with `middleware::from_fn`. Checks the `Host` and `Origin` of every
+/// request against the `Data<OriginPolicy>` of the app, answers CORS preflight requests and adds
+/// the CORS headers to responses for allowed origins. Without an origin policy nothing is checked.
+pub async fn check_origin(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let Some(policy) = req.app_data::<Data<OriginPolicy>>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    // the Host header, or the authority of HTTP/2 requests; not X-Forwarded-Host, a page can set that
+    let host = req.headers().get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| req.uri().authority().map(|authority| authority.to_string()));
+    if let Some(host) = host.filter(|host| !policy.allows_host(host)) {
+        return Ok(forbidden(req, format!("host {} is not allowed", host)).map_into_right_body());
+    }
+    let Some(origin) = req.headers().get(header::ORIGIN).cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    if !origin.to_str().is_ok_and(|origin| policy.allows_origin(origin)) {
+        return Ok(forbidden(req, format!("origin {:?} is not allowed", origin)).map_into_right_body());
+    }
+
+    if req.method() == Method::OPTIONS && req.headers().contains_key(header::ACCESS_CONTROL_REQUEST_METHOD) {
+        let response = HttpResponse::NoContent()
+            .insert_header((header::ACCESS_CONTROL_ALLOW_ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_ALLOW_METHODS, "GET, POST, OPTIONS"))
+            .insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, ALLOWED_HEADERS))
+            .insert_header((header::ACCESS_CONTROL_MAX_AGE, PREFLIGHT_MAX_AGE.to_string()))
+            .insert_header((header::VARY, "Origin"))
+            .finish();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    let mut response = next.call(req).await?;
+    let headers = response.headers_mut();
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    // so a page can read the challenge of a 401
+    headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, HeaderValue::from_static("www-authenticate"));
+    headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+    Ok(response.map_into_left_body())
+}
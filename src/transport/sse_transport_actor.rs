@@ -1,30 +1,48 @@
 use actix::prelude::*;
 use actix_web::dev::ServerHandle;
-use actix_web::middleware::Logger;
-use actix_web::{web, App, HttpServer, HttpResponse};
+use actix_web::middleware::{from_fn, Logger};
+use actix_web::dev::Payload;
+use actix_web::{web, App, FromRequest, HttpRequest, HttpServer, HttpResponse};
 use actix_web::web::Data;
 use actix_web_lab::sse::{Sse, Data as SseData, Event};
 use futures::StreamExt;
-use mcp_spec::protocol::{ErrorData, JsonRpcError, JsonRpcMessage, JsonRpcRequest, JsonRpcResponse};
-use serde_json::Value;
-use tracing::{error, info, trace};
-use crate::client::ClientRegistryActor;
-use crate::client::client_registry::{RegisterClient, NotifyClient}; 
+use mcp_spec::protocol::{ErrorData, JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+use serde_json::{json, Value};
+use tracing::{error, info, trace, warn, Instrument, Span};
+use crate::auth::oauth::{protected_resource_metadata, PROTECTED_RESOURCE_PATH};
+use crate::auth::{authenticate, AccessControl, Authentication, Principal};
+use crate::limits::payload_limits::json_len;
+use crate::limits::{Caller, PayloadLimit, PayloadLimiter, RateLimiter};
+use crate::health::{liveness_handler, readiness_handler, router_health_handler, Health, LIVENESS_PATH, READINESS_PATH, ROUTER_HEALTH_PATH};
+use crate::metrics::{metrics_handler, Metrics, METRICS_PATH};
+use crate::telemetry::{ERROR_CODE_FIELD, REQUEST_SPAN, TRACEPARENT};
+use crate::client::{ClientHandle, ClientRegistryActor};
+use crate::error::McpEctorsError;
+use crate::client::SessionBinding;
+use crate::client::client_registry::{CancelRequest, ClientResponse, NotifyClientRaw, OpenSession, RefreshRoots, RegisterClient, RegisterRawRecipient, ResolveSession, SetClientCapabilities, SetLogLevel, TrackRequest, UntrackRequest}; 
 
 use crate::mcp::{InitializationActor, ListPromptsActor, ListResourcesActor, ListToolsActor};
 // Ensure these are imported correctly
 use crate::messages::transport_messages::{TransportRequest, StartTransport, StopTransport};
-use crate::messages::{BroadcastSseMessage, CallToolRequest, ClientMessage, DeregisterSseClient, GetPromptRequest, GetRouter, InitializeRequest, InitializedNotificationRequest, ListPromptsRequest, ListResourceTemplatesRequest, ListResourcesRequest, ListToolsRequest, NotifySseClient, ReadResourceRequest, RegisterSseClient, SubscribeRequest, UnsubscribeRequest, JSONRPC_VERSION};
-use crate::router::router_registry::ActorRouterRegistry;
-use crate::utils::json_rpc::{JSON_RPC_INTERNAL_ERROR, MCP_INTERNAL_SERVER_ERROR, MCP_INVALID_METHOD, MCP_INVALID_REQUEST, MCP_SERVICE_UNAVAILABLE};
-use crate::utils::JsonRpcUtils;
+use crate::messages::{CallToolRequest, CancelledNotification, ClientMessage, RawClientMessage, GetPromptRequest, GetRouter, IncomingMessage, ResolveAlias, InitializeRequest, PingRequest, RequestId, outgoing_error, outgoing_response, InitializedNotificationRequest, ListPromptsRequest, ListResourceTemplatesRequest, ListResourcesRequest, ListToolsRequest, ReadResourceRequest, RootsListChangedNotification, SetLevelRequest, SubscribeRequest, UnsubscribeRequest, JSONRPC_VERSION};
+use crate::router::router_registry::{ActorRouterRegistry, ROUTER_SEPERATOR};
+use crate::router::{unwrap_proxy_calls, ProgressReporter, RequestContext};
+use crate::utils::json_rpc::JSON_RPC_INVALID_REQUEST;
+use crate::utils::LoggingLevel;
 
 use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use tokio::sync::mpsc;
 use std::time::Duration;
 use actix_web::error::Error;
 
+use super::listen::{self, ListenAddress, Listener};
+use super::origin::{check_origin, OriginPolicy};
+use super::tls;
 use super::transport_actor::TransportActorTrait;
 use super::TransportError; 
 
@@ -33,17 +51,34 @@ use super::TransportError;
 // Configuration struct for the server
 #[derive(Clone,Debug)]
 pub struct SseTransportConfig {
+    /// The port of `localhost` if there are no `listen` addresses
     pub port: u16,
+    /// Where to accept connections, e.g. `127.0.0.1:3000`, `[::]:3000` or `unix:/run/mcp.sock`.
+    /// Sockets passed by systemd socket activation (`LISTEN_FDS`) replace them.
+    pub listen: Vec<ListenAddress>,
+    /// Serves HTTPS instead of HTTP with this PEM certificate chain and `tls_key`, both reloaded when they change
     pub tls_cert: Option<String>,
     pub tls_key: Option<String>,
+    /// PEM bundle of the CAs client certificates must be issued by, requires `tls_cert` (mTLS)
+    pub tls_client_ca: Option<String>,
     pub log_dir: String,
     pub log_file: String,
 }
 
+impl SseTransportConfig {
+    /// The configured listen addresses, or only `localhost` on `port` without any
+    pub fn listen_addresses(&self) -> Vec<ListenAddress> {
+        if self.listen.is_empty() {
+            vec![ListenAddress::localhost(self.port)]
+        } else {
+            self.listen.clone()
+        }
+    }
+}
+
 // SseTransportActor to manage SSE client connectionspub struct SseTransportActor<R>
 pub struct SseTransportActor
 {
-    clients: HashMap<u64, Recipient<ClientMessage>>, // Track connected SSE clients
     config: SseTransportConfig,
     registry_addr: Addr<ClientRegistryActor>,
     router_registry: Addr<ActorRouterRegistry>,
@@ -51,6 +86,13 @@ pub struct SseTransportActor
     prompts: Addr<ListPromptsActor>,
     tools: Addr<ListToolsActor>,
     resources: Addr<ListResourcesActor>,
+    auth: Authentication,
+    access: AccessControl,
+    limiter: RateLimiter,
+    payload_limits: PayloadLimiter,
+    origins: OriginPolicy,
+    metrics: Metrics,
+    health: Health,
     server: Option<ServerHandle>,
 }
 impl SseTransportActor
@@ -64,7 +106,6 @@ impl SseTransportActor
         resources: Addr<ListResourcesActor>,
     ) -> Self {
         Self {
-            clients: HashMap::new(),
             config,
             registry_addr,
             router_registry,
@@ -72,10 +113,58 @@ impl SseTransportActor
             prompts,
             tools,
             resources,
+            auth: Authentication::default(),
+            access: AccessControl::default(),
+            limiter: RateLimiter::default(),
+            payload_limits: PayloadLimiter::default(),
+            origins: OriginPolicy::default(),
+            metrics: Metrics::default(),
+            health: Health::default(),
             server: None,
         }
     }
-    
+
+    /// Requires every request to `/sse` and `/messages/` to be authenticated by `auth`
+    pub fn with_auth(mut self, auth: Authentication) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Limits what sessions can list and use to what the policy of `access` allows
+    pub fn with_access_control(mut self, access: AccessControl) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Rejects requests over the limits of `limiter` with `MCP_RATE_LIMITED`
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Rejects messages over the size limits of `payload_limits` with the error of the limit
+    pub fn with_payload_limits(mut self, payload_limits: PayloadLimiter) -> Self {
+        self.payload_limits = payload_limits;
+        self
+    }
+
+    /// The browser origins and host names to let in, besides the loopback and listen hosts
+    pub fn with_origin_policy(mut self, origins: OriginPolicy) -> Self {
+        self.origins = origins;
+        self
+    }
+
+    /// Counts requests and sessions in `metrics` and serves them at `/metrics`
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Serves the readiness and router states of `health` at `/readyz` and `/health/routers`
+    pub fn with_health(mut self, health: Health) -> Self {
+        self.health = health;
+        self
+    }
 }
 
 impl TransportActorTrait for SseTransportActor 
@@ -102,95 +191,63 @@ impl Actor for SseTransportActor
 
 }
 
-/// Registers a new SSE client.
-impl Handler<RegisterSseClient> for SseTransportActor 
+impl Handler<TransportRequest> for SseTransportActor
 {
-    type Result = u64;
+    type Result = Result<JsonRpcResponse, JsonRpcError>;
 
-    fn handle(&mut self, msg: RegisterSseClient, _ctx: &mut Self::Context) -> Self::Result {
-        let client_id = rand::random::<u64>(); // Generate unique client ID
-        self.clients.insert(client_id, msg.recipient);
-        tracing::info!("Registered SSE client with ID: {}", client_id);
-        client_id
+    fn handle(&mut self, msg: TransportRequest, _ctx: &mut Self::Context) -> Self::Result {
+        Err(McpEctorsError::InvalidRequest(format!("Did not expect this request: {:?}",msg.request)).to_json_rpc_error(msg.request.id))
     }
 }
 
-/// Deregisters an SSE client when they disconnect.
-impl Handler<DeregisterSseClient> for SseTransportActor
-{
-    type Result = ();
 
-    fn handle(&mut self, msg: DeregisterSseClient, _ctx: &mut Self::Context) -> Self::Result {
-        if self.clients.remove(&msg.client_id).is_some() {
-            tracing::info!("Deregistered SSE client with ID: {}", msg.client_id);
-        } else {
-            tracing::warn!("Tried to deregister unknown SSE client: {}", msg.client_id);
-        }
-    }
-}
+/// Handles sending SSE messages to the client.
+impl Handler<ClientMessage> for SseRecipient {
+    type Result = ();
 
-/// Sends a message to a specific SSE client.
-impl Handler<NotifySseClient> for SseTransportActor
-{
-    type Result = ResponseActFuture<Self, Result<(), ()>>;
-
-    fn handle(&mut self, msg: NotifySseClient, _ctx: &mut Self::Context) -> Self::Result {
-        if let Some(recipient) = self.clients.get(&msg.client_id) {
-            let fut = recipient.send(ClientMessage(msg.message.clone()));
-            tracing::info!("Sending message to SSE client: {}", msg.client_id);
-            Box::pin(actix::fut::wrap_future(async move {
-                fut.await.map_err(|_| ())
-            }))
-        } else {
-            tracing::warn!("SSE client {} not found", msg.client_id);
-            Box::pin(actix::fut::ready(Err(())))
-        }
+    fn handle(&mut self, msg: ClientMessage, ctx: &mut Self::Context) {
+        let data = serde_json::to_string(&msg.0).unwrap_or_else(|_| "{}".to_string());
+        self.queue(data, ctx);
     }
 }
 
-/// Broadcasts a message to all connected SSE clients.
-impl Handler<BroadcastSseMessage> for SseTransportActor 
-{
+/// Handles sending already serialised messages, e.g. batch responses, to the client.
+impl Handler<RawClientMessage> for SseRecipient {
     type Result = ();
 
-    fn handle(&mut self, msg: BroadcastSseMessage, _ctx: &mut Self::Context) -> Self::Result {
-        tracing::info!("Broadcasting message to {} SSE clients", self.clients.len());
-        for (_id, recipient) in self.clients.iter() {
-            let _ = recipient.do_send(ClientMessage(msg.message.clone()));
-        }
+    fn handle(&mut self, msg: RawClientMessage, ctx: &mut Self::Context) {
+        self.queue(msg.0.to_string(), ctx);
     }
 }
-impl Handler<TransportRequest> for SseTransportActor
-{
-    type Result = Result<JsonRpcResponse, JsonRpcError>;
 
-    fn handle(&mut self, msg: TransportRequest, _ctx: &mut Self::Context) -> Self::Result {
-        Err(JsonRpcUtils::error_response(msg.request.id, 
-            MCP_INVALID_REQUEST, 
-            format!("Did not expect this request: {:?}",msg.request).as_str(), 
-        None))
-    }
+/// Represents an SSE recipient that forwards messages to the client.
+pub struct SseRecipient {
+    // events with their size in bytes
+    sender: mpsc::Sender<(usize, Event)>,
+    // bytes of the events sent to the channel and not yet taken by the stream
+    queued: Arc<AtomicUsize>,
+    payload_limits: PayloadLimiter,
+    metrics: Metrics,
 }
 
-
-/// Handles sending SSE messages to the client.
-impl Handler<ClientMessage> for SseRecipient {
-    type Result = ();
-
-    fn handle(&mut self, msg: ClientMessage, _ctx: &mut Self::Context) {
-        let data = serde_json::to_string(&msg.0).unwrap_or_else(|_| "{}".to_string());
-        let event = Event::Data(SseData::new(data));
-        if self.sender.try_send(event).is_err() {
+impl SseRecipient {
+    fn queue(&mut self, data: String, ctx: &mut Context<Self>) {
+        let size = data.len();
+        if let Err(e) = self.payload_limits.check(PayloadLimit::Queue, self.queued.load(Ordering::Relaxed) + size) {
+            // the client does not read its stream, it ends once the queued events are written
+            warn!("Closing the stream of a client that does not keep up: {}", e);
+            ctx.stop();
+            return;
+        }
+        self.queued.fetch_add(size, Ordering::Relaxed);
+        if self.sender.try_send((size, Event::Data(SseData::new(data)))).is_err() {
+            self.queued.fetch_sub(size, Ordering::Relaxed);
+            self.metrics.event_dropped();
             tracing::warn!("Failed to send SSE message to client");
         }
     }
 }
 
-/// Represents an SSE recipient that forwards messages to the client.
-pub struct SseRecipient {
-    sender: mpsc::Sender<Event>,
-}
-
 impl Actor for SseRecipient {
     type Context = Context<Self>;
 }
@@ -204,7 +261,6 @@ impl Handler<StartTransport> for SseTransportActor
     fn handle(&mut self, _msg: StartTransport, _ctx: &mut Self::Context) -> Self::Result {
         
         tracing::info!("Starting SSE transport...");
-        let addr_str = format!("0.0.0.0:{}", self.config.port);
         let registry_addr = self.registry_addr.clone();
         let router_registry = self.router_registry.clone();
         //let sse_transport_addr = ctx.address();
@@ -212,36 +268,91 @@ impl Handler<StartTransport> for SseTransportActor
         let prompts = self.prompts.clone();
         let tools = self.tools.clone();
         let resources = self.resources.clone();
+        let auth = Data::new(self.auth.clone());
+        let access = Data::new(self.access.clone());
+        let limiter = Data::new(self.limiter.clone());
+        let payload_limits = Data::new(self.payload_limits.clone());
+        let metrics = Data::new(self.metrics.clone());
+        let health = Data::new(self.health.clone());
+        let origins = Data::new(self.origins.clone().with_listen_hosts(&self.config.listen_addresses()));
 
         // Wrap the async logic inside a future and ensure it resolves to `()`.
 
-        // Attempt to bind the HTTP server.
-        let server_result = HttpServer::new(move || {
-            App::new()
-                .wrap(Logger::default())
-                .app_data(Data::new(registry_addr.clone()))
-                .app_data(Data::new(router_registry.clone()))
-                .app_data(Data::new(initialize.clone()))
-                .app_data(Data::new(prompts.clone()))
-                .app_data(Data::new(tools.clone()))
-                .app_data(Data::new(resources.clone()))
-                .route("/sse", web::get().to(sse_handler))
-                .route("/messages/", web::post().to(post_handler))
-        })
-        .bind(addr_str.clone());
+        let tls = match (&self.config.tls_cert, &self.config.tls_key, &self.config.tls_client_ca) {
+            (Some(cert), Some(key), client_ca) => match tls::server_config(Path::new(cert), Path::new(key), client_ca.as_deref().map(Path::new)) {
+                Ok(tls) => Some(tls),
+                Err(e) => {
+                    error!("Could not start the server because of an error: {}", e);
+                    return Box::pin(actix::fut::ready(Err(e)));
+                }
+            },
+            (None, None, None) => None,
+            _ => {
+                return Box::pin(actix::fut::ready(Err(TransportError::configuration_error(
+                    "TLS needs both a certificate and a key, a client CA needs both too",
+                ))))
+            }
+        };
 
-        let server = match server_result {
-            Ok(srv) => srv,
+        let listeners = match listen::listeners(&self.config.listen_addresses()) {
+            Ok(listeners) => listeners,
             Err(e) => {
-                error!("Could not start the server because of an error: {}",e);
-                return Box::pin(actix::fut::ready(Err(
-                    TransportError::ConfigurationError(format!(
-                        "Failed to bind HTTP on {}: {:?}",
-                        addr_str, e
-                    )),
-                )))
+                error!("Could not start the server because of an error: {}", e);
+                return Box::pin(actix::fut::ready(Err(e)));
             }
         };
+        if self.config.tls_client_ca.is_some() && listeners.iter().any(|listener| listener.is_unix()) {
+            // clients of the socket would get in without a certificate
+            return Box::pin(actix::fut::ready(Err(TransportError::configuration_error(
+                "Unix domain sockets are served without TLS, so they cannot require client certificates",
+            ))));
+        }
+
+        // Attempt to bind the HTTP server.
+        let routes = configure_routes(registry_addr, router_registry, initialize, prompts, tools, resources);
+        let mut server = HttpServer::new(move || {
+            App::new()
+                .wrap(Logger::default())
+                .app_data(auth.clone())
+                .app_data(access.clone())
+                .app_data(limiter.clone())
+                .app_data(payload_limits.clone())
+                .app_data(metrics.clone())
+                .app_data(health.clone())
+                .app_data(origins.clone())
+                .configure(routes.clone())
+        });
+        if tls.is_some() {
+            server = server.on_connect(tls::client_certificate_on_connect);
+        }
+        for listener in listeners {
+            let description = listener.to_string();
+            let bound = match (listener, &tls) {
+                (Listener::Address(ListenAddress::Tcp(address)), Some(tls)) => server.bind_rustls_0_23(address, tls.clone()),
+                (Listener::Address(ListenAddress::Tcp(address)), None) => server.bind(address),
+                (Listener::Tcp(listener), Some(tls)) => server.listen_rustls_0_23(listener, tls.clone()),
+                (Listener::Tcp(listener), None) => server.listen(listener),
+                #[cfg(unix)]
+                (Listener::Address(ListenAddress::Unix(path)), _) => server.bind_uds(path),
+                #[cfg(unix)]
+                (Listener::Unix(listener), _) => server.listen_uds(listener),
+                #[cfg(not(unix))]
+                (Listener::Address(ListenAddress::Unix(_)), _) => Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Unix domain sockets are not supported on this platform")),
+            };
+            server = match bound {
+                Ok(server) => server,
+                Err(e) => {
+                    error!("Could not start the server because of an error: {}",e);
+                    return Box::pin(actix::fut::ready(Err(
+                        TransportError::ConfigurationError(format!(
+                            "Failed to bind HTTP on {}: {:?}",
+                            description, e
+                        )),
+                    )))
+                }
+            };
+            info!("Listening on {}{}", description, if tls.is_some() && !description.starts_with(listen::UNIX_PREFIX) { " with TLS" } else { "" });
+        }
 
         let server = server.run();
 
@@ -283,27 +394,76 @@ impl Handler<StopTransport> for SseTransportActor
 }
 
 
+/// Adds the `/sse`, `/messages/`, `/metrics` and health endpoints, and the actors their handlers use, to an actix-web app
+pub fn configure_routes(
+    registry: Addr<ClientRegistryActor>,
+    router_registry: Addr<ActorRouterRegistry>,
+    initialize: InitializationActor,
+    prompts: Addr<ListPromptsActor>,
+    tools: Addr<ListToolsActor>,
+    resources: Addr<ListResourcesActor>,
+) -> impl Fn(&mut web::ServiceConfig) + Clone + Send + 'static {
+    move |cfg: &mut web::ServiceConfig| {
+        cfg.app_data(Data::new(registry.clone()))
+            .app_data(Data::new(router_registry.clone()))
+            .app_data(Data::new(initialize.clone()))
+            .app_data(Data::new(prompts.clone()))
+            .app_data(Data::new(tools.clone()))
+            .app_data(Data::new(resources.clone()))
+            // tells OAuth clients where to get a token, so it must not need one
+            .route(PROTECTED_RESOURCE_PATH, web::get().to(protected_resource_metadata))
+            // probes neither authenticate nor send a host name the origin policy knows
+            .route(LIVENESS_PATH, web::get().to(liveness_handler))
+            .route(READINESS_PATH, web::get().to(readiness_handler))
+            // authenticates against the Data<Authentication> of the app, if there is one, after the
+            // Data<OriginPolicy> checks, so CORS preflight requests need no credentials
+            .service(web::scope("")
+                .wrap(from_fn(authenticate))
+                .wrap(from_fn(check_origin))
+                .route("/sse", web::get().to(sse_handler))
+                .route("/messages/", web::post().to(post_handler))
+                .route(METRICS_PATH, web::get().to(metrics_handler))
+                .route(ROUTER_HEALTH_PATH, web::get().to(router_health_handler)));
+    }
+}
+
 // --- Helper functions for POST and SSE Handlers ---
-async fn sse_handler(registry: Data<Addr<ClientRegistryActor>>) -> Sse<impl Stream<Item = Result<Event, Error>>> {
-    let (tx, rx) = mpsc::channel::<Event>(10000);
-    let sse_recipient = SseRecipient { sender: tx.clone() }.start();
+async fn sse_handler(
+    req: HttpRequest,
+    registry: Data<Addr<ClientRegistryActor>>,
+    principal: Option<web::ReqData<Principal>>,
+    payload_limits: Option<Data<PayloadLimiter>>,
+    metrics: Option<Data<Metrics>>,
+) -> Sse<impl Stream<Item = Result<Event, Error>>> {
+    let (tx, rx) = mpsc::channel::<(usize, Event)>(10000);
+    let queued = Arc::new(AtomicUsize::new(0));
+    let payload_limits = payload_limits.map(|limits| limits.get_ref().clone()).unwrap_or_default();
+    let metrics = metrics.map(|metrics| metrics.get_ref().clone()).unwrap_or_default();
+    // counts the session as long as its stream is open
+    let session = metrics.open_session();
+    let sse_recipient = SseRecipient { sender: tx, queued: queued.clone(), payload_limits, metrics }.start();
     let client_id = registry
-        .send(RegisterClient { recipient: sse_recipient.recipient() })
+        .send(RegisterClient { recipient: sse_recipient.clone().recipient() })
         .await
         .unwrap();
+    registry.do_send(RegisterRawRecipient { client_id, recipient: sse_recipient.recipient() });
+    // the session is bound to whoever opened it, and to the client certificate of the connection
+    let binding = SessionBinding::new(principal.map(web::ReqData::into_inner), req.conn_data::<Principal>());
+    let token = registry.send(OpenSession { client_id, binding }).await.unwrap();
 
-    let init_event: Event = SseData::new(format!("/messages/?session_id={}", client_id))
+    let init_event: Event = SseData::new(format!("/messages/?session_id={}", token))
         .event("endpoint")
         .into();
 
     let stream = futures::stream::once(async { 
             Ok(init_event) 
         })
-        .chain(futures::stream::unfold(rx, |mut rx| async {
+        .chain(futures::stream::unfold((rx, queued, session), |(mut rx, queued, session)| async {
             match rx.recv().await {
-                Some(event) => {
+                Some((size, event)) => {
                     tracing::info!("Received event: {:?}", event); // Add logging here
-                    Some((Ok(event), rx))
+                    queued.fetch_sub(size, Ordering::Relaxed);
+                    Some((Ok(event), (rx, queued, session)))
                 }
                 None => {
                     tracing::warn!("Event stream closed");
@@ -315,45 +475,278 @@ async fn sse_handler(registry: Data<Addr<ClientRegistryActor>>) -> Sse<impl Stre
     Sse::from_stream(stream).with_keep_alive(Duration::from_secs(15))
 }
 
-async fn post_handler(
-    query: web::Query<HashMap<String, String>>,
-    payload: web::Json<JsonRpcRequest>,
+/// The actors a message POSTed by a client may be dispatched to, and the policies it is subject to
+#[derive(Clone)]
+struct PostServices {
     registry: Data<Addr<ClientRegistryActor>>,
     router_registry: Data<Addr<ActorRouterRegistry>>,
     initialization_actor: Data<InitializationActor>,
     prompts: Data<Addr<ListPromptsActor>>,
     tools: Data<Addr<ListToolsActor>>,
     resources: Data<Addr<ListResourcesActor>>,
+    access: AccessControl,
+    limiter: RateLimiter,
+    payload_limits: PayloadLimiter,
+    metrics: Metrics,
+}
+
+impl PostServices {
+    fn from_app_data(req: &HttpRequest) -> Result<Self, Error> {
+        fn data<T: 'static>(req: &HttpRequest) -> Result<Data<T>, Error> {
+            req.app_data::<Data<T>>()
+                .cloned()
+                .ok_or_else(|| actix_web::error::ErrorInternalServerError(format!("{} is not configured", std::any::type_name::<T>())))
+        }
+        // the access policy and the rate limits are optional, without them there are no restrictions,
+        // without payload limits the default ones apply, without metrics requests are not counted
+        fn optional<T: Clone + Default + 'static>(req: &HttpRequest) -> T {
+            req.app_data::<Data<T>>().map(|data| data.get_ref().clone()).unwrap_or_default()
+        }
+        Ok(PostServices {
+            registry: data(req)?,
+            router_registry: data(req)?,
+            initialization_actor: data(req)?,
+            prompts: data(req)?,
+            tools: data(req)?,
+            resources: data(req)?,
+            access: optional(req),
+            limiter: optional(req),
+            payload_limits: optional(req),
+            metrics: optional(req),
+        })
+    }
+}
+
+impl FromRequest for PostServices {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Self::from_app_data(req))
+    }
+}
+
+async fn post_handler(
+    req: HttpRequest,
+    query: web::Query<HashMap<String, String>>,
+    payload: web::Payload,
+    principal: Option<web::ReqData<Principal>>,
+    services: PostServices,
 ) -> Result<HttpResponse, Error>  
 {
     let session_id = query.get("session_id")
         .ok_or_else(|| actix_web::error::ErrorBadRequest("Missing session_id"))?;
-    let client_id: u64 = session_id.parse()
-        .map_err(|_| actix_web::error::ErrorBadRequest("Invalid session_id"))?;
-    info!("Post request: {:?} from {}",payload.clone(),session_id);
+    let principal = principal.map(web::ReqData::into_inner);
+    let traceparent = req.headers().get(TRACEPARENT).and_then(|value| value.to_str().ok()).map(str::to_string);
+    // only whoever opened the session may post to it
+    let binding = SessionBinding::new(principal.clone(), req.conn_data::<Principal>());
+    let client_id = match services.registry.send(ResolveSession { token: session_id.clone(), binding }).await {
+        Ok(Ok(client_id)) => client_id,
+        Ok(Err(error)) => {
+            warn!("Rejected POST from {:?} {:?}: {}", req.peer_addr(), principal.as_ref().map(|p| &p.subject), error);
+            let mut response = match error {
+                McpEctorsError::UnknownSession => HttpResponse::NotFound(),
+                McpEctorsError::SessionExpired(_) => HttpResponse::Gone(),
+                _ => HttpResponse::Forbidden(),
+            };
+            return Ok(response.json(outgoing_error(None, error.to_error_data())));
+        },
+        Err(e) => return Err(actix_web::error::ErrorInternalServerError(e.to_string())),
+    };
+    let body = match read_body(&req, payload, &services.payload_limits).await {
+        Ok(body) => body,
+        Err(error) => {
+            warn!("Rejected POST from client {}: {}", client_id, error);
+            return Ok(HttpResponse::PayloadTooLarge().json(outgoing_error(None, error.to_error_data())));
+        }
+    };
+    // the token stays out of the logs, it is as good as a password
+    info!("Post request: {:?} from {}", body, client_id);
+
+    let body = match services.payload_limits.check_raw(&body).map(|_| serde_json::from_slice::<Value>(&body)) {
+        Ok(Ok(body)) => body,
+        Ok(Err(e)) => {
+            services.registry.do_send(NotifyClientRaw { client_id, message: outgoing_error(None, McpEctorsError::Parse(e.to_string()).to_error_data()) });
+            return Ok(HttpResponse::Ok().json("Accepted"));
+        }
+        Err(error) => {
+            warn!("Rejected message from client {}: {}", client_id, error);
+            services.registry.do_send(NotifyClientRaw { client_id, message: outgoing_error(None, error.to_error_data()) });
+            return Ok(HttpResponse::Ok().json("Accepted"));
+        }
+    };
+    // strings and arrays are checked before any of it is handled, e.g. passed to a WASM router
+    if let Err(error) = services.payload_limits.check_value(&body) {
+        warn!("Rejected message from client {}: {}", client_id, error);
+        let id = body.get("id").and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok());
+        services.registry.do_send(NotifyClientRaw { client_id, message: outgoing_error(id.as_ref(), error.to_error_data()) });
+        return Ok(HttpResponse::Ok().json("Accepted"));
+    }
+    let reply = match body {
+        Value::Array(items) if items.is_empty() => Some(outgoing_error(None, ErrorData { code: JSON_RPC_INVALID_REQUEST, message: "Empty batch".to_string(), data: None })),
+        Value::Array(items) => {
+            // a batch gets one array with the responses to its requests, nothing if it only held notifications
+            let responses: Vec<Value> = futures::future::join_all(items.into_iter().map(|item| handle_message(item, client_id, principal.clone(), traceparent.as_deref(), services.clone())))
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+            if responses.is_empty() { None } else { Some(Value::Array(responses)) }
+        },
+        item => handle_message(item, client_id, principal.clone(), traceparent.as_deref(), services.clone()).await,
+    };
+    if let Some(reply) = reply {
+        services.registry.do_send(NotifyClientRaw { client_id, message: reply });
+    }
+
+    Ok(HttpResponse::Ok().json("Accepted"))
+}
+
+/// Reads the body of a request, up to the body limit
+async fn read_body(req: &HttpRequest, mut payload: web::Payload, payload_limits: &PayloadLimiter) -> Result<web::Bytes, McpEctorsError> {
+    let content_length = req.headers().get(actix_web::http::header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<usize>().ok());
+    if let Some(content_length) = content_length {
+        payload_limits.check(PayloadLimit::Body, content_length)?;
+    }
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| McpEctorsError::InvalidRequest(e.to_string()))?;
+        payload_limits.check(PayloadLimit::Body, body.len() + chunk.len())?;
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.freeze())
+}
+
+/// Handles one message of a client and returns what the client should get back, if anything.
+/// `traceparent` is the one of the HTTP request, the `_meta` of a request can have its own.
+async fn handle_message(item: Value, client_id: u64, principal: Option<Principal>, traceparent: Option<&str>, services: PostServices) -> Option<Value> {
+    let PostServices { registry, router_registry, metrics, .. } = services.clone();
+    let (request_id, method, params) = match IncomingMessage::parse(item) {
+        IncomingMessage::Request { id, method, params } => (Some(id), method, params),
+        IncomingMessage::Notification { method, params } => (None, method, params),
+        IncomingMessage::Response { id, result, error } => {
+            // the answer to a request we sent to the client, e.g. sampling/createMessage, those always have numeric ids
+            if let Some(id) = match id { RequestId::Number(id) => id.as_u64(), RequestId::String(_) => None } {
+                registry.do_send(ClientResponse { client_id, response: JsonRpcResponse { jsonrpc: JSONRPC_VERSION.to_owned(), id: Some(id), result, error } });
+            }
+            return None;
+        },
+        IncomingMessage::Invalid { id, error } => return Some(outgoing_error(id.as_ref(), error)),
+    };
+
+    if request_id.is_none() {
+        // notifications never get a response
+        match method.as_str() {
+            CancelledNotification::METHOD => {
+                match params.as_ref().and_then(|params| params.get("requestId")).and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok()) {
+                    Some(request_id) => {
+                        let reason = params.as_ref().and_then(|params| params.get("reason")).and_then(|r| r.as_str()).unwrap_or("");
+                        info!("Client {} cancels request {}: {}", client_id, request_id, reason);
+                        registry.do_send(CancelRequest { client_id, request_id });
+                    },
+                    None => error!("Ignoring notifications/cancelled without a requestId from {}", client_id),
+                }
+            },
+            RootsListChangedNotification::METHOD => registry.do_send(RefreshRoots { client_id }),
+            InitializedNotificationRequest::METHOD => {
+                tracing::info!("Received InitializedNotificationRequest");
+                registry.do_send(RefreshRoots { client_id });
+            },
+            method => trace!("Ignoring notification {} from {}", method, client_id),
+        }
+        return None;
+    }
+
+    let mut payload = JsonRpcRequest { jsonrpc: JSONRPC_VERSION.to_owned(), id: request_id.as_ref().map(|_| RequestId::next_internal()), method, params };
+    unwrap_proxy_calls(&mut payload);
+    resolve_alias(&mut payload, &router_registry).await;
+    let (router_id, tool_name) = limited_target(&payload);
+    let traceparent = payload.params.as_ref()
+        .and_then(|params| params.pointer(&format!("/_meta/{}", TRACEPARENT)))
+        .and_then(Value::as_str)
+        .or(traceparent)
+        .unwrap_or_default();
+    let span = tracing::info_span!(REQUEST_SPAN,
+        otel.kind = "server",
+        session_id = client_id,
+        request_id = %request_id.as_ref().map(ToString::to_string).unwrap_or_default(),
+        method = %payload.method,
+        router_id = tracing::field::Empty,
+        tool_name = tracing::field::Empty,
+        error_code = tracing::field::Empty,
+        traceparent,
+    );
+    if let Some(router_id) = &router_id {
+        span.record("router_id", router_id.as_str());
+    }
+    if let Some(tool_name) = &tool_name {
+        span.record("tool_name", tool_name.as_str());
+    }
+    let timer = metrics.start_request(metrics.request_labels(&payload.method, router_id.as_deref(), tool_name.as_deref()));
+    let reply = handle_request(payload, request_id, router_id, tool_name, client_id, principal, services)
+        .instrument(span.clone())
+        .await;
+    if let Some(code) = timer.finish(reply.as_ref()) {
+        span.record(ERROR_CODE_FIELD, code);
+    }
+    reply
+}
+
+/// Handles a request of a client to `router_id` and `tool_name`, see [`limited_target`], once its alias is resolved
+async fn handle_request(payload: JsonRpcRequest, request_id: Option<RequestId>, router_id: Option<String>, tool_name: Option<String>, client_id: u64, principal: Option<Principal>, services: PostServices) -> Option<Value> {
+    let PostServices { registry, router_registry, initialization_actor, prompts, tools, resources, access, limiter, payload_limits, .. } = services;
+    // held until the request is answered, it counts against the max-in-flight caps until then
+    let caller = principal.as_ref().map_or(Caller::Session(client_id), |principal| Caller::Principal(&principal.subject));
+    let _permit = match limiter.acquire(caller, router_id.as_deref(), tool_name.as_deref()) {
+        Ok(permit) => permit,
+        Err(e) => {
+            warn!("Client {} is rate limited: {}", client_id, e);
+            return Some(outgoing_error(request_id.as_ref(), e.to_error_data()));
+        },
+    };
+    let mut context = RequestContext::for_session(client_id)
+        .with_client(ClientHandle::new(client_id, registry.get_ref().clone()))
+        .with_span(Span::current())
+        .with_access(principal.clone(), access.clone());
+    if let Some(token) = payload.params.as_ref().and_then(|params| params.pointer("/_meta/progressToken")) {
+        context = context.with_progress(ProgressReporter::new(token.clone(), client_id, registry.get_ref().clone()));
+    }
+    if let Some(request_id) = request_id.clone() {
+        registry.do_send(TrackRequest { client_id, request_id, cancellation: context.cancellation.clone() });
+    }
+
     let response: Result<JsonRpcResponse, JsonRpcError> = match payload.method.as_str() {
-        CallToolRequest::METHOD | GetPromptRequest::METHOD | ListResourceTemplatesRequest::METHOD => {
+        ListResourceTemplatesRequest::METHOD => {
+            trace!("Calling list resource templates");
+            let id = payload.id;
+            let att = "name".to_string();
+            match string_param(&payload, &att).and_then(|action| authorize(principal.as_ref(), &access, id, action)) {
+                Ok(action) => {
+                    let router_id = action.split(ROUTER_SEPERATOR).next().unwrap_or_default().to_string();
+                    router_request(id, context.clone(), action, router_registry, payload, att).await
+                        .map(|response| filter_listed(namespace_templates(response, &router_id), "resourceTemplates", principal.as_ref(), &access))
+                },
+                Err(err) => Err(err),
+            }
+        },
+        CallToolRequest::METHOD | GetPromptRequest::METHOD => {
             trace!("Calling call tool/prompt");
-            let id = payload.id.clone();
-            let req = payload.into_inner();
+            let id = payload.id;
             let att = "name".to_string();
-            let params=  req.clone().params.expect("no params found");
-            let action = params[att.clone()].as_str().expect("tool/prompt name not found");
-            match router_request(id, action.to_string(), router_registry, req.clone(), att.clone()).await {
-                Ok(res) => Ok(res),
+            match string_param(&payload, &att).and_then(|action| authorize(principal.as_ref(), &access, id, action)) {
+                Ok(action) => router_request(id, context.clone(), action, router_registry, payload, att).await,
                 Err(err) => Err(err),
             }
 
         },
         ReadResourceRequest::METHOD | SubscribeRequest::METHOD | UnsubscribeRequest::METHOD => {
             tracing::trace!("Calling read/subscribe/unsubscribe resource");
-            let id = payload.id.clone();
-            let req = payload.into_inner();
+            let id = payload.id;
             let att = "uri".to_string();
-            let params=  req.clone().params.expect("no params found");
-            let action = params[att.clone()].as_str().expect("resource uri not found");
-            match router_request(id, action.to_string(), router_registry, req.clone(), att.clone()).await {
-                Ok(res) => Ok(res),
+            match string_param(&payload, &att).and_then(|action| authorize(principal.as_ref(), &access, id, action)) {
+                Ok(action) => router_request(id, context.clone(), action, router_registry, payload, att).await,
                 Err(err) => Err(err),
             }
 
@@ -361,82 +754,182 @@ async fn post_handler(
         InitializeRequest::METHOD => {
             // Handle InitializeRequest by calling InitializationActor
             tracing::info!("Received InitializeRequest");
+            if let Some(capabilities) = payload.params.as_ref().and_then(|params| params.get("capabilities")) {
+                registry.do_send(SetClientCapabilities { client_id, capabilities: capabilities.clone() });
+            }
             // Call the InitializationActor for InitializeRequest
-            initialization_actor.handle_initialize_request(payload.0)
+            initialization_actor.handle_initialize_request(payload)
             
         },
-        InitializedNotificationRequest::METHOD => {
-            // Handle InitializedNotificationRequest by calling InitializationActor
-            tracing::info!("Received InitializedNotificationRequest");
-            // Call the InitializationActor for InitializedNotificationRequest
-            initialization_actor.handle_initialized_notification_request(payload.0)
-
+        PingRequest::METHOD => {
+            Ok(JsonRpcResponse { jsonrpc: JSONRPC_VERSION.to_owned(), id: payload.id, result: Some(json!({})), error: None })
+        },
+        SetLevelRequest::METHOD => {
+            tracing::trace!("Calling set log level");
+            let id = payload.id;
+            let level = payload.params.as_ref()
+                .and_then(|params| params.get("level"))
+                .and_then(|level| level.as_str())
+                .map(|level| level.parse::<LoggingLevel>());
+            match level {
+                Some(Ok(level)) => {
+                    registry.do_send(SetLogLevel { client_id, level });
+                    Ok(JsonRpcResponse { jsonrpc: JSONRPC_VERSION.to_owned(), id, result: Some(json!({})), error: None })
+                },
+                Some(Err(e)) => Err(McpEctorsError::InvalidParams(e).to_json_rpc_error(id)),
+                None => Err(McpEctorsError::InvalidParams("Missing log level".to_string()).to_json_rpc_error(id)),
+            }
         },
         ListToolsRequest::METHOD => {
             tracing::trace!("Calling list tools");
-            let id = payload.id.clone();
-            let result = tools.send(ListToolsRequest{request: payload.into_inner()})
+            let id = payload.id;
+            tools.send(ListToolsRequest{request: payload})
             .await
-            .map_err(|e| JsonRpcError{jsonrpc: JSONRPC_VERSION.to_owned(), id, error: ErrorData{code: MCP_SERVICE_UNAVAILABLE, message: format!("Transport actor error: {}",e), data: None }, }).unwrap()
-            .map_err(|e| JsonRpcError{jsonrpc: JSONRPC_VERSION.to_owned(), id, error: ErrorData{code: MCP_INTERNAL_SERVER_ERROR, message: format!("Processing actor error: {:?}",e), data: None }, }).unwrap();
-
-            Ok(result)
+            .map_err(McpEctorsError::from)
+            .and_then(|result| result)
+            .map(|response| filter_listed(response, "tools", principal.as_ref(), &access))
+            .map_err(|e| e.to_json_rpc_error(id))
         },
         ListPromptsRequest::METHOD => {
             tracing::trace!("Calling list prompts");
-            let id = payload.id.clone();
-            let result = prompts.send(ListPromptsRequest{request: payload.into_inner()})
+            let id = payload.id;
+            prompts.send(ListPromptsRequest{request: payload})
             .await
-            .map_err(|e| JsonRpcError{jsonrpc: JSONRPC_VERSION.to_owned(), id, error: ErrorData{code: MCP_SERVICE_UNAVAILABLE, message: format!("Transport actor error: {}",e), data: None }, }).unwrap()
-            .map_err(|e| JsonRpcError{jsonrpc: JSONRPC_VERSION.to_owned(), id, error: ErrorData{code: MCP_INTERNAL_SERVER_ERROR, message: format!("Processing actor error: {:?}",e), data: None }, }).unwrap();
-
-            Ok(result)
+            .map_err(McpEctorsError::from)
+            .and_then(|result| result)
+            .map(|response| filter_listed(response, "prompts", principal.as_ref(), &access))
+            .map_err(|e| e.to_json_rpc_error(id))
 
         },
         ListResourcesRequest::METHOD => {
             tracing::trace!("Calling list resources");
-            let id = payload.id.clone();
-            let result = resources.send(ListResourcesRequest{request: payload.into_inner()})
+            let id = payload.id;
+            resources.send(ListResourcesRequest{request: payload})
             .await
-            .map_err(|e| JsonRpcError{jsonrpc: JSONRPC_VERSION.to_owned(), id, error: ErrorData{code: MCP_SERVICE_UNAVAILABLE, message: format!("Transport actor error: {}",e), data: None }, }).unwrap()
-            .map_err(|e| JsonRpcError{jsonrpc: JSONRPC_VERSION.to_owned(), id, error: ErrorData{code: MCP_INTERNAL_SERVER_ERROR, message: format!("Processing actor error: {:?}",e), data: None }, }).unwrap();
-
-            Ok(result)
+            .map_err(McpEctorsError::from)
+            .and_then(|result| result)
+            .map(|response| filter_listed(response, "resources", principal.as_ref(), &access))
+            .map_err(|e| e.to_json_rpc_error(id))
 
         },
-        method => {
-            let id = payload.id.clone();
-            Err(JsonRpcError{jsonrpc: JSONRPC_VERSION.to_owned(), id, error: ErrorData{code: MCP_INVALID_METHOD, message: format!("Invalid method: {}",method), data: None }, })
-        }
+        method => Err(McpEctorsError::MethodNotFound(method.to_string()).to_json_rpc_error(payload.id)),
 
 
     };
 
-    match response {
-        Ok(json_rpc_response) => {
-            // Send the successful JsonRpcResponse to the client
-            registry.do_send(NotifyClient {
-                client_id,
-                message: JsonRpcMessage::Response(json_rpc_response), // Pass the JsonRpcResponse directly
-            });
-        }
-        Err(error) => {
-            registry.do_send(NotifyClient {
-                client_id,
-                message: JsonRpcMessage::Error(error), // Pass the JsonRpcResponse directly
-            });
-        }
+    if let Some(request_id) = request_id.clone() {
+        registry.do_send(UntrackRequest { client_id, request_id });
+    }
+    if context.is_cancelled() {
+        // the client cancelled the request or went away, it must not get a response
+        info!("Dropping the response to cancelled request {:?} from {}", request_id, client_id);
+        return None;
+    }
+
+    let reply = outgoing_response(request_id.as_ref(), response);
+    // the client gets an error it can handle instead of a result it may not be able to take
+    if let Err(error) = payload_limits.check(PayloadLimit::Result, json_len(&reply)) {
+        warn!("Dropping the result of request {:?} from {}: {}", request_id, client_id, error);
+        return Some(outgoing_error(request_id.as_ref(), error.to_error_data()));
+    }
+    Some(reply)
+}
+
+/// The string parameter `name` of a request, or the Invalid params error the client gets when it is missing
+fn string_param(request: &JsonRpcRequest, name: &str) -> Result<String, JsonRpcError> {
+    request.params.as_ref()
+        .and_then(|params| params.get(name))
+        .and_then(|value| value.as_str())
+        .map(|value| value.to_string())
+        .ok_or_else(|| McpEctorsError::InvalidParams(format!("{} must be a string", name)).to_json_rpc_error(request.id))
+}
+
+/// Replaces a router alias in the name or uri a request targets with the router id, so limits,
+/// scopes and policies apply to the router whichever id the client used
+async fn resolve_alias(request: &mut JsonRpcRequest, router_registry: &Addr<ActorRouterRegistry>) {
+    let attribute = match request.method.as_str() {
+        CallToolRequest::METHOD | GetPromptRequest::METHOD | ListResourceTemplatesRequest::METHOD => "name",
+        ReadResourceRequest::METHOD | SubscribeRequest::METHOD | UnsubscribeRequest::METHOD => "uri",
+        _ => return,
+    };
+    let Some(Value::String(name)) = request.params.as_mut().and_then(|params| params.get_mut(attribute)) else {
+        return;
     };
+    match router_registry.send(ResolveAlias { name: name.clone() }).await {
+        Ok(resolved) => *name = resolved,
+        Err(e) => error!("Failed to resolve the router alias of {}: {}", name, e),
+    }
+}
+
+/// The router and, for `tools/call`, the namespaced tool a request goes to, for the rate limits
+fn limited_target(request: &JsonRpcRequest) -> (Option<String>, Option<String>) {
+    let param = |name: &str| request.params.as_ref().and_then(|params| params.get(name)).and_then(|value| value.as_str());
+    let router_id = |action: &str| action.split(ROUTER_SEPERATOR).next().map(str::to_string);
+    match request.method.as_str() {
+        CallToolRequest::METHOD => (param("name").and_then(router_id), param("name").map(str::to_string)),
+        GetPromptRequest::METHOD => (param("name").and_then(router_id), None),
+        ReadResourceRequest::METHOD | SubscribeRequest::METHOD | UnsubscribeRequest::METHOD => (param("uri").and_then(router_id), None),
+        _ => (None, None),
+    }
+}
 
+/// Whether the scopes of the caller and the access policy allow it to use `name`, a namespaced tool, prompt or resource
+fn may_use(principal: Option<&Principal>, access: &AccessControl, name: &str) -> bool {
+    access.permits(principal, name)
+}
 
-    Ok(HttpResponse::Ok().json("Accepted"))
+/// Passes `action` through if the caller may use it
+fn authorize(principal: Option<&Principal>, access: &AccessControl, id: Option<u64>, action: String) -> Result<String, JsonRpcError> {
+    if may_use(principal, access, &action) {
+        return Ok(action);
+    }
+    warn!("{} may not use {}", principal.map_or("anonymous", |principal| principal.subject.as_str()), action);
+    Err(McpEctorsError::Forbidden(format!("not allowed to use {}", action)).to_json_rpc_error(id))
 }
 
-async fn router_request(id: Option<u64>, action: String, router_registry:Data<Addr<ActorRouterRegistry>>, req: JsonRpcRequest, attribute: String) -> Result<JsonRpcResponse,JsonRpcError> {
-    let response = router_registry
-        .send(GetRouter { router_id: action.clone(), _marker: std::marker::PhantomData })
-        .await
-        .unwrap();
+/// Drops what the caller may not use from a `tools`, `prompts`, `resources` or `resourceTemplates` list
+fn filter_listed(mut response: JsonRpcResponse, list: &str, principal: Option<&Principal>, access: &AccessControl) -> JsonRpcResponse {
+    if let Some(Value::Array(items)) = response.result.as_mut().and_then(|result| result.get_mut(list)) {
+        items.retain(|item| listed_name(list, item).is_some_and(|name| may_use(principal, access, &name)));
+    }
+    response
+}
+
+/// The namespaced name clients use for a listed item, resources are read by router id and uri
+/// and templates by router id and the uri template
+fn listed_name(list: &str, item: &Value) -> Option<String> {
+    let name = item.get("name")?.as_str()?;
+    let uri = match list {
+        "resources" => "uri",
+        "resourceTemplates" => "uriTemplate",
+        _ => return Some(name.to_string()),
+    };
+    let router_id = name.split(ROUTER_SEPERATOR).next()?;
+    Some(format!("{}{}{}", router_id, ROUTER_SEPERATOR, item.get(uri)?.as_str()?))
+}
+
+/// Prefixes the names of the templates a router listed with its id, as the other lists are
+fn namespace_templates(mut response: JsonRpcResponse, router_id: &str) -> JsonRpcResponse {
+    if let Some(Value::Array(templates)) = response.result.as_mut().and_then(|result| result.get_mut("resourceTemplates")) {
+        for template in templates {
+            if let Some(Value::String(name)) = template.get_mut("name") {
+                *name = format!("{}{}{}", router_id, ROUTER_SEPERATOR, name);
+            }
+        }
+    }
+    response
+}
+
+async fn router_request(id: Option<u64>, context: RequestContext, action: String, router_registry:Data<Addr<ActorRouterRegistry>>, req: JsonRpcRequest, attribute: String) -> Result<JsonRpcResponse,JsonRpcError> {
+    let response = match router_registry
+        .send(GetRouter { router_id: action.clone(), _marker: std::marker::PhantomData, span: Span::current() })
+        .await {
+            Ok(response) => response,
+            Err(e) => {
+                error!("Failed to look up router for {}: {}", action, e);
+                return Err(McpEctorsError::from(e).to_json_rpc_error(id));
+            }
+        };
 
     let (router, action) = match response {
         Some(response) => (Some(response.0),response.1),
@@ -456,34 +949,46 @@ async fn router_request(id: Option<u64>, action: String, router_registry:Data<Ad
 
     match router {
         Some(router) => {
-            match router.send(TransportRequest{request:req_cloned}).await {
-                Ok(response) => match response {
-                    Ok(json_rpc_response) => Ok(json_rpc_response),
-                    Err(error) => {
-                        error!("Failed to send {:?} to actor", action.clone());
-                        Err(JsonRpcUtils::error_response(id, 
-                        MCP_INVALID_REQUEST, 
-                        format!("transport error: {:?}",error).as_str(), 
-                        None))
-                    }
-                }, // Successfully retrieved response
-                Err(_) => {
+            match router.send(TransportRequest{request:req_cloned, context}).await {
+                // the router already answers with the error the client should see
+                Ok(response) => response,
+                Err(e) => {
                     // Log error if sending the message failed
                     error!("Failed to send {:?} to router", action);
-                    Err(JsonRpcUtils::error_response(id, 
-                        JSON_RPC_INTERNAL_ERROR, 
-                        "transport error: ", 
-                        None))
+                    Err(McpEctorsError::from(e).to_json_rpc_error(id))
                 }
             }
         }
         None => {
             error!("Failed to find router for {:?}", req);
-            Err(JsonRpcUtils::error_response(id, 
-                JSON_RPC_INTERNAL_ERROR, 
-                format!("transport error, no router for {}", action).as_str(), 
-            None))
+            Err(McpEctorsError::RouterNotFound(action).to_json_rpc_error(id))
 
         }
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Policy;
+
+    #[test]
+    fn test_templates_are_filtered_by_router_id_and_uri_template() {
+        let policy: Policy = serde_json::from_value(json!({
+            "default_roles": ["guest"],
+            "roles": { "guest": { "allow": ["files_file:///public/*"] } }
+        })).unwrap();
+        let access = AccessControl::new(policy);
+        let response = JsonRpcResponse {
+            jsonrpc: JSONRPC_VERSION.to_owned(),
+            id: Some(1),
+            result: Some(json!({ "resourceTemplates": [
+                { "name": "public", "uriTemplate": "file:///public/{path}" },
+                { "name": "private", "uriTemplate": "file:///private/{path}" },
+            ]})),
+            error: None,
+        };
+
+        let response = filter_listed(namespace_templates(response, "files"), "resourceTemplates", None, &access);
+        assert_eq!(response.result.unwrap()["resourceTemplates"], json!([{ "name": "files_public", "uriTemplate": "file:///public/{path}" }]));
+    }
+}
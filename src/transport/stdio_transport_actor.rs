@@ -1,9 +1,12 @@
 use actix::prelude::*;
 use mcp_spec::protocol::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
-use serde_json::json;
+use serde_json::{json, Value};
 
-use crate::{client::ClientRegistryActor, mcp::{InitializationActor, ListPromptsActor, ListResourcesActor, ListToolsActor}, messages::transport_messages::{StartTransport, StopTransport, TransportRequest}, router::router_registry::ActorRouterRegistry};
-use std::io::{self, BufRead, Write};
+use crate::limits::{PayloadLimit, PayloadLimiter};
+use crate::messages::{outgoing_error, RequestId};
+use crate::McpEctorsError;
+use crate::{client::ClientRegistryActor, mcp::{InitializationActor, ListPromptsActor, ListResourcesActor, ListToolsActor}, messages::transport_messages::{StartTransport, StopTransport, TransportRequest}, router::{router_registry::ActorRouterRegistry, RequestContext}};
+use std::io::{self, BufRead, Read, Write};
 use tokio::task;
 use tracing::{info, error};
 
@@ -14,6 +17,7 @@ pub struct StdioTransportConfig;
 pub struct StdioTransportActor
 {
     _router_registry: Addr<ActorRouterRegistry>,
+    payload_limits: PayloadLimiter,
 }
 
 impl Actor for StdioTransportActor
@@ -24,7 +28,48 @@ impl Actor for StdioTransportActor
 impl StdioTransportActor
 {
     pub fn new(router_registry: Addr<ActorRouterRegistry>) -> Self {
-        Self { _router_registry: router_registry }
+        Self { _router_registry: router_registry, payload_limits: PayloadLimiter::default() }
+    }
+
+    /// Answers lines over the size limits of `payload_limits` with the error of the limit
+    pub fn with_payload_limits(mut self, payload_limits: PayloadLimiter) -> Self {
+        self.payload_limits = payload_limits;
+        self
+    }
+}
+
+/// Reads the next line of `input` into `buffer`, or as much of it as the body limit allows and
+/// skips the rest. `Ok(false)` at the end of the input.
+fn read_limited_line(input: &mut impl BufRead, buffer: &mut Vec<u8>, payload_limits: &PayloadLimiter) -> io::Result<bool> {
+    buffer.clear();
+    let max = payload_limits.max(PayloadLimit::Body) as u64;
+    let read = Read::take(&mut *input, max + 1).read_until(b'\n', buffer)?;
+    if read == 0 {
+        return Ok(false);
+    }
+    if buffer.last() != Some(&b'\n') && read as u64 > max {
+        // the line is too long, the rest of it is read in pieces and dropped
+        let mut rest = Vec::new();
+        while Read::take(&mut *input, max + 1).read_until(b'\n', &mut rest)? > 0 && rest.last() != Some(&b'\n') {
+            rest.clear();
+        }
+    }
+    Ok(true)
+}
+
+/// The message on `line` if it is within the payload limits, otherwise the error response the
+/// client gets, with the id of the request if it could be read
+fn check_line(line: &[u8], payload_limits: &PayloadLimiter) -> Result<Value, Value> {
+    let reject = |id: Option<RequestId>, e: McpEctorsError| {
+        error!("Rejected request from stdin: {}", e);
+        outgoing_error(id.as_ref(), e.to_error_data())
+    };
+    let value = payload_limits.check_raw(line)
+        .and_then(|_| serde_json::from_slice::<Value>(line).map_err(|e| McpEctorsError::Parse(e.to_string())))
+        .map_err(|e| reject(None, e))?;
+    match payload_limits.check_value(&value) {
+        Ok(()) => Ok(value),
+        Err(e) => Err(reject(value.get("id").and_then(|id| serde_json::from_value(id.clone()).ok()), e)),
     }
 }
 
@@ -54,20 +99,28 @@ impl Handler<StartTransport> for StdioTransportActor
         info!("StdioTransportActor started. Listening on stdin...");
 
         let addr = ctx.address();
+        let payload_limits = self.payload_limits.clone();
 
         // Spawn a blocking task for stdin reading
         task::spawn_blocking(move || {
             let stdin = io::stdin();
             let mut handle = stdin.lock();
-            let mut buffer = String::new();
-
-            while handle.read_line(&mut buffer).is_ok() {
-                let request: Result<JsonRpcRequest, _> = serde_json::from_str(&buffer.trim());
-                buffer.clear();
+            let mut buffer = Vec::new();
+
+            while let Ok(true) = read_limited_line(&mut handle, &mut buffer, &payload_limits) {
+                let request = match check_line(buffer.trim_ascii(), &payload_limits) {
+                    Ok(value) => serde_json::from_value::<JsonRpcRequest>(value),
+                    Err(reply) => {
+                        if let Err(e) = writeln!(io::stdout().lock(), "{}", reply) {
+                            error!("Failed to write JSON-RPC error to stdout: {:?}", e);
+                        }
+                        continue;
+                    }
+                };
 
                 match request {
                     Ok(req) => {
-                        addr.do_send(TransportRequest { request: req });
+                        addr.do_send(TransportRequest { request: req, context: RequestContext::default() });
                     }
                     Err(e) => {
                         error!("Failed to parse JSON-RPC request from stdin: {:?}", e);
@@ -137,7 +190,7 @@ async fn main() {
                 match request {
                     Ok(req) => {
                         // Send the request to the transport actor
-                        let _reply = transport_actor.do_send(TransportRequest { request: req.clone() });
+                        transport_actor.do_send(TransportRequest { request: req.clone(), context: RequestContext::default() });
 
                         let id = req.id.clone();
                         // Simulate printing the response back to stdout
@@ -167,4 +220,32 @@ async fn main() {
     // This example runs indefinitely, handling stdin commands.
     // Implement a graceful shutdown if necessary.
     info!("StdioTransportActor is now listening for requests on stdin...");
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::PayloadLimits;
+
+    #[test]
+    fn test_lines_over_the_limits_get_an_error() {
+        let payload_limits = PayloadLimiter::new(PayloadLimits::new().with_limit(PayloadLimit::Body, 128).with_limit(PayloadLimit::String, 8));
+        let mut input = io::Cursor::new(format!("{}\n{}\n", "x".repeat(300), r#"{"jsonrpc":"2.0","id":"a","method":"ping"}"#));
+        let mut buffer = Vec::new();
+
+        assert!(read_limited_line(&mut input, &mut buffer, &payload_limits).unwrap());
+        let reply = check_line(buffer.trim_ascii(), &payload_limits).unwrap_err();
+        assert_eq!(reply["id"], Value::Null);
+        assert_eq!(reply["error"]["code"], json!(-32012));
+
+        // the rest of the long line was skipped, the next line is read whole
+        assert!(read_limited_line(&mut input, &mut buffer, &payload_limits).unwrap());
+        assert!(check_line(buffer.trim_ascii(), &payload_limits).is_ok());
+        assert!(!read_limited_line(&mut input, &mut buffer, &payload_limits).unwrap());
+
+        let reply = check_line(br#"{"jsonrpc":"2.0","id":7,"method":"tools/call","params":{"name":"counter_increment"}}"#, &payload_limits).unwrap_err();
+        assert_eq!(reply["id"], json!(7));
+        assert_eq!(reply["error"]["code"], json!(-32014));
+        assert_eq!(check_line(b"{not json", &payload_limits).unwrap_err()["error"]["code"], json!(-32700));
+    }
+}
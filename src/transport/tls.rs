@@ -0,0 +1,162 @@
+use std::any::Any;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use actix_tls::accept::rustls_0_23::TlsStream;
+use actix_web::dev::Extensions;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::CertificateDer;
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+use rustls::{RootCertStore, ServerConfig};
+use tokio::net::TcpStream;
+use tracing::{error, info};
+
+use crate::auth::client_certificate_principal;
+
+use super::TransportError;
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::aws_lc_rs::default_provider())
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, TransportError> {
+    let file = File::open(path)
+        .map_err(|e| TransportError::configuration_error(format!("Could not read certificates from {}: {}", path.display(), e)))?;
+    let certificates = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TransportError::configuration_error(format!("Invalid certificate in {}: {}", path.display(), e)))?;
+    if certificates.is_empty() {
+        return Err(TransportError::configuration_error(format!("No certificate in {}", path.display())));
+    }
+    Ok(certificates)
+}
+
+/// Reads the certificate chain in `cert` and the private key in `key`, both PEM
+pub fn load_certified_key(cert: &Path, key: &Path) -> Result<CertifiedKey, TransportError> {
+    let certificates = read_certificates(cert)?;
+    let file = File::open(key)
+        .map_err(|e| TransportError::configuration_error(format!("Could not read private key from {}: {}", key.display(), e)))?;
+    let private_key = rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| TransportError::configuration_error(format!("Invalid private key in {}: {}", key.display(), e)))?
+        .ok_or_else(|| TransportError::configuration_error(format!("No private key in {}", key.display())))?;
+    let signing_key = provider().key_provider.load_private_key(private_key)
+        .map_err(|e| TransportError::configuration_error(format!("Unsupported private key in {}: {}", key.display(), e)))?;
+    let certified_key = CertifiedKey::new(certificates, signing_key);
+    // a certificate renewed before its key is a mismatch until the key is there too
+    certified_key.keys_match()
+        .map_err(|e| TransportError::configuration_error(format!("The key in {} does not belong to {}: {}", key.display(), cert.display(), e)))?;
+    Ok(certified_key)
+}
+
+/// Hands out the server certificate and reloads it whenever the certificate or key file changes,
+/// e.g. when it is renewed. New connections get the new certificate, open ones keep theirs. A
+/// reload that fails keeps the previous certificate.
+pub struct CertificateResolver {
+    key: Arc<RwLock<Arc<CertifiedKey>>>,
+    /// Keeps the watcher of the files alive as long as the resolver is in use
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl CertificateResolver {
+    pub fn from_files(cert: &Path, key: &Path) -> Result<Self, TransportError> {
+        let resolver = Self {
+            key: Arc::new(RwLock::new(Arc::new(load_certified_key(cert, key)?))),
+            watcher: Mutex::new(None),
+        };
+        resolver.watch(cert, key)?;
+        Ok(resolver)
+    }
+
+    /// The certificate chain and key new connections are served with
+    pub fn certified_key(&self) -> Arc<CertifiedKey> {
+        self.key.read().unwrap().clone()
+    }
+
+    fn watch(&self, cert: &Path, key: &Path) -> Result<(), TransportError> {
+        let canonical = |path: &Path| path.canonicalize()
+            .map_err(|e| TransportError::configuration_error(format!("Could not watch {}: {}", path.display(), e)));
+        let (cert, key) = (canonical(cert)?, canonical(key)?);
+        // certificate tools replace files instead of writing them, so the directories are watched
+        let mut directories: Vec<PathBuf> = [&cert, &key].iter()
+            .map(|file| file.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from(".")))
+            .collect();
+        directories.dedup();
+
+        let current = self.key.clone();
+        let files = [cert.clone(), key.clone()];
+        let mut watcher = RecommendedWatcher::new(move |result: notify::Result<Event>| match result {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) && event.paths.iter().any(|p| files.contains(p)) => {
+                match load_certified_key(&cert, &key) {
+                    Ok(loaded) => {
+                        *current.write().unwrap() = Arc::new(loaded);
+                        info!("Reloaded TLS certificate from {}", cert.display());
+                    }
+                    Err(e) => error!("Keeping the previous TLS certificate: {}", e),
+                }
+            }
+            Ok(_) => {}
+            Err(e) => error!("Error watching the TLS certificate: {:?}", e),
+        }, notify::Config::default())
+        .map_err(|e| TransportError::configuration_error(format!("Could not watch the TLS certificate: {}", e)))?;
+        for directory in &directories {
+            watcher.watch(directory, RecursiveMode::NonRecursive)
+                .map_err(|e| TransportError::configuration_error(format!("Could not watch {}: {}", directory.display(), e)))?;
+        }
+        *self.watcher.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for CertificateResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CertificateResolver").finish_non_exhaustive()
+    }
+}
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.certified_key())
+    }
+}
+
+/// The rustls configuration of an HTTPS listener serving the certificate in `cert` with the key
+/// in `key`, both PEM and reloaded when they change. With a `client_ca` bundle every client must
+/// present a certificate issued by one of its CAs (mTLS).
+pub fn server_config(cert: &Path, key: &Path, client_ca: Option<&Path>) -> Result<ServerConfig, TransportError> {
+    let builder = ServerConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| TransportError::configuration_error(format!("Invalid TLS configuration: {}", e)))?;
+    let builder = match client_ca {
+        Some(client_ca) => {
+            let mut roots = RootCertStore::empty();
+            for certificate in read_certificates(client_ca)? {
+                roots.add(certificate)
+                    .map_err(|e| TransportError::configuration_error(format!("Invalid CA certificate in {}: {}", client_ca.display(), e)))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider())
+                .build()
+                .map_err(|e| TransportError::configuration_error(format!("Invalid client CA bundle {}: {}", client_ca.display(), e)))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    Ok(builder.with_cert_resolver(Arc::new(CertificateResolver::from_files(cert, key)?)))
+}
+
+/// `HttpServer::on_connect` callback of an HTTPS listener. Puts the [`Principal`](crate::auth::Principal)
+/// of the client certificate, if the client presented one, in the connection data, where the
+/// authentication middleware picks it up.
+pub fn client_certificate_on_connect(connection: &dyn Any, data: &mut Extensions) {
+    let Some(stream) = connection.downcast_ref::<TlsStream<TcpStream>>() else {
+        return;
+    };
+    let (_, session) = stream.get_ref();
+    if let Some(principal) = session.peer_certificates().and_then(|chain| chain.first()).and_then(client_certificate_principal) {
+        data.insert(principal);
+    }
+}
@@ -1,7 +1,7 @@
 use actix::prelude::*;
 use mcp_spec::protocol::{JsonRpcError, JsonRpcResponse};
 
-use crate::{client::ClientRegistryActor, mcp::{InitializationActor, ListPromptsActor, ListResourcesActor, ListToolsActor}, messages::transport_messages::{StartTransport, StopTransport, TransportRequest}, router::router_registry::ActorRouterRegistry, utils::{json_rpc::MCP_INVALID_REQUEST, JsonRpcUtils}};
+use crate::{client::ClientRegistryActor, mcp::{InitializationActor, ListPromptsActor, ListResourcesActor, ListToolsActor}, messages::transport_messages::{StartTransport, StopTransport, TransportRequest}, router::router_registry::ActorRouterRegistry, McpEctorsError};
 use wasmtime::*;
 use tracing::info;
 
@@ -92,10 +92,7 @@ impl Handler<TransportRequest> for WasiTransportActor
     type Result = Result<JsonRpcResponse, JsonRpcError>;
 
     fn handle(&mut self, msg: TransportRequest, _ctx: &mut Self::Context) -> Self::Result {
-        Err(JsonRpcUtils::error_response(msg.request.id, 
-            MCP_INVALID_REQUEST, 
-            format!("Did not expect this request: {:?}",msg.request).as_str(), 
-        None))
+        Err(McpEctorsError::InvalidRequest(format!("Did not expect this request: {:?}",msg.request)).to_json_rpc_error(msg.request.id))
     }
 }
 
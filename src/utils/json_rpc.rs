@@ -10,15 +10,30 @@ pub const JSON_RPC_INVALID_PARAMS: i32 = -32602;
 pub const JSON_RPC_INTERNAL_ERROR: i32 = -32603;
 pub const JSON_RPC_APPLICATION_ERROR_START: i32 = -32000; // Range -32000 to -32099 for app-specific errors
 
-// MCP Error Codes
-pub const MCP_INVALID_REQUEST: i32 = 100;
-pub const MCP_INVALID_METHOD: i32 = 101;
-pub const MCP_INVALID_PARAMS: i32 = 102;
-pub const MCP_AUTH_ERROR: i32 = 103;
-pub const MCP_TIMEOUT_ERROR: i32 = 104;
-pub const MCP_INTERNAL_SERVER_ERROR: i32 = 105;
-pub const MCP_SERVICE_UNAVAILABLE: i32 = 106;
-pub const MCP_FORBIDDEN_ERROR: i32 = 107;
+// MCP Error Codes, the generic ones are the JSON-RPC codes and the others are taken from the
+// application range. See `McpEctorsError::code` for which error gets which code.
+pub const MCP_INVALID_REQUEST: i32 = JSON_RPC_INVALID_REQUEST;
+pub const MCP_INVALID_METHOD: i32 = JSON_RPC_METHOD_NOT_FOUND;
+pub const MCP_INVALID_PARAMS: i32 = JSON_RPC_INVALID_PARAMS;
+pub const MCP_INTERNAL_SERVER_ERROR: i32 = JSON_RPC_INTERNAL_ERROR;
+pub const MCP_TIMEOUT_ERROR: i32 = -32001;
+pub const MCP_RESOURCE_NOT_FOUND: i32 = -32002; // as defined by the MCP specification
+pub const MCP_REQUEST_CANCELLED: i32 = -32003;
+pub const MCP_SERVICE_UNAVAILABLE: i32 = -32004;
+pub const MCP_ROUTER_EXISTS: i32 = -32005;
+pub const MCP_INVALID_ROUTER_ID: i32 = -32006;
+pub const MCP_AUTH_ERROR: i32 = -32007;
+pub const MCP_FORBIDDEN_ERROR: i32 = -32008;
+pub const MCP_RATE_LIMITED: i32 = -32009;
+pub const MCP_UNKNOWN_SESSION: i32 = -32010;
+pub const MCP_SESSION_EXPIRED: i32 = -32011;
+// one code for each payload limit
+pub const MCP_BODY_TOO_LARGE: i32 = -32012;
+pub const MCP_NESTING_TOO_DEEP: i32 = -32013;
+pub const MCP_STRING_TOO_LONG: i32 = -32014;
+pub const MCP_ARRAY_TOO_LONG: i32 = -32015;
+pub const MCP_RESULT_TOO_LARGE: i32 = -32016;
+pub const MCP_QUEUE_FULL: i32 = -32017;
 
 
 /// Helper functions for JSON-RPC handling
@@ -74,6 +89,6 @@ impl JsonRpcUtils {
 
     /// Returns a predefined error for internal server errors
     pub fn internal_error(id: Option<u64>, detail: Option<String>) -> JsonRpcError {
-        JsonRpcUtils::error_response(id, -1, "Internal server error", detail.map(|d| json!(d)))
+        JsonRpcUtils::error_response(id, JSON_RPC_INTERNAL_ERROR, "Internal server error", detail.map(|d| json!(d)))
     }
 }
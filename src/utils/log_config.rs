@@ -1,7 +1,8 @@
 
 
 use tracing::Level;
-use tracing_subscriber::{fmt, layer::SubscriberExt, EnvFilter, Registry};
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, EnvFilter, Registry};
+use crate::error::McpEctorsError;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 
 /// Configuration for dynamic logging.
@@ -12,6 +13,28 @@ pub struct LogConfig {
     pub level: Level,
 }
 
+/// Changes the level of the log file while the server runs, see [`crate::McpServer::log_level`]
+#[derive(Clone)]
+pub struct LogLevelHandle {
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+impl LogLevelHandle {
+    pub(crate) fn new(handle: reload::Handle<EnvFilter, Registry>) -> Self {
+        Self { handle }
+    }
+
+    pub fn set_level(&self, level: Level) -> Result<(), McpEctorsError> {
+        self.handle.reload(log_filter(level))
+            .map_err(|e| McpEctorsError::Internal(format!("Could not change the log level: {}", e)))
+    }
+}
+
+/// What the log file gets: `RUST_LOG` directives, at `level` at least
+pub(crate) fn log_filter(level: Level) -> EnvFilter {
+    EnvFilter::from_default_env().add_directive(level.into())
+}
+
 /// Initializes a tracing subscriber that logs to both stdout and a rolling file.
 pub fn init_logging(config: &LogConfig) {
     // Create a rolling file appender that rotates daily.
@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
+
+use actix::Addr;
+use mcp_spec::protocol::{JsonRpcMessage, JsonRpcNotification};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Record};
+use tracing::{Event, Id, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::client::client_registry::NotifyClient;
+use crate::client::ClientRegistryActor;
+use crate::messages::{LoggingMessageNotification, JSONRPC_VERSION};
+
+/// Name of the span a `RouterActor` opens around every request it handles.
+pub const ROUTER_SPAN: &str = "router";
+/// Span field holding the id the router was registered under.
+pub const ROUTER_ID_FIELD: &str = "router_id";
+/// Span field holding the client session a request originates from.
+pub const SESSION_ID_FIELD: &str = "session_id";
+
+/// MCP log levels as defined by the spec (RFC 5424 severities).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoggingLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl LoggingLevel {
+    /// Maps a tracing level onto the closest MCP level.
+    pub fn from_tracing(level: &Level) -> Self {
+        match *level {
+            Level::TRACE | Level::DEBUG => LoggingLevel::Debug,
+            Level::INFO => LoggingLevel::Info,
+            Level::WARN => LoggingLevel::Warning,
+            Level::ERROR => LoggingLevel::Error,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+impl fmt::Display for LoggingLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LoggingLevel::Debug => "debug",
+            LoggingLevel::Info => "info",
+            LoggingLevel::Notice => "notice",
+            LoggingLevel::Warning => "warning",
+            LoggingLevel::Error => "error",
+            LoggingLevel::Critical => "critical",
+            LoggingLevel::Alert => "alert",
+            LoggingLevel::Emergency => "emergency",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for LoggingLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(Value::String(s.to_lowercase())).map_err(|_| format!("Unknown log level: {}", s))
+    }
+}
+
+/// No session asked for logs, nothing has to be forwarded.
+const LEVEL_OFF: u8 = u8::MAX;
+
+struct SinkInner {
+    levels: RwLock<HashMap<u64, LoggingLevel>>,
+    registry: RwLock<Option<Addr<ClientRegistryActor>>>,
+    /// lowest level any session asked for, so the layer can bail out without locking
+    min_level: AtomicU8,
+}
+
+/// Shared state between the `McpLoggingLayer` and the `ClientRegistryActor`.
+/// It keeps the minimum log level per session and knows where to deliver the notifications.
+#[derive(Clone)]
+pub struct McpLogSink {
+    inner: Arc<SinkInner>,
+}
+
+impl Default for McpLogSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl McpLogSink {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(SinkInner {
+                levels: RwLock::new(HashMap::new()),
+                registry: RwLock::new(None),
+                min_level: AtomicU8::new(LEVEL_OFF),
+            }),
+        }
+    }
+
+    /// Connects the sink to the client registry which delivers the notifications.
+    pub fn attach(&self, registry: Addr<ClientRegistryActor>) {
+        *self.inner.registry.write().unwrap() = Some(registry);
+    }
+
+    /// Sets the minimum level a session wants to receive.
+    pub fn set_level(&self, client_id: u64, level: LoggingLevel) {
+        let mut levels = self.inner.levels.write().unwrap();
+        levels.insert(client_id, level);
+        self.update_min_level(&levels);
+    }
+
+    /// Stops forwarding logs to a session.
+    pub fn remove(&self, client_id: u64) {
+        let mut levels = self.inner.levels.write().unwrap();
+        levels.remove(&client_id);
+        self.update_min_level(&levels);
+    }
+
+    pub fn level(&self, client_id: u64) -> Option<LoggingLevel> {
+        self.inner.levels.read().unwrap().get(&client_id).copied()
+    }
+
+    /// Cheap check whether any session is interested in this level.
+    pub fn enabled(&self, level: LoggingLevel) -> bool {
+        level.as_u8() >= self.inner.min_level.load(Ordering::Relaxed)
+    }
+
+    fn update_min_level(&self, levels: &HashMap<u64, LoggingLevel>) {
+        let min = levels.values().map(|l| l.as_u8()).min().unwrap_or(LEVEL_OFF);
+        self.inner.min_level.store(min, Ordering::Relaxed);
+    }
+
+    /// Sends a `notifications/message` to the session the event belongs to, if its level allows it.
+    fn dispatch(&self, level: LoggingLevel, logger: String, data: Value, session_id: u64) {
+        let registry = match self.inner.registry.read().unwrap().clone() {
+            Some(registry) => registry,
+            None => return,
+        };
+        if self.level(session_id).is_none_or(|min| level < min) {
+            return;
+        }
+
+        let params = json!({
+            "level": level,
+            "logger": logger,
+            "data": data,
+        });
+        registry.do_send(NotifyClient {
+            client_id: session_id,
+            message: JsonRpcMessage::Notification(JsonRpcNotification {
+                jsonrpc: JSONRPC_VERSION.to_string(),
+                method: LoggingMessageNotification::METHOD.to_string(),
+                params: Some(params),
+            }),
+        });
+    }
+}
+
+/// Router and session a span belongs to, stored in the span extensions.
+#[derive(Default, Clone)]
+struct McpSpanFields {
+    router_id: Option<String>,
+    session_id: Option<u64>,
+}
+
+impl Visit for McpSpanFields {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == SESSION_ID_FIELD {
+            self.session_id = Some(value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == ROUTER_ID_FIELD {
+            self.router_id = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == ROUTER_ID_FIELD {
+            self.router_id = Some(format!("{:?}", value).trim_matches('"').to_string());
+        }
+    }
+}
+
+/// Collects the message and the fields of an event into a JSON object.
+#[derive(Default)]
+struct JsonVisitor {
+    fields: Map<String, Value>,
+}
+
+impl Visit for JsonVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields.insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+}
+
+/// A tracing `Layer` that forwards events as MCP `notifications/message` to the sessions
+/// that enabled logging via `logging/setLevel`.
+/// Only events inside the router span of a request are forwarded, with the router id as `logger`
+/// and only to the session that made the request. Everything else the server logs stays local.
+pub struct McpLoggingLayer {
+    sink: McpLogSink,
+}
+
+impl McpLoggingLayer {
+    pub fn new(sink: McpLogSink) -> Self {
+        Self { sink }
+    }
+
+    /// Spans are always needed to find the router of an event, events only when a session wants them.
+    pub fn wants(sink: &McpLogSink, metadata: &Metadata<'_>) -> bool {
+        metadata.is_span() || sink.enabled(LoggingLevel::from_tracing(metadata.level()))
+    }
+}
+
+impl<S> Layer<S> for McpLoggingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        // the spans of the transport have these fields too, but their events are not the router's
+        if attrs.metadata().name() != ROUTER_SPAN {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            let mut fields = McpSpanFields::default();
+            attrs.record(&mut fields);
+            span.extensions_mut().insert(fields);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            if let Some(fields) = extensions.get_mut::<McpSpanFields>() {
+                values.record(fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = LoggingLevel::from_tracing(metadata.level());
+        if !self.sink.enabled(level) {
+            return;
+        }
+
+        // the innermost router span decides the logger and the session
+        let mut span_fields = McpSpanFields::default();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope {
+                if let Some(fields) = span.extensions().get::<McpSpanFields>() {
+                    if span_fields.router_id.is_none() {
+                        span_fields.router_id = fields.router_id.clone();
+                    }
+                    if span_fields.session_id.is_none() {
+                        span_fields.session_id = fields.session_id;
+                    }
+                }
+            }
+        }
+
+        let (Some(router_id), Some(session_id)) = (span_fields.router_id, span_fields.session_id) else {
+            return;
+        };
+
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        self.sink.dispatch(level, router_id, Value::Object(visitor.fields), session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix::{Actor, Handler, Message};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::client::client_registry::{HasCapability, RegisterClient};
+    use crate::messages::ClientMessage;
+
+    /// Stands in for the SSE stream of a session
+    #[derive(Default)]
+    struct Session {
+        received: Vec<Value>,
+    }
+
+    impl Actor for Session {
+        type Context = actix::Context<Self>;
+    }
+
+    impl Handler<ClientMessage> for Session {
+        type Result = ();
+
+        fn handle(&mut self, msg: ClientMessage, _ctx: &mut Self::Context) -> Self::Result {
+            if let JsonRpcMessage::Notification(notification) = msg.0 {
+                self.received.push(notification.params.unwrap());
+            }
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<Value>")]
+    struct Received;
+
+    impl Handler<Received> for Session {
+        type Result = Vec<Value>;
+
+        fn handle(&mut self, _msg: Received, _ctx: &mut Self::Context) -> Self::Result {
+            std::mem::take(&mut self.received)
+        }
+    }
+
+    #[test]
+    fn test_level_ordering_and_parsing() {
+        assert!(LoggingLevel::Debug < LoggingLevel::Info);
+        assert!(LoggingLevel::Warning < LoggingLevel::Emergency);
+        assert_eq!("warning".parse::<LoggingLevel>().unwrap(), LoggingLevel::Warning);
+        assert_eq!("ERROR".parse::<LoggingLevel>().unwrap(), LoggingLevel::Error);
+        assert!("verbose".parse::<LoggingLevel>().is_err());
+        assert_eq!(LoggingLevel::from_tracing(&Level::TRACE), LoggingLevel::Debug);
+        assert_eq!(LoggingLevel::from_tracing(&Level::WARN), LoggingLevel::Warning);
+    }
+
+    #[test]
+    fn test_sink_min_level() {
+        let sink = McpLogSink::new();
+        assert!(!sink.enabled(LoggingLevel::Emergency));
+
+        sink.set_level(1, LoggingLevel::Warning);
+        assert!(!sink.enabled(LoggingLevel::Info));
+        assert!(sink.enabled(LoggingLevel::Error));
+
+        sink.set_level(2, LoggingLevel::Debug);
+        assert!(sink.enabled(LoggingLevel::Debug));
+
+        sink.remove(2);
+        assert!(!sink.enabled(LoggingLevel::Debug));
+        assert_eq!(sink.level(1), Some(LoggingLevel::Warning));
+    }
+
+    #[actix_rt::test]
+    async fn test_events_only_go_to_the_session_of_their_router_span() {
+        let sink = McpLogSink::new();
+        let registry = ClientRegistryActor::new().with_log_sink(sink.clone()).start();
+        sink.attach(registry.clone());
+        let first = Session::default().start();
+        let second = Session::default().start();
+        let first_id = registry.send(RegisterClient { recipient: first.clone().recipient() }).await.unwrap();
+        let second_id = registry.send(RegisterClient { recipient: second.clone().recipient() }).await.unwrap();
+        sink.set_level(first_id, LoggingLevel::Debug);
+        sink.set_level(second_id, LoggingLevel::Debug);
+
+        let subscriber = tracing_subscriber::registry().with(McpLoggingLayer::new(sink.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!("server wide");
+            tracing::info_span!(ROUTER_SPAN, router_id = "counter", session_id = first_id)
+                .in_scope(|| tracing::warn!(value = 1, "for the first session"));
+            // a request without a session has no one to tell
+            tracing::info_span!(ROUTER_SPAN, router_id = "counter", session_id = tracing::field::Empty)
+                .in_scope(|| tracing::warn!("no session"));
+        });
+
+        // the registry has passed the notifications on once it answers
+        registry.send(HasCapability { client_id: first_id, capability: "roots".to_string() }).await.unwrap();
+        assert_eq!(
+            first.send(Received).await.unwrap(),
+            vec![json!({ "level": "warning", "logger": "counter", "data": { "message": "for the first session", "value": 1 } })]
+        );
+        assert!(second.send(Received).await.unwrap().is_empty());
+    }
+}
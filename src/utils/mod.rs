@@ -1,7 +1,9 @@
 pub mod json_rpc;
 pub mod wasm_loader;
 pub mod log_config;
+pub mod mcp_logging;
 
 pub use json_rpc::JsonRpcUtils;
 pub use wasm_loader::WasmLoader;
-pub use log_config::LogConfig;
+pub use log_config::{LogConfig, LogLevelHandle};
+pub use mcp_logging::{LoggingLevel, McpLogSink, McpLoggingLayer};
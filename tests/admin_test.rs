@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use mcp_ectors::admin::{configure_admin_routes, AdminServer};
+    use mcp_ectors::auth::{ApiKeyAuthenticator, Authentication};
+    use mcp_ectors::messages::ListToolsRequest;
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::transport::ListenAddress;
+    use mcp_spec::protocol::JsonRpcRequest;
+    use serde_json::{json, Value};
+
+    const KEY: &str = "Bearer admin-key";
+
+    fn auth() -> Authentication {
+        let keys = ApiKeyAuthenticator::new().with_hashed_key("admin", &ApiKeyAuthenticator::hash_key("admin-key")).unwrap();
+        Authentication::new().with_authenticator(keys)
+    }
+
+    // the tool names clients see
+    async fn listed_tools(manager: &RouterServiceManager) -> Vec<String> {
+        let request = JsonRpcRequest { jsonrpc: "2.0".to_string(), id: Some(1), method: "tools/list".to_string(), params: None };
+        let response = manager.get_list_tools().send(ListToolsRequest { request }).await.unwrap().unwrap();
+        response.result.unwrap()["tools"].as_array().unwrap().iter().map(|tool| tool["name"].as_str().unwrap().to_string()).collect()
+    }
+
+    macro_rules! call {
+        ($app:expr, $request:expr) => {{
+            let response = test::call_service(&$app, $request.insert_header(("Authorization", KEY)).to_request()).await;
+            let status = response.status();
+            let body: Value = test::read_body_json(response).await;
+            (status, body)
+        }};
+    }
+
+    #[actix_rt::test]
+    async fn test_admin_api_needs_authentication() {
+        let manager = RouterServiceManager::default(None).await;
+        let server = AdminServer::new(ListenAddress::localhost(0), manager.clone(), std::env::temp_dir());
+        assert!(server.start().is_err());
+
+        let app = test::init_service(App::new()
+            .app_data(Data::new(auth()))
+            .configure(configure_admin_routes(manager, std::env::temp_dir()))).await;
+        let response = test::call_service(&app, test::TestRequest::get().uri("/routers").to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = test::call_service(&app, test::TestRequest::get()
+            .uri("/routers")
+            .insert_header(("Authorization", "Bearer wrong-key"))
+            .to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let (status, body) = call!(app, test::TestRequest::get().uri("/routers"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["routers"][0]["router_id"], "system");
+        assert_eq!(body["routers"][0]["enabled"], true);
+        assert_eq!(body["routers"][0]["state"], "ready");
+        assert!(body["routers"][0].get("wasm").is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_upload_disable_reload_and_delete() {
+        let directory = tempfile::tempdir().unwrap();
+        let manager = RouterServiceManager::default(None).await;
+        let app = test::init_service(App::new()
+            .app_data(Data::new(auth()))
+            .configure(configure_admin_routes(manager.clone(), directory.path().to_path_buf()))).await;
+        let component = std::fs::read("wasm/mcp_weather_api2.wasm").unwrap();
+
+        // nothing is written unless it is a component
+        let (status, _) = call!(app, test::TestRequest::put().uri("/routers/weather").set_payload("not a component"));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = call!(app, test::TestRequest::put().uri("/routers/we_ather").set_payload(component.clone()));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = call!(app, test::TestRequest::put().uri("/routers/system").set_payload(component.clone()));
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(std::fs::read_dir(directory.path()).unwrap().count(), 0);
+
+        let (status, body) = call!(app, test::TestRequest::put().uri("/routers/weather").set_payload(component.clone()));
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["router_id"], "weather");
+        assert_eq!(body["path"], directory.path().join("weather.wasm").display().to_string());
+        assert!(body["tools"].as_u64().unwrap() > 0);
+        assert!(body["wasm"].is_object());
+        let tools = listed_tools(&manager).await;
+        assert!(tools.iter().any(|tool| tool.starts_with("weather_")));
+
+        let (status, body) = call!(app, test::TestRequest::get().uri("/routers/weather/tools"));
+        assert_eq!(status, StatusCode::OK);
+        let tool = body["tools"][0]["name"].as_str().unwrap().to_string();
+        assert!(tools.contains(&format!("weather_{}", tool)));
+        let (status, body) = call!(app, test::TestRequest::get().uri("/routers/weather/prompts"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["prompts"][0]["name"], "GetWeather");
+
+        // disabled routers keep their aliases, but clients see nothing of them
+        manager.add_alias("forecast", "weather").await.unwrap();
+        let (status, body) = call!(app, test::TestRequest::post().uri("/routers/weather/disable"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["enabled"], false);
+        assert!(manager.get_router(format!("weather_{}", tool)).await.is_none());
+        assert!(!listed_tools(&manager).await.iter().any(|tool| tool.starts_with("weather_")));
+
+        let (status, _) = call!(app, test::TestRequest::post().uri("/routers/weather/enable"));
+        assert_eq!(status, StatusCode::OK);
+        assert!(manager.get_router(format!("forecast_{}", tool)).await.is_some());
+        assert_eq!(listed_tools(&manager).await, tools);
+
+        // a reload swaps the instance, clients keep calling it by its id and aliases
+        let (status, body) = call!(app, test::TestRequest::post().uri("/routers/weather/reload"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["enabled"], true);
+        assert!(manager.get_router(format!("forecast_{}", tool)).await.is_some());
+        assert_eq!(listed_tools(&manager).await, tools);
+        let (status, _) = call!(app, test::TestRequest::put().uri("/routers/weather").set_payload(component.clone()));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed_tools(&manager).await, tools);
+        // a broken file leaves the old instance in place
+        std::fs::write(directory.path().join("weather.wasm"), "not a component").unwrap();
+        let (status, _) = call!(app, test::TestRequest::post().uri("/routers/weather/reload"));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(manager.get_router(format!("forecast_{}", tool)).await.is_some());
+        assert_eq!(listed_tools(&manager).await, tools);
+        std::fs::remove_file(directory.path().join("weather.wasm")).unwrap();
+        let (status, _) = call!(app, test::TestRequest::post().uri("/routers/weather/reload"));
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(listed_tools(&manager).await, tools);
+        std::fs::write(directory.path().join("weather.wasm"), component).unwrap();
+
+        // native routers have no file to reload from
+        let (status, _) = call!(app, test::TestRequest::post().uri("/routers/system/reload"));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = call!(app, test::TestRequest::delete().uri("/routers/weather"));
+        assert_eq!(status, StatusCode::OK);
+        assert!(manager.get_router(format!("weather_{}", tool)).await.is_none());
+        assert!(!listed_tools(&manager).await.iter().any(|tool| tool.starts_with("weather_")));
+        assert!(directory.path().join("weather.wasm").exists());
+        let (status, body) = call!(app, test::TestRequest::get().uri("/routers/weather"));
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, json!({"error": "No router for weather"}));
+        let (status, _) = call!(app, test::TestRequest::post().uri("/routers/weather/enable"));
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,128 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use actix::Actor;
+    use actix_web::body::MessageBody;
+    use actix_web::http::header::HeaderMap;
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use jsonwebtoken::jwk::JwkSet;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use mcp_ectors::auth::{ApiKeyAuthenticator, AuthMethod, Authentication, Authenticator, JwtAuthenticator};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::McpEctorsError;
+    use serde_json::{json, Value};
+
+    use crate::common::{next_event, routes};
+
+    const SECRET: &[u8] = b"a-test-secret-that-is-long-enough";
+    const ISSUER: &str = "https://auth.example.com";
+    const AUDIENCE: &str = "mcp-ectors";
+
+    fn jwks() -> JwkSet {
+        use base64::Engine;
+        let k = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(SECRET);
+        serde_json::from_value(json!({ "keys": [{ "kty": "oct", "kid": "test", "alg": "HS256", "k": k }] })).unwrap()
+    }
+
+    fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    fn token(claims: Value, kid: &str) -> String {
+        let mut header = Header::new(jsonwebtoken::Algorithm::HS256);
+        header.kid = Some(kid.to_string());
+        encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+    }
+
+    fn valid_claims(sub: &str) -> Value {
+        json!({ "sub": sub, "iss": ISSUER, "aud": AUDIENCE, "exp": now() + 600, "scope": "tools:call resources:read" })
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(actix_web::http::header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    #[actix_rt::test]
+    async fn test_api_keys_are_hashed() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "# keys\nalice: {}\n\nbob: {}", ApiKeyAuthenticator::hash_key("alice-key"), ApiKeyAuthenticator::hash_key("bob-key")).unwrap();
+        let keys = ApiKeyAuthenticator::from_file(file.path()).unwrap();
+        assert!(!std::fs::read_to_string(file.path()).unwrap().contains("alice-key"));
+
+        let principal = keys.authenticate(&bearer("alice-key")).await.unwrap().unwrap();
+        assert_eq!(principal.subject, "alice");
+        assert_eq!(principal.method, AuthMethod::ApiKey);
+        let mut headers = HeaderMap::new();
+        headers.insert(actix_web::http::header::HeaderName::from_static("x-api-key"), "bob-key".parse().unwrap());
+        assert_eq!(keys.authenticate(&headers).await.unwrap().unwrap().subject, "bob");
+        assert_eq!(keys.authenticate(&bearer("mallory-key")).await.unwrap(), None);
+        assert!(ApiKeyAuthenticator::new().with_hashed_key("eve", "plaintext").is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_jwt_validation() {
+        let jwt = JwtAuthenticator::new(jwks(), ISSUER, AUDIENCE).with_leeway(0);
+        let principal = jwt.authenticate(&bearer(&token(valid_claims("carol"), "test"))).await.unwrap().unwrap();
+        assert_eq!(principal.subject, "carol");
+        assert_eq!(principal.method, AuthMethod::Jwt);
+        assert_eq!(principal.scopes, vec!["tools:call", "resources:read"]);
+
+        let mut wrong_issuer = valid_claims("carol");
+        wrong_issuer["iss"] = json!("https://evil.example.com");
+        let mut wrong_audience = valid_claims("carol");
+        wrong_audience["aud"] = json!("another-server");
+        let mut expired = valid_claims("carol");
+        expired["exp"] = json!(now() - 60);
+        let mut no_expiry = valid_claims("carol");
+        no_expiry.as_object_mut().unwrap().remove("exp");
+        for (claims, kid) in [(wrong_issuer, "test"), (wrong_audience, "test"), (expired, "test"), (no_expiry, "test"), (valid_claims("carol"), "other")] {
+            let error = jwt.authenticate(&bearer(&token(claims.clone(), kid))).await.unwrap_err();
+            assert!(matches!(error, McpEctorsError::Unauthorized(_)), "{:?} for {}", error, claims);
+        }
+        // not a JWT, maybe an API key
+        assert_eq!(jwt.authenticate(&bearer("alice-key")).await.unwrap(), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_sessions_require_and_keep_their_principal() {
+        let keys = ApiKeyAuthenticator::new()
+            .with_hashed_key("alice", &ApiKeyAuthenticator::hash_key("alice-key")).unwrap()
+            .with_hashed_key("bob", &ApiKeyAuthenticator::hash_key("bob-key")).unwrap();
+        let auth = Authentication::new()
+            .with_authenticator(keys)
+            .with_authenticator(JwtAuthenticator::new(jwks(), ISSUER, AUDIENCE));
+        let manager = RouterServiceManager::default(None).await;
+        let app = test::init_service(App::new().app_data(Data::new(auth)).configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers().get("www-authenticate").unwrap(), "Bearer");
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").insert_header(("Authorization", "Bearer wrong")).to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").insert_header(("Authorization", "Bearer alice-key")).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let messages = next_event(&mut Box::pin(response.into_body().boxed())).await;
+
+        let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"});
+        let post = |authorization: String| test::TestRequest::post().uri(&messages).insert_header(("Authorization", authorization)).set_json(ping.clone()).to_request();
+        assert_eq!(test::call_service(&app, post("Bearer alice-key".to_string())).await.status(), StatusCode::OK);
+        assert_eq!(test::call_service(&app, post("Bearer bob-key".to_string())).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(test::call_service(&app, post(format!("Bearer {}", token(valid_claims("alice"), "test")))).await.status(), StatusCode::FORBIDDEN);
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").insert_header(("Authorization", format!("Bearer {}", token(valid_claims("alice"), "test")))).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let messages = next_event(&mut Box::pin(response.into_body().boxed())).await;
+        let request = test::TestRequest::post().uri(&messages).insert_header(("Authorization", format!("Bearer {}", token(valid_claims("alice"), "test")))).set_json(ping.clone()).to_request();
+        assert_eq!(test::call_service(&app, request).await.status(), StatusCode::OK);
+    }
+}
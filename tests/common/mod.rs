@@ -0,0 +1,45 @@
+//! Helpers shared by the integration tests that talk to the SSE transport
+#![allow(dead_code)]
+
+use std::pin::Pin;
+
+use actix::Addr;
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::web::ServiceConfig;
+use mcp_ectors::client::ClientRegistryActor;
+use mcp_ectors::mcp::InitializationActor;
+use mcp_ectors::router::RouterServiceManager;
+use mcp_ectors::transport::sse_transport_actor::configure_routes;
+
+/// The SSE and message routes for the routers of `manager`, for `App::configure`
+pub fn routes(registry: Addr<ClientRegistryActor>, manager: &RouterServiceManager) -> impl Fn(&mut ServiceConfig) + Clone + Send + 'static {
+    configure_routes(
+        registry,
+        manager.get_registry(),
+        InitializationActor::new(),
+        manager.get_list_prompts(),
+        manager.get_list_tools(),
+        manager.get_list_resources(),
+    )
+}
+
+/// The data of the SSE event in `chunk`, if it has one
+pub fn event_data(chunk: &[u8]) -> Option<String> {
+    let event = String::from_utf8(chunk.to_vec()).unwrap();
+    event.lines().find_map(|line| line.strip_prefix("data: ")).map(str::to_string)
+}
+
+/// Reads the next event from the SSE stream and returns its data
+pub async fn next_event(body: &mut Pin<Box<BoxBody>>) -> String {
+    try_next_event(body).await.expect("the SSE stream ended")
+}
+
+/// Like [`next_event`], `None` once the stream ended or failed
+pub async fn try_next_event(body: &mut Pin<Box<BoxBody>>) -> Option<String> {
+    loop {
+        let chunk = futures::future::poll_fn(|cx| body.as_mut().poll_next(cx)).await?.ok()?;
+        if let Some(data) = event_data(&chunk) {
+            return Some(data);
+        }
+    }
+}
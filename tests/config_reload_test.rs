@@ -0,0 +1,186 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use actix::Actor;
+    use mcp_ectors::auth::AccessControl;
+    use mcp_ectors::config::{ConfigReloader, ReloadConfig, ReloadReport, ServerConfig};
+    use mcp_ectors::limits::{Limit, PayloadLimit, PayloadLimiter, RateLimiter};
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::McpEctorsError;
+
+    fn write(path: &Path, content: &str) {
+        fs::write(path, content).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_changes_are_listed_by_key() {
+        let running = ServerConfig::from_toml("[router.counter]\ntimeout = 5").unwrap();
+        assert!(running.changes(&running.clone()).is_empty());
+        // an empty list is the same as none
+        assert!(running.changes(&ServerConfig::from_toml("[router.counter]\ntimeout = 5\naliases = []").unwrap()).is_empty());
+
+        let config = ServerConfig::from_toml(r#"
+            policy = "policy.json"
+            [transport]
+            port = 3000
+            [router.counter]
+            timeout = 6
+            [router.echo]
+            aliases = ["say"]
+        "#).unwrap();
+        assert_eq!(running.changes(&config), vec!["policy", "router.counter.timeout", "router.echo.aliases", "transport.port"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_reload_applies_live_changes_and_reports_the_others() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("mcp-ectors.toml");
+        let routers = directory.path().join("routers");
+        fs::create_dir(&routers).unwrap();
+        let policy = directory.path().join("policy.json");
+        write(&policy, r#"{"default_roles": ["guest"], "roles": {"guest": {"allow": ["system_*"]}}}"#);
+        write(&file, "[routers]\ndirectories = []\n");
+
+        let running = ServerConfig::from_file(&file).unwrap();
+        let manager = RouterServiceManager::default(None).await;
+        let limiter = RateLimiter::default();
+        let payload_limits = PayloadLimiter::default();
+        let access = AccessControl::default();
+        let reloader = ConfigReloader::new(Some(file.clone()), running, manager.clone())
+            .with_access_control(access.clone())
+            .with_rate_limiter(limiter.clone())
+            .with_payload_limits(payload_limits.clone())
+            .with_watch(false)
+            .start();
+
+        write(&file, &format!(r#"
+            policy = "{}"
+            [transport]
+            port = 3000
+            [routers]
+            directories = ["{}"]
+            request_timeout = 10
+            [router.system]
+            timeout = 2
+            rate_limit = "5/5"
+            aliases = ["sys"]
+            [rate_limits]
+            principal = "3"
+            [payload_limits]
+            body = 2048
+            [metrics]
+            tool_labels = true
+        "#, policy.display(), routers.display()));
+        let report = reloader.send(ReloadConfig).await.unwrap().unwrap();
+        assert_eq!(report.restart_required, vec!["transport.port"]);
+        assert!(report.applied.contains(&"router.system.aliases".to_string()), "{:?}", report);
+
+        assert!(access.allows(None, "system_all") && !access.allows(None, "counter_increment"));
+        assert_eq!(limiter.limits().principal, Some(Limit::rate(3.0, 3)));
+        assert_eq!(limiter.limits().routers["system"], Limit::rate(5.0, 5));
+        assert_eq!(payload_limits.max(PayloadLimit::Body), 2048);
+        assert_eq!(manager.get_timeouts().timeout_for("system", None), Duration::from_secs(2));
+        assert_eq!(manager.get_timeouts().timeout_for("echo", None), Duration::from_secs(10));
+        assert!(manager.get_router("sys_all".to_string()).await.is_some());
+        assert_eq!(manager.wasm_directories(), vec![routers.display().to_string()]);
+        assert!(manager.metrics().tool_labels());
+
+        // taking settings out undoes them
+        write(&file, "[routers]\ndirectories = []\n[transport]\nport = 3000\n");
+        let report = reloader.send(ReloadConfig).await.unwrap().unwrap();
+        // the port is still the one the server started with
+        assert_eq!(report.restart_required, vec!["transport.port"]);
+        assert!(access.allows(None, "counter_increment"));
+        assert_eq!(limiter.limits().principal, None);
+        assert!(manager.get_router("sys_all".to_string()).await.is_none());
+        assert!(manager.wasm_directories().is_empty());
+
+        // a configuration that does not validate changes nothing
+        write(&file, "[rate_limits]\nprincipal = \"fast\"\n");
+        assert!(matches!(reloader.send(ReloadConfig).await.unwrap(), Err(McpEctorsError::InvalidParams(_))));
+        write(&file, "[routers]\ndirectories = []\n[transport]\nport = 3000\n");
+        let report = reloader.send(ReloadConfig).await.unwrap().unwrap();
+        assert_eq!(report, ReloadReport { restart_required: vec!["transport.port".to_string()], ..Default::default() });
+    }
+
+    #[actix_rt::test]
+    async fn test_a_failed_reload_keeps_what_it_applied() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("mcp-ectors.toml");
+        write(&file, "[routers]\ndirectories = []\n");
+        let limiter = RateLimiter::default();
+        let reloader = ConfigReloader::new(Some(file.clone()), ServerConfig::from_file(&file).unwrap(), RouterServiceManager::default(None).await)
+            .with_rate_limiter(limiter.clone())
+            .with_watch(false)
+            .start();
+
+        // neither a directory nor a policy that is not there changes anything
+        write(&file, "policy = \"missing.json\"\n[routers]\ndirectories = []\n[rate_limits]\nprincipal = \"3\"\n");
+        assert!(reloader.send(ReloadConfig).await.unwrap().is_err());
+        write(&file, "[routers]\ndirectories = [\"missing\"]\n[rate_limits]\nprincipal = \"3\"\n");
+        assert!(matches!(reloader.send(ReloadConfig).await.unwrap(), Err(McpEctorsError::InvalidParams(_))));
+        assert_eq!(limiter.limits().principal, None);
+
+        // the rate limits are applied before the alias of a router that does not exist fails
+        write(&file, "[routers]\ndirectories = []\n[rate_limits]\nprincipal = \"3\"\n[router.missing]\naliases = [\"gone\"]\n");
+        let report = reloader.send(ReloadConfig).await.unwrap().unwrap();
+        assert_eq!(report.applied, vec!["rate_limits.principal"]);
+        assert_eq!(report.not_applied, vec!["router.missing.aliases"]);
+        assert!(report.error.unwrap().contains("missing"));
+        assert_eq!(limiter.limits().principal, Some(Limit::rate(3.0, 3)));
+
+        write(&file, "[routers]\ndirectories = []\n[rate_limits]\nprincipal = \"3\"\n");
+        assert_eq!(reloader.send(ReloadConfig).await.unwrap().unwrap(), Default::default());
+        write(&file, "[routers]\ndirectories = []\n");
+        let report = reloader.send(ReloadConfig).await.unwrap().unwrap();
+        assert_eq!(report.applied, vec!["rate_limits.principal"]);
+        assert_eq!(limiter.limits().principal, None);
+    }
+
+    #[actix_rt::test]
+    async fn test_overrides_apply_after_every_reload() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("mcp-ectors.toml");
+        write(&file, "[routers]\ndirectories = []\n");
+        let limiter = RateLimiter::default();
+        let reloader = ConfigReloader::new(Some(file.clone()), ServerConfig::from_file(&file).unwrap(), RouterServiceManager::default(None).await)
+            .with_rate_limiter(limiter.clone())
+            .with_overrides(Arc::new(|mut config: ServerConfig| {
+                config.rate_limits.global = Some("100".to_string());
+                Ok(config)
+            }))
+            .with_watch(false)
+            .start();
+
+        write(&file, "[routers]\ndirectories = []\n[rate_limits]\nglobal = \"1\"\nprincipal = \"2\"\n");
+        let report = reloader.send(ReloadConfig).await.unwrap().unwrap();
+        assert_eq!(report.applied, vec!["rate_limits.global", "rate_limits.principal"]);
+        assert_eq!(limiter.limits().global, Some(Limit::rate(100.0, 100)));
+        assert_eq!(limiter.limits().principal, Some(Limit::rate(2.0, 2)));
+    }
+
+    #[actix_rt::test]
+    async fn test_changes_of_the_file_are_picked_up() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("mcp-ectors.toml");
+        write(&file, "[routers]\ndirectories = []\n");
+        let limiter = RateLimiter::default();
+        let _reloader = ConfigReloader::new(Some(file.clone()), ServerConfig::from_file(&file).unwrap(), RouterServiceManager::default(None).await)
+            .with_rate_limiter(limiter.clone())
+            .start();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        write(&file, "[routers]\ndirectories = []\n[rate_limits]\nglobal = \"7\"\n");
+        for _ in 0..50 {
+            if limiter.limits().global.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(limiter.limits().global, Some(Limit::rate(7.0, 7)));
+    }
+}
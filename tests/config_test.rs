@@ -0,0 +1,174 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use mcp_ectors::config::{ServerConfig, MASK};
+    use mcp_ectors::limits::{Limit, PayloadLimit};
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::McpEctorsError;
+
+    const CONFIG: &str = r#"
+        [transport]
+        port = 3000
+        listen = ["127.0.0.1:3000"]
+
+        [logging]
+        level = "info"
+
+        [routers]
+        directories = ["./wasm", "./more"]
+        request_timeout = 30
+
+        [router.counter]
+        timeout = 5
+        rate_limit = "10/20"
+        max_in_flight = 2
+        scopes = ["counter:use"]
+        aliases = ["count"]
+
+        [auth.introspection]
+        endpoint = "https://as.example.com/introspect"
+        client_id = "mcp"
+        client_secret = "hunter2"
+
+        [rate_limits]
+        principal = "5"
+
+        [payload_limits]
+        body = 1024
+    "#;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[actix_rt::test]
+    async fn test_values_are_layered_over_the_defaults() {
+        let defaults = ServerConfig::default();
+        assert_eq!(defaults.transport.port, 8080);
+        assert_eq!(defaults.logging.level, "warn");
+        assert_eq!(defaults.routers.directories, vec!["./wasm"]);
+        assert_eq!(defaults.routers.request_timeout, 60);
+
+        let config = ServerConfig::from_toml(CONFIG).unwrap();
+        assert_eq!(config.transport.port, 3000);
+        // what the file leaves out keeps its default
+        assert_eq!(config.logging.dir, "logs");
+        assert_eq!(config.transport.session_idle_timeout, 1800);
+
+        let config = config.with_env(env(&[
+            ("MCPECTORS_TRANSPORT__PORT", "4000"),
+            ("MCPECTORS_LOGGING__LEVEL", "debug"),
+            ("MCPECTORS_ROUTERS__DIRECTORIES", r#"["/opt/routers"]"#),
+            ("MCPECTORS_ROUTER__COUNTER__TIMEOUT", "7"),
+            ("MCPECTORS_ROUTER__ECHO__ALIASES", r#"["say"]"#),
+            ("MCPECTORS_CONFIG", "ignored.toml"),
+            // keys that do not exist are ignored
+            ("MCPECTORS_TRANSPORT__PROT", "1"),
+            ("MCPECTORS_BOGUS__SETTING", "on"),
+            ("PATH", "/usr/bin"),
+        ])).unwrap();
+        assert_eq!(config.transport.port, 4000);
+        assert_eq!(config.logging.level, "debug");
+        assert_eq!(config.routers.directories, vec!["/opt/routers"]);
+        assert_eq!(config.router["counter"].timeout, Some(7));
+        assert_eq!(config.router["counter"].aliases, vec!["count"]);
+        assert_eq!(config.router["echo"].aliases, vec!["say"]);
+        // the rest of the file still applies
+        assert_eq!(config.routers.request_timeout, 30);
+        assert_eq!(config.log_config().unwrap().level, tracing::Level::DEBUG);
+    }
+
+    #[actix_rt::test]
+    async fn test_router_settings() {
+        let config = ServerConfig::from_toml(CONFIG).unwrap();
+        config.validate().unwrap();
+
+        let timeouts = config.request_timeouts();
+        assert_eq!(timeouts.timeout_for("counter", Some("increment")), Duration::from_secs(5));
+        assert_eq!(timeouts.timeout_for("echo", None), Duration::from_secs(30));
+
+        let limits = config.rate_limits().unwrap();
+        assert_eq!(limits.routers["counter"], Limit::rate(10.0, 20).with_max_in_flight(2));
+        assert_eq!(limits.principal, Some(Limit::rate(5.0, 5)));
+        assert_eq!(config.payload_limits().max(PayloadLimit::Body), 1024);
+        assert_eq!(config.payload_limits().max(PayloadLimit::Depth), 64);
+
+        let scope_map = config.scope_map().unwrap().unwrap();
+        assert_eq!(scope_map.permissions(&["counter:use".to_string()]), vec!["counter"]);
+        assert_eq!(config.router_aliases(), vec![("count".to_string(), "counter".to_string())]);
+        assert_eq!(config.listen_addresses().unwrap().len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_invalid_configurations_are_rejected() {
+        assert!(matches!(ServerConfig::from_toml("[transport]\nprot = 1"), Err(McpEctorsError::InvalidParams(_))));
+        assert!(ServerConfig::from_toml("[bogus]").is_err());
+        assert!(ServerConfig::default().with_env(env(&[("MCPECTORS_TRANSPORT__PORT", "not a port")])).is_err());
+
+        for config in [
+            "[logging]\nlevel = \"loud\"",
+            "[rate_limits]\nglobal = \"fast\"",
+            "[router.counter]\nrate_limit = \"-1\"",
+            "[transport]\nlisten = [\"nowhere\"]",
+            "[transport.tls]\ncert = \"cert.pem\"",
+            "[auth]\napi_keys = \"/does/not/exist\"",
+            "policy = \"/does/not/exist.json\"",
+            "[admin]\nlisten = \"127.0.0.1:9090\"",
+            "[admin]\nlisten = \"nowhere\"\napi_keys = \"keys.txt\"",
+        ] {
+            let config = ServerConfig::from_toml(config).unwrap();
+            assert!(config.validate().is_err(), "{:?}", config);
+        }
+        for config in ["[router.my_router]", "[router.counter]\naliases = [\"my_counter\"]"] {
+            match ServerConfig::from_toml(config).unwrap().validate() {
+                Err(McpEctorsError::InvalidRouterId(_)) => {}
+                other => panic!("{} gave {:?}", config, other),
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_secrets_are_masked() {
+        let config = ServerConfig::from_toml(&format!(r#"{}
+            [transport.tls]
+            cert = "cert.pem"
+            key = "secret/tls-key.pem"
+            [auth]
+            api_keys = "secret/keys.txt"
+            [auth.jwt]
+            jwks = "secret/jwks.json"
+            issuer = "https://as.example.com"
+            audience = "mcp"
+            [admin]
+            api_keys = "secret/admin-keys.txt"
+        "#, CONFIG)).unwrap();
+        let masked = config.to_masked_toml().unwrap();
+        assert!(!masked.contains("hunter2") && !masked.contains("secret/"), "{}", masked);
+        assert!(masked.contains(MASK));
+        // the masked configuration is still a valid one
+        let reread = ServerConfig::from_toml(&masked).unwrap();
+        assert_eq!(reread.router, config.router);
+        assert_eq!(config.auth.introspection.unwrap().client_secret.as_deref(), Some("hunter2"));
+    }
+
+    #[actix_rt::test]
+    async fn test_routers_are_called_by_their_aliases() {
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.add_alias("sys", "system").await.unwrap();
+
+        let (router, action) = manager.get_router("sys_all".to_string()).await.unwrap();
+        let (system, _) = manager.get_router("system_all".to_string()).await.unwrap();
+        assert_eq!(router, system);
+        assert_eq!(action, "all");
+
+        assert!(matches!(manager.add_alias("sys", "system").await, Err(McpEctorsError::RouterExists(_))));
+        assert!(matches!(manager.add_alias("system", "system").await, Err(McpEctorsError::RouterExists(_))));
+        assert!(matches!(manager.add_alias("my_sys", "system").await, Err(McpEctorsError::InvalidRouterId(_))));
+        assert!(matches!(manager.add_alias("other", "missing").await, Err(McpEctorsError::RouterNotFound(_))));
+
+        // the alias goes with its router
+        manager.unregister_router("system").await.unwrap();
+        assert!(manager.get_router("sys_all".to_string()).await.is_none());
+    }
+}
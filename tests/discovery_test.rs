@@ -0,0 +1,154 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use actix::Actor;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use mcp_ectors::auth::{AccessControl, ApiKeyAuthenticator, Authentication, Policy};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::examples::{CounterRouter, HelloWorldRouter};
+    use mcp_ectors::messages::{ListToolsRequest, TransportRequest};
+    use mcp_ectors::router::{unwrap_proxy_calls, RequestContext, RouterServiceManager};
+    use mcp_spec::protocol::JsonRpcRequest;
+    use serde_json::{json, Value};
+
+    use crate::common::{next_event, routes};
+
+    async fn manager() -> RouterServiceManager {
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.register_router::<CounterRouter>("counter".to_string(), Box::new(CounterRouter::new())).await.unwrap();
+        manager.register_router::<HelloWorldRouter>("hello".to_string(), Box::new(HelloWorldRouter::new())).await.unwrap();
+        manager
+    }
+
+    fn request(method: &str, params: Value) -> JsonRpcRequest {
+        JsonRpcRequest { jsonrpc: "2.0".to_string(), id: Some(1), method: method.to_string(), params: Some(params) }
+    }
+
+    // calls the tool `name` (e.g. `system_search_tools`) and returns the text of its result, or the error
+    async fn call_tool(manager: &RouterServiceManager, name: &str, arguments: Value) -> Result<(String, bool), Value> {
+        let (router, name) = manager.get_router(name.to_string()).await.unwrap();
+        let request = request("tools/call", json!({ "name": name, "arguments": arguments }));
+        router.send(TransportRequest { request, context: RequestContext::default() }).await.unwrap()
+            .map(|response| {
+                let result = response.result.unwrap();
+                (result["content"][0]["text"].as_str().unwrap().to_string(), result["isError"] == json!(true))
+            })
+            .map_err(|error| json!(error.error))
+    }
+
+    async fn listed_tools(manager: &RouterServiceManager) -> Vec<String> {
+        let response = manager.get_list_tools().send(ListToolsRequest { request: request("tools/list", json!({})) }).await.unwrap().unwrap();
+        let mut tools: Vec<String> = response.result.unwrap()["tools"].as_array().unwrap().iter()
+            .map(|tool| tool["name"].as_str().unwrap().to_string())
+            .collect();
+        tools.sort();
+        tools
+    }
+
+    #[actix_rt::test]
+    async fn test_search_describe_and_call_tools() {
+        let manager = manager().await;
+
+        let (text, _) = call_tool(&manager, "system_search_tools", json!({ "query": "increment the counter" })).await.unwrap();
+        let found: Value = serde_json::from_str(&text).unwrap();
+        let names: Vec<&str> = found["tools"].as_array().unwrap().iter().map(|tool| tool["name"].as_str().unwrap()).collect();
+        assert_eq!(names[0], "counter_increment", "{}", text);
+        assert!(!names.contains(&"hello_greet"));
+        assert!(names.iter().all(|name| !name.starts_with("system_")));
+        let (text, _) = call_tool(&manager, "system_search_tools", json!({ "query": "greeting", "limit": 1 })).await.unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap()["tools"][0]["name"], "hello_greet");
+        let (text, _) = call_tool(&manager, "system_search_tools", json!({ "query": "weather" })).await.unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap(), json!({ "tools": [] }));
+        assert!(call_tool(&manager, "system_search_tools", json!({})).await.is_err());
+
+        let (text, _) = call_tool(&manager, "system_describe_tool", json!({ "name": "hello_greet" })).await.unwrap();
+        let tool: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(tool["inputSchema"]["required"], json!(["name"]));
+        let (text, is_error) = call_tool(&manager, "system_describe_tool", json!({ "name": "hello_wave" })).await.unwrap();
+        assert!(is_error, "{}", text);
+
+        assert_eq!(call_tool(&manager, "system_call_tool", json!({ "name": "counter_increment" })).await.unwrap().0, "1");
+        let proxied = json!({ "name": "system_call_tool", "arguments": { "name": "counter_increment", "arguments": {} } });
+        assert_eq!(call_tool(&manager, "system_call_tool", proxied).await.unwrap().0, "2");
+        assert!(call_tool(&manager, "system_call_tool", json!({ "name": "counter_nothere" })).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_lazy_tools_lists_only_the_discovery_tools() {
+        let manager = manager().await;
+        let discovery = vec!["system_call_tool", "system_describe_tool", "system_search_tools"];
+        assert_eq!(listed_tools(&manager).await.len(), discovery.len() + 4);
+
+        manager.set_lazy_tools(true);
+        assert_eq!(listed_tools(&manager).await, discovery);
+        // the others can still be found and called
+        let (text, _) = call_tool(&manager, "system_search_tools", json!({ "query": "counter value" })).await.unwrap();
+        assert!(text.contains("counter_get_value"), "{}", text);
+        assert_eq!(call_tool(&manager, "counter_increment", json!({})).await.unwrap().0, "1");
+
+        manager.set_lazy_tools(false);
+        assert_eq!(listed_tools(&manager).await.len(), discovery.len() + 4);
+    }
+
+    #[actix_rt::test]
+    async fn test_proxy_calls_are_unwrapped() {
+        let mut call = request("tools/call", json!({
+            "name": "system_call_tool",
+            "arguments": { "name": "system_call_tool", "arguments": { "name": "hello_greet", "arguments": { "name": "Ann" } } },
+            "_meta": { "progressToken": 7 }
+        }));
+        unwrap_proxy_calls(&mut call);
+        assert_eq!(call.params, Some(json!({ "name": "hello_greet", "arguments": { "name": "Ann" }, "_meta": { "progressToken": 7 } })));
+
+        let mut call = request("tools/call", json!({ "name": "system_call_tool", "arguments": { "name": "counter_increment" } }));
+        unwrap_proxy_calls(&mut call);
+        assert_eq!(call.params, Some(json!({ "name": "counter_increment", "arguments": {} })));
+
+        let mut search = request("tools/call", json!({ "name": "system_search_tools", "arguments": { "query": "counter" } }));
+        unwrap_proxy_calls(&mut search);
+        assert_eq!(search.params, Some(json!({ "name": "system_search_tools", "arguments": { "query": "counter" } })));
+    }
+
+    #[actix_rt::test]
+    async fn test_discovery_follows_the_policy() {
+        let manager = manager().await;
+        let policy: Policy = serde_json::from_value(json!({
+            "principals": { "alice": ["counting"] },
+            "roles": { "counting": { "allow": ["counter_*", "system_*"] } }
+        })).unwrap();
+        let keys = ApiKeyAuthenticator::new().with_hashed_key("alice", &ApiKeyAuthenticator::hash_key("alice-key")).unwrap();
+        let app = test::init_service(App::new()
+            .app_data(Data::new(Authentication::new().with_authenticator(keys)))
+            .app_data(Data::new(AccessControl::new(policy)))
+            .configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").insert_header(("X-API-Key", "alice-key")).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = Box::pin(response.into_body().boxed());
+        let messages = next_event(&mut body).await;
+        let mut id = 0;
+        let mut call = async |name: &str, arguments: Value| -> Value {
+            id += 1;
+            let message = json!({ "jsonrpc": "2.0", "id": id, "method": "tools/call", "params": { "name": name, "arguments": arguments } });
+            let request = test::TestRequest::post().uri(&messages).insert_header(("X-API-Key", "alice-key")).set_json(message).to_request();
+            assert_eq!(test::call_service(&app, request).await.status(), StatusCode::OK);
+            serde_json::from_str(&next_event(&mut body).await).unwrap()
+        };
+        let text = |reply: &Value| reply["result"]["content"][0]["text"].as_str().unwrap_or_default().to_string();
+
+        let found = text(&call("system_search_tools", json!({ "query": "greeting counter" })).await);
+        assert!(found.contains("counter_increment"), "{}", found);
+        assert!(!found.contains("hello_greet"), "{}", found);
+        assert_eq!(call("system_describe_tool", json!({ "name": "hello_greet" })).await["result"]["isError"], json!(true));
+
+        assert_eq!(text(&call("system_call_tool", json!({ "name": "counter_increment", "arguments": {} })).await), "1");
+        // the proxied call is checked as the tool it calls
+        let reply = call("system_call_tool", json!({ "name": "hello_greet", "arguments": { "name": "Ann" } })).await;
+        assert_eq!(reply["error"]["code"], json!(-32008), "{}", reply);
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix::Actor;
+    use mcp_ectors::messages::{RegisterRouter, TransportRequest};
+    use mcp_ectors::router::router_registry::ActorRouterRegistry;
+    use mcp_ectors::router::{router::ResponseFuture, RequestContext, Router, RouterActor};
+    use mcp_ectors::McpEctorsError;
+    use mcp_spec::handler::{PromptError, ResourceError};
+    use mcp_spec::prompt::Prompt;
+    use mcp_spec::protocol::{CallToolResult, GetPromptResult, JsonRpcRequest, ReadResourceResult, ServerCapabilities, ToolsCapability};
+    use mcp_spec::{Content, Resource, Tool, ToolError};
+    use serde_json::{json, Value};
+
+    /// A router whose tools fail in every way a tool can fail
+    struct FailingRouter;
+
+    impl Router for FailingRouter {
+        fn name(&self) -> String {
+            "FailingRouter".to_string()
+        }
+
+        fn instructions(&self) -> String {
+            "Failing instructions".to_string()
+        }
+
+        fn capabilities(&self) -> ServerCapabilities {
+            ServerCapabilities { tools: Some(ToolsCapability { list_changed: None }), resources: None, prompts: None }
+        }
+
+        fn list_tools(&self) -> Vec<Tool> {
+            vec![]
+        }
+
+        fn call_tool(&self, tool_name: &str, _arguments: Value) -> ResponseFuture<Result<CallToolResult, ToolError>> {
+            let tool_name = tool_name.to_string();
+            Box::pin(async move {
+                match tool_name.as_str() {
+                    "ok" => Ok(CallToolResult { content: vec![Content::text("done")], is_error: Some(false) }),
+                    "fails" => Err(ToolError::ExecutionError("disk full".to_string())),
+                    "strict" => Err(ToolError::InvalidParameters("count is required".to_string())),
+                    _ => Err(ToolError::NotFound(tool_name)),
+                }
+            })
+        }
+
+        fn list_resources(&self) -> Vec<Resource> {
+            vec![]
+        }
+
+        fn read_resource(&self, uri: &str) -> ResponseFuture<Result<ReadResourceResult, ResourceError>> {
+            let uri = uri.to_string();
+            Box::pin(async move { Err(ResourceError::NotFound(uri)) })
+        }
+
+        fn list_prompts(&self) -> Vec<Prompt> {
+            vec![]
+        }
+
+        fn get_prompt(&self, prompt_name: &str) -> ResponseFuture<Result<GetPromptResult, PromptError>> {
+            let prompt_name = prompt_name.to_string();
+            Box::pin(async move { Err(PromptError::NotFound(prompt_name)) })
+        }
+    }
+
+    fn request(id: u64, method: &str, params: Value) -> TransportRequest {
+        TransportRequest {
+            request: JsonRpcRequest { jsonrpc: "2.0".to_string(), id: Some(id), method: method.to_string(), params: Some(params) },
+            context: RequestContext::default(),
+        }
+    }
+
+    fn start_router() -> actix::Addr<RouterActor> {
+        RouterActor::new("failing".to_string(), Arc::new(Box::new(FailingRouter))).start()
+    }
+
+    #[actix_rt::test]
+    async fn test_tool_execution_failure_is_a_result() {
+        let router = start_router();
+        let response = router.send(request(1, "tools/call", json!({ "name": "fails" }))).await.unwrap().expect("a result, not an error");
+        let result = response.result.unwrap();
+        assert_eq!(result["isError"], json!(true));
+        assert_eq!(result["content"][0]["text"], json!("Execution failed: disk full"));
+
+        let response = router.send(request(2, "tools/call", json!({ "name": "ok" }))).await.unwrap().unwrap();
+        assert_eq!(response.result.unwrap()["isError"], json!(false));
+    }
+
+    #[actix_rt::test]
+    async fn test_protocol_errors_keep_their_cause() {
+        let router = start_router();
+        let cases = [
+            (request(1, "tools/call", json!({ "name": "missing" })), -32602, json!({ "tool": "missing" })),
+            (request(2, "tools/call", json!({ "name": "strict" })), -32602, Value::Null),
+            (request(3, "tools/call", json!({})), -32602, Value::Null),
+            (request(4, "resources/read", json!({ "uri": "nothing" })), -32002, json!({ "uri": "nothing" })),
+            (request(5, "prompts/get", json!({ "name": "nothing" })), -32602, json!({ "prompt": "nothing" })),
+            (request(6, "completion/complete", json!({})), -32601, json!({ "method": "completion/complete" })),
+        ];
+        for (request, code, data) in cases {
+            let id = request.request.id;
+            let error = router.send(request).await.unwrap().expect_err("a protocol error");
+            assert_eq!(error.id, id);
+            assert_eq!(error.error.code, code, "{:?}", error);
+            assert_eq!(error.error.data.unwrap_or_default(), data);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_register_router_errors() {
+        let registry = ActorRouterRegistry::new().start();
+        let register = |router_id: &str| RegisterRouter { router_id: router_id.to_string(), router_addr: start_router(), capabilities: None };
+
+        assert_eq!(registry.send(register("failing")).await.unwrap(), Ok(()));
+        assert_eq!(registry.send(register("failing")).await.unwrap(), Err(McpEctorsError::RouterExists("failing".to_string())));
+        let error = registry.send(register("not_allowed")).await.unwrap().unwrap_err();
+        assert_eq!(error, McpEctorsError::InvalidRouterId("not_allowed".to_string()));
+        assert_eq!(error.to_error_data().code, -32006);
+    }
+}
@@ -0,0 +1,137 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use actix::Actor;
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use mcp_ectors::auth::{ApiKeyAuthenticator, Authentication};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::health::{Health, RouterHealth, RouterState};
+    use mcp_ectors::router::RouterServiceManager;
+    use serde_json::{json, Value};
+
+    use crate::common::routes;
+
+    // waits for the state of `router_id` to become `state`, and returns its last error
+    async fn wait_for(health: &Health, router_id: &str, state: RouterState) -> Option<String> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let router = health.router(router_id).unwrap();
+            if router.state == state {
+                return router.last_error;
+            }
+            assert!(Instant::now() < deadline, "{} stayed {:?}", router_id, router.state);
+            actix_rt::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_readiness_waits_for_the_scan_and_required_routers() {
+        let health = Health::new();
+        assert_eq!(health.readiness(), Err(vec!["the WASM routers are still being loaded".to_string()]));
+        health.set_scanned();
+        assert_eq!(health.readiness(), Ok(()));
+
+        health.set_required(vec!["counter".to_string()]);
+        assert_eq!(health.readiness(), Err(vec!["router counter is not loaded".to_string()]));
+        let counter = Arc::new(RouterHealth::new(RouterState::Loading));
+        health.add_router("counter", counter.clone());
+        assert_eq!(health.readiness(), Err(vec!["router counter is loading".to_string()]));
+        counter.ready();
+        assert_eq!(health.readiness(), Ok(()));
+        counter.degraded("trapped");
+        assert_eq!(health.readiness(), Ok(()));
+        counter.failed("gone");
+        assert_eq!(health.readiness(), Err(vec!["router counter failed".to_string()]));
+
+        // the last error stays when the router recovers
+        counter.ready();
+        let routers = health.routers();
+        assert_eq!(routers[0].state, RouterState::Ready);
+        assert_eq!(routers[0].last_error.as_deref(), Some("gone"));
+    }
+
+    #[actix_rt::test]
+    async fn test_endpoints() {
+        let keys = ApiKeyAuthenticator::new().with_hashed_key("alice", &ApiKeyAuthenticator::hash_key("alice-key")).unwrap();
+        let manager = RouterServiceManager::default(None).await;
+        let health = manager.health();
+        let app = test::init_service(App::new()
+            .app_data(Data::new(Authentication::new().with_authenticator(keys)))
+            .app_data(Data::new(health.clone()))
+            .configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+
+        // probes need no credentials, and no host name the origin policy knows
+        let response = test::call_service(&app, test::TestRequest::get().uri("/healthz").insert_header(("Host", "10.1.2.3:8080")).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = test::call_service(&app, test::TestRequest::get().uri("/readyz").to_request()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        health.set_scanned();
+        let response = test::call_service(&app, test::TestRequest::get().uri("/readyz").insert_header(("Host", "10.1.2.3:8080")).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        health.set_required(vec!["counter".to_string()]);
+        let response = test::call_service(&app, test::TestRequest::get().uri("/readyz").to_request()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body, json!({"status": "not ready", "problems": ["router counter is not loaded"]}));
+
+        // the routers are not for anyone to see
+        let response = test::call_service(&app, test::TestRequest::get().uri("/health/routers").to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = test::call_service(&app, test::TestRequest::get()
+            .uri("/health/routers")
+            .insert_header(("Authorization", "Bearer alice-key"))
+            .to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body, json!({"routers": [{"router_id": "system", "state": "ready"}]}));
+    }
+
+    #[actix_rt::test]
+    async fn test_wasm_router_states() {
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::copy("wasm/mcp_weather_api2.wasm", directory.path().join("weather.wasm")).unwrap();
+        std::fs::write(directory.path().join("broken.wasm"), b"not a component").unwrap();
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.health().set_required(vec!["weather".to_string()]);
+        manager.load_wasm_directory(directory.path().to_str().unwrap()).await.unwrap();
+        manager.health().set_scanned();
+
+        let routers = manager.health().routers();
+        let states: Vec<(&str, RouterState)> = routers.iter().map(|router| (router.router_id.as_str(), router.state)).collect();
+        assert_eq!(states, vec![("broken", RouterState::Failed), ("system", RouterState::Ready), ("weather", RouterState::Ready)]);
+        assert!(routers[0].last_error.as_deref().unwrap().contains("could not be read"));
+        assert_eq!(manager.health().readiness(), Ok(()));
+
+        manager.health().set_required(vec!["broken".to_string()]);
+        assert_eq!(manager.health().readiness(), Err(vec!["router broken failed".to_string()]));
+
+        manager.unload_wasm_directory(directory.path().to_str().unwrap()).await.unwrap();
+        assert_eq!(manager.health().routers().len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_health_export_is_polled() {
+        let directory = tempfile::tempdir().unwrap();
+        // its check fails every other time, starting with the first
+        std::fs::copy("tests/wasm/health_router.wat", directory.path().join("health.wasm")).unwrap();
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.set_health_interval(Duration::from_millis(100));
+        manager.load_wasm_directory(directory.path().to_str().unwrap()).await.unwrap();
+        let health = manager.health();
+
+        assert_eq!(wait_for(&health, "health", RouterState::Degraded).await.as_deref(), Some("backend unreachable"));
+        assert_eq!(wait_for(&health, "health", RouterState::Ready).await.as_deref(), Some("backend unreachable"));
+        wait_for(&health, "health", RouterState::Degraded).await;
+        // a degraded router still serves its clients
+        health.set_scanned();
+        health.set_required(vec!["health".to_string()]);
+        assert_eq!(health.readiness(), Ok(()));
+    }
+}
@@ -0,0 +1,110 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use actix::{Actor, Addr, Context, Handler};
+    use actix_web::{test, App};
+    use mcp_ectors::client::client_registry::{OpenSession, RegisterClient, RegisterRawRecipient};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::messages::{ClientMessage, RawClientMessage};
+    use mcp_ectors::router::RouterServiceManager;
+    use serde_json::{json, Value};
+
+    use crate::common::routes;
+
+    /// Stands in for the SSE stream of a session and collects everything sent to it
+    struct Session {
+        received: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl Actor for Session {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<ClientMessage> for Session {
+        type Result = ();
+
+        fn handle(&mut self, msg: ClientMessage, _ctx: &mut Self::Context) -> Self::Result {
+            self.received.lock().unwrap().push(serde_json::to_value(msg.0).unwrap());
+        }
+    }
+
+    impl Handler<RawClientMessage> for Session {
+        type Result = ();
+
+        fn handle(&mut self, msg: RawClientMessage, _ctx: &mut Self::Context) -> Self::Result {
+            self.received.lock().unwrap().push(msg.0);
+        }
+    }
+
+    async fn session(registry: &Addr<ClientRegistryActor>) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let received = Arc::new(Mutex::new(vec![]));
+        let session = Session { received: received.clone() }.start();
+        let client_id = registry.send(RegisterClient { recipient: session.clone().recipient() }).await.unwrap();
+        registry.send(RegisterRawRecipient { client_id, recipient: session.recipient() }).await.unwrap();
+        let token = registry.send(OpenSession { client_id, binding: Default::default() }).await.unwrap();
+        (token, received)
+    }
+
+    /// POSTs `body` for the session and returns what the session received
+    async fn post(body: Value) -> Vec<Value> {
+        let manager = RouterServiceManager::default(None).await;
+        let registry = ClientRegistryActor::new().start();
+        let (token, received) = session(&registry).await;
+        let app = test::init_service(App::new().configure(routes(registry, &manager))).await;
+
+        let request = test::TestRequest::post()
+            .uri(&format!("/messages/?session_id={}", token))
+            .set_json(body)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let received = received.lock().unwrap().clone();
+        received
+    }
+
+    #[actix_rt::test]
+    async fn test_ping_with_string_id() {
+        let received = post(json!({"jsonrpc": "2.0", "id": "ping-1", "method": "ping"})).await;
+        assert_eq!(received, vec![json!({"jsonrpc": "2.0", "id": "ping-1", "result": {}})]);
+    }
+
+    #[actix_rt::test]
+    async fn test_batch_gets_one_response_per_request() {
+        let received = post(json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "ping"},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "id": "tools", "method": "tools/list", "params": {}},
+            {"jsonrpc": "2.0", "id": 2},
+        ])).await;
+        assert_eq!(received.len(), 1);
+        let responses = received[0].as_array().expect("batch response");
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0], json!({"jsonrpc": "2.0", "id": 1, "result": {}}));
+        assert_eq!(responses[1]["id"], json!("tools"));
+        assert!(responses[1]["result"]["tools"].is_array());
+        assert_eq!(responses[2]["id"], json!(2));
+        assert_eq!(responses[2]["error"]["code"], json!(-32600));
+    }
+
+    #[actix_rt::test]
+    async fn test_notifications_and_client_responses_get_no_reply() {
+        let received = post(json!([
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": "abc"}},
+            {"jsonrpc": "2.0", "id": 5, "result": {}},
+        ])).await;
+        assert!(received.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_empty_batch_is_invalid() {
+        let received = post(json!([])).await;
+        assert_eq!(received, vec![json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600, "message": "Empty batch"}})]);
+    }
+}
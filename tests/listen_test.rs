@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use actix::Actor;
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::mcp::InitializationActor;
+    use mcp_ectors::messages::transport_messages::{StartTransport, StopTransport};
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::transport::listen::inherited_listeners;
+    use mcp_ectors::transport::sse_transport_actor::SseTransportConfig;
+    use mcp_ectors::transport::{ListenAddress, SseTransportActor};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn config(listen: Vec<ListenAddress>) -> SseTransportConfig {
+        SseTransportConfig {
+            port: 3000,
+            listen,
+            tls_cert: None,
+            tls_key: None,
+            tls_client_ca: None,
+            log_dir: "logs".into(),
+            log_file: "test-listen.log".into(),
+        }
+    }
+
+    async fn transport(config: SseTransportConfig) -> actix::Addr<SseTransportActor> {
+        let manager = RouterServiceManager::default(None).await;
+        SseTransportActor::new(
+            config,
+            ClientRegistryActor::new().start(),
+            manager.get_registry(),
+            InitializationActor::new(),
+            manager.get_list_prompts(),
+            manager.get_list_tools(),
+            manager.get_list_resources(),
+        ).start()
+    }
+
+    #[actix_rt::test]
+    async fn test_parse_listen_addresses() {
+        for (address, parsed) in [
+            ("127.0.0.1:3000", ListenAddress::Tcp("127.0.0.1:3000".to_string())),
+            ("[::1]:3000", ListenAddress::Tcp("[::1]:3000".to_string())),
+            ("[::]:3000", ListenAddress::Tcp("[::]:3000".to_string())),
+            ("localhost:3000", ListenAddress::Tcp("localhost:3000".to_string())),
+            ("unix:/run/mcp-ectors.sock", ListenAddress::Unix(PathBuf::from("/run/mcp-ectors.sock"))),
+        ] {
+            assert_eq!(address.parse::<ListenAddress>().unwrap(), parsed);
+            assert_eq!(parsed.to_string(), address);
+        }
+        for invalid in ["", "3000", "::1:3000", "localhost", "localhost:http", "unix:"] {
+            assert!(invalid.parse::<ListenAddress>().is_err(), "{}", invalid);
+        }
+        // without listen addresses only localhost is served
+        assert_eq!(config(vec![]).listen_addresses(), vec![ListenAddress::localhost(3000)]);
+    }
+
+    #[actix_rt::test]
+    async fn test_serves_unix_socket_and_several_addresses() {
+        let directory = tempfile::tempdir().unwrap();
+        let socket = directory.path().join("mcp-ectors.sock");
+        let transport = transport(config(vec![
+            ListenAddress::Unix(socket.clone()),
+            "127.0.0.1:3461".parse().unwrap(),
+            "127.0.0.1:3462".parse().unwrap(),
+        ])).await;
+        transport.send(StartTransport).await.unwrap().unwrap();
+
+        let mut stream = tokio::net::UnixStream::connect(&socket).await.unwrap();
+        stream.write_all(b"GET /sse HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        while !response.contains("/messages/?session_id=") {
+            let mut buffer = [0u8; 1024];
+            let read = stream.read(&mut buffer).await.unwrap();
+            assert!(read > 0, "connection closed after {}", response);
+            response.push_str(&String::from_utf8_lossy(&buffer[..read]));
+        }
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+
+        for port in [3461, 3462] {
+            let response = reqwest::get(format!("http://127.0.0.1:{}/sse", port)).await.unwrap();
+            assert_eq!(response.status(), 200);
+        }
+        transport.send(StopTransport).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_failing_bind_is_reported() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = ListenAddress::Tcp(taken.local_addr().unwrap().to_string());
+        assert!(transport(config(vec![address])).await.send(StartTransport).await.unwrap().is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_socket_activation_for_another_process_is_ignored() {
+        std::env::set_var("LISTEN_FDS", "1");
+        std::env::set_var("LISTEN_PID", (std::process::id() + 1).to_string());
+        assert!(inherited_listeners().unwrap().is_empty());
+        std::env::remove_var("LISTEN_FDS");
+        std::env::remove_var("LISTEN_PID");
+        assert!(inherited_listeners().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use actix::{Actor, Context, Handler};
+    use actix_web::{test, App};
+    use mcp_ectors::client::client_registry::{OpenSession, RegisterClient, RegisterRawRecipient};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::messages::{ClientMessage, RawClientMessage};
+    use mcp_ectors::router::RouterServiceManager;
+    use proptest::prelude::*;
+    use proptest::strategy::ValueTree;
+    use proptest::test_runner::{Config, TestRunner};
+    use serde_json::{json, Map, Value};
+
+    use crate::common::routes;
+
+    /// Stands in for the SSE stream of a session and collects everything sent to it
+    struct Session {
+        received: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl Actor for Session {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<ClientMessage> for Session {
+        type Result = ();
+
+        fn handle(&mut self, msg: ClientMessage, _ctx: &mut Self::Context) -> Self::Result {
+            self.received.lock().unwrap().push(serde_json::to_value(msg.0).unwrap());
+        }
+    }
+
+    impl Handler<RawClientMessage> for Session {
+        type Result = ();
+
+        fn handle(&mut self, msg: RawClientMessage, _ctx: &mut Self::Context) -> Self::Result {
+            self.received.lock().unwrap().push(msg.0);
+        }
+    }
+
+    const METHODS: &[&str] = &[
+        "initialize", "ping", "logging/setLevel",
+        "tools/list", "tools/call", "prompts/list", "prompts/get",
+        "resources/list", "resources/read", "resources/templates/list", "resources/subscribe", "resources/unsubscribe",
+        "notifications/initialized", "notifications/cancelled", "notifications/roots/list_changed",
+    ];
+
+    fn any_json() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            any::<f64>().prop_filter("finite", |f| f.is_finite()).prop_map(Value::from),
+            ".{0,12}".prop_map(Value::from),
+            Just(json!("system_all")),
+        ];
+        leaf.prop_recursive(4, 32, 6, |inner| prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+            prop::collection::hash_map(prop_oneof![Just("name".to_string()), Just("uri".to_string()), Just("level".to_string()), ".{0,6}"], inner, 0..6)
+                .prop_map(|map| Value::Object(map.into_iter().collect::<Map<String, Value>>())),
+        ])
+    }
+
+    /// A JSON-RPC looking message where any member may be missing or of the wrong type
+    fn message() -> impl Strategy<Value = Value> {
+        (
+            prop::option::of(prop_oneof![Just(json!("2.0")), any_json()]),
+            prop::option::of(any_json()),
+            prop::option::of(prop_oneof![prop::sample::select(METHODS).prop_map(Value::from), any_json()]),
+            prop::option::of(any_json()),
+            prop::option::of(any_json()),
+        )
+            .prop_map(|(jsonrpc, id, method, params, extra)| {
+                let mut message = Map::new();
+                let members = [("jsonrpc", jsonrpc), ("id", id), ("method", method), ("params", params), ("result", extra)];
+                for (key, value) in members {
+                    if let Some(value) = value {
+                        message.insert(key.to_string(), value);
+                    }
+                }
+                Value::Object(message)
+            })
+    }
+
+    fn body() -> impl Strategy<Value = Vec<u8>> {
+        prop_oneof![
+            4 => message().prop_map(|m| m.to_string().into_bytes()),
+            2 => prop::collection::vec(message(), 0..4).prop_map(|b| Value::Array(b).to_string().into_bytes()),
+            1 => any_json().prop_map(|v| v.to_string().into_bytes()),
+            1 => prop::collection::vec(any::<u8>(), 0..64),
+        ]
+    }
+
+    #[actix_rt::test]
+    async fn test_random_messages_never_panic() {
+        let manager = RouterServiceManager::default(None).await;
+        let registry = ClientRegistryActor::new().start();
+        let received = Arc::new(Mutex::new(vec![]));
+        let session = Session { received: received.clone() }.start();
+        let client_id = registry.send(RegisterClient { recipient: session.clone().recipient() }).await.unwrap();
+        registry.send(RegisterRawRecipient { client_id, recipient: session.recipient() }).await.unwrap();
+        let token = registry.send(OpenSession { client_id, binding: Default::default() }).await.unwrap();
+        let app = test::init_service(App::new().configure(routes(registry, &manager))).await;
+        let uri = format!("/messages/?session_id={}", token);
+
+        // the runner is driven by hand, proptest! cannot await the service
+        let mut runner = TestRunner::new(Config { cases: 256, ..Config::default() });
+        for _ in 0..runner.config().cases {
+            let body = body().new_tree(&mut runner).unwrap().current();
+            let request = test::TestRequest::post().uri(&uri).set_payload(body.clone()).insert_header(("content-type", "application/json")).to_request();
+            let response = test::call_service(&app, request).await;
+            assert!(response.status().is_success(), "{} for {}", response.status(), String::from_utf8_lossy(&body));
+        }
+
+        // the workers and actors behind the endpoint must still answer
+        received.lock().unwrap().clear();
+        let request = test::TestRequest::post().uri(&uri)
+            .set_json(json!([{"jsonrpc": "2.0", "id": 1, "method": "ping"}, {"jsonrpc": "2.0", "id": 2, "method": "resources/read", "params": {"uri": "system_all"}}]))
+            .to_request();
+        assert!(test::call_service(&app, request).await.status().is_success());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let received = received.lock().unwrap().clone();
+        let responses = received.last().and_then(|r| r.as_array()).expect("batch response");
+        assert_eq!(responses[0], json!({"jsonrpc": "2.0", "id": 1, "result": {}}));
+        assert!(responses[1]["result"]["contents"].is_array());
+    }
+
+    #[actix_rt::test]
+    async fn test_invalid_params_and_json_are_reported_to_the_session() {
+        let manager = RouterServiceManager::default(None).await;
+        let registry = ClientRegistryActor::new().start();
+        let received = Arc::new(Mutex::new(vec![]));
+        let session = Session { received: received.clone() }.start();
+        let client_id = registry.send(RegisterClient { recipient: session.clone().recipient() }).await.unwrap();
+        registry.send(RegisterRawRecipient { client_id, recipient: session.recipient() }).await.unwrap();
+        let token = registry.send(OpenSession { client_id, binding: Default::default() }).await.unwrap();
+        let app = test::init_service(App::new().configure(routes(registry, &manager))).await;
+        let uri = format!("/messages/?session_id={}", token);
+
+        for body in [
+            json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call"}).to_string(),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "prompts/get", "params": {"name": 5}}).to_string(),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "resources/read", "params": {"uri": "nosuchrouter_x"}}).to_string(),
+            "{\"jsonrpc\": \"2.0\", \"id\": 4,".to_string(),
+        ] {
+            let request = test::TestRequest::post().uri(&uri).set_payload(body).insert_header(("content-type", "application/json")).to_request();
+            assert!(test::call_service(&app, request).await.status().is_success());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let received = received.lock().unwrap().clone();
+        let codes: Vec<(Value, Value)> = received.iter().map(|r| (r["id"].clone(), r["error"]["code"].clone())).collect();
+        assert_eq!(codes, vec![
+            (json!(1), json!(-32602)),
+            (json!(2), json!(-32602)),
+            (json!(3), json!(-32602)),
+            (Value::Null, json!(-32700)),
+        ]);
+    }
+}
@@ -0,0 +1,126 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix::Actor;
+    use actix_web::body::MessageBody;
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::limits::{Caller, Limit, PayloadLimiter, RateLimiter, RateLimits};
+    use mcp_ectors::metrics::{render, Metrics, RequestLabels};
+    use mcp_ectors::router::RouterServiceManager;
+    use serde_json::{json, Value};
+
+    use crate::common::{next_event, routes};
+
+    fn labels(method: &str, router: &str, tool: &str) -> RequestLabels {
+        RequestLabels { method: method.to_string(), router: router.to_string(), tool: tool.to_string() }
+    }
+
+    #[actix_rt::test]
+    async fn test_labels_are_bounded() {
+        let metrics = Metrics::new();
+        metrics.add_router("counter", vec!["increment".to_string()]);
+
+        assert_eq!(metrics.request_labels("tools/call", Some("counter"), Some("counter_increment")), labels("tools/call", "counter", ""));
+        assert_eq!(metrics.request_labels("made/up", None, None), labels("other", "", ""));
+        assert_eq!(metrics.request_labels("tools/call", Some("nothere"), Some("nothere_x")), labels("tools/call", "unknown", ""));
+
+        metrics.set_tool_labels(true);
+        assert_eq!(metrics.request_labels("tools/call", Some("counter"), Some("counter_increment")), labels("tools/call", "counter", "increment"));
+        assert_eq!(metrics.request_labels("tools/call", Some("counter"), Some("counter_anything")), labels("tools/call", "counter", ""));
+
+        metrics.remove_router("counter");
+        assert_eq!(metrics.request_labels("tools/call", Some("counter"), Some("counter_increment")), labels("tools/call", "unknown", ""));
+    }
+
+    #[actix_rt::test]
+    async fn test_prometheus_text_format() {
+        let metrics = Metrics::new();
+        metrics.record_request(labels("tools/call", "counter", ""), Duration::from_millis(20), None);
+        metrics.record_request(labels("tools/call", "counter", ""), Duration::from_secs(2), Some(-32001));
+        metrics.event_dropped();
+        let limiter = RateLimiter::new(RateLimits::new().with_global_limit(Limit::rate(5.0, 5)).with_principal_limit(Limit::rate(5.0, 5)));
+        let _permit = limiter.acquire(Caller::Session(1), None, None).unwrap();
+        let _other = limiter.acquire(Caller::Session(2), None, None).unwrap();
+
+        let text = render(&metrics, &limiter, &PayloadLimiter::default());
+        for line in [
+            "# TYPE mcp_requests_total counter",
+            r#"mcp_requests_total{method="tools/call",router="counter"} 2"#,
+            r#"mcp_request_errors_total{method="tools/call",router="counter",code="-32001"} 1"#,
+            r#"mcp_request_duration_seconds_bucket{method="tools/call",router="counter",le="0.025"} 1"#,
+            r#"mcp_request_duration_seconds_bucket{method="tools/call",router="counter",le="2.5"} 2"#,
+            r#"mcp_request_duration_seconds_bucket{method="tools/call",router="counter",le="+Inf"} 2"#,
+            r#"mcp_request_duration_seconds_count{method="tools/call",router="counter"} 2"#,
+            "mcp_sse_events_dropped_total 1",
+            r#"mcp_rate_limit_requests_total{limit="global",outcome="allowed"} 2"#,
+            r#"mcp_rate_limit_in_flight{limit="global"} 2"#,
+            // anonymous sessions share one series
+            r#"mcp_rate_limit_requests_total{limit="session",outcome="allowed"} 2"#,
+            r#"mcp_payload_limit_max{limit="body"} 4194304"#,
+        ] {
+            assert!(text.lines().any(|l| l == line), "{} missing from\n{}", line, text);
+        }
+        assert!(!text.contains("session:"), "{}", text);
+    }
+
+    #[actix_rt::test]
+    async fn test_endpoint_counts_requests_and_sessions() {
+        let manager = RouterServiceManager::default(None).await;
+        let metrics = manager.metrics();
+        let app = test::init_service(App::new().app_data(Data::new(metrics.clone())).configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").to_request()).await;
+        let mut body = Box::pin(response.into_body().boxed());
+        let messages = next_event(&mut body).await;
+        assert_eq!(metrics.active_sessions(), 1);
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "ping"},
+            {"jsonrpc": "2.0", "id": 2, "method": "made/up"},
+            {"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "system_nothere"}},
+            {"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {"name": "random_tool"}},
+        ]);
+        test::call_service(&app, test::TestRequest::post().uri(&messages).set_json(batch).to_request()).await;
+        let replies: Value = serde_json::from_str(&next_event(&mut body).await).unwrap();
+        assert_eq!(replies.as_array().unwrap().len(), 4);
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+        assert!(response.status().is_success());
+        assert!(response.headers().get("content-type").unwrap().to_str().unwrap().starts_with("text/plain"));
+        let text = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+        for line in [
+            r#"mcp_requests_total{method="ping"} 1"#,
+            r#"mcp_requests_total{method="other"} 1"#,
+            r#"mcp_request_errors_total{method="other",code="-32601"} 1"#,
+            r#"mcp_requests_total{method="tools/call",router="system"} 1"#,
+            r#"mcp_requests_total{method="tools/call",router="unknown"} 1"#,
+            "mcp_sse_sessions_active 1",
+        ] {
+            assert!(text.lines().any(|l| l == line), "{} missing from\n{}", line, text);
+        }
+
+        drop(body);
+        assert_eq!(metrics.active_sessions(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_wasm_router_stats() {
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.load_wasm_directory("wasm").await.unwrap();
+        let wasm = manager.metrics().wasm_routers();
+        assert_eq!(wasm.len(), 1);
+        let (router_id, stats) = &wasm[0];
+        assert_eq!(router_id, "mcpweatherapi2");
+        assert_eq!(stats.instantiation().count, 1);
+        assert!(stats.fuel_consumed() > 0);
+        assert!(stats.memory_high_water() > 0);
+        assert_eq!(stats.queue_depth(), 0);
+
+        manager.unload_wasm_directory("wasm").await.unwrap();
+        assert!(manager.metrics().wasm_routers().is_empty());
+    }
+}
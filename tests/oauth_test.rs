@@ -0,0 +1,132 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use actix::Actor;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::web::{self, Data};
+    use actix_web::{test, App, HttpRequest, HttpResponse, HttpServer};
+    use mcp_ectors::auth::{AuthMethod, Authentication, IntrospectionAuthenticator, Principal, ProtectedResource, ScopeMap};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::router::RouterServiceManager;
+    use serde_json::{json, Value};
+
+    use crate::common::{next_event, routes};
+
+    const RESOURCE: &str = "https://mcp.example.com";
+    const AUTHORIZATION_SERVER: &str = "https://auth.example.com";
+
+    /// Stands in for the authorization server, knows `dave-token`, `erin-token` and `foreign-token`
+    async fn introspect(req: HttpRequest, form: web::Form<HashMap<String, String>>) -> HttpResponse {
+        if req.headers().get("authorization").is_none() {
+            return HttpResponse::Unauthorized().finish();
+        }
+        let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 600;
+        let response = match form.get("token").map(|token| token.as_str()) {
+            Some("dave-token") => json!({ "active": true, "sub": "dave", "aud": "mcp-ectors", "scope": "mcp:system", "exp": exp }),
+            Some("erin-token") => json!({ "active": true, "sub": "erin", "aud": ["mcp-ectors"], "scope": "mcp:counter", "exp": exp }),
+            Some("foreign-token") => json!({ "active": true, "sub": "dave", "aud": "another-server", "exp": exp }),
+            _ => json!({ "active": false }),
+        };
+        HttpResponse::Ok().json(response)
+    }
+
+    /// Starts the stub authorization server and returns its introspection endpoint
+    fn start_introspection() -> String {
+        let server = HttpServer::new(|| App::new().route("/introspect", web::post().to(introspect)))
+            .workers(1)
+            .bind("127.0.0.1:0")
+            .unwrap();
+        let addr = server.addrs()[0];
+        actix_rt::spawn(server.run());
+        format!("http://{}/introspect", addr)
+    }
+
+    fn authentication(endpoint: &str) -> Authentication {
+        let scope_map = ScopeMap::new()
+            .with_scope("mcp:system", vec!["system".to_string()])
+            .with_scope("mcp:counter", vec!["counter".to_string()]);
+        Authentication::new()
+            .with_authenticator(IntrospectionAuthenticator::new(endpoint).with_client_credentials("mcp-ectors", Some("secret")).with_audience("mcp-ectors"))
+            .with_protected_resource(ProtectedResource::new(RESOURCE, vec![AUTHORIZATION_SERVER.to_string()]).with_scopes_supported(scope_map.scopes()))
+            .with_scope_map(scope_map)
+    }
+
+    #[actix_rt::test]
+    async fn test_permissions_of_scopes() {
+        let principal = |permissions: Option<Vec<&str>>| Principal {
+            subject: "dave".to_string(),
+            method: AuthMethod::Jwt,
+            scopes: vec![],
+            claims: Value::Null,
+            permissions: permissions.map(|permissions| permissions.into_iter().map(str::to_string).collect()),
+        };
+        assert!(principal(None).may_use("counter_increment"));
+        assert!(principal(Some(vec!["*"])).may_use("counter_increment"));
+        assert!(principal(Some(vec!["counter"])).may_use("counter_increment"));
+        assert!(principal(Some(vec!["counter_increment"])).may_use("counter_increment"));
+        assert!(!principal(Some(vec!["counter_decrement"])).may_use("counter_increment"));
+        assert!(!principal(Some(vec!["count"])).may_use("counter_increment"));
+        assert!(!principal(Some(vec![])).may_use("counter_increment"));
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"# scopes\nmcp:read = system, counter_get\n\nmcp:admin = *\n").unwrap();
+        let scope_map = ScopeMap::from_file(file.path()).unwrap();
+        assert_eq!(scope_map.scopes(), vec!["mcp:admin", "mcp:read"]);
+        assert_eq!(scope_map.permissions(&["mcp:read".to_string(), "unknown".to_string()]), vec!["counter_get", "system"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_resource_server() {
+        let endpoint = start_introspection();
+        let manager = RouterServiceManager::default(None).await;
+        let app = test::init_service(App::new().app_data(Data::new(authentication(&endpoint))).configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+
+        // the metadata needs no token
+        let metadata: Value = test::call_and_read_body_json(&app, test::TestRequest::get().uri("/.well-known/oauth-protected-resource").to_request()).await;
+        assert_eq!(metadata, json!({
+            "resource": RESOURCE,
+            "authorization_servers": [AUTHORIZATION_SERVER],
+            "scopes_supported": ["mcp:counter", "mcp:system"],
+            "bearer_methods_supported": ["header"],
+        }));
+
+        let challenge = format!("Bearer resource_metadata=\"{}/.well-known/oauth-protected-resource\"", RESOURCE);
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers().get("www-authenticate").unwrap().to_str().unwrap(), challenge);
+        for token in ["expired-token", "foreign-token"] {
+            let response = test::call_service(&app, test::TestRequest::get().uri("/sse").insert_header(("Authorization", format!("Bearer {}", token))).to_request()).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", token);
+            assert_eq!(response.headers().get("www-authenticate").unwrap().to_str().unwrap(), format!("{}, error=\"invalid_token\"", challenge));
+        }
+
+        // the scopes decide which routers a caller may use
+        let read_system = json!({"jsonrpc": "2.0", "id": 1, "method": "resources/read", "params": {"uri": "system_all"}});
+        for (token, allowed) in [("dave-token", true), ("erin-token", false)] {
+            let response = test::call_service(&app, test::TestRequest::get().uri("/sse").insert_header(("Authorization", format!("Bearer {}", token))).to_request()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let mut body = Box::pin(response.into_body().boxed());
+            let messages = next_event(&mut body).await;
+            let request = test::TestRequest::post().uri(&messages).insert_header(("Authorization", format!("Bearer {}", token))).set_json(read_system.clone()).to_request();
+            assert_eq!(test::call_service(&app, request).await.status(), StatusCode::OK);
+            let reply: Value = serde_json::from_str(&next_event(&mut body).await).unwrap();
+            assert_eq!(reply.get("result").is_some(), allowed, "{}: {}", token, reply);
+            if !allowed {
+                assert_eq!(reply["error"]["code"], json!(-32008));
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_unreachable_introspection_is_unavailable() {
+        let manager = RouterServiceManager::default(None).await;
+        let app = test::init_service(App::new().app_data(Data::new(authentication("http://127.0.0.1:9/introspect"))).configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").insert_header(("Authorization", "Bearer dave-token")).to_request()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,117 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+    use actix_web::body::MessageBody;
+    use actix_web::http::header;
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use mcp_ectors::auth::{ApiKeyAuthenticator, Authentication};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::transport::{ListenAddress, OriginPolicy};
+    use serde_json::{json, Value};
+
+    use crate::common::routes;
+
+    const AGENT_UI: &str = "https://agent.example.com";
+
+    #[actix_rt::test]
+    async fn test_allowed_hosts() {
+        let policy = OriginPolicy::new();
+        for host in ["localhost", "localhost:3000", "127.0.0.1:3000", "127.0.0.2", "[::1]:3000", "::1"] {
+            assert!(policy.allows_host(host), "{}", host);
+        }
+        for host in ["evil.example:3000", "localhost.evil.example", "10.0.0.5:3000", "0.0.0.0:3000"] {
+            assert!(!policy.allows_host(host), "{}", host);
+        }
+
+        let policy = OriginPolicy::new().with_allowed_host("mcp.example.com").with_listen_hosts(&[
+            "10.0.0.5:3000".parse::<ListenAddress>().unwrap(),
+            "[fd00::5]:3000".parse::<ListenAddress>().unwrap(),
+            "0.0.0.0:3000".parse::<ListenAddress>().unwrap(),
+            "[::]:3000".parse::<ListenAddress>().unwrap(),
+            "unix:/run/mcp-ectors.sock".parse::<ListenAddress>().unwrap(),
+        ]);
+        for host in ["MCP.example.com:443", "10.0.0.5:3000", "[fd00::5]:3000"] {
+            assert!(policy.allows_host(host), "{}", host);
+        }
+        // listening on every interface does not say which names clients use
+        assert!(!policy.allows_host("0.0.0.0:3000") && !policy.allows_host("[::]:3000"));
+        assert!(OriginPolicy::new().with_allowed_host("*").allows_host("evil.example"));
+    }
+
+    #[actix_rt::test]
+    async fn test_allowed_origins() {
+        assert!(!OriginPolicy::new().allows_origin(AGENT_UI));
+        let policy = OriginPolicy::new().with_allowed_origin(AGENT_UI);
+        assert!(policy.allows_origin(AGENT_UI));
+        assert!(policy.allows_origin("HTTPS://agent.example.com/"));
+        assert!(!policy.allows_origin("http://agent.example.com"));
+        assert!(!policy.allows_origin("https://agent.example.com:8443"));
+        assert!(!policy.allows_origin("null"));
+        assert!(OriginPolicy::new().with_allowed_origin("*").allows_origin("https://anything.example"));
+    }
+
+    #[actix_rt::test]
+    async fn test_requests_are_checked_against_the_origin_policy() {
+        let manager = RouterServiceManager::default(None).await;
+        let keys = ApiKeyAuthenticator::new().with_hashed_key("alice", &ApiKeyAuthenticator::hash_key("alice-key")).unwrap();
+        let app = test::init_service(App::new()
+            .app_data(Data::new(OriginPolicy::new().with_allowed_origin(AGENT_UI)))
+            .app_data(Data::new(Authentication::new().with_authenticator(keys)))
+            .configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+        let sse = |host: &str, origin: Option<&str>| {
+            let mut request = test::TestRequest::get().uri("/sse").insert_header((header::HOST, host)).insert_header(("X-API-Key", "alice-key"));
+            if let Some(origin) = origin {
+                request = request.insert_header((header::ORIGIN, origin));
+            }
+            request.to_request()
+        };
+
+        // DNS rebinding: the page of evil.example resolves to 127.0.0.1 but still names its own host
+        let response = test::call_service(&app, sse("evil.example:3000", None)).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body: Value = serde_json::from_slice(&response.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(body["error"]["code"], json!(-32008));
+        // other web pages
+        let response = test::call_service(&app, sse("localhost:3000", Some("https://evil.example"))).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // clients that are not browsers, and the allowed web page
+        assert_eq!(test::call_service(&app, sse("localhost:3000", None)).await.status(), StatusCode::OK);
+        let response = test::call_service(&app, sse("127.0.0.1:3000", Some(AGENT_UI))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), AGENT_UI);
+        assert_eq!(response.headers().get(header::VARY).unwrap(), "Origin");
+
+        // preflight requests carry no credentials, the allowed page gets an answer and nobody else
+        let preflight = |origin: &str| test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/messages/?session_id=1")
+            .insert_header((header::HOST, "localhost:3000"))
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type, x-api-key"))
+            .to_request();
+        let response = test::call_service(&app, preflight(AGENT_UI)).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let headers = response.headers();
+        assert_eq!(headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), AGENT_UI);
+        assert!(headers.get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap().to_str().unwrap().contains("POST"));
+        assert!(headers.get(header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap().to_str().unwrap().contains("x-api-key"));
+        assert_eq!(test::call_service(&app, preflight("https://evil.example")).await.status(), StatusCode::FORBIDDEN);
+
+        // the allowed page can read why it was not let in
+        let request = test::TestRequest::get().uri("/sse")
+            .insert_header((header::HOST, "localhost:3000"))
+            .insert_header((header::ORIGIN, AGENT_UI))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), AGENT_UI);
+        assert_eq!(response.headers().get(header::ACCESS_CONTROL_EXPOSE_HEADERS).unwrap(), "www-authenticate");
+    }
+}
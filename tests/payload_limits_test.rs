@@ -0,0 +1,146 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix::Actor;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::limits::payload_limits::{json_len, parse_payload_limit};
+    use mcp_ectors::limits::{PayloadLimit, PayloadLimiter, PayloadLimits};
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::McpEctorsError;
+    use serde_json::{json, Value};
+
+    use crate::common::{routes, try_next_event};
+
+    fn rejected(limiter: &PayloadLimiter, limit: PayloadLimit) -> u64 {
+        limiter.snapshot().into_iter().find(|state| state.limit == limit).unwrap().rejected
+    }
+
+    #[actix_rt::test]
+    async fn test_limits_of_messages() {
+        let limiter = PayloadLimiter::new(PayloadLimits::new()
+            .with_limit(PayloadLimit::Depth, 3)
+            .with_limit(PayloadLimit::String, 5)
+            .with_limit(PayloadLimit::Array, 2));
+        assert!(limiter.check_raw(br#"{"a": [{"b": "[[[[{{{{"}]}"#).is_ok());
+        assert!(limiter.check_raw(br#"{"a": "\"[[[["}"#).is_ok());
+        match limiter.check_raw(br#"{"a": [[{"b": 1}]]}"#) {
+            Err(McpEctorsError::NestingTooDeep { size: 4, max: 3 }) => {}
+            other => panic!("{:?}", other),
+        }
+
+        assert!(limiter.check_value(&json!({"a": ["hello", 1], "b": null})).is_ok());
+        for (value, limit) in [
+            (json!({"a": "hello!"}), PayloadLimit::String),
+            (json!({"longer": 1}), PayloadLimit::String),
+            (json!({"a": [1, 2, 3]}), PayloadLimit::Array),
+            (json!({"a": 1, "b": 2, "c": 3}), PayloadLimit::Array),
+        ] {
+            match limiter.check_value(&value) {
+                Err(error) => assert_eq!(error.payload_limit().map(|(hit, _, _)| hit), Some(limit), "{}", value),
+                other => panic!("{} gave {:?}", value, other),
+            }
+        }
+        assert_eq!(rejected(&limiter, PayloadLimit::Depth), 1);
+        assert_eq!(rejected(&limiter, PayloadLimit::String), 2);
+        assert_eq!(rejected(&limiter, PayloadLimit::Array), 2);
+        assert_eq!(rejected(&limiter, PayloadLimit::Body), 0);
+
+        let error = McpEctorsError::BodyTooLarge { size: 10, max: 5 }.to_error_data();
+        assert_eq!(error.code, -32012);
+        assert_eq!(error.message, "Request body of 10 bytes exceeds the limit of 5 bytes");
+        assert_eq!(error.data, Some(json!({"limit": "body", "size": 10, "max": 5})));
+        // every limit has an error, code and message of its own
+        let errors: Vec<_> = PayloadLimit::ALL.into_iter().map(|limit| McpEctorsError::payload_too_large(limit, 10, 5).to_error_data()).collect();
+        let codes: Vec<i32> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, vec![-32012, -32013, -32014, -32015, -32016, -32017]);
+        let messages: std::collections::HashSet<&String> = errors.iter().map(|error| &error.message).collect();
+        assert_eq!(messages.len(), PayloadLimit::ALL.len());
+        assert_eq!(McpEctorsError::payload_too_large(PayloadLimit::Queue, 10, 5).payload_limit(), Some((PayloadLimit::Queue, 10, 5)));
+
+        assert_eq!(parse_payload_limit("queue=1024").unwrap(), (PayloadLimit::Queue, 1024));
+        assert!(parse_payload_limit("queue").is_err() && parse_payload_limit("bogus=1").is_err() && parse_payload_limit("body=-1").is_err());
+        assert_eq!(json_len(&json!({"a": [1, "b"]})), json!({"a": [1, "b"]}).to_string().len());
+    }
+
+    #[actix_rt::test]
+    async fn test_requests_and_results_over_the_limits_are_rejected() {
+        let manager = RouterServiceManager::default(None).await;
+        let limiter = PayloadLimiter::new(PayloadLimits::new()
+            .with_limit(PayloadLimit::Body, 1024)
+            .with_limit(PayloadLimit::Depth, 8)
+            .with_limit(PayloadLimit::String, 100)
+            .with_limit(PayloadLimit::Result, 200));
+        let app = test::init_service(App::new().app_data(Data::new(limiter.clone())).configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").to_request()).await;
+        let mut body = Box::pin(response.into_body().boxed());
+        let messages = try_next_event(&mut body).await.unwrap();
+        let post = |body: Value| test::TestRequest::post().uri(&messages).set_json(body).to_request();
+        let reply = |event: String| serde_json::from_str::<Value>(&event).unwrap();
+
+        let response = test::call_service(&app, post(json!({"jsonrpc": "2.0", "id": 1, "method": "ping", "params": {"padding": "x".repeat(2000)}}))).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let error: Value = serde_json::from_slice(&response.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(error["error"]["code"], json!(-32012));
+        assert_eq!(error["error"]["data"]["limit"], json!("body"));
+
+        let mut nested = json!(1);
+        for _ in 0..10 {
+            nested = json!([nested]);
+        }
+        test::call_service(&app, post(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "counter_increment", "arguments": {"a": nested}}}))).await;
+        let error = reply(try_next_event(&mut body).await.unwrap());
+        assert_eq!(error["error"]["code"], json!(-32013));
+        assert_eq!(error["error"]["data"]["limit"], json!("depth"));
+
+        // the arguments never reach the router
+        test::call_service(&app, post(json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "counter_increment", "arguments": {"a": "x".repeat(101)}}}))).await;
+        let error = reply(try_next_event(&mut body).await.unwrap());
+        assert_eq!(error["id"], json!(3));
+        assert_eq!(error["error"]["code"], json!(-32014));
+        assert_eq!(error["error"]["data"]["limit"], json!("string"));
+
+        test::call_service(&app, post(json!({"jsonrpc": "2.0", "id": 4, "method": "resources/read", "params": {"uri": "system_all"}}))).await;
+        let error = reply(try_next_event(&mut body).await.unwrap());
+        assert_eq!(error["id"], json!(4));
+        assert_eq!(error["error"]["code"], json!(-32016));
+        assert_eq!(error["error"]["data"]["limit"], json!("result"));
+
+        // small enough messages still get through
+        test::call_service(&app, post(json!({"jsonrpc": "2.0", "id": 5, "method": "ping"}))).await;
+        assert_eq!(reply(try_next_event(&mut body).await.unwrap()), json!({"jsonrpc": "2.0", "id": 5, "result": {}}));
+        for limit in [PayloadLimit::Body, PayloadLimit::Depth, PayloadLimit::String, PayloadLimit::Result] {
+            assert_eq!(rejected(&limiter, limit), 1, "{}", limit);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_streams_of_clients_that_do_not_read_are_closed() {
+        let manager = RouterServiceManager::default(None).await;
+        let limiter = PayloadLimiter::new(PayloadLimits::new().with_limit(PayloadLimit::Queue, 2000));
+        let app = test::init_service(App::new().app_data(Data::new(limiter.clone())).configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").to_request()).await;
+        let mut body = Box::pin(response.into_body().boxed());
+        let messages = try_next_event(&mut body).await.unwrap();
+
+        // the client does not read while the responses pile up
+        for id in 0..100 {
+            let ping = json!({"jsonrpc": "2.0", "id": id, "method": "ping"});
+            test::call_service(&app, test::TestRequest::post().uri(&messages).set_json(ping).to_request()).await;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rejected(&limiter, PayloadLimit::Queue) >= 1);
+
+        let mut received = 0;
+        while tokio::time::timeout(Duration::from_secs(2), try_next_event(&mut body)).await.unwrap().is_some() {
+            received += 1;
+        }
+        assert!(received > 0 && received < 100, "{}", received);
+    }
+}
@@ -0,0 +1,208 @@
+#[cfg(test)]
+#[path = "../tests/mock_router.rs"]
+mod mock_router;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix::Actor;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use mcp_ectors::auth::policy::glob_match;
+    use mcp_ectors::auth::{AccessControl, ApiKeyAuthenticator, AuthMethod, Authentication, Policy, Principal};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_spec::protocol::{Implementation, InitializeResult, ServerCapabilities};
+    use mcp_spec::Tool;
+    use serde_json::{json, Value};
+
+    use crate::common::{next_event, routes};
+    use crate::mock_router::MockRouter;
+
+    fn mock_router() -> MockRouter {
+        let initialize_result = InitializeResult {
+            protocol_version: "2024-11-05".to_string(),
+            capabilities: ServerCapabilities { tools: None, resources: None, prompts: None },
+            server_info: Implementation { name: "mock".to_string(), version: "0.1.0".to_string() },
+            instructions: None,
+        };
+        let tool = |name: &str| Tool { name: name.to_string(), description: name.to_string(), input_schema: json!({}) };
+        MockRouter::new(initialize_result, vec![tool("tool1"), tool("tool2")])
+    }
+
+    fn policy(value: Value) -> Policy {
+        let policy: Policy = serde_json::from_value(value).unwrap();
+        policy.validate().unwrap();
+        policy
+    }
+
+    fn principal(subject: &str, scopes: &[&str]) -> Principal {
+        Principal {
+            subject: subject.to_string(),
+            method: AuthMethod::Jwt,
+            scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            claims: Value::Null,
+            permissions: None,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_glob_match() {
+        assert!(glob_match("finance_*", "finance_report"));
+        assert!(glob_match("finance_*", "finance_"));
+        assert!(!glob_match("finance_*", "financial_report"));
+        assert!(glob_match("system_all", "system_all"));
+        assert!(!glob_match("system_all", "system_all2"));
+        assert!(glob_match("*_delete*", "finance_delete_all"));
+        assert!(glob_match("hr_tool?", "hr_tool1"));
+        assert!(!glob_match("hr_tool?", "hr_tool12"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+    }
+
+    #[actix_rt::test]
+    async fn test_policy_rules() {
+        let policy = policy(json!({
+            "default_roles": ["guest"],
+            "principals": { "alice": ["finance"], "bob": ["finance", "auditor"] },
+            "roles": {
+                "guest": { "allow": ["system_*"] },
+                "finance": { "allow": ["finance_*", "system_all"], "deny": ["finance_delete*"] },
+                "auditor": { "allow": ["*"], "deny": ["hr_*"] },
+                "mcp:hr": { "allow": ["hr_*"] }
+            }
+        }));
+        let alice = principal("alice", &[]);
+        assert!(policy.allows(Some(&alice), "finance_report"));
+        assert!(policy.allows(Some(&alice), "system_all"));
+        assert!(!policy.allows(Some(&alice), "finance_delete_all"));
+        assert!(!policy.allows(Some(&alice), "hr_salaries"));
+        // a deny of one role wins over the allow of another
+        let bob = principal("bob", &[]);
+        assert!(policy.allows(Some(&bob), "counter_increment"));
+        assert!(!policy.allows(Some(&bob), "finance_delete_all"));
+        assert!(!policy.allows(Some(&bob), "hr_salaries"));
+        // scopes can name roles, unknown principals and anonymous sessions get the defaults
+        assert!(policy.allows(Some(&principal("carol", &["mcp:hr"])), "hr_salaries"));
+        assert!(!policy.allows(Some(&principal("carol", &["mcp:hr"])), "system_all"));
+        assert!(policy.allows(Some(&principal("dave", &["unknown"])), "system_all"));
+        assert!(!policy.allows(Some(&principal("dave", &[])), "finance_report"));
+        assert!(policy.allows(None, "system_all"));
+        assert!(!policy.allows(None, "finance_report"));
+
+        let typo: Policy = serde_json::from_value(json!({ "principals": { "alice": ["finanse"] }, "roles": { "finance": {} } })).unwrap();
+        assert!(typo.validate().is_err());
+        // without a policy everything is allowed
+        assert!(AccessControl::default().allows(None, "finance_delete_all"));
+    }
+
+    #[actix_rt::test]
+    async fn test_policy_file_is_reloaded() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("policy.json");
+        let write = |allow: &str| std::fs::write(&path, json!({ "default_roles": ["all"], "roles": { "all": { "allow": [allow] } } }).to_string()).unwrap();
+        write("finance_*");
+        let access = AccessControl::from_file(&path).unwrap();
+        assert!(access.allows(None, "finance_report"));
+        assert!(!access.allows(None, "hr_salaries"));
+
+        write("hr_*");
+        let mut waited = Duration::ZERO;
+        while !access.allows(None, "hr_salaries") && waited < Duration::from_secs(5) {
+            actix_rt::time::sleep(Duration::from_millis(50)).await;
+            waited += Duration::from_millis(50);
+        }
+        assert!(access.allows(None, "hr_salaries"));
+        assert!(!access.allows(None, "finance_report"));
+
+        // a broken file keeps the policy that was working
+        std::fs::write(&path, "{ not json").unwrap();
+        actix_rt::time::sleep(Duration::from_millis(300)).await;
+        assert!(access.allows(None, "hr_salaries"));
+    }
+
+    #[actix_rt::test]
+    async fn test_sessions_only_see_and_use_what_the_policy_allows() {
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.register_router::<MockRouter>("finance".to_string(), Box::new(mock_router())).await.unwrap();
+        manager.register_router::<MockRouter>("hr".to_string(), Box::new(mock_router())).await.unwrap();
+        let access = AccessControl::new(policy(json!({
+            "principals": { "alice": ["finance"] },
+            "roles": { "finance": { "allow": ["finance_*"], "deny": ["finance_tool2"] } }
+        })));
+        let keys = ApiKeyAuthenticator::new().with_hashed_key("alice", &ApiKeyAuthenticator::hash_key("alice-key")).unwrap();
+        let app = test::init_service(App::new()
+            .app_data(Data::new(Authentication::new().with_authenticator(keys)))
+            .app_data(Data::new(access.clone()))
+            .configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").insert_header(("X-API-Key", "alice-key")).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = Box::pin(response.into_body().boxed());
+        let messages = next_event(&mut body).await;
+        let mut id = 0;
+        let mut call = async |method: &str, params: Value| -> Value {
+            id += 1;
+            let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+            let request = test::TestRequest::post().uri(&messages).insert_header(("X-API-Key", "alice-key")).set_json(message).to_request();
+            assert_eq!(test::call_service(&app, request).await.status(), StatusCode::OK);
+            serde_json::from_str(&next_event(&mut body).await).unwrap()
+        };
+        let names = |reply: &Value, list: &str| -> Vec<String> {
+            reply["result"][list].as_array().unwrap().iter().map(|item| item["name"].as_str().unwrap().to_string()).collect()
+        };
+
+        let tools = call("tools/list", json!({})).await;
+        assert_eq!(names(&tools, "tools"), vec!["finance_tool1"]);
+        assert_eq!(names(&call("prompts/list", json!({})).await, "prompts"), vec!["finance_dummy_prompt"]);
+        // resources are checked by router id and uri, finance_echo://fixedresource here
+        assert_eq!(names(&call("resources/list", json!({})).await, "resources"), vec!["finance_resource_name"]);
+
+        assert!(call("tools/call", json!({ "name": "finance_tool1", "arguments": {} })).await.get("result").is_some());
+        for (method, params) in [
+            ("tools/call", json!({ "name": "finance_tool2", "arguments": {} })),
+            ("tools/call", json!({ "name": "hr_tool1", "arguments": {} })),
+            ("prompts/get", json!({ "name": "hr_dummy_prompt" })),
+            ("resources/read", json!({ "uri": "system_all" })),
+        ] {
+            let reply = call(method, params.clone()).await;
+            assert_eq!(reply["error"]["code"], json!(-32008), "{} {}: {}", method, params, reply);
+        }
+
+        // a new policy applies to the open session right away
+        access.set_policy(Some(policy(json!({
+            "principals": { "alice": ["finance"] },
+            "roles": { "finance": { "allow": ["finance_*", "hr_*", "system_all"] } }
+        }))));
+        let mut tools = names(&call("tools/list", json!({})).await, "tools");
+        tools.sort();
+        assert_eq!(tools, vec!["finance_tool1", "finance_tool2", "hr_tool1", "hr_tool2"]);
+        assert!(call("resources/read", json!({ "uri": "system_all" })).await.get("result").is_some());
+
+        // the catalogue only shows what the caller may use
+        access.set_policy(Some(policy(json!({
+            "principals": { "alice": ["finance"] },
+            "roles": { "finance": { "allow": ["finance_*", "system_all.json"], "deny": ["finance_tool2"] } }
+        }))));
+        let reply = call("resources/read", json!({ "uri": "system_all.json" })).await;
+        let catalogue: Value = serde_json::from_str(reply["result"]["contents"][0]["text"].as_str().unwrap()).unwrap();
+        let mut catalogued: Vec<String> = catalogue["routers"].as_array().unwrap().iter()
+            .flat_map(|router| ["tools", "prompts", "resources"].map(|list| router[list].clone()))
+            .flat_map(|items| items.as_array().unwrap().clone())
+            .map(|item| item["name"].as_str().unwrap().to_string())
+            .collect();
+        catalogued.sort();
+        assert_eq!(catalogued, vec![
+            "finance_dummy_prompt",
+            "finance_resource_name",
+            "finance_tool1",
+            "system_all resources, prompts, tools,... registered in this mcp multi router server as JSON",
+        ]);
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use actix::{Actor, Context, Handler};
+    use mcp_ectors::client::client_registry::RegisterClient;
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::messages::ClientMessage;
+    use mcp_ectors::router::ProgressReporter;
+    use mcp_spec::protocol::JsonRpcMessage;
+    use serde_json::{json, Value};
+
+    /// Stands in for an SSE session and remembers the params of every notification it receives
+    struct Collector {
+        received: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl Actor for Collector {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<ClientMessage> for Collector {
+        type Result = ();
+
+        fn handle(&mut self, msg: ClientMessage, _ctx: &mut Self::Context) -> Self::Result {
+            if let JsonRpcMessage::Notification(notification) = msg.0 {
+                assert_eq!(notification.method, "notifications/progress");
+                self.received.lock().unwrap().push(notification.params.unwrap());
+            }
+        }
+    }
+
+    async fn reporter(token: Value) -> (ProgressReporter, Arc<Mutex<Vec<Value>>>) {
+        let received = Arc::new(Mutex::new(vec![]));
+        let collector = Collector { received: received.clone() }.start();
+        let registry = ClientRegistryActor::new().start();
+        let client_id = registry.send(RegisterClient { recipient: collector.recipient() }).await.unwrap();
+        (ProgressReporter::new(token, client_id, registry), received)
+    }
+
+    #[actix_rt::test]
+    async fn test_progress_is_rate_limited_and_stops_after_finish() {
+        let (progress, received) = reporter(json!("token-1")).await;
+
+        progress.report(1.0, Some(10.0), Some("started".to_string()));
+        // too soon after the previous report
+        progress.report(2.0, Some(10.0), None);
+        // progress has to increase
+        progress.report(1.0, Some(10.0), None);
+        // completing the total is always reported
+        progress.report(10.0, Some(10.0), Some("done".to_string()));
+        progress.finish();
+        progress.report(11.0, None, None);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0], json!({"progressToken": "token-1", "progress": 1.0, "total": 10.0, "message": "started"}));
+        assert_eq!(received[1]["progress"], json!(10.0));
+        assert!(progress.is_finished());
+    }
+
+    #[actix_rt::test]
+    async fn test_reports_after_interval_are_sent() {
+        let (progress, received) = reporter(json!(42)).await;
+
+        progress.report(1.0, None, None);
+        tokio::time::sleep(mcp_ectors::router::progress::PROGRESS_MIN_INTERVAL + Duration::from_millis(20)).await;
+        progress.report(2.0, None, None);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[1], json!({"progressToken": 42, "progress": 2.0}));
+    }
+
+    #[test]
+    fn test_disabled_reporter_does_nothing() {
+        let progress = ProgressReporter::default();
+        assert!(progress.token().is_none());
+        progress.report(1.0, None, None);
+        assert!(progress.is_finished());
+    }
+}
@@ -0,0 +1,136 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix::Actor;
+    use actix_web::body::MessageBody;
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::limits::rate_limits::IN_FLIGHT_RETRY_AFTER;
+    use mcp_ectors::limits::{Caller, Limit, LimiterState, RateLimiter, RateLimits};
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::McpEctorsError;
+    use serde_json::{json, Value};
+
+    use crate::common::{next_event, routes};
+
+    fn retry_after(error: McpEctorsError) -> (String, Duration) {
+        match error {
+            McpEctorsError::RateLimited { limit, retry_after } => (limit, retry_after),
+            e => panic!("expected a rate limit error, got {:?}", e),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_parse_limit() {
+        assert_eq!("10/50".parse::<Limit>().unwrap(), Limit::rate(10.0, 50));
+        assert_eq!("2.5".parse::<Limit>().unwrap(), Limit::rate(2.5, 3));
+        assert_eq!("0.1".parse::<Limit>().unwrap(), Limit::rate(0.1, 1));
+        for invalid in ["", "fast", "0", "-1", "10/many"] {
+            assert!(invalid.parse::<Limit>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_token_bucket() {
+        let limiter = RateLimiter::new(RateLimits::new().with_principal_limit(Limit::rate(10.0, 2)));
+        let _first = limiter.acquire(Caller::Principal("alice"), None, None).unwrap();
+        let _second = limiter.acquire(Caller::Principal("alice"), None, None).unwrap();
+        let (limit, wait) = retry_after(limiter.acquire(Caller::Principal("alice"), None, None).err().unwrap());
+        assert_eq!(limit, "principal:alice");
+        assert!(wait > Duration::from_millis(50) && wait <= Duration::from_millis(100), "{:?}", wait);
+        // every principal has its own bucket
+        assert!(limiter.acquire(Caller::Principal("bob"), None, None).is_ok());
+
+        actix_rt::time::sleep(wait + Duration::from_millis(10)).await;
+        assert!(limiter.acquire(Caller::Principal("alice"), None, None).is_ok());
+        assert!(limiter.acquire(Caller::Principal("alice"), None, None).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_max_in_flight() {
+        let limiter = RateLimiter::new(RateLimits::new()
+            .with_router_limit("finance", Limit::in_flight(2))
+            .with_tool_limit("finance_report", Limit::in_flight(1)));
+        let report = limiter.acquire(Caller::Principal("alice"), Some("finance"), Some("finance_report")).unwrap();
+        let (limit, wait) = retry_after(limiter.acquire(Caller::Principal("bob"), Some("finance"), Some("finance_report")).err().unwrap());
+        assert_eq!((limit.as_str(), wait), ("tool:finance_report", IN_FLIGHT_RETRY_AFTER));
+        let other = limiter.acquire(Caller::Principal("bob"), Some("finance"), Some("finance_forecast")).unwrap();
+        assert_eq!(retry_after(limiter.acquire(Caller::Principal("carol"), Some("finance"), None).err().unwrap()).0, "router:finance");
+        // requests to other routers are not affected
+        assert!(limiter.acquire(Caller::Principal("carol"), Some("hr"), Some("hr_report")).is_ok());
+
+        drop(report);
+        assert!(limiter.acquire(Caller::Principal("bob"), Some("finance"), Some("finance_report")).is_ok());
+        drop(other);
+    }
+
+    #[actix_rt::test]
+    async fn test_rejected_requests_take_nothing_and_are_counted() {
+        let limiter = RateLimiter::new(RateLimits::new()
+            .with_global_limit(Limit::rate(0.001, 100))
+            .with_principal_limit(Limit::rate(0.001, 1))
+            .with_limit_for_principal("batch", Limit::rate(0.001, 3)));
+        drop(limiter.acquire(Caller::Session(1), None, None).unwrap());
+        assert_eq!(retry_after(limiter.acquire(Caller::Session(1), None, None).err().unwrap()).0, "session:1");
+        // another anonymous session has a bucket of its own
+        drop(limiter.acquire(Caller::Session(2), None, None).unwrap());
+        for _ in 0..3 {
+            drop(limiter.acquire(Caller::Principal("batch"), None, None).unwrap());
+        }
+
+        let snapshot = limiter.snapshot();
+        let keys: Vec<&str> = snapshot.iter().map(|state| state.key.as_str()).collect();
+        // anonymous sessions are summed up
+        assert_eq!(keys, vec!["global", "principal:batch", "session"]);
+        let LimiterState { tokens, in_flight, allowed, rejected, .. } = snapshot[0].clone();
+        // the rejected request did not take a token from the global bucket
+        assert!((tokens.unwrap() - 95.0).abs() < 0.01, "{:?}", tokens);
+        assert_eq!((in_flight, allowed, rejected), (0, 5, 0));
+        assert_eq!((snapshot[1].allowed, snapshot[1].rejected), (3, 0));
+        assert_eq!((snapshot[2].tokens, snapshot[2].allowed, snapshot[2].rejected), (None, 2, 1));
+
+        // new limits apply to existing buckets
+        limiter.set_limits(RateLimits::new());
+        assert!(limiter.acquire(Caller::Session(1), None, None).is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_flooding_clients_get_retry_after() {
+        let manager = RouterServiceManager::default(None).await;
+        let limiter = RateLimiter::new(RateLimits::new().with_principal_limit(Limit::rate(1.0, 3)));
+        let app = test::init_service(App::new().app_data(Data::new(limiter.clone())).configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").to_request()).await;
+        let mut body = Box::pin(response.into_body().boxed());
+        let messages = next_event(&mut body).await;
+        let pings: Vec<Value> = (1..=5).map(|id| json!({"jsonrpc": "2.0", "id": id, "method": "ping"})).collect();
+        let request = test::TestRequest::post().uri(&messages).set_json(Value::Array(pings)).to_request();
+        test::call_service(&app, request).await;
+
+        let replies: Value = serde_json::from_str(&next_event(&mut body).await).unwrap();
+        let replies = replies.as_array().unwrap();
+        assert_eq!(replies.iter().filter(|reply| reply.get("result").is_some()).count(), 3);
+        let limited: Vec<&Value> = replies.iter().filter(|reply| reply.get("error").is_some()).collect();
+        assert_eq!(limited.len(), 2);
+        for reply in limited {
+            assert_eq!(reply["error"]["code"], json!(-32009));
+            assert!(reply["error"]["data"]["limit"].as_str().unwrap().starts_with("session:"), "{}", reply);
+            let retry_after_ms = reply["error"]["data"]["retryAfterMs"].as_u64().unwrap();
+            assert!(retry_after_ms > 0 && retry_after_ms <= 1000, "{}", reply);
+        }
+        assert_eq!(limiter.snapshot()[0].rejected, 2);
+
+        // another anonymous session is not limited by the first one
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").to_request()).await;
+        let mut body = Box::pin(response.into_body().boxed());
+        let messages = next_event(&mut body).await;
+        let request = test::TestRequest::post().uri(&messages).set_json(json!({"jsonrpc": "2.0", "id": 6, "method": "ping"})).to_request();
+        test::call_service(&app, request).await;
+        let reply: Value = serde_json::from_str(&next_event(&mut body).await).unwrap();
+        assert!(reply.get("result").is_some(), "{}", reply);
+    }
+}
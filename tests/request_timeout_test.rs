@@ -0,0 +1,216 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+    use std::time::Duration;
+
+    use actix::Actor;
+    use mcp_ectors::client::client_registry::{RegisterClient, SetClientCapabilities};
+    use mcp_ectors::client::{ClientHandle, ClientRegistryActor};
+    use mcp_ectors::messages::{ClientMessage, TransportRequest};
+    use mcp_ectors::router::{router::ResponseFuture, RequestContext, RequestTimeouts, Router, RouterActor, RouterServiceManager};
+    use mcp_ectors::utils::json_rpc::{MCP_REQUEST_CANCELLED, MCP_TIMEOUT_ERROR};
+    use mcp_spec::handler::{PromptError, ResourceError};
+    use mcp_spec::prompt::Prompt;
+    use mcp_spec::protocol::{CallToolResult, GetPromptResult, JsonRpcRequest, ReadResourceResult, ServerCapabilities, ToolsCapability};
+    use mcp_spec::{Resource, Tool, ToolError};
+    use serde_json::{json, Value};
+
+    /// A router whose `slow` tool never finishes in time and whose `fast` tool answers immediately.
+    struct SlowRouter;
+
+    impl Router for SlowRouter {
+        fn name(&self) -> String {
+            "SlowRouter".to_string()
+        }
+
+        fn instructions(&self) -> String {
+            "Slow instructions".to_string()
+        }
+
+        fn capabilities(&self) -> ServerCapabilities {
+            ServerCapabilities { tools: Some(ToolsCapability { list_changed: None }), resources: None, prompts: None }
+        }
+
+        fn list_tools(&self) -> Vec<Tool> {
+            vec![]
+        }
+
+        fn call_tool(&self, tool_name: &str, _arguments: Value) -> ResponseFuture<Result<CallToolResult, ToolError>> {
+            let tool_name = tool_name.to_string();
+            Box::pin(async move {
+                if tool_name == "slow" {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                }
+                Ok(CallToolResult { content: vec![], is_error: Some(false) })
+            })
+        }
+
+        fn list_resources(&self) -> Vec<Resource> {
+            vec![]
+        }
+
+        fn read_resource(&self, uri: &str) -> ResponseFuture<Result<ReadResourceResult, ResourceError>> {
+            let uri = uri.to_string();
+            Box::pin(async move { Err(ResourceError::NotFound(uri)) })
+        }
+
+        fn list_prompts(&self) -> Vec<Prompt> {
+            vec![]
+        }
+
+        fn get_prompt(&self, prompt_name: &str) -> ResponseFuture<Result<GetPromptResult, PromptError>> {
+            let prompt_name = prompt_name.to_string();
+            Box::pin(async move { Err(PromptError::NotFound(prompt_name)) })
+        }
+    }
+
+    fn call_tool_request(id: u64, tool_name: &str) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            method: "tools/call".to_string(),
+            params: Some(json!({ "name": tool_name, "arguments": {} })),
+        }
+    }
+
+    fn start_router(timeouts: RequestTimeouts) -> actix::Addr<RouterActor> {
+        RouterActor::new("slowrouter".to_string(), Arc::new(Box::new(SlowRouter)))
+            .with_timeouts(Arc::new(RwLock::new(timeouts)))
+            .start()
+    }
+
+    #[actix_rt::test]
+    async fn test_tool_timeout_returns_timeout_error() {
+        let timeouts = RequestTimeouts::default()
+            .with_tool_timeout("slowrouter_slow", Duration::from_millis(50));
+        let router = start_router(timeouts);
+
+        let response = router
+            .send(TransportRequest { request: call_tool_request(1, "slow"), context: RequestContext::for_session(1) })
+            .await
+            .unwrap();
+        let error = response.expect_err("slow tool should time out");
+        assert_eq!(error.id, Some(1));
+        assert_eq!(error.error.code, MCP_TIMEOUT_ERROR);
+
+        // other tools of the router keep the default timeout
+        let response = router
+            .send(TransportRequest { request: call_tool_request(2, "fast"), context: RequestContext::for_session(1) })
+            .await
+            .unwrap();
+        assert_eq!(response.unwrap().id, Some(2));
+    }
+
+    #[actix_rt::test]
+    async fn test_cancellation_aborts_request() {
+        let router = start_router(RequestTimeouts::default());
+        let context = RequestContext::for_session(1);
+        let cancellation = context.cancellation.clone();
+
+        let pending = router.send(TransportRequest { request: call_tool_request(3, "slow"), context });
+        actix_rt::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancellation.cancel();
+        });
+
+        let response = tokio::time::timeout(Duration::from_secs(5), pending)
+            .await
+            .expect("cancelled request should finish quickly")
+            .unwrap();
+        assert_eq!(response.expect_err("request was cancelled").error.code, MCP_REQUEST_CANCELLED);
+    }
+
+    #[actix_rt::test]
+    async fn test_wasm_prompts_and_resources_are_interrupted() {
+        let directory = tempfile::tempdir().unwrap();
+        // its get-prompt and read-resource never return
+        std::fs::copy("tests/wasm/health_router.wat", directory.path().join("spinning.wasm")).unwrap();
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.set_timeouts(RequestTimeouts::default().with_router_timeout("spinning", Duration::from_millis(200)));
+        manager.load_wasm_directory(directory.path().to_str().unwrap()).await.unwrap();
+        let (router, _) = manager.get_router("spinning_anything".to_string()).await.unwrap();
+        let request = |id: u64, method: &str, params: Value| JsonRpcRequest { jsonrpc: "2.0".to_string(), id: Some(id), method: method.to_string(), params: Some(params) };
+
+        for (id, method, params) in [(1, "prompts/get", json!({ "name": "forever" })), (2, "resources/read", json!({ "uri": "forever" }))] {
+            let response = tokio::time::timeout(Duration::from_secs(5), router.send(TransportRequest { request: request(id, method, params), context: RequestContext::for_session(1) }))
+                .await
+                .expect("the actor is not blocked")
+                .unwrap();
+            assert_eq!(response.expect_err(method).error.code, MCP_TIMEOUT_ERROR, "{}", method);
+        }
+        // the guest was interrupted, the router answers again
+        let response = router.send(TransportRequest { request: call_tool_request(3, "anything"), context: RequestContext::for_session(1) }).await.unwrap();
+        assert_eq!(response.unwrap().id, Some(3));
+    }
+
+    /// A client that declared sampling but never answers
+    struct SilentClient;
+
+    impl Actor for SilentClient {
+        type Context = actix::Context<Self>;
+    }
+
+    impl actix::Handler<ClientMessage> for SilentClient {
+        type Result = ();
+
+        fn handle(&mut self, _msg: ClientMessage, _ctx: &mut Self::Context) -> Self::Result {}
+    }
+
+    /// wasm routers take one string argument
+    fn call_wasm_tool_request(id: u64, tool_name: &str) -> JsonRpcRequest {
+        JsonRpcRequest { params: Some(json!({ "name": tool_name, "arguments": { "text": "hello" } })), ..call_tool_request(id, tool_name) }
+    }
+
+    #[actix_rt::test]
+    async fn test_wasm_waits_for_the_client_only_as_long_as_the_request() {
+        let directory = tempfile::tempdir().unwrap();
+        // its call-tool asks the client for a completion
+        std::fs::copy("tests/wasm/health_router.wat", directory.path().join("sampling.wasm")).unwrap();
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.set_timeouts(RequestTimeouts::default().with_router_timeout("sampling", Duration::from_millis(300)));
+        manager.load_wasm_directory(directory.path().to_str().unwrap()).await.unwrap();
+        let (router, _) = manager.get_router("sampling_anything".to_string()).await.unwrap();
+        let registry = ClientRegistryActor::new().start();
+        let client_id = registry.send(RegisterClient { recipient: SilentClient.start().recipient() }).await.unwrap();
+        registry.send(SetClientCapabilities { client_id, capabilities: json!({ "sampling": {} }) }).await.unwrap();
+        let asking = || RequestContext::for_session(client_id).with_client(ClientHandle::new(client_id, registry.clone()));
+        // without a client the guest gets an error from the host right away, unless it still waits for an earlier request
+        let answers = async |id: u64| {
+            let request = router.send(TransportRequest { request: call_wasm_tool_request(id, "anything"), context: RequestContext::for_session(1) });
+            let response = tokio::time::timeout(Duration::from_secs(5), request).await.expect("the guest waits no longer").unwrap();
+            assert_eq!(response.unwrap().id, Some(id));
+        };
+
+        // the host stops waiting when the router times out, whichever of them answers first
+        let request = router.send(TransportRequest { request: call_wasm_tool_request(1, "anything"), context: asking() });
+        match tokio::time::timeout(Duration::from_secs(5), request).await.expect("the host waits no longer than the request").unwrap() {
+            Ok(response) => assert_eq!(response.id, Some(1)),
+            Err(error) => assert_eq!(error.error.code, MCP_TIMEOUT_ERROR),
+        }
+        answers(2).await;
+
+        manager.set_timeouts(RequestTimeouts::default());
+        let context = asking();
+        let cancellation = context.cancellation.clone();
+        let pending = router.send(TransportRequest { request: call_wasm_tool_request(3, "anything"), context });
+        actix_rt::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            cancellation.cancel();
+        });
+        match tokio::time::timeout(Duration::from_secs(5), pending).await.expect("the host stops waiting when cancelled").unwrap() {
+            Ok(response) => assert_eq!(response.id, Some(3)),
+            Err(error) => assert_eq!(error.error.code, MCP_REQUEST_CANCELLED),
+        }
+        answers(4).await;
+    }
+
+    #[test]
+    fn test_timeout_precedence() {
+        let timeouts = RequestTimeouts::new(Duration::from_secs(60))
+            .with_router_timeout("router", Duration::from_secs(10))
+            .with_tool_timeout("router_tool", Duration::from_secs(1));
+        assert_eq!(timeouts.timeout_for("router", Some("tool")), Duration::from_secs(1));
+        assert_eq!(timeouts.timeout_for("router", Some("other")), Duration::from_secs(10));
+        assert_eq!(timeouts.timeout_for("other", None), Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use actix::{Actor, Addr, Context, Handler};
+    use mcp_ectors::client::client_registry::{ClientResponse, RefreshRoots, RegisterClient, Root, SetClientCapabilities};
+    use mcp_ectors::client::{ClientHandle, ClientRegistryActor};
+    use mcp_ectors::messages::ClientMessage;
+    use mcp_spec::protocol::{JsonRpcMessage, JsonRpcResponse};
+    use serde_json::{json, Value};
+
+    /// A client that answers `roots/list` with whatever roots the test gave it
+    struct RootsClient {
+        registry: Addr<ClientRegistryActor>,
+        client_id: Arc<Mutex<u64>>,
+        roots: Arc<Mutex<Value>>,
+    }
+
+    impl Actor for RootsClient {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<ClientMessage> for RootsClient {
+        type Result = ();
+
+        fn handle(&mut self, msg: ClientMessage, _ctx: &mut Self::Context) -> Self::Result {
+            if let JsonRpcMessage::Request(request) = msg.0 {
+                assert_eq!(request.method, "roots/list");
+                let response = JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(json!({ "roots": self.roots.lock().unwrap().clone() })),
+                    error: None,
+                };
+                self.registry.do_send(ClientResponse { client_id: *self.client_id.lock().unwrap(), response });
+            }
+        }
+    }
+
+    async fn connect(capabilities: Value, roots: Arc<Mutex<Value>>) -> (Addr<ClientRegistryActor>, ClientHandle) {
+        let registry = ClientRegistryActor::new().start();
+        let client_id = Arc::new(Mutex::new(0));
+        let client = RootsClient { registry: registry.clone(), client_id: client_id.clone(), roots }.start();
+        let id = registry.send(RegisterClient { recipient: client.recipient() }).await.unwrap();
+        *client_id.lock().unwrap() = id;
+        registry.send(SetClientCapabilities { client_id: id, capabilities }).await.unwrap();
+        (registry.clone(), ClientHandle::new(id, registry))
+    }
+
+    #[actix_rt::test]
+    async fn test_roots_are_fetched_and_refreshed() {
+        let roots = Arc::new(Mutex::new(json!([{ "uri": "file:///home/user/project", "name": "project" }])));
+        let (registry, client) = connect(json!({ "roots": { "listChanged": true } }), roots.clone()).await;
+        assert!(client.roots().await.is_empty());
+
+        registry.send(RefreshRoots { client_id: client.client_id() }).await.unwrap();
+        assert_eq!(client.roots().await, vec![Root { uri: "file:///home/user/project".to_string(), name: Some("project".to_string()) }]);
+
+        // what the server does on notifications/roots/list_changed
+        *roots.lock().unwrap() = json!([{ "uri": "file:///home/user/other" }, { "uri": "file:///tmp" }]);
+        registry.send(RefreshRoots { client_id: client.client_id() }).await.unwrap();
+        let refreshed = client.roots().await;
+        assert_eq!(refreshed.len(), 2);
+        assert_eq!(refreshed[0].uri, "file:///home/user/other");
+        assert_eq!(refreshed[0].name, None);
+    }
+
+    #[actix_rt::test]
+    async fn test_roots_are_not_requested_without_capability() {
+        let roots = Arc::new(Mutex::new(json!([{ "uri": "file:///home/user/project" }])));
+        let (registry, client) = connect(json!({ "sampling": {} }), roots).await;
+        registry.send(RefreshRoots { client_id: client.client_id() }).await.unwrap();
+        assert!(client.roots().await.is_empty());
+    }
+}
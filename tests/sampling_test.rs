@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix::{Actor, Addr, Context, Handler};
+    use mcp_ectors::client::client_registry::{ClientRequestError, ClientResponse, RegisterClient, RequestClient, SetClientCapabilities};
+    use mcp_ectors::client::{ClientHandle, ClientRegistryActor};
+    use mcp_ectors::messages::ClientMessage;
+    use mcp_spec::protocol::{ErrorData, JsonRpcMessage, JsonRpcResponse};
+    use serde_json::{json, Value};
+
+    /// A client that answers every sampling request, or never answers when `silent`
+    struct SamplingClient {
+        registry: Addr<ClientRegistryActor>,
+        client_id: Option<u64>,
+        silent: bool,
+    }
+
+    impl Actor for SamplingClient {
+        type Context = Context<Self>;
+    }
+
+    #[derive(actix::Message)]
+    #[rtype(result = "()")]
+    struct SetId(u64);
+
+    impl Handler<SetId> for SamplingClient {
+        type Result = ();
+
+        fn handle(&mut self, msg: SetId, _ctx: &mut Self::Context) -> Self::Result {
+            self.client_id = Some(msg.0);
+        }
+    }
+
+    impl Handler<ClientMessage> for SamplingClient {
+        type Result = ();
+
+        fn handle(&mut self, msg: ClientMessage, _ctx: &mut Self::Context) -> Self::Result {
+            if let JsonRpcMessage::Request(request) = msg.0 {
+                assert_eq!(request.method, "sampling/createMessage");
+                if self.silent {
+                    return;
+                }
+                let params = request.params.unwrap();
+                let response = if params["maxTokens"] == json!(0) {
+                    JsonRpcResponse { jsonrpc: "2.0".to_string(), id: request.id, result: None, error: Some(ErrorData { code: -1, message: "User rejected sampling request".to_string(), data: None }) }
+                } else {
+                    let text = params["messages"][0]["content"]["text"].as_str().unwrap().to_uppercase();
+                    JsonRpcResponse { jsonrpc: "2.0".to_string(), id: request.id, result: Some(json!({"role": "assistant", "content": {"type": "text", "text": text}, "model": "test"})), error: None }
+                };
+                self.registry.do_send(ClientResponse { client_id: self.client_id.unwrap(), response });
+            }
+        }
+    }
+
+    async fn connect(capabilities: Value, silent: bool) -> (Addr<ClientRegistryActor>, ClientHandle) {
+        let registry = ClientRegistryActor::new().start();
+        let client = SamplingClient { registry: registry.clone(), client_id: None, silent }.start();
+        let client_id = registry.send(RegisterClient { recipient: client.clone().recipient() }).await.unwrap();
+        client.send(SetId(client_id)).await.unwrap();
+        registry.send(SetClientCapabilities { client_id, capabilities }).await.unwrap();
+        (registry.clone(), ClientHandle::new(client_id, registry))
+    }
+
+    fn sampling_params(text: &str, max_tokens: u64) -> Value {
+        json!({
+            "messages": [{"role": "user", "content": {"type": "text", "text": text}}],
+            "maxTokens": max_tokens,
+        })
+    }
+
+    #[actix_rt::test]
+    async fn test_create_message_round_trip() {
+        let (_registry, client) = connect(json!({"sampling": {}}), false).await;
+        assert!(client.has_capability("sampling").await);
+
+        let result = client.create_message(sampling_params("summarise this", 100)).await.unwrap();
+        assert_eq!(result["content"]["text"], json!("SUMMARISE THIS"));
+
+        let error = client.create_message(sampling_params("rejected", 0)).await.unwrap_err();
+        assert!(matches!(error, ClientRequestError::Client(ErrorData { code: -1, .. })));
+    }
+
+    #[actix_rt::test]
+    async fn test_create_message_requires_sampling_capability() {
+        let (_registry, client) = connect(json!({"roots": {"listChanged": true}}), false).await;
+        assert!(!client.has_capability("sampling").await);
+        let error = client.create_message(sampling_params("hello", 100)).await.unwrap_err();
+        assert_eq!(error, ClientRequestError::NotSupported("sampling".to_string()));
+    }
+
+    #[actix_rt::test]
+    async fn test_request_times_out() {
+        let (registry, client) = connect(json!({"sampling": {}}), true).await;
+        let error = registry
+            .send(RequestClient {
+                client_id: client.client_id(),
+                method: "sampling/createMessage".to_string(),
+                params: Some(sampling_params("hello", 100)),
+                capability: Some("sampling".to_string()),
+                timeout: Duration::from_millis(50),
+            })
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error, ClientRequestError::Timeout);
+    }
+}
@@ -0,0 +1,121 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    use actix::{Actor, Addr};
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use mcp_ectors::auth::{AuthMethod, Principal};
+    use mcp_ectors::client::client_registry::{OpenSession, ResolveSession};
+    use mcp_ectors::client::sessions::{new_session_token, SESSION_TOKEN_BYTES};
+    use mcp_ectors::client::{ClientRegistryActor, SessionBinding, SessionLimits};
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::McpEctorsError;
+    use serde_json::{json, Value};
+
+    use crate::common::{routes, try_next_event};
+
+    fn principal(subject: &str, method: AuthMethod, claims: Value) -> Principal {
+        Principal { subject: subject.to_string(), method, scopes: vec![], claims, permissions: None }
+    }
+
+    async fn resolve(registry: &Addr<ClientRegistryActor>, token: &str, binding: SessionBinding) -> Result<u64, McpEctorsError> {
+        registry.send(ResolveSession { token: token.to_string(), binding }).await.unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_session_tokens_are_long_and_random() {
+        let tokens: HashSet<String> = (0..1000).map(|_| new_session_token()).collect();
+        assert_eq!(tokens.len(), 1000);
+        for token in tokens {
+            // 256 bits in base64url without padding
+            assert_eq!(token.len(), (SESSION_TOKEN_BYTES * 8).div_ceil(6));
+            assert!(token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'), "{}", token);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_sessions_are_bound_to_principal_and_certificate() {
+        let registry = ClientRegistryActor::new().start();
+        let certificate = |fingerprint: &str| principal("alice", AuthMethod::ClientCertificate, json!({ "fingerprint": fingerprint }));
+        let alice = principal("alice", AuthMethod::ApiKey, Value::Null);
+        let binding = SessionBinding::new(Some(alice.clone()), Some(&certificate("aa")));
+        assert_eq!(binding.certificate.as_deref(), Some("aa"));
+        let token = registry.send(OpenSession { client_id: 7, binding: binding.clone() }).await.unwrap();
+
+        assert_eq!(resolve(&registry, &token, binding).await.unwrap(), 7);
+        // the same principal over a connection with another certificate, or without one
+        let other_connection = SessionBinding::new(Some(alice.clone()), Some(&certificate("bb")));
+        assert!(matches!(resolve(&registry, &token, other_connection).await, Err(McpEctorsError::Forbidden(_))));
+        assert!(matches!(resolve(&registry, &token, SessionBinding::new(Some(alice), None)).await, Err(McpEctorsError::Forbidden(_))));
+        // another principal, and nobody
+        let bob = SessionBinding::new(Some(principal("bob", AuthMethod::ApiKey, Value::Null)), Some(&certificate("aa")));
+        assert!(matches!(resolve(&registry, &token, bob).await, Err(McpEctorsError::Forbidden(_))));
+        assert!(matches!(resolve(&registry, &token, SessionBinding::default()).await, Err(McpEctorsError::Forbidden(_))));
+
+        // only certificates bind sessions to connections
+        let anonymous = registry.send(OpenSession { client_id: 8, binding: SessionBinding::new(None, None) }).await.unwrap();
+        assert_eq!(resolve(&registry, &anonymous, SessionBinding::default()).await.unwrap(), 8);
+        assert!(matches!(resolve(&registry, "guessed", SessionBinding::default()).await, Err(McpEctorsError::UnknownSession)));
+    }
+
+    #[actix_rt::test]
+    async fn test_sessions_expire_when_idle_and_after_their_lifetime() {
+        let registry = ClientRegistryActor::new()
+            .with_session_limits(SessionLimits::new(Duration::from_millis(200), Duration::from_millis(600)))
+            .start();
+        let idle = registry.send(OpenSession { client_id: 1, binding: SessionBinding::default() }).await.unwrap();
+        let busy = registry.send(OpenSession { client_id: 2, binding: SessionBinding::default() }).await.unwrap();
+
+        // using a session keeps it from idling out, but not past its lifetime
+        for _ in 0..4 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert_eq!(resolve(&registry, &busy, SessionBinding::default()).await.unwrap(), 2);
+        }
+        match resolve(&registry, &idle, SessionBinding::default()).await {
+            Err(McpEctorsError::SessionExpired(reason)) => assert_eq!(reason, "idle"),
+            other => panic!("{:?}", other),
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        match resolve(&registry, &busy, SessionBinding::default()).await {
+            Err(McpEctorsError::SessionExpired(reason)) => assert_eq!(reason, "lifetime"),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_expired_and_unknown_sessions_are_rejected_distinctly() {
+        let manager = RouterServiceManager::default(None).await;
+        let registry = ClientRegistryActor::new()
+            .with_session_limits(SessionLimits::new(Duration::from_millis(300), Duration::from_secs(60)))
+            .start();
+        let app = test::init_service(App::new().configure(routes(registry, &manager))).await;
+        let post = |uri: &str| test::TestRequest::post().uri(uri).set_json(json!({"jsonrpc": "2.0", "id": 1, "method": "ping"})).to_request();
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").to_request()).await;
+        let mut body = Box::pin(response.into_body().boxed());
+        let messages = try_next_event(&mut body).await.unwrap();
+        let token = messages.strip_prefix("/messages/?session_id=").unwrap();
+        assert!(token.parse::<u64>().is_err() && token.len() >= 22, "{}", token);
+        assert_eq!(test::call_service(&app, post(&messages)).await.status(), StatusCode::OK);
+        assert_eq!(try_next_event(&mut body).await.unwrap(), json!({"jsonrpc": "2.0", "id": 1, "result": {}}).to_string());
+
+        let response = test::call_service(&app, post("/messages/?session_id=12345")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let error: Value = serde_json::from_slice(&response.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(error["error"]["code"], json!(-32010));
+
+        // the expired session is closed, the stream ends
+        let closed = tokio::time::timeout(Duration::from_secs(2), try_next_event(&mut body)).await;
+        assert_eq!(closed.unwrap(), None);
+        let response = test::call_service(&app, post(&messages)).await;
+        assert_eq!(response.status(), StatusCode::GONE);
+        let error: Value = serde_json::from_slice(&response.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(error["error"]["code"], json!(-32011));
+        assert_eq!(error["error"]["data"]["reason"], json!("idle"));
+    }
+}
@@ -77,8 +77,10 @@ mod tests {
         info!("Starting MCP Server...");
         let config = SseTransportConfig {
             port: 3000,
+            listen: vec![],
             tls_cert: None,
             tls_key: None,
+            tls_client_ca: None,
             log_dir: "logs".into(),
             log_file: "test-sse.log".into(),
         };
@@ -161,7 +163,8 @@ mod tests {
 
             let list_tools_req = client.list_tools(None).await;
             let list_tools = list_tools_req.unwrap();
-            assert_eq!(list_tools.tools.len(), get_initial_tools().len());
+            // the system router adds its tool discovery tools
+            assert_eq!(list_tools.tools.iter().filter(|t| !t.name.starts_with("system_")).count(), get_initial_tools().len());
             for expected in &get_initial_tools() {
                 assert!(list_tools.tools.iter().any(|t| t.name == format!("mockrouter{}{}",ROUTER_SEPERATOR,expected.name)));
             }
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use actix::{Actor, Context, Handler};
+    use mcp_ectors::client::client_registry::RegisterClient;
+    use mcp_ectors::client::{ClientHandle, ClientRegistryActor};
+    use mcp_ectors::messages::{ClientMessage, ListResourcesRequest, TransportRequest};
+    use mcp_ectors::router::{RequestContext, RouterServiceManager};
+    use mcp_spec::protocol::{JsonRpcMessage, JsonRpcRequest};
+    use serde_json::{json, Value};
+
+    /// Stands in for an SSE session and remembers the params of every notification it receives
+    struct Collector {
+        received: Arc<Mutex<Vec<(String, Value)>>>,
+    }
+
+    impl Actor for Collector {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<ClientMessage> for Collector {
+        type Result = ();
+
+        fn handle(&mut self, msg: ClientMessage, _ctx: &mut Self::Context) -> Self::Result {
+            if let JsonRpcMessage::Notification(notification) = msg.0 {
+                self.received.lock().unwrap().push((notification.method, notification.params.unwrap()));
+            }
+        }
+    }
+
+    async fn manager() -> RouterServiceManager {
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.load_wasm_router("weather", Path::new("wasm/mcp_weather_api2.wasm")).await.unwrap();
+        manager
+    }
+
+    // sends `method` for the system resource `uri` (e.g. `system_all`) and returns the result or the error
+    async fn call(manager: &RouterServiceManager, method: &str, uri: &str, context: RequestContext) -> Result<Value, Value> {
+        let (router, uri) = manager.get_router(uri.to_string()).await.unwrap();
+        let request = JsonRpcRequest { jsonrpc: "2.0".to_string(), id: Some(1), method: method.to_string(), params: Some(json!({ "uri": uri })) };
+        router.send(TransportRequest { request, context }).await.unwrap()
+            .map(|response| response.result.unwrap())
+            .map_err(|error| json!(error.error))
+    }
+
+    async fn read(manager: &RouterServiceManager, uri: &str) -> (String, String) {
+        let result = call(manager, "resources/read", uri, RequestContext::default()).await.unwrap();
+        let contents = &result["contents"][0];
+        (contents["mime_type"].as_str().unwrap().to_string(), contents["text"].as_str().unwrap().to_string())
+    }
+
+    #[actix_rt::test]
+    async fn test_catalogue_describes_the_installed_routers() {
+        let manager = manager().await;
+
+        let (mime_type, text) = read(&manager, "system_all").await;
+        assert_eq!(mime_type, "text/markdown");
+        assert!(text.starts_with("# Routers installed in this server\n\n## system\n"), "{}", text);
+        assert!(text.contains("\n## weather\n"));
+        assert!(text.contains("Version: 0.1.0"));
+        assert!(text.contains("- `weather_GetWeather`"));
+        assert!(!text.contains("counter_router"));
+
+        let (mime_type, text) = read(&manager, "system_all.json").await;
+        assert_eq!(mime_type, "application/json");
+        let catalogue: Value = serde_json::from_str(&text).unwrap();
+        let routers: Vec<&str> = catalogue["routers"].as_array().unwrap().iter().map(|router| router["router_id"].as_str().unwrap()).collect();
+        assert_eq!(routers, vec!["system", "weather"]);
+        let weather = &catalogue["routers"][1];
+        assert!(weather["tools"][0]["name"].as_str().unwrap().starts_with("weather_"));
+        assert!(weather["tools"][0]["inputSchema"].is_object());
+        assert_eq!(weather["prompts"][0]["name"], "weather_GetWeather");
+        assert_eq!(catalogue["routers"][0]["resources"][0]["uri"], "system_all");
+
+        let (_, text) = read(&manager, "system_router/weather").await;
+        assert!(text.starts_with("# weather\n"), "{}", text);
+        let (_, text) = read(&manager, "system_router/weather.json").await;
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap(), *weather);
+        assert!(call(&manager, "resources/read", "system_router/nothere", RequestContext::default()).await.is_err());
+        assert!(call(&manager, "resources/read", "system_everything", RequestContext::default()).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_every_router_has_a_resource() {
+        let mut manager = manager().await;
+        let uris = |manager: RouterServiceManager| async move {
+            let request = JsonRpcRequest { jsonrpc: "2.0".to_string(), id: Some(1), method: "resources/list".to_string(), params: None };
+            let response = manager.get_list_resources().send(ListResourcesRequest { request }).await.unwrap().unwrap();
+            let mut uris: Vec<String> = response.result.unwrap()["resources"].as_array().unwrap().iter()
+                .filter(|resource| resource["name"].as_str().unwrap().starts_with("system_"))
+                .map(|resource| resource["uri"].as_str().unwrap().to_string())
+                .collect();
+            uris.sort();
+            uris
+        };
+        assert_eq!(uris(manager.clone()).await, vec!["all", "all.json", "router/system", "router/system.json", "router/weather", "router/weather.json"]);
+
+        manager.unregister_router("weather").await.unwrap();
+        assert_eq!(uris(manager.clone()).await, vec!["all", "all.json", "router/system", "router/system.json"]);
+        assert!(!read(&manager, "system_all").await.1.contains("weather"));
+    }
+
+    #[actix_rt::test]
+    async fn test_subscribers_hear_of_changes() {
+        let manager = manager().await;
+        let received = Arc::new(Mutex::new(vec![]));
+        let collector = Collector { received: received.clone() }.start();
+        let registry = ClientRegistryActor::new().start();
+        let client_id = registry.send(RegisterClient { recipient: collector.recipient() }).await.unwrap();
+        let context = || RequestContext::for_session(client_id).with_client(ClientHandle::new(client_id, registry.clone()));
+
+        assert_eq!(call(&manager, "resources/subscribe", "system_all", context()).await, Ok(json!({})));
+        assert_eq!(call(&manager, "resources/subscribe", "system_router/weather.json", context()).await, Ok(json!({})));
+        // without a session there is no one to tell
+        assert!(call(&manager, "resources/subscribe", "system_all", RequestContext::default()).await.is_err());
+
+        manager.set_router_enabled("weather", false).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*received.lock().unwrap(), vec![
+            ("notifications/resources/updated".to_string(), json!({"uri": "system_all"})),
+            ("notifications/resources/updated".to_string(), json!({"uri": "system_router/weather.json"})),
+        ]);
+        assert!(!read(&manager, "system_all").await.1.contains("weather"));
+
+        received.lock().unwrap().clear();
+        assert_eq!(call(&manager, "resources/unsubscribe", "system_all", context()).await, Ok(json!({})));
+        manager.set_router_enabled("weather", true).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*received.lock().unwrap(), vec![
+            ("notifications/resources/updated".to_string(), json!({"uri": "system_router/weather.json"})),
+        ]);
+    }
+}
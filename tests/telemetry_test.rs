@@ -0,0 +1,168 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::Duration;
+
+    use actix::Actor;
+    use actix_web::body::MessageBody;
+    use actix_web::{test, App};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::telemetry::{OtlpExporter, OtlpLayer, TraceContext};
+    use serde_json::{json, Value};
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Registry;
+
+    use crate::common::{next_event, routes};
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    const META_TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+    /// Stands in for an OpenTelemetry collector, every body POSTed to it comes out of the receiver
+    fn collector() -> (String, Receiver<(String, Value)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    let mut stream = stream.unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    loop {
+                        let mut request_line = String::new();
+                        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let mut length = 0;
+                        loop {
+                            let mut header = String::new();
+                            reader.read_line(&mut header).unwrap();
+                            if header.trim().is_empty() {
+                                break;
+                            }
+                            if let Some((name, value)) = header.split_once(':') {
+                                if name.eq_ignore_ascii_case("content-length") {
+                                    length = value.trim().parse().unwrap();
+                                }
+                            }
+                        }
+                        let mut body = vec![0; length];
+                        reader.read_exact(&mut body).unwrap();
+                        let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_string();
+                        let _ = sender.send((path, serde_json::from_slice(&body).unwrap()));
+                        stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").unwrap();
+                    }
+                });
+            }
+        });
+        (endpoint, receiver)
+    }
+
+    fn attribute<'a>(span: &'a Value, key: &str) -> Option<&'a Value> {
+        span["attributes"].as_array().unwrap().iter().find(|attribute| attribute["key"] == key).map(|attribute| &attribute["value"])
+    }
+
+    #[actix_rt::test]
+    async fn test_traceparent_is_parsed_and_formatted() {
+        let context: TraceContext = TRACEPARENT.parse().unwrap();
+        assert_eq!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.span_id_hex(), "00f067aa0ba902b7");
+        assert_eq!(context.flags, 1);
+        assert_eq!(context.to_string(), TRACEPARENT);
+
+        let child = context.child();
+        assert_eq!(child.trace_id, context.trace_id);
+        assert_ne!(child.span_id, context.span_id);
+
+        // later versions may add fields
+        assert!("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra".parse::<TraceContext>().is_ok());
+        for invalid in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e47-00f067aa0ba902b7-01",
+        ] {
+            assert!(invalid.parse::<TraceContext>().is_err(), "{} was accepted", invalid);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_request_is_traced_end_to_end() {
+        let (endpoint, collected) = collector();
+        let exporter = OtlpExporter::new(&endpoint, "telemetry-test").unwrap();
+        let _subscriber = tracing::subscriber::set_default(Registry::default().with(OtlpLayer::new(exporter.clone())));
+
+        let mut manager = RouterServiceManager::default(None).await;
+        manager.load_wasm_directory("wasm").await.unwrap();
+        let app = test::init_service(App::new().configure(routes(ClientRegistryActor::new().start(), &manager))).await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/sse").to_request()).await;
+        let mut body = Box::pin(response.into_body().boxed());
+        let messages = next_event(&mut body).await;
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "system_nothere"}},
+            {"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "mcpweatherapi2_nothere", "arguments": {}, "_meta": {"traceparent": META_TRACEPARENT}}},
+        ]);
+        test::call_service(&app, test::TestRequest::post()
+            .uri(&messages)
+            .insert_header(("traceparent", TRACEPARENT))
+            .set_json(batch)
+            .to_request()).await;
+        let replies: Value = serde_json::from_str(&next_event(&mut body).await).unwrap();
+        assert_eq!(replies.as_array().unwrap().len(), 2);
+        exporter.flush();
+
+        let mut spans = vec![];
+        while let Ok((path, body)) = collected.recv_timeout(Duration::from_secs(5)) {
+            assert_eq!(path, "/v1/traces");
+            let resource = &body["resourceSpans"][0];
+            assert_eq!(resource["resource"]["attributes"][0]["value"]["stringValue"], "telemetry-test");
+            spans.extend(resource["scopeSpans"][0]["spans"].as_array().unwrap().iter().cloned());
+            if spans.iter().filter(|span| span["name"] == "mcp.request").count() == 2 {
+                break;
+            }
+        }
+        manager.unload_wasm_directory("wasm").await.unwrap();
+
+        let span = |name: &str, trace_id: &str| spans.iter()
+            .find(|span| span["name"] == name && span["traceId"] == trace_id)
+            .unwrap_or_else(|| panic!("no {} span in trace {} among {:#?}", name, trace_id, spans));
+
+        // the header is the parent of the request without a traceparent of its own
+        let request = span("mcp.request", "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(request["parentSpanId"], "00f067aa0ba902b7");
+        assert_eq!(request["kind"], 2);
+        assert_eq!(request["status"]["code"], 2);
+        assert_eq!(attribute(request, "method").unwrap()["stringValue"], "tools/call");
+        assert_eq!(attribute(request, "router_id").unwrap()["stringValue"], "system");
+        assert_eq!(attribute(request, "tool_name").unwrap()["stringValue"], "system_nothere");
+        assert_eq!(attribute(request, "request_id").unwrap()["stringValue"], "1");
+        assert!(attribute(request, "session_id").is_some());
+        assert!(attribute(request, "error_code").is_some());
+        let get_router = span("mcp.get_router", "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(get_router["parentSpanId"], request["spanId"]);
+        let router = span("router", "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(router["parentSpanId"], request["spanId"]);
+
+        // _meta wins over the header, and the WASM guest is in the same trace
+        let request = span("mcp.request", "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(request["parentSpanId"], "b7ad6b7169203331");
+        assert_eq!(attribute(request, "router_id").unwrap()["stringValue"], "mcpweatherapi2");
+        let router = span("router", "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(router["parentSpanId"], request["spanId"]);
+        let wasm = span("mcp.wasm", "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(wasm["parentSpanId"], router["spanId"]);
+        assert_eq!(attribute(wasm, "export").unwrap()["stringValue"], "call-tool");
+        assert!(attribute(wasm, "fuel").is_some());
+    }
+}
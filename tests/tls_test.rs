@@ -0,0 +1,187 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use actix::Actor;
+    use mcp_ectors::auth::{client_certificate_principal, AuthMethod};
+    use mcp_ectors::client::ClientRegistryActor;
+    use mcp_ectors::mcp::InitializationActor;
+    use mcp_ectors::messages::transport_messages::{StartTransport, StopTransport};
+    use mcp_ectors::router::RouterServiceManager;
+    use mcp_ectors::transport::sse_transport_actor::SseTransportConfig;
+    use mcp_ectors::transport::tls::CertificateResolver;
+    use mcp_ectors::transport::SseTransportActor;
+    use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair};
+    use rustls::pki_types::CertificateDer;
+    use serde_json::{json, Value};
+    use tempfile::TempDir;
+
+    use crate::common::event_data;
+
+    /// A CA and PEM files of a server certificate and a client certificate it issued
+    struct Certificates {
+        directory: TempDir,
+        ca: Certificate,
+        ca_key: KeyPair,
+    }
+
+    impl Certificates {
+        fn new() -> Self {
+            let ca_key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec![]).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params.distinguished_name.push(DnType::CommonName, "mcp-ectors test CA");
+            let ca = params.self_signed(&ca_key).unwrap();
+            let certificates = Self { directory: tempfile::tempdir().unwrap(), ca, ca_key };
+            std::fs::write(certificates.path("ca.pem"), certificates.ca.pem()).unwrap();
+            certificates.issue("server", "localhost", ExtendedKeyUsagePurpose::ServerAuth);
+            certificates.issue("alice", "alice", ExtendedKeyUsagePurpose::ClientAuth);
+            certificates
+        }
+
+        fn path(&self, file: &str) -> PathBuf {
+            self.directory.path().join(file)
+        }
+
+        /// Writes `<name>.pem` and `<name>.key`
+        fn issue(&self, name: &str, common_name: &str, usage: ExtendedKeyUsagePurpose) -> Certificate {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec![common_name.to_string()]).unwrap();
+            params.distinguished_name.push(DnType::CommonName, common_name);
+            params.extended_key_usages = vec![usage];
+            let certificate = params.signed_by(&key, &self.ca, &self.ca_key).unwrap();
+            std::fs::write(self.path(&format!("{}.pem", name)), certificate.pem()).unwrap();
+            std::fs::write(self.path(&format!("{}.key", name)), key.serialize_pem()).unwrap();
+            certificate
+        }
+
+        fn client(&self, identity: Option<&str>) -> reqwest::Client {
+            let ca = reqwest::Certificate::from_pem(&std::fs::read(self.path("ca.pem")).unwrap()).unwrap();
+            let mut builder = reqwest::Client::builder().add_root_certificate(ca);
+            if let Some(name) = identity {
+                let mut pem = std::fs::read(self.path(&format!("{}.pem", name))).unwrap();
+                pem.extend(std::fs::read(self.path(&format!("{}.key", name))).unwrap());
+                builder = builder.identity(reqwest::Identity::from_pem(&pem).unwrap());
+            }
+            builder.build().unwrap()
+        }
+    }
+
+    fn config(port: u16, cert: Option<&Path>, key: Option<&Path>, client_ca: Option<&Path>) -> SseTransportConfig {
+        let path = |path: Option<&Path>| path.map(|path| path.to_string_lossy().to_string());
+        SseTransportConfig {
+            port,
+            listen: vec![],
+            tls_cert: path(cert),
+            tls_key: path(key),
+            tls_client_ca: path(client_ca),
+            log_dir: "logs".into(),
+            log_file: "test-tls.log".into(),
+        }
+    }
+
+    async fn transport(config: SseTransportConfig) -> actix::Addr<SseTransportActor> {
+        let manager = RouterServiceManager::default(None).await;
+        SseTransportActor::new(
+            config,
+            ClientRegistryActor::new().start(),
+            manager.get_registry(),
+            InitializationActor::new(),
+            manager.get_list_prompts(),
+            manager.get_list_tools(),
+            manager.get_list_resources(),
+        ).start()
+    }
+
+    /// Reads the SSE stream until an event with data arrives and returns its data
+    async fn next_event(response: &mut reqwest::Response) -> String {
+        loop {
+            let chunk = response.chunk().await.unwrap().unwrap();
+            if let Some(data) = event_data(&chunk) {
+                return data;
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_client_certificate_principal() {
+        let certificates = Certificates::new();
+        let alice = certificates.issue("alice", "alice", ExtendedKeyUsagePurpose::ClientAuth);
+        let principal = client_certificate_principal(&CertificateDer::from(alice.der().to_vec())).unwrap();
+        assert_eq!(principal.subject, "alice");
+        assert_eq!(principal.method, AuthMethod::ClientCertificate);
+        assert!(principal.scopes.is_empty() && principal.permissions.is_none());
+        assert_eq!(principal.claims["issuer"], json!("CN=mcp-ectors test CA"));
+        assert_eq!(principal.claims["fingerprint"].as_str().unwrap().len(), 64);
+        assert!(client_certificate_principal(&CertificateDer::from(b"not a certificate".to_vec())).is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_https_with_client_certificates() {
+        let certificates = Certificates::new();
+        let port = 3443;
+        let transport = transport(config(port, Some(&certificates.path("server.pem")), Some(&certificates.path("server.key")), Some(&certificates.path("ca.pem")))).await;
+        transport.send(StartTransport).await.unwrap().unwrap();
+        let base = format!("https://localhost:{}", port);
+
+        let alice = certificates.client(Some("alice"));
+        let mut sse = alice.get(format!("{}/sse", base)).send().await.unwrap();
+        assert_eq!(sse.status(), 200);
+        let messages = next_event(&mut sse).await;
+        let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"});
+        // a new connection with the same certificate is the same principal, so it may post to the session
+        let posted = certificates.client(Some("alice")).post(format!("{}{}", base, messages)).json(&ping).send().await.unwrap();
+        assert_eq!(posted.status(), 200);
+        let reply: Value = serde_json::from_str(&next_event(&mut sse).await).unwrap();
+        assert_eq!(reply["id"], json!(1));
+        assert!(reply.get("result").is_some());
+
+        // the session belongs to the principal of the certificate that opened it
+        certificates.issue("bob", "bob", ExtendedKeyUsagePurpose::ClientAuth);
+        let hijack = certificates.client(Some("bob")).post(format!("{}{}", base, messages)).json(&ping).send().await.unwrap();
+        assert_eq!(hijack.status(), 403);
+
+        // without a client certificate, or without TLS, there is no way in
+        assert!(certificates.client(None).get(format!("{}/sse", base)).send().await.is_err());
+        assert!(reqwest::get(format!("http://localhost:{}/sse", port)).await.is_err());
+
+        transport.send(StopTransport).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_incomplete_tls_configuration_is_rejected() {
+        let certificates = Certificates::new();
+        for config in [
+            config(3444, Some(&certificates.path("server.pem")), None, None),
+            config(3444, None, None, Some(&certificates.path("ca.pem"))),
+            config(3444, Some(&certificates.path("server.pem")), Some(&certificates.path("alice.key")), None),
+        ] {
+            assert!(transport(config).await.send(StartTransport).await.unwrap().is_err());
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_certificate_is_reloaded() {
+        let certificates = Certificates::new();
+        let resolver = CertificateResolver::from_files(&certificates.path("server.pem"), &certificates.path("server.key")).unwrap();
+        let served = |resolver: &CertificateResolver| resolver.certified_key().cert[0].to_vec();
+        let before = served(&resolver);
+
+        let renewed = certificates.issue("server", "localhost", ExtendedKeyUsagePurpose::ServerAuth);
+        let mut waited = Duration::ZERO;
+        while served(&resolver) != renewed.der().to_vec() && waited < Duration::from_secs(5) {
+            actix_rt::time::sleep(Duration::from_millis(50)).await;
+            waited += Duration::from_millis(50);
+        }
+        assert_ne!(served(&resolver), before);
+        assert_eq!(served(&resolver), renewed.der().to_vec());
+
+        // a broken certificate keeps the one that was working
+        std::fs::write(certificates.path("server.pem"), "not a certificate").unwrap();
+        actix_rt::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(served(&resolver), renewed.der().to_vec());
+    }
+}
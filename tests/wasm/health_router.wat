@@ -0,0 +1,152 @@
+;; A router without tools that exports `wasix:mcp/health`: every other call of `check` fails,
+;; starting with the first. Its `get-prompt` and `read-resource` never return, and `call-tool`
+;; asks the client for a completion first. The tests use it to see the health export being
+;; polled and guests being interrupted or waiting for the host.
+(component
+  (import "wasix:mcp/host@0.0.1" (instance $host
+    (export "create-message" (func (param "params" string) (result (result string (error string)))))))
+
+  ;; the memory is a module of its own, the import of the host is lowered with it
+  (core module $mem
+    (memory (export "memory") 1)
+    ;; where `realloc` hands out memory for the arguments of the host
+    (global $heap (mut i32) (i32.const 4096))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      ;; aligned to the requested alignment
+      (local.set $ptr (i32.and
+        (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+        (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr)))
+  (core instance $mi (instantiate $mem))
+  (alias core export $mi "memory" (core memory $memory))
+  (alias core export $mi "realloc" (core func $realloc))
+  (alias export $host "create-message" (func $create-message))
+  (core func $create-message-lowered (canon lower (func $create-message) (memory $memory) (realloc $realloc)))
+
+  (core module $m
+    (import "mem" "memory" (memory 1))
+    ;; params as (pointer, length), then where the result is written to
+    (import "host" "create-message" (func $create-message (param i32 i32 i32)))
+    (global $checks (mut i32) (i32.const 0))
+    ;; the name and instructions, as (pointer, length) followed by the text
+    (data (i32.const 16) "\20\00\00\00\06\00\00\00")
+    (data (i32.const 32) "health")
+    (data (i32.const 48) "\40\00\00\00\41\00\00\00")
+    (data (i32.const 64) "Reports a backend that is unreachable at every other health check")
+    ;; the error of a failed check
+    (data (i32.const 512) "\01\00\00\00\20\02\00\00\13\00\00\00")
+    (data (i32.const 544) "backend unreachable")
+    ;; the bytes at 1024 stay zero: no capabilities, empty lists and results that are ok and empty
+    ;; the params of the completion `call-tool` asks for
+    (data (i32.const 2048) "{}")
+    (func (export "name") (result i32) (i32.const 16))
+    (func (export "instructions") (result i32) (i32.const 48))
+    (func (export "empty") (result i32) (i32.const 1024))
+    (func (export "call-tool") (param i32 i32 i32 i32 i32 i32) (result i32)
+      ;; whatever the client answers, the tool succeeds
+      (call $create-message (i32.const 2048) (i32.const 2) (i32.const 3072))
+      (i32.const 1024))
+    (func (export "spin") (param i32 i32) (result i32)
+      (loop $spin (br $spin))
+      (unreachable))
+    (func (export "check") (result i32)
+      (global.set $checks (i32.add (global.get $checks) (i32.const 1)))
+      (select (i32.const 512) (i32.const 1024) (i32.and (global.get $checks) (i32.const 1))))
+  )
+  (core instance $i (instantiate $m
+    (with "mem" (instance $mi))
+    (with "host" (instance (export "create-message" (func $create-message-lowered))))))
+
+  (type $value (record (field "key" string) (field "data" string)))
+  (type $tool (record (field "name" string) (field "description" string) (field "input-schema" $value)))
+  (type $prompts-capability (record (field "list-changed" (option bool))))
+  (type $resources-capability (record (field "subscribe" (option bool)) (field "list-changed" (option bool))))
+  (type $tools-capability (record (field "list-changed" (option bool))))
+  (type $server-capabilities (record
+    (field "prompts" (option $prompts-capability))
+    (field "resources" (option $resources-capability))
+    (field "tools" (option $tools-capability))))
+  (type $role (enum "user" "assistant"))
+  (type $annotations (record (field "audience" (option (list $role))) (field "priority" (option f32)) (field "timestamp" (option string))))
+  (type $text-content (record (field "text" string) (field "annotations" (option $annotations))))
+  (type $image-content (record (field "data" string) (field "mime-type" string) (field "annotations" (option $annotations))))
+  (type $text-resource-contents (record (field "uri" string) (field "mime-type" (option string)) (field "text" string)))
+  (type $blob-resource-contents (record (field "uri" string) (field "mime-type" (option string)) (field "blob" string)))
+  (type $resource-contents (variant (case "text" $text-resource-contents) (case "blob" $blob-resource-contents)))
+  (type $embedded-resource (record (field "resource-contents" $resource-contents) (field "annotations" (option $annotations))))
+  (type $content (variant (case "text" $text-content) (case "image" $image-content) (case "embedded" $embedded-resource)))
+  (type $call-tool-result (record (field "content" (list $content)) (field "is-error" (option bool))))
+  (type $mcp-resource (record
+    (field "uri" string)
+    (field "name" string)
+    (field "description" (option string))
+    (field "mime-type" string)
+    (field "annotations" (option $annotations))))
+  (type $read-resource-result (record (field "contents" (list $resource-contents))))
+  (type $tool-error (variant (case "invalid-parameters" string) (case "execution-error" string) (case "schema-error" string) (case "not-found" string)))
+  (type $resource-error (variant (case "execution-error" string) (case "not-found" string)))
+  (type $prompt-error (variant (case "invalid-parameters" string) (case "internal-error" string) (case "not-found" string)))
+  (type $prompt-argument (record (field "name" string) (field "description" (option string)) (field "required" (option bool))))
+  (type $prompt (record (field "name" string) (field "description" (option string)) (field "arguments" (option (list $prompt-argument)))))
+  (type $prompt-message-role (enum "user" "assistant"))
+  (type $prompt-message-content (variant (case "text" $text-content) (case "image" $image-content) (case "mcp-resource" $embedded-resource)))
+  (type $prompt-message (record (field "role" $prompt-message-role) (field "content" $prompt-message-content)))
+  (type $get-prompt-result (record (field "description" (option string)) (field "messages" (list $prompt-message))))
+
+  (func $name (result string) (canon lift (core func $i "name") (memory $memory) (realloc $realloc)))
+  (func $instructions (result string) (canon lift (core func $i "instructions") (memory $memory) (realloc $realloc)))
+  (func $capabilities (result $server-capabilities) (canon lift (core func $i "empty") (memory $memory) (realloc $realloc)))
+  (func $list-tools (result (list $tool)) (canon lift (core func $i "empty") (memory $memory) (realloc $realloc)))
+  (func $call-tool (param "tool-name" string) (param "arguments" $value) (result (result $call-tool-result (error $tool-error)))
+    (canon lift (core func $i "call-tool") (memory $memory) (realloc $realloc)))
+  (func $list-resources (result (list $mcp-resource)) (canon lift (core func $i "empty") (memory $memory) (realloc $realloc)))
+  (func $read-resource (param "uri" string) (result (result $read-resource-result (error $resource-error)))
+    (canon lift (core func $i "spin") (memory $memory) (realloc $realloc)))
+  (func $list-prompts (result (list $prompt)) (canon lift (core func $i "empty") (memory $memory) (realloc $realloc)))
+  (func $get-prompt (param "prompt-name" string) (result (result $get-prompt-result (error $prompt-error)))
+    (canon lift (core func $i "spin") (memory $memory) (realloc $realloc)))
+  (func $check (result (result (error string))) (canon lift (core func $i "check") (memory $memory) (realloc $realloc)))
+
+  (instance $router
+    (export "value" (type $value))
+    (export "tool" (type $tool))
+    (export "prompts-capability" (type $prompts-capability))
+    (export "resources-capability" (type $resources-capability))
+    (export "tools-capability" (type $tools-capability))
+    (export "server-capabilities" (type $server-capabilities))
+    (export "role" (type $role))
+    (export "annotations" (type $annotations))
+    (export "text-content" (type $text-content))
+    (export "image-content" (type $image-content))
+    (export "text-resource-contents" (type $text-resource-contents))
+    (export "blob-resource-contents" (type $blob-resource-contents))
+    (export "resource-contents" (type $resource-contents))
+    (export "embedded-resource" (type $embedded-resource))
+    (export "content" (type $content))
+    (export "call-tool-result" (type $call-tool-result))
+    (export "mcp-resource" (type $mcp-resource))
+    (export "read-resource-result" (type $read-resource-result))
+    (export "tool-error" (type $tool-error))
+    (export "resource-error" (type $resource-error))
+    (export "prompt-error" (type $prompt-error))
+    (export "prompt-argument" (type $prompt-argument))
+    (export "prompt" (type $prompt))
+    (export "prompt-message-role" (type $prompt-message-role))
+    (export "prompt-message-content" (type $prompt-message-content))
+    (export "prompt-message" (type $prompt-message))
+    (export "get-prompt-result" (type $get-prompt-result))
+    (export "name" (func $name))
+    (export "instructions" (func $instructions))
+    (export "capabilities" (func $capabilities))
+    (export "list-tools" (func $list-tools))
+    (export "call-tool" (func $call-tool))
+    (export "list-resources" (func $list-resources))
+    (export "read-resource" (func $read-resource))
+    (export "list-prompts" (func $list-prompts))
+    (export "get-prompt" (func $get-prompt)))
+  (instance $health (export "check" (func $check)))
+  (export "wasix:mcp/router@0.0.1" (instance $router))
+  (export "wasix:mcp/health@0.0.1" (instance $health))
+)